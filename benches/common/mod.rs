@@ -0,0 +1,35 @@
+//! Timing harness shared by the benchmarks.
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use json_parser_serializer::*;
+
+const ITERATION_COUNT: usize = 5;
+
+/// Serialize the document several times with the default options and print the size, the times and the throughput.
+/// * Parameters:
+///     * `bench_name` : Name printed at the head of the line.
+///     * `json_object` : Document to serialize.
+pub fn serialize_bench(bench_name: &str, json_object: &JsonObject) {
+    let options = JsonSerializerOptions::new();
+
+    let mut serialized_len = 0;
+    let mut elapsed_times: Vec<Duration> = Vec::with_capacity(ITERATION_COUNT);
+    for _ in 0..ITERATION_COUNT {
+        let start = Instant::now();
+        let serialized_string = json_object.serialize_with_options(black_box(&options)).unwrap();
+        elapsed_times.push(start.elapsed());
+        serialized_len = black_box(serialized_string).len();
+    }
+    elapsed_times.sort();
+
+    let median = elapsed_times[ITERATION_COUNT / 2];
+    println!(
+        "{}: {:.1} MB, min {:?}, median {:?}, {:.1} MB/s",
+        bench_name,
+        serialized_len as f64 / 1_000_000.0,
+        elapsed_times[0],
+        median,
+        serialized_len as f64 / 1_000_000.0 / median.as_secs_f64()
+    );
+}
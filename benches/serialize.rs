@@ -0,0 +1,58 @@
+//! Benchmark of serializing a large JSON document.
+//! Run `cargo bench --bench serialize` on the revisions to compare.
+mod common;
+
+use json_parser_serializer::*;
+
+const RECORD_COUNT: usize = 150_000;
+
+/// Make a document of about 50 MB: `{ "records": [ { "id": .., "name": .., "score": .., "tags": [..], "detail": {..} }, .. ] }`
+fn make_document() -> JsonObject {
+    let mut records = Vec::with_capacity(RECORD_COUNT);
+    for idx in 0..RECORD_COUNT {
+        let mut detail = JsonObject::new();
+        detail.members.insert(
+            JsonKey("description".to_string()),
+            JsonValue::ValueString(format!("Record number {} of the benchmark document.", idx)),
+        );
+        detail
+            .members
+            .insert(JsonKey("active".to_string()), JsonValue::ValueBool(idx % 2 == 0));
+        detail.members.insert(JsonKey("parent".to_string()), JsonValue::ValueNull);
+
+        let mut record = JsonObject::new();
+        record.members.insert(
+            JsonKey("id".to_string()),
+            JsonValue::ValueNumber(JsonNumberType::Int(idx as i64)),
+        );
+        record.members.insert(
+            JsonKey("name".to_string()),
+            JsonValue::ValueString(format!("record_{:08}", idx)),
+        );
+        record.members.insert(
+            JsonKey("score".to_string()),
+            JsonValue::ValueNumber(JsonNumberType::Float(idx as f64 / 7.0)),
+        );
+        record.members.insert(
+            JsonKey("tags".to_string()),
+            JsonValue::ValueArray(vec![
+                JsonValue::ValueString("alpha".to_string()),
+                JsonValue::ValueString("beta".to_string()),
+                JsonValue::ValueNumber(JsonNumberType::Int(idx as i64 % 10)),
+            ]),
+        );
+        record
+            .members
+            .insert(JsonKey("detail".to_string()), JsonValue::ValueObject(detail.into_shared()));
+        records.push(JsonValue::ValueObject(record.into_shared()));
+    }
+    let mut json_object = JsonObject::new();
+    json_object
+        .members
+        .insert(JsonKey("records".to_string()), JsonValue::ValueArray(records));
+    json_object
+}
+
+fn main() {
+    common::serialize_bench("serialize", &make_document());
+}
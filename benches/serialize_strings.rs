@@ -0,0 +1,27 @@
+//! Benchmark of serializing a JSON document dominated by long strings.
+//! Run `cargo bench --bench serialize_strings` on the revisions to compare.
+mod common;
+
+use json_parser_serializer::*;
+
+const STRING_COUNT: usize = 10_000;
+
+/// Make a document of about 40 MB: `{ "texts": [ "..", .. ] }` with strings of 4 KB that contain a few escapes.
+fn make_document() -> JsonObject {
+    let mut texts = Vec::with_capacity(STRING_COUNT);
+    for idx in 0..STRING_COUNT {
+        let paragraph = format!("Paragraph {} of the benchmark document, written in plain ASCII text. ", idx);
+        let mut text = paragraph.repeat(4096 / paragraph.len());
+        text.push_str("\"quoted\"\n日本語のテキスト");
+        texts.push(JsonValue::ValueString(text));
+    }
+    let mut json_object = JsonObject::new();
+    json_object
+        .members
+        .insert(JsonKey("texts".to_string()), JsonValue::ValueArray(texts));
+    json_object
+}
+
+fn main() {
+    common::serialize_bench("serialize_strings", &make_document());
+}
@@ -0,0 +1,63 @@
+//! Semantic equality module.
+use super::*;
+
+pub(crate) fn value_eq(json_value: &JsonValue, json_other_value: &JsonValue, options: &JsonEqOptions) -> bool {
+    match (json_value, json_other_value) {
+        (JsonValue::ValueNumber(json_number), JsonValue::ValueNumber(json_other_number)) => {
+            number_eq(json_number, json_other_number, options)
+        }
+        (JsonValue::ValueArray(json_array), JsonValue::ValueArray(json_other_array)) => {
+            json_array.len() == json_other_array.len()
+                && json_array
+                    .iter()
+                    .zip(json_other_array)
+                    .all(|(json_element_value, json_other_element_value)| {
+                        value_eq(json_element_value, json_other_element_value, options)
+                    })
+        }
+        // 共有と所有の違いは問わない
+        _ => match (json_value.read_any_object(), json_other_value.read_any_object()) {
+            (Some(json_object), Some(json_other_object)) => object_eq(&json_object, &json_other_object, options),
+            _ => json_value == json_other_value,
+        },
+    }
+}
+
+pub(crate) fn object_eq(json_object: &JsonObject, json_other_object: &JsonObject, options: &JsonEqOptions) -> bool {
+    json_object.len() == json_other_object.len()
+        && json_object
+            .iter()
+            .all(|(json_key, json_value)| match json_other_object.get(&json_key.0) {
+                Some(json_other_value) => value_eq(json_value, json_other_value, options),
+                None => false,
+            })
+}
+
+fn number_eq(json_number: &JsonNumberType, json_other_number: &JsonNumberType, options: &JsonEqOptions) -> bool {
+    let (float_number, other_float_number) = match (json_number, json_other_number) {
+        (JsonNumberType::Float(float_number), JsonNumberType::Float(other_float_number)) => (*float_number, *other_float_number),
+        // BigDecimal 同士は文字列で比べる
+        (JsonNumberType::BigDecimal(number_string), JsonNumberType::BigDecimal(other_number_string)) => {
+            return number_string == other_number_string
+        }
+        (JsonNumberType::Float(_) | JsonNumberType::BigDecimal(_), _)
+        | (_, JsonNumberType::Float(_) | JsonNumberType::BigDecimal(_))
+            if options.numeric_coercion =>
+        {
+            (json_number.as_f64(), json_other_number.as_f64())
+        }
+        (JsonNumberType::Float(_) | JsonNumberType::BigDecimal(_), _)
+        | (_, JsonNumberType::Float(_) | JsonNumberType::BigDecimal(_)) => return false,
+        // 整数同士は常に厳密に比べる
+        (JsonNumberType::Int(int_number), JsonNumberType::Uint(uint_number))
+        | (JsonNumberType::Uint(uint_number), JsonNumberType::Int(int_number)) => {
+            return i128::from(*int_number) == i128::from(*uint_number)
+        }
+        _ => return json_number == json_other_number,
+    };
+    // 無限大同士は差が NaN になるので先に比べる
+    float_number == other_float_number
+        || options
+            .float_epsilon
+            .is_some_and(|float_epsilon| (float_number - other_float_number).abs() <= float_epsilon)
+}
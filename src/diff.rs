@@ -0,0 +1,138 @@
+//! Structural diff report module.
+use std::collections::BTreeSet;
+use std::fmt;
+use std::mem;
+
+use super::merge::deep_copy_value;
+use super::shared::ptr_eq;
+use super::{display_options, display_serialized};
+use super::{JsonDiffEntry, JsonDiffKind, JsonDiffReport, JsonObject, JsonPath, JsonValue};
+
+pub(crate) fn diff_report(json_object: &JsonObject, json_other_object: &JsonObject) -> JsonDiffReport {
+    let mut report = JsonDiffReport { entries: Vec::new() };
+    object_reporter(json_object, json_other_object, &mut JsonPath::default(), &mut report);
+    report
+}
+
+fn push_entry(
+    report: &mut JsonDiffReport,
+    kind: JsonDiffKind,
+    path: &JsonPath,
+    op_old_value: Option<&JsonValue>,
+    op_new_value: Option<&JsonValue>,
+) {
+    report.entries.push(JsonDiffEntry {
+        path: path.clone(),
+        kind,
+        old_value: op_old_value.map(deep_copy_value),
+        new_value: op_new_value.map(deep_copy_value),
+    });
+}
+
+fn object_reporter(json_object: &JsonObject, json_other_object: &JsonObject, path: &mut JsonPath, report: &mut JsonDiffReport) {
+    // 両方のキーをソート順に見て、パス順のレポートにする
+    let key_set: BTreeSet<&str> = json_object.keys().chain(json_other_object.keys()).collect();
+    for key_str in key_set {
+        path.push_key(key_str);
+        match (json_object.get(key_str), json_other_object.get(key_str)) {
+            (Some(json_value), Some(json_other_value)) => value_reporter(json_value, json_other_value, path, report),
+            (Some(json_value), None) => push_entry(report, JsonDiffKind::Removed, path, Some(json_value), None),
+            (None, op_json_other_value) => push_entry(report, JsonDiffKind::Added, path, None, op_json_other_value),
+        }
+        path.pop();
+    }
+}
+
+fn value_reporter(json_value: &JsonValue, json_other_value: &JsonValue, path: &mut JsonPath, report: &mut JsonDiffReport) {
+    match (json_value, json_other_value) {
+        (JsonValue::ValueObject(refcell_json_object), JsonValue::ValueObject(refcell_other_object))
+            if ptr_eq(refcell_json_object, refcell_other_object) => {}
+        // 共有と所有の違いは型の違いとしない
+        (
+            JsonValue::ValueObject(_) | JsonValue::ValueOwnedObject(_),
+            JsonValue::ValueObject(_) | JsonValue::ValueOwnedObject(_),
+        ) => {
+            if let (Some(json_object), Some(json_other_object)) =
+                (json_value.read_any_object(), json_other_value.read_any_object())
+            {
+                object_reporter(&json_object, &json_other_object, path, report);
+            }
+        }
+        (JsonValue::ValueArray(json_array), JsonValue::ValueArray(json_other_array)) => {
+            for idx in 0..json_array.len().max(json_other_array.len()) {
+                path.push_index(idx);
+                match (json_array.get(idx), json_other_array.get(idx)) {
+                    (Some(json_element_value), Some(json_other_element_value)) => {
+                        value_reporter(json_element_value, json_other_element_value, path, report)
+                    }
+                    (Some(json_element_value), None) => {
+                        push_entry(report, JsonDiffKind::Removed, path, Some(json_element_value), None)
+                    }
+                    (None, op_json_other_element_value) => {
+                        push_entry(report, JsonDiffKind::Added, path, None, op_json_other_element_value)
+                    }
+                }
+                path.pop();
+            }
+        }
+        _ if mem::discriminant(json_value) != mem::discriminant(json_other_value) => push_entry(
+            report,
+            JsonDiffKind::TypeChanged,
+            path,
+            Some(json_value),
+            Some(json_other_value),
+        ),
+        _ => {
+            if json_value != json_other_value {
+                push_entry(
+                    report,
+                    JsonDiffKind::ValueChanged,
+                    path,
+                    Some(json_value),
+                    Some(json_other_value),
+                );
+            }
+        }
+    }
+}
+
+/// 値をキー順のコンパクトな JSON で書く
+fn value_writer(op_json_value: Option<&JsonValue>, f: &mut fmt::Formatter) -> fmt::Result {
+    match op_json_value {
+        Some(json_value) => display_serialized(json_value.serialize_with_options(&display_options().sort_keys(true)), f),
+        None => Ok(()),
+    }
+}
+
+impl fmt::Display for JsonDiffEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: ", self.path)?;
+        match self.kind {
+            JsonDiffKind::Added => {
+                f.write_str("added ")?;
+                value_writer(self.new_value.as_ref(), f)
+            }
+            JsonDiffKind::Removed => {
+                f.write_str("removed ")?;
+                value_writer(self.old_value.as_ref(), f)
+            }
+            JsonDiffKind::TypeChanged | JsonDiffKind::ValueChanged => {
+                value_writer(self.old_value.as_ref(), f)?;
+                f.write_str(" -> ")?;
+                value_writer(self.new_value.as_ref(), f)
+            }
+        }
+    }
+}
+
+impl fmt::Display for JsonDiffReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (idx, entry) in self.entries.iter().enumerate() {
+            if idx > 0 {
+                f.write_str("\n")?;
+            }
+            write!(f, "{}", entry)?;
+        }
+        Ok(())
+    }
+}
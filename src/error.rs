@@ -1,7 +1,10 @@
+//! Error module. Functions of this crate return `Result`, whose error can be downcast to `JsonError`.
 use std::fmt;
 
+/// Result of this crate. The error is boxed so that IO errors can be returned as they are.
 pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync + 'static>>;
 
+/// Error of this crate, e.g. `err.downcast_ref::<JsonError>().map(|json_err| &json_err.err_kind)`.
 #[derive(Debug, Clone, PartialEq)]
 pub struct JsonError {
     pub err_kind: JsonErrorKind,
@@ -9,13 +12,14 @@ pub struct JsonError {
 }
 
 impl JsonError {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(
         err_kind: JsonErrorKind,
         op_additional_message: Option<String>,
     ) -> Box<dyn std::error::Error + Send + Sync + 'static> {
         Box::<JsonError>::new(JsonError {
-            err_kind: err_kind,
-            op_additional_message: op_additional_message,
+            err_kind,
+            op_additional_message,
         })
     }
 }
@@ -25,9 +29,9 @@ impl fmt::Display for JsonError {
         for err_message in JSON_ERR_MESSAGE {
             if err_message.err_kind == self.err_kind {
                 if let Some(additional_message) = &self.op_additional_message {
-                    return write!(f, "{}", format!("{} : {}", err_message.message, additional_message));
+                    return write!(f, "{} : {}", err_message.message, additional_message);
                 } else {
-                    return write!(f, "{}", format!("{}", err_message.message));
+                    return write!(f, "{}", err_message.message);
                 }
             }
         }
@@ -56,6 +60,14 @@ pub enum JsonErrorKind {
     SerializeErrorInBool,
     SerializeErrorInNull,
     SerializeErrorInArray,
+    SerializeErrorInOptions,
+    IoError,
+    KeyNotFound,
+    TypeMismatch,
+    InvalidPatch,
+    PatchTestFailed,
+    OutOfRange,
+    KeyAlreadyExists,
 }
 
 struct JsonErrorMessage {
@@ -63,7 +75,7 @@ struct JsonErrorMessage {
     message: &'static str,
 }
 
-const JSON_ERR_MESSAGE: [JsonErrorMessage; 16] = [
+const JSON_ERR_MESSAGE: [JsonErrorMessage; 24] = [
     JsonErrorMessage {
         err_kind: JsonErrorKind::ParseErrorInObject,
         message: "Parse error in object",
@@ -109,7 +121,7 @@ const JSON_ERR_MESSAGE: [JsonErrorMessage; 16] = [
         message: "Serialize error in value",
     },
     JsonErrorMessage {
-        err_kind: JsonErrorKind::ParseErrorInString,
+        err_kind: JsonErrorKind::SerializeErrorInString,
         message: "Serialize error in string",
     },
     JsonErrorMessage {
@@ -121,11 +133,43 @@ const JSON_ERR_MESSAGE: [JsonErrorMessage; 16] = [
         message: "Serialize error in bool",
     },
     JsonErrorMessage {
-        err_kind: JsonErrorKind::ParseErrorInNull,
+        err_kind: JsonErrorKind::SerializeErrorInNull,
         message: "Serialize error in null",
     },
     JsonErrorMessage {
         err_kind: JsonErrorKind::SerializeErrorInArray,
         message: "Serialize error in array",
     },
+    JsonErrorMessage {
+        err_kind: JsonErrorKind::SerializeErrorInOptions,
+        message: "Serialize error in options",
+    },
+    JsonErrorMessage {
+        err_kind: JsonErrorKind::IoError,
+        message: "IO error",
+    },
+    JsonErrorMessage {
+        err_kind: JsonErrorKind::KeyNotFound,
+        message: "Key not found",
+    },
+    JsonErrorMessage {
+        err_kind: JsonErrorKind::TypeMismatch,
+        message: "Type mismatch",
+    },
+    JsonErrorMessage {
+        err_kind: JsonErrorKind::InvalidPatch,
+        message: "Invalid patch",
+    },
+    JsonErrorMessage {
+        err_kind: JsonErrorKind::PatchTestFailed,
+        message: "Patch test failed",
+    },
+    JsonErrorMessage {
+        err_kind: JsonErrorKind::OutOfRange,
+        message: "Out of range",
+    },
+    JsonErrorMessage {
+        err_kind: JsonErrorKind::KeyAlreadyExists,
+        message: "Key already exists",
+    },
 ];
@@ -0,0 +1,245 @@
+//! Flatten module, e.g. `{"a": {"b": [1, 2]}}` to `{"a.b[0]": 1, "a.b[1]": 2}` and back.
+use std::collections::{BTreeMap, HashMap};
+
+use super::merge::deep_copy_value;
+use super::shared::unwrap_or_clone_object;
+use super::{JsonKey, JsonObject, JsonValue};
+
+use super::error::*;
+
+fn flatten_error(detail_str: &str, flat_key_str: &str) -> Box<dyn std::error::Error + Send + Sync + 'static> {
+    JsonError::new(
+        JsonErrorKind::ParseErrorInKey,
+        Some(format!("{} | key:{}", detail_str, flat_key_str)),
+    )
+}
+
+/// Escape `\`, `[`, `]` and every character of the separator in the key with `\`, wherever it appears,
+/// so that a part of the separator at the end or the start of a key cannot join the separator next to it.
+fn escape_key(key_str: &str, separator_str: &str) -> String {
+    let mut escaped_string = String::with_capacity(key_str.len());
+    for unicode_char in key_str.chars() {
+        if matches!(unicode_char, '\\' | '[' | ']') || separator_str.contains(unicode_char) {
+            escaped_string.push('\\');
+        }
+        escaped_string.push(unicode_char);
+    }
+    escaped_string
+}
+
+pub(crate) fn flatten_object(json_object: &JsonObject, separator_str: &str) -> HashMap<String, JsonValue> {
+    let mut flat_map = HashMap::new();
+    for (json_key, json_value) in json_object {
+        value_flattener(
+            json_value,
+            escape_key(&json_key.0, separator_str),
+            separator_str,
+            &mut flat_map,
+        );
+    }
+    flat_map
+}
+
+fn value_flattener(
+    json_value: &JsonValue,
+    flat_key_string: String,
+    separator_str: &str,
+    flat_map: &mut HashMap<String, JsonValue>,
+) {
+    match json_value {
+        JsonValue::ValueObject(_) | JsonValue::ValueOwnedObject(_)
+            if !json_value.read_any_object().is_some_and(|json_object| json_object.is_empty()) =>
+        {
+            for (json_key, json_child_value) in &*json_value.read_any_object().unwrap() {
+                let child_flat_key_string = format!(
+                    "{}{}{}",
+                    flat_key_string,
+                    separator_str,
+                    escape_key(&json_key.0, separator_str)
+                );
+                value_flattener(json_child_value, child_flat_key_string, separator_str, flat_map);
+            }
+        }
+        JsonValue::ValueArray(json_array) if !json_array.is_empty() => {
+            for (idx, json_element_value) in json_array.iter().enumerate() {
+                value_flattener(
+                    json_element_value,
+                    format!("{}[{}]", flat_key_string, idx),
+                    separator_str,
+                    flat_map,
+                );
+            }
+        }
+        // 空のオブジェクトと配列もそのまま葉にする
+        _ => {
+            flat_map.insert(flat_key_string, deep_copy_value(json_value));
+        }
+    }
+}
+
+enum FlattenSegment {
+    Key(String),
+    Index(usize),
+}
+
+/// Split the flattened key into keys and indexes. The first segment is always a key.
+fn parse_flat_key(flat_key_str: &str, separator_str: &str) -> Result<Vec<FlattenSegment>> {
+    let mut segments = Vec::new();
+    let mut op_key_string = Some(String::new());
+    let mut rest_str = flat_key_str;
+    while let Some(unicode_char) = rest_str.chars().next() {
+        if rest_str.starts_with(separator_str) {
+            if let Some(key_string) = op_key_string.take() {
+                segments.push(FlattenSegment::Key(key_string));
+            }
+            op_key_string = Some(String::new());
+            rest_str = &rest_str[separator_str.len()..];
+            continue;
+        }
+        rest_str = &rest_str[unicode_char.len_utf8()..];
+        match unicode_char {
+            '[' => {
+                if let Some(key_string) = op_key_string.take() {
+                    segments.push(FlattenSegment::Key(key_string));
+                }
+                let Some((index_str, after_str)) = rest_str.split_once(']') else {
+                    return Err(flatten_error("Flatten: Bracket is not closed.", flat_key_str));
+                };
+                match index_str.parse::<usize>() {
+                    Ok(idx) if index_str.bytes().all(|byte| byte.is_ascii_digit()) => segments.push(FlattenSegment::Index(idx)),
+                    _ => return Err(flatten_error("Flatten: Index is not a number.", flat_key_str)),
+                }
+                rest_str = after_str;
+            }
+            _ => {
+                // インデックスの後は区切り文字か "[" だけ
+                let Some(key_string) = op_key_string.as_mut() else {
+                    return Err(flatten_error(
+                        "Flatten: Expected the separator or '[' after the index.",
+                        flat_key_str,
+                    ));
+                };
+                match unicode_char {
+                    '\\' => {
+                        // 区切り文字の文字はどこにあっても1文字ずつエスケープされている
+                        let Some(escaped_char) = rest_str.chars().next() else {
+                            return Err(flatten_error("Flatten: '\\' is not followed by a character.", flat_key_str));
+                        };
+                        key_string.push(escaped_char);
+                        rest_str = &rest_str[escaped_char.len_utf8()..];
+                    }
+                    ']' => return Err(flatten_error("Flatten: ']' is not escaped.", flat_key_str)),
+                    _ => key_string.push(unicode_char),
+                }
+            }
+        }
+    }
+    if let Some(key_string) = op_key_string {
+        segments.push(FlattenSegment::Key(key_string));
+    }
+    Ok(segments)
+}
+
+/// Tree under construction. Arrays are kept as maps until all the indexes are known.
+enum FlattenNode {
+    Leaf(JsonValue),
+    Object(HashMap<String, FlattenNode>),
+    Array(BTreeMap<usize, FlattenNode>),
+}
+
+impl FlattenNode {
+    fn new(segment: &FlattenSegment) -> FlattenNode {
+        match segment {
+            FlattenSegment::Key(_) => FlattenNode::Object(HashMap::new()),
+            FlattenSegment::Index(_) => FlattenNode::Array(BTreeMap::new()),
+        }
+    }
+}
+
+pub(crate) fn unflatten_object(flat_map: &HashMap<String, JsonValue>, separator_str: &str) -> Result<JsonObject> {
+    if separator_str.is_empty() {
+        return Err(flatten_error("Flatten: Separator is empty.", ""));
+    }
+    // エラーになるキーが毎回同じになるように並べる
+    let mut flat_keys: Vec<&String> = flat_map.keys().collect();
+    flat_keys.sort_unstable();
+    let mut root_node = FlattenNode::Object(HashMap::new());
+    for flat_key_string in flat_keys {
+        let segments = parse_flat_key(flat_key_string, separator_str)?;
+        node_inserter(&mut root_node, &segments, &flat_map[flat_key_string], flat_key_string)?;
+    }
+    match node_converter(root_node, "", separator_str)? {
+        JsonValue::ValueObject(refcell_json_object) => Ok(unwrap_or_clone_object(refcell_json_object)),
+        _ => unreachable!(),
+    }
+}
+
+/// `segments` must not be empty.
+fn node_inserter(node: &mut FlattenNode, segments: &[FlattenSegment], json_value: &JsonValue, flat_key_str: &str) -> Result<()> {
+    let (segment, rest_segments) = segments.split_first().unwrap();
+    let child_node = match (node, segment) {
+        (FlattenNode::Object(child_nodes), FlattenSegment::Key(key_string)) => {
+            if rest_segments.is_empty() {
+                if child_nodes.contains_key(key_string) {
+                    return Err(flatten_error("Flatten: Key is both a leaf and a prefix.", flat_key_str));
+                }
+                child_nodes.insert(key_string.clone(), FlattenNode::Leaf(deep_copy_value(json_value)));
+                return Ok(());
+            }
+            child_nodes
+                .entry(key_string.clone())
+                .or_insert_with(|| FlattenNode::new(&rest_segments[0]))
+        }
+        (FlattenNode::Array(child_nodes), FlattenSegment::Index(idx)) => {
+            if rest_segments.is_empty() {
+                if child_nodes.contains_key(idx) {
+                    return Err(flatten_error("Flatten: Key is both a leaf and a prefix.", flat_key_str));
+                }
+                child_nodes.insert(*idx, FlattenNode::Leaf(deep_copy_value(json_value)));
+                return Ok(());
+            }
+            child_nodes.entry(*idx).or_insert_with(|| FlattenNode::new(&rest_segments[0]))
+        }
+        (FlattenNode::Leaf(_), _) => return Err(flatten_error("Flatten: Key is both a leaf and a prefix.", flat_key_str)),
+        _ => return Err(flatten_error("Flatten: Keys and indexes are mixed.", flat_key_str)),
+    };
+    node_inserter(child_node, rest_segments, json_value, flat_key_str)
+}
+
+/// `flat_key_str` is the flattened key of the node, used in errors.
+fn node_converter(node: FlattenNode, flat_key_str: &str, separator_str: &str) -> Result<JsonValue> {
+    match node {
+        FlattenNode::Leaf(json_value) => Ok(json_value),
+        FlattenNode::Object(child_nodes) => {
+            let mut json_object = JsonObject::new();
+            for (key_string, child_node) in child_nodes {
+                let escaped_key_string = escape_key(&key_string, separator_str);
+                let child_flat_key_string = if flat_key_str.is_empty() {
+                    escaped_key_string
+                } else {
+                    format!("{}{}{}", flat_key_str, separator_str, escaped_key_string)
+                };
+                let json_value = node_converter(child_node, &child_flat_key_string, separator_str)?;
+                json_object.members.insert(JsonKey(key_string), json_value);
+            }
+            Ok(JsonValue::from(json_object))
+        }
+        FlattenNode::Array(child_nodes) => {
+            let mut json_array = Vec::with_capacity(child_nodes.len());
+            for (idx, child_node) in child_nodes {
+                if idx != json_array.len() {
+                    return Err(flatten_error(
+                        &format!("Flatten: Array does not have the index {}.", json_array.len()),
+                        flat_key_str,
+                    ));
+                }
+                json_array.push(node_converter(
+                    child_node,
+                    &format!("{}[{}]", flat_key_str, idx),
+                    separator_str,
+                )?);
+            }
+            Ok(JsonValue::ValueArray(json_array))
+        }
+    }
+}
@@ -0,0 +1,473 @@
+//! JSON text formatter module. JSON text is reformatted token by token without building `JsonObject`,
+//! so the order of keys and the text of numbers are kept as they are.
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+use super::error::*;
+use super::parser::{parse_error, CharPosition};
+use super::serializer::{make_colon_str, make_indent_string, make_newline_str, make_trailing_newline_str, validate_options};
+use super::{JsonSerializerArrayStyle, JsonSerializerOptions};
+
+/// Token of JSON text. Strings and literals(numbers, `true`, `false` and `null`) keep the text of the input.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum JsonToken<'a> {
+    BeginObject,
+    EndObject,
+    BeginArray,
+    EndArray,
+    Comma,
+    Colon,
+    String(&'a str),
+    Literal(&'a str),
+}
+
+/// Token that the grammar of JSON allows next.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum JsonTokenExpect {
+    Value,
+    // '[' の直後は値または ']'
+    ValueOrEnd,
+    Key,
+    // '{' の直後はキーまたは '}'
+    KeyOrEnd,
+    Colon,
+    CommaOrEnd,
+    EndOfText,
+}
+
+/// Scanner that splits JSON text into tokens.
+/// It checks the grammar of JSON(the order of keys, colons, values and commas, the literals and the escapes in strings),
+/// so the tokens always make one valid JSON value.
+struct JsonTokenizer<'a> {
+    content_str: &'a str,
+    content_chars: Peekable<CharIndices<'a>>,
+    char_position: CharPosition,
+    // 開いている括弧('{' または '[')
+    open_chars: Vec<char>,
+    expect: JsonTokenExpect,
+}
+
+impl<'a> JsonTokenizer<'a> {
+    fn new(content_str: &'a str) -> JsonTokenizer<'a> {
+        JsonTokenizer {
+            content_str,
+            content_chars: content_str.char_indices().peekable(),
+            char_position: CharPosition::new(),
+            open_chars: Vec::new(),
+            expect: JsonTokenExpect::Value,
+        }
+    }
+
+    fn next_char(&mut self) -> Option<(usize, char)> {
+        let (byte_idx, unicode_char) = self.content_chars.next()?;
+        self.char_position.increment(&unicode_char);
+        Some((byte_idx, unicode_char))
+    }
+
+    /// Returns the next token, or `None` at the end of the text.
+    fn next_token(&mut self) -> Result<Option<JsonToken<'a>>> {
+        let token = self.scan_token()?;
+        self.grammar_checker(token)?;
+        Ok(token)
+    }
+
+    /// Check that the token is allowed by the grammar after the previous tokens.
+    fn grammar_checker(&mut self, token: Option<JsonToken>) -> Result<()> {
+        // 値の後は、括弧の中ならカンマか閉じ括弧、トップレベルならテキストの終わり
+        let expect_after_value = if self.open_chars.is_empty() {
+            JsonTokenExpect::EndOfText
+        } else {
+            JsonTokenExpect::CommaOrEnd
+        };
+        self.expect = match (self.expect, token) {
+            (JsonTokenExpect::Value | JsonTokenExpect::ValueOrEnd, Some(JsonToken::BeginObject)) => JsonTokenExpect::KeyOrEnd,
+            (JsonTokenExpect::Value | JsonTokenExpect::ValueOrEnd, Some(JsonToken::BeginArray)) => JsonTokenExpect::ValueOrEnd,
+            (JsonTokenExpect::Value | JsonTokenExpect::ValueOrEnd, Some(JsonToken::String(_))) => expect_after_value,
+            (JsonTokenExpect::Value | JsonTokenExpect::ValueOrEnd, Some(JsonToken::Literal(literal_str))) => {
+                if !matches!(literal_str, "true" | "false" | "null") && !is_json_number(literal_str) {
+                    return Err(parse_error(
+                        JsonErrorKind::ParseErrorInNumber,
+                        "Format: Literal is neither a number nor true, false or null.",
+                        &self.char_position,
+                    ));
+                }
+                expect_after_value
+            }
+            // 閉じ括弧の種類はスキャンで確認済み
+            (
+                JsonTokenExpect::ValueOrEnd | JsonTokenExpect::KeyOrEnd | JsonTokenExpect::CommaOrEnd,
+                Some(JsonToken::EndObject | JsonToken::EndArray),
+            ) => expect_after_value,
+            (JsonTokenExpect::Key | JsonTokenExpect::KeyOrEnd, Some(JsonToken::String(_))) => JsonTokenExpect::Colon,
+            (JsonTokenExpect::Colon, Some(JsonToken::Colon)) => JsonTokenExpect::Value,
+            (JsonTokenExpect::CommaOrEnd, Some(JsonToken::Comma)) => {
+                if self.open_chars.last() == Some(&'{') {
+                    JsonTokenExpect::Key
+                } else {
+                    JsonTokenExpect::Value
+                }
+            }
+            (JsonTokenExpect::EndOfText, None) => JsonTokenExpect::EndOfText,
+            (expect, _) => {
+                let (err_kind, detail_str) = match expect {
+                    JsonTokenExpect::Value | JsonTokenExpect::ValueOrEnd if token.is_none() => {
+                        (JsonErrorKind::ParseErrorInValue, "Format: Text has no value.")
+                    }
+                    JsonTokenExpect::Value | JsonTokenExpect::ValueOrEnd => (
+                        JsonErrorKind::ParseErrorInValue,
+                        "Format: Expected a value but found an another token.",
+                    ),
+                    JsonTokenExpect::Key | JsonTokenExpect::KeyOrEnd => (
+                        JsonErrorKind::ParseErrorInKey,
+                        "Format: Expected a key but found an another token.",
+                    ),
+                    JsonTokenExpect::Colon => (
+                        JsonErrorKind::ParseErrorInObject,
+                        "Format: Expected ':' but found an another token.",
+                    ),
+                    JsonTokenExpect::CommaOrEnd if self.open_chars.last() == Some(&'{') => (
+                        JsonErrorKind::ParseErrorInObject,
+                        "Format: Expected ',' or '}' but found an another token.",
+                    ),
+                    JsonTokenExpect::CommaOrEnd => (
+                        JsonErrorKind::ParseErrorInArray,
+                        "Format: Expected ',' or ']' but found an another token.",
+                    ),
+                    JsonTokenExpect::EndOfText => (JsonErrorKind::ParseErrorInValue, "Format: Text has more than one value."),
+                };
+                return Err(parse_error(err_kind, detail_str, &self.char_position));
+            }
+        };
+        Ok(())
+    }
+
+    fn scan_token(&mut self) -> Result<Option<JsonToken<'a>>> {
+        while let Some(&(byte_idx, unicode_char)) = self.content_chars.peek() {
+            match unicode_char {
+                ' ' | '\t' | '\n' | '\r' => {
+                    self.next_char();
+                }
+                '{' | '[' => {
+                    self.next_char();
+                    self.open_chars.push(unicode_char);
+                    return Ok(Some(if unicode_char == '{' {
+                        JsonToken::BeginObject
+                    } else {
+                        JsonToken::BeginArray
+                    }));
+                }
+                '}' | ']' => {
+                    let open_char = if unicode_char == '}' { '{' } else { '[' };
+                    if self.open_chars.pop() != Some(open_char) {
+                        return Err(parse_error(
+                            JsonErrorKind::ParseErrorInValue,
+                            "Format: Closing bracket does not match the opening one.",
+                            &self.char_position,
+                        ));
+                    }
+                    self.next_char();
+                    return Ok(Some(if unicode_char == '}' {
+                        JsonToken::EndObject
+                    } else {
+                        JsonToken::EndArray
+                    }));
+                }
+                ',' => {
+                    self.next_char();
+                    return Ok(Some(JsonToken::Comma));
+                }
+                ':' => {
+                    self.next_char();
+                    return Ok(Some(JsonToken::Colon));
+                }
+                '\"' => return self.string_tokenizer(byte_idx).map(Some),
+                '-' | '+' | '.' | ('0'..='9') | ('a'..='z') | ('A'..='Z') => {
+                    let mut end_byte_idx = byte_idx;
+                    while let Some(&(literal_byte_idx, literal_char)) = self.content_chars.peek() {
+                        if !matches!(literal_char, '-' | '+' | '.' | ('0'..='9') | ('a'..='z') | ('A'..='Z')) {
+                            break;
+                        }
+                        end_byte_idx = literal_byte_idx + literal_char.len_utf8();
+                        self.next_char();
+                    }
+                    return Ok(Some(JsonToken::Literal(&self.content_str[byte_idx..end_byte_idx])));
+                }
+                _ => {
+                    return Err(parse_error(
+                        JsonErrorKind::ParseErrorInValue,
+                        "Format: Expected any charcter that start token but found an another character.",
+                        &self.char_position,
+                    ));
+                }
+            }
+        }
+        if !self.open_chars.is_empty() {
+            return Err(parse_error(
+                JsonErrorKind::ParseErrorInObject,
+                "Format: Object is not closed.",
+                &self.char_position,
+            ));
+        }
+        Ok(None)
+    }
+
+    fn string_tokenizer(&mut self, start_byte_idx: usize) -> Result<JsonToken<'a>> {
+        // 開始の '"'
+        self.next_char();
+        while let Some((byte_idx, unicode_char)) = self.next_char() {
+            match unicode_char {
+                '\"' => return Ok(JsonToken::String(&self.content_str[start_byte_idx..byte_idx + 1])),
+                // エスケープされた文字は '"' であっても文字列の終わりではない
+                '\\' => self.escape_tokenizer()?,
+                '\u{0000}'..='\u{001F}' => {
+                    return Err(parse_error(
+                        JsonErrorKind::ParseErrorInString,
+                        "Format: Control character in string is not escaped.",
+                        &self.char_position,
+                    ));
+                }
+                _ => {}
+            }
+        }
+        Err(parse_error(
+            JsonErrorKind::ParseErrorInString,
+            "Format: String is not closed.",
+            &self.char_position,
+        ))
+    }
+
+    /// Check the escape sequence after '\\'. A high surrogate of `\uXXXX` must be followed by a low surrogate.
+    fn escape_tokenizer(&mut self) -> Result<()> {
+        match self.next_char() {
+            Some((_, '\"' | '\\' | '/' | 'b' | 'f' | 'n' | 'r' | 't')) => Ok(()),
+            Some((_, 'u')) => match self.utf16_tokenizer()? {
+                0xD800..=0xDBFF => {
+                    if self.next_char().map(|(_, unicode_char)| unicode_char) != Some('\\')
+                        || self.next_char().map(|(_, unicode_char)| unicode_char) != Some('u')
+                        || !matches!(self.utf16_tokenizer()?, 0xDC00..=0xDFFF)
+                    {
+                        return Err(parse_error(
+                            JsonErrorKind::ParseErrorInString,
+                            "Format: High surrogate is not followed by a low surrogate.",
+                            &self.char_position,
+                        ));
+                    }
+                    Ok(())
+                }
+                0xDC00..=0xDFFF => Err(parse_error(
+                    JsonErrorKind::ParseErrorInString,
+                    "Format: Low surrogate is not preceded by a high surrogate.",
+                    &self.char_position,
+                )),
+                _ => Ok(()),
+            },
+            _ => Err(parse_error(
+                JsonErrorKind::ParseErrorInString,
+                "Format: Expected any escaped character but found an another character.",
+                &self.char_position,
+            )),
+        }
+    }
+
+    /// Read the 4 hexadecimal digits of `\uXXXX`.
+    fn utf16_tokenizer(&mut self) -> Result<u16> {
+        let mut utf16_code: u16 = 0;
+        for _ in 0..4 {
+            match self.next_char().and_then(|(_, unicode_char)| unicode_char.to_digit(16)) {
+                Some(hex_digit) => utf16_code = utf16_code * 16 + hex_digit as u16,
+                None => {
+                    return Err(parse_error(
+                        JsonErrorKind::ParseErrorInString,
+                        "Format: Expected any Hexadecimal character but found an another character.",
+                        &self.char_position,
+                    ));
+                }
+            }
+        }
+        Ok(utf16_code)
+    }
+}
+
+/// Whether the literal is a number of the JSON grammar, i.e. `-?(0|[1-9][0-9]*)(.[0-9]+)?([eE][+-]?[0-9]+)?`.
+fn is_json_number(literal_str: &str) -> bool {
+    let mut literal_bytes = literal_str.as_bytes();
+    // 連続する数字を読み、その数を返す
+    let digits = |literal_bytes: &mut &[u8]| {
+        let digit_count = literal_bytes.iter().take_while(|byte| byte.is_ascii_digit()).count();
+        *literal_bytes = &literal_bytes[digit_count..];
+        digit_count
+    };
+    if let [b'-', rest @ ..] = literal_bytes {
+        literal_bytes = rest;
+    }
+    match literal_bytes {
+        [b'0', rest @ ..] => literal_bytes = rest,
+        [b'1'..=b'9', ..] => {
+            digits(&mut literal_bytes);
+        }
+        _ => return false,
+    }
+    if let [b'.', rest @ ..] = literal_bytes {
+        literal_bytes = rest;
+        if digits(&mut literal_bytes) == 0 {
+            return false;
+        }
+    }
+    if let [b'e' | b'E', rest @ ..] = literal_bytes {
+        literal_bytes = rest;
+        if let [b'+' | b'-', rest @ ..] = literal_bytes {
+            literal_bytes = rest;
+        }
+        if digits(&mut literal_bytes) == 0 {
+            return false;
+        }
+    }
+    literal_bytes.is_empty()
+}
+
+/// Check that JSON text is one value following the grammar of JSON.
+pub(crate) fn validate_raw_value(content_str: &str) -> Result<()> {
+    let mut tokenizer = JsonTokenizer::new(content_str);
+    while tokenizer.next_token()?.is_some() {}
+    Ok(())
+}
+
+/// Remove insignificant whitespace from JSON text. Strings, keys and numbers are copied as they are.
+pub(crate) fn minify(content_str: &str) -> Result<String> {
+    let mut tokenizer = JsonTokenizer::new(content_str);
+    let mut content_string = String::with_capacity(content_str.len());
+    while let Some(token) = tokenizer.next_token()? {
+        match token {
+            JsonToken::BeginObject => content_string.push('{'),
+            JsonToken::EndObject => content_string.push('}'),
+            JsonToken::BeginArray => content_string.push('['),
+            JsonToken::EndArray => content_string.push(']'),
+            JsonToken::Comma => content_string.push(','),
+            JsonToken::Colon => content_string.push(':'),
+            JsonToken::String(token_str) | JsonToken::Literal(token_str) => content_string.push_str(token_str),
+        }
+    }
+    Ok(content_string)
+}
+
+/// Reformat JSON text with the serializer options(newline, indent, colon style, compact and array style).
+/// The order of keys and the text of strings and numbers are kept as they are.
+/// Arrays are laid out like the serializer, i.e. one element per line if they contain a non-empty object.
+pub(crate) fn prettify(content_str: &str, options: &JsonSerializerOptions) -> Result<String> {
+    validate_options(options)?;
+    let mut tokenizer = JsonTokenizer::new(content_str);
+    let mut tokens: Vec<JsonToken> = Vec::new();
+    while let Some(token) = tokenizer.next_token()? {
+        tokens.push(token);
+    }
+    let multiline_arrays = find_multiline_arrays(&tokens, options);
+
+    let newline_str = make_newline_str(options);
+    let indent_string = make_indent_string(options);
+    let colon_str = make_colon_str(options);
+    let push_newline_and_indent = |content_string: &mut String, indent_level: usize| {
+        content_string.push_str(newline_str);
+        for _ in 0..indent_level {
+            content_string.push_str(&indent_string);
+        }
+    };
+
+    let mut content_string = String::with_capacity(content_str.len() * 2);
+    let mut indent_level: usize = 0;
+    // 開いているオブジェクト・配列が複数行かどうか
+    let mut open_multilines: Vec<bool> = Vec::new();
+    let mut token_idx = 0;
+    while token_idx < tokens.len() {
+        let next_token = tokens.get(token_idx + 1).copied();
+        match tokens[token_idx] {
+            JsonToken::BeginObject | JsonToken::BeginArray
+                if next_token == Some(JsonToken::EndObject) || next_token == Some(JsonToken::EndArray) =>
+            {
+                content_string.push_str(if next_token == Some(JsonToken::EndObject) {
+                    "{}"
+                } else {
+                    "[]"
+                });
+                token_idx += 1;
+            }
+            JsonToken::BeginObject => {
+                content_string.push('{');
+                indent_level += 1;
+                push_newline_and_indent(&mut content_string, indent_level);
+                open_multilines.push(true);
+            }
+            JsonToken::BeginArray => {
+                content_string.push('[');
+                let is_multiline = multiline_arrays[token_idx];
+                if is_multiline {
+                    indent_level += 1;
+                    push_newline_and_indent(&mut content_string, indent_level);
+                } else if !options.compact {
+                    content_string.push(' ');
+                }
+                open_multilines.push(is_multiline);
+            }
+            JsonToken::EndObject | JsonToken::EndArray => {
+                if open_multilines.pop() == Some(true) {
+                    indent_level -= 1;
+                    push_newline_and_indent(&mut content_string, indent_level);
+                }
+                content_string.push(if tokens[token_idx] == JsonToken::EndObject { '}' } else { ']' });
+            }
+            JsonToken::Comma => {
+                content_string.push(',');
+                if open_multilines.last() == Some(&true) {
+                    push_newline_and_indent(&mut content_string, indent_level);
+                } else if !options.compact {
+                    content_string.push(' ');
+                }
+            }
+            JsonToken::Colon => content_string.push_str(colon_str),
+            JsonToken::String(token_str) | JsonToken::Literal(token_str) => content_string.push_str(token_str),
+        }
+        token_idx += 1;
+    }
+    if options.trailing_newline {
+        // 最後に改行する
+        content_string.push_str(make_trailing_newline_str(options));
+    }
+    Ok(content_string)
+}
+
+/// Whether each `BeginArray` token starts a multi-line array, indexed by the token index.
+fn find_multiline_arrays(tokens: &[JsonToken], options: &JsonSerializerOptions) -> Vec<bool> {
+    let is_expanded = !options.compact && options.array_style == JsonSerializerArrayStyle::Expanded;
+    let mut multiline_arrays = vec![is_expanded; tokens.len()];
+    if options.compact || is_expanded {
+        return multiline_arrays;
+    }
+    // 開いている配列のトークン位置。オブジェクトは None
+    let mut open_array_idxs: Vec<Option<usize>> = Vec::new();
+    for (token_idx, token) in tokens.iter().enumerate() {
+        match token {
+            JsonToken::BeginObject => {
+                if let Some(Some(array_idx)) = open_array_idxs.last() {
+                    if tokens.get(token_idx + 1) != Some(&JsonToken::EndObject) {
+                        multiline_arrays[*array_idx] = true;
+                    }
+                }
+                open_array_idxs.push(None);
+            }
+            JsonToken::BeginArray => open_array_idxs.push(Some(token_idx)),
+            JsonToken::EndObject => {
+                open_array_idxs.pop();
+            }
+            JsonToken::EndArray => {
+                if let Some(Some(array_idx)) = open_array_idxs.pop() {
+                    if let Some(Some(parent_array_idx)) = open_array_idxs.last() {
+                        if multiline_arrays[array_idx] {
+                            multiline_arrays[*parent_array_idx] = true;
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    multiline_arrays
+}
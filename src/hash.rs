@@ -0,0 +1,160 @@
+//! Canonical hash module.
+use std::hash::Hasher;
+
+use super::shared::{object_ptr, JsonObjectPtr, JsonSharedObject};
+use super::JsonObjectLock;
+use super::{JsonNumberType, JsonObject, JsonValue};
+
+/// FNV-1a (64 bit), whose result does not depend on the process or the Rust version unlike `DefaultHasher`.
+pub(crate) struct JsonFnvHasher(u64);
+
+impl JsonFnvHasher {
+    pub(crate) fn new() -> JsonFnvHasher {
+        JsonFnvHasher(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for JsonFnvHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+// 型ごとのタグ。数値の種類は正規化した表現で分ける
+const TAG_STRING: u8 = 0;
+const TAG_INTEGER: u8 = 1;
+const TAG_FLOAT: u8 = 2;
+const TAG_BIG_DECIMAL: u8 = 3;
+const TAG_BOOL: u8 = 4;
+const TAG_NULL: u8 = 5;
+const TAG_ARRAY: u8 = 6;
+const TAG_OBJECT: u8 = 7;
+const TAG_RAW: u8 = 8;
+const TAG_CYCLE: u8 = 9;
+
+/// Bits of the float that `Hash` and `==` of `JsonValue` use. All NaNs have the same bits.
+pub(crate) fn float_bits(float_number: f64) -> u64 {
+    // NaN のビット列はまとめる
+    if float_number.is_nan() {
+        f64::NAN.to_bits()
+    } else {
+        float_number.to_bits()
+    }
+}
+
+/// `op_root_object` is the shared object that the root is borrowed from, so that a reference cycle to the root is detected there.
+pub(crate) fn object_hasher<H: Hasher>(json_object: &JsonObject, op_root_object: Option<&JsonSharedObject>, state: &mut H) {
+    CanonicalHasher {
+        state,
+        ancestor_ptrs: op_root_object.map(object_ptr).into_iter().collect(),
+    }
+    .object_hasher(json_object);
+}
+
+pub(crate) fn value_hasher<H: Hasher>(json_value: &JsonValue, state: &mut H) {
+    CanonicalHasher {
+        state,
+        ancestor_ptrs: Vec::new(),
+    }
+    .value_hasher(json_value);
+}
+
+struct CanonicalHasher<'a, H> {
+    state: &'a mut H,
+    /// 循環を検出するため、たどっている途中のオブジェクト
+    ancestor_ptrs: Vec<JsonObjectPtr>,
+}
+
+impl<H: Hasher> CanonicalHasher<'_, H> {
+    /// 長さを前に付けて、連結しても区別できるようにする
+    fn str_hasher(&mut self, string_str: &str) {
+        self.state.write_u64(string_str.len() as u64);
+        self.state.write(string_str.as_bytes());
+    }
+
+    fn number_hasher(&mut self, json_number: &JsonNumberType) {
+        match json_number {
+            JsonNumberType::Int(int_number) => {
+                self.state.write_u8(TAG_INTEGER);
+                self.state.write_i128(i128::from(*int_number));
+            }
+            JsonNumberType::Uint(uint_number) => {
+                self.state.write_u8(TAG_INTEGER);
+                self.state.write_i128(i128::from(*uint_number));
+            }
+            JsonNumberType::Float(float_number) => {
+                if let Ok(int_number) = json_number.try_into_i64() {
+                    self.state.write_u8(TAG_INTEGER);
+                    self.state.write_i128(i128::from(int_number));
+                } else if let Ok(uint_number) = json_number.try_into_u64() {
+                    self.state.write_u8(TAG_INTEGER);
+                    self.state.write_i128(i128::from(uint_number));
+                } else {
+                    self.state.write_u8(TAG_FLOAT);
+                    self.state.write_u64(float_bits(*float_number));
+                }
+            }
+            JsonNumberType::BigDecimal(number_string) => {
+                self.state.write_u8(TAG_BIG_DECIMAL);
+                self.str_hasher(number_string);
+            }
+        }
+    }
+
+    fn value_hasher(&mut self, json_value: &JsonValue) {
+        match json_value {
+            JsonValue::ValueString(json_string) => {
+                self.state.write_u8(TAG_STRING);
+                self.str_hasher(json_string);
+            }
+            JsonValue::ValueNumber(json_number) => self.number_hasher(json_number),
+            JsonValue::ValueBool(json_bool) => {
+                self.state.write_u8(TAG_BOOL);
+                self.state.write_u8(u8::from(*json_bool));
+            }
+            JsonValue::ValueNull => self.state.write_u8(TAG_NULL),
+            JsonValue::ValueArray(json_array) => {
+                self.state.write_u8(TAG_ARRAY);
+                self.state.write_u64(json_array.len() as u64);
+                for json_element_value in json_array {
+                    self.value_hasher(json_element_value);
+                }
+            }
+            JsonValue::ValueObject(refcell_json_object) => {
+                let json_object_ptr = object_ptr(refcell_json_object);
+                // 循環参照は何階層上のオブジェクトを指すかで表す
+                if let Some(ancestor_idx) = self.ancestor_ptrs.iter().position(|ptr| *ptr == json_object_ptr) {
+                    self.state.write_u8(TAG_CYCLE);
+                    self.state.write_u64((self.ancestor_ptrs.len() - ancestor_idx) as u64);
+                    return;
+                }
+                let json_object = refcell_json_object.read_object();
+                self.ancestor_ptrs.push(json_object_ptr);
+                self.object_hasher(&json_object);
+                self.ancestor_ptrs.pop();
+            }
+            // 所有オブジェクトは共有オブジェクトと同じに扱う
+            JsonValue::ValueOwnedObject(json_object) => self.object_hasher(json_object),
+            JsonValue::ValueRaw(json_raw_value) => {
+                self.state.write_u8(TAG_RAW);
+                self.str_hasher(json_raw_value.as_str());
+            }
+        }
+    }
+
+    fn object_hasher(&mut self, json_object: &JsonObject) {
+        self.state.write_u8(TAG_OBJECT);
+        self.state.write_u64(json_object.len() as u64);
+        for key_str in json_object.keys_sorted() {
+            self.str_hasher(key_str);
+            self.value_hasher(&json_object[key_str]);
+        }
+    }
+}
@@ -0,0 +1,380 @@
+//! JSONPath module. A practical subset: `$`, `.name`, `['name']`, `[0]`, `[*]`, `..` and `[?(@.key == value)]`.
+use super::shared::{object_ptr, JsonObjectPtr};
+use super::{JsonEqOptions, JsonNumberType, JsonObject, JsonValue};
+
+use super::error::*;
+
+enum QuerySelector {
+    Name(String),
+    /// Negative indexes count from the end.
+    Index(i64),
+    Wildcard,
+    Filter(QueryFilter),
+}
+
+struct QuerySegment {
+    /// `..` selects from the node and all its descendants.
+    descendant: bool,
+    selector: QuerySelector,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum QueryOperator {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// `[?(@.a.b)]` tests that the member exists, `[?(@.a.b == 1)]` compares it.
+struct QueryFilter {
+    keys: Vec<String>,
+    op_comparison: Option<(QueryOperator, JsonValue)>,
+}
+
+struct QueryParser<'a> {
+    query_chars: Vec<char>,
+    char_idx: usize,
+    query_str: &'a str,
+}
+
+impl<'a> QueryParser<'a> {
+    fn new(query_str: &'a str) -> QueryParser<'a> {
+        QueryParser {
+            query_chars: query_str.chars().collect(),
+            char_idx: 0,
+            query_str,
+        }
+    }
+
+    fn error(&self, detail_str: &str, char_idx: usize) -> Box<dyn std::error::Error + Send + Sync + 'static> {
+        // 文字数は1始まり
+        JsonError::new(
+            JsonErrorKind::ParseErrorInKey,
+            Some(format!("{} | column:{} | query:{}", detail_str, char_idx + 1, self.query_str)),
+        )
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.query_chars.get(self.char_idx).copied()
+    }
+
+    fn space_skipper(&mut self) {
+        while self.peek().is_some_and(|unicode_char| unicode_char == ' ') {
+            self.char_idx += 1;
+        }
+    }
+
+    fn expect(&mut self, expected_char: char, detail_str: &str) -> Result<()> {
+        if self.peek() != Some(expected_char) {
+            return Err(self.error(detail_str, self.char_idx));
+        }
+        self.char_idx += 1;
+        Ok(())
+    }
+
+    fn parse(mut self) -> Result<Vec<QuerySegment>> {
+        self.expect('$', "JSONPath: Query does not start with '$'.")?;
+        let mut segments = Vec::new();
+        while let Some(unicode_char) = self.peek() {
+            match unicode_char {
+                '.' => {
+                    self.char_idx += 1;
+                    let descendant = self.peek() == Some('.');
+                    if descendant {
+                        self.char_idx += 1;
+                    }
+                    let selector = match self.peek() {
+                        Some('[') if descendant => self.bracket_parser()?,
+                        Some('*') => {
+                            self.char_idx += 1;
+                            QuerySelector::Wildcard
+                        }
+                        _ => QuerySelector::Name(self.name_parser()?),
+                    };
+                    segments.push(QuerySegment { descendant, selector });
+                }
+                '[' => {
+                    let selector = self.bracket_parser()?;
+                    segments.push(QuerySegment {
+                        descendant: false,
+                        selector,
+                    });
+                }
+                _ => return Err(self.error("JSONPath: Expected '.' or '['.", self.char_idx)),
+            }
+        }
+        Ok(segments)
+    }
+
+    fn name_parser(&mut self) -> Result<String> {
+        let start_char_idx = self.char_idx;
+        while self
+            .peek()
+            .is_some_and(|unicode_char| unicode_char.is_alphanumeric() || unicode_char == '_' || unicode_char == '-')
+        {
+            self.char_idx += 1;
+        }
+        if self.char_idx == start_char_idx {
+            return Err(self.error("JSONPath: Name is empty.", start_char_idx));
+        }
+        Ok(self.query_chars[start_char_idx..self.char_idx].iter().collect())
+    }
+
+    fn bracket_parser(&mut self) -> Result<QuerySelector> {
+        let open_char_idx = self.char_idx;
+        self.char_idx += 1;
+        self.space_skipper();
+        let selector = match self.peek() {
+            Some('\'' | '\"') => QuerySelector::Name(self.quoted_parser()?),
+            Some('*') => {
+                self.char_idx += 1;
+                QuerySelector::Wildcard
+            }
+            Some('?') => QuerySelector::Filter(self.filter_parser()?),
+            Some(unicode_char) if unicode_char == '-' || unicode_char.is_ascii_digit() => {
+                let start_char_idx = self.char_idx;
+                self.char_idx += 1;
+                while self.peek().is_some_and(|unicode_char| unicode_char.is_ascii_digit()) {
+                    self.char_idx += 1;
+                }
+                let index_string: String = self.query_chars[start_char_idx..self.char_idx].iter().collect();
+                match index_string.parse::<i64>() {
+                    Ok(idx) => QuerySelector::Index(idx),
+                    Err(_) => return Err(self.error("JSONPath: Index is not a number.", start_char_idx)),
+                }
+            }
+            Some(_) => return Err(self.error("JSONPath: Expected a name, an index, '*' or a filter.", self.char_idx)),
+            None => return Err(self.error("JSONPath: Bracket is not closed.", open_char_idx)),
+        };
+        self.space_skipper();
+        match self.peek() {
+            Some(']') => {
+                self.char_idx += 1;
+                Ok(selector)
+            }
+            Some(_) => Err(self.error("JSONPath: Expected ']'.", self.char_idx)),
+            None => Err(self.error("JSONPath: Bracket is not closed.", open_char_idx)),
+        }
+    }
+
+    /// `'name'` or `"name"`. `\` escapes the next character.
+    fn quoted_parser(&mut self) -> Result<String> {
+        let quote_char_idx = self.char_idx;
+        let quote_char = self.query_chars[quote_char_idx];
+        self.char_idx += 1;
+        let mut quoted_string = String::new();
+        loop {
+            match self.peek() {
+                Some(unicode_char) if unicode_char == quote_char => break,
+                Some('\\') => {
+                    self.char_idx += 1;
+                    match self.peek() {
+                        Some(unicode_char) => quoted_string.push(unicode_char),
+                        None => return Err(self.error("JSONPath: Quote is not closed.", quote_char_idx)),
+                    }
+                }
+                Some(unicode_char) => quoted_string.push(unicode_char),
+                None => return Err(self.error("JSONPath: Quote is not closed.", quote_char_idx)),
+            }
+            self.char_idx += 1;
+        }
+        self.char_idx += 1;
+        Ok(quoted_string)
+    }
+
+    fn filter_parser(&mut self) -> Result<QueryFilter> {
+        self.char_idx += 1;
+        self.expect('(', "JSONPath: Expected '(' after '?'.")?;
+        self.space_skipper();
+        self.expect('@', "JSONPath: Filter does not start with '@'.")?;
+        let mut keys = Vec::new();
+        while self.peek() == Some('.') {
+            self.char_idx += 1;
+            keys.push(self.name_parser()?);
+        }
+        self.space_skipper();
+        let mut op_comparison = None;
+        if self.peek() != Some(')') {
+            let operator = self.operator_parser()?;
+            self.space_skipper();
+            op_comparison = Some((operator, self.literal_parser()?));
+            self.space_skipper();
+        }
+        self.expect(')', "JSONPath: Expected ')'.")?;
+        Ok(QueryFilter { keys, op_comparison })
+    }
+
+    fn operator_parser(&mut self) -> Result<QueryOperator> {
+        let next_char = self.query_chars.get(self.char_idx + 1).copied();
+        let (operator, len) = match (self.peek(), next_char) {
+            (Some('='), Some('=')) => (QueryOperator::Eq, 2),
+            (Some('!'), Some('=')) => (QueryOperator::Ne, 2),
+            (Some('<'), Some('=')) => (QueryOperator::Le, 2),
+            (Some('>'), Some('=')) => (QueryOperator::Ge, 2),
+            (Some('<'), _) => (QueryOperator::Lt, 1),
+            (Some('>'), _) => (QueryOperator::Gt, 1),
+            _ => return Err(self.error("JSONPath: Expected an operator.", self.char_idx)),
+        };
+        self.char_idx += len;
+        Ok(operator)
+    }
+
+    /// String, number, `true`, `false` or `null`.
+    fn literal_parser(&mut self) -> Result<JsonValue> {
+        let start_char_idx = self.char_idx;
+        if matches!(self.peek(), Some('\'' | '\"')) {
+            return Ok(JsonValue::ValueString(self.quoted_parser()?));
+        }
+        while self
+            .peek()
+            .is_some_and(|unicode_char| unicode_char.is_ascii_alphanumeric() || matches!(unicode_char, '-' | '+' | '.'))
+        {
+            self.char_idx += 1;
+        }
+        let literal_string: String = self.query_chars[start_char_idx..self.char_idx].iter().collect();
+        match literal_string.as_str() {
+            "true" => Ok(JsonValue::ValueBool(true)),
+            "false" => Ok(JsonValue::ValueBool(false)),
+            "null" => Ok(JsonValue::ValueNull),
+            _ => {
+                if let Ok(int_number) = literal_string.parse::<i64>() {
+                    return Ok(JsonValue::ValueNumber(JsonNumberType::Int(int_number)));
+                }
+                if let Ok(uint_number) = literal_string.parse::<u64>() {
+                    return Ok(JsonValue::ValueNumber(JsonNumberType::Uint(uint_number)));
+                }
+                match literal_string.parse::<f64>() {
+                    Ok(float_number) if float_number.is_finite() => {
+                        Ok(JsonValue::ValueNumber(JsonNumberType::Float(float_number)))
+                    }
+                    _ => Err(self.error("JSONPath: Literal is not a string, a number, a bool or null.", start_char_idx)),
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn query(json_object: &JsonObject, query_str: &str) -> Result<Vec<JsonValue>> {
+    let segments = QueryParser::new(query_str).parse()?;
+    let mut nodes = vec![JsonValue::from(json_object.clone())];
+    for segment in &segments {
+        if segment.descendant {
+            let mut descendant_nodes = Vec::new();
+            for json_value in &nodes {
+                descendant_collector(json_value, &mut Vec::new(), &mut descendant_nodes);
+            }
+            nodes = descendant_nodes;
+        }
+        nodes = nodes
+            .iter()
+            .flat_map(|json_value| selected_values(json_value, &segment.selector))
+            .collect();
+    }
+    Ok(nodes)
+}
+
+/// Elements of the array, or values of the object in the order of the sorted keys.
+fn child_values(json_value: &JsonValue) -> Vec<JsonValue> {
+    match json_value {
+        JsonValue::ValueArray(json_array) => json_array.clone(),
+        JsonValue::ValueObject(_) | JsonValue::ValueOwnedObject(_) => {
+            let json_object = json_value.read_any_object().unwrap();
+            json_object
+                .keys_sorted()
+                .into_iter()
+                .map(|key_str| json_object[key_str].clone())
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// The node and all its descendants in document order. Reference cycles are not followed.
+fn descendant_collector(json_value: &JsonValue, ancestor_ptrs: &mut Vec<JsonObjectPtr>, descendant_nodes: &mut Vec<JsonValue>) {
+    descendant_nodes.push(json_value.clone());
+    let op_object_ptr = match json_value {
+        JsonValue::ValueObject(refcell_json_object) => {
+            if ancestor_ptrs.contains(&(object_ptr(refcell_json_object))) {
+                return;
+            }
+            Some(object_ptr(refcell_json_object))
+        }
+        _ => None,
+    };
+    if let Some(object_ptr) = op_object_ptr {
+        ancestor_ptrs.push(object_ptr);
+    }
+    for json_child_value in child_values(json_value) {
+        descendant_collector(&json_child_value, ancestor_ptrs, descendant_nodes);
+    }
+    if op_object_ptr.is_some() {
+        ancestor_ptrs.pop();
+    }
+}
+
+fn selected_values(json_value: &JsonValue, selector: &QuerySelector) -> Vec<JsonValue> {
+    match selector {
+        QuerySelector::Name(key_string) => match json_value.read_any_object() {
+            Some(json_object) => json_object.get(key_string).cloned().into_iter().collect(),
+            None => Vec::new(),
+        },
+        QuerySelector::Index(idx) => match json_value {
+            JsonValue::ValueArray(json_array) => {
+                // 負のインデックスは末尾から数える
+                let op_idx = if *idx < 0 {
+                    json_array.len().checked_sub(idx.unsigned_abs() as usize)
+                } else {
+                    Some(*idx as usize)
+                };
+                op_idx.and_then(|idx| json_array.get(idx)).cloned().into_iter().collect()
+            }
+            _ => Vec::new(),
+        },
+        QuerySelector::Wildcard => child_values(json_value),
+        QuerySelector::Filter(filter) => child_values(json_value)
+            .into_iter()
+            .filter(|json_child_value| filter_matcher(json_child_value, filter))
+            .collect(),
+    }
+}
+
+fn filter_matcher(json_value: &JsonValue, filter: &QueryFilter) -> bool {
+    let mut json_target_value = json_value.clone();
+    for key_string in &filter.keys {
+        let op_json_member_value = json_target_value
+            .read_any_object()
+            .and_then(|json_object| json_object.get(key_string).cloned());
+        match op_json_member_value {
+            Some(json_member_value) => json_target_value = json_member_value,
+            None => return false,
+        }
+    }
+    let Some((operator, json_literal_value)) = &filter.op_comparison else {
+        return true;
+    };
+    let eq_options = JsonEqOptions::new().numeric_coercion(true);
+    match operator {
+        QueryOperator::Eq => json_target_value.deep_eq(json_literal_value, &eq_options),
+        QueryOperator::Ne => !json_target_value.deep_eq(json_literal_value, &eq_options),
+        _ => {
+            let op_ordering = match (&json_target_value, json_literal_value) {
+                (JsonValue::ValueNumber(json_number), JsonValue::ValueNumber(json_literal_number)) => {
+                    json_number.as_f64().partial_cmp(&json_literal_number.as_f64())
+                }
+                (JsonValue::ValueString(json_string), JsonValue::ValueString(json_literal_string)) => {
+                    Some(json_string.cmp(json_literal_string))
+                }
+                _ => None,
+            };
+            op_ordering.is_some_and(|ordering| match operator {
+                QueryOperator::Lt => ordering.is_lt(),
+                QueryOperator::Le => ordering.is_le(),
+                QueryOperator::Gt => ordering.is_gt(),
+                _ => ordering.is_ge(),
+            })
+        }
+    }
+}
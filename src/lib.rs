@@ -1,86 +1,2891 @@
 //! JSON Parser & Serializer library.
-use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::Hasher;
+use std::io::Write;
+use std::path::Path;
 use std::rc::Rc;
 
-mod error;
+mod compare;
+mod diff;
+pub mod error;
+mod flatten;
+mod formatter;
+mod hash;
+mod jsonpath;
+mod macros;
+mod merge;
 mod parser;
+mod patch;
+mod path;
+mod pointer;
+mod project;
+mod prune;
+mod rename;
+mod schema;
+mod search;
+#[cfg(feature = "serde")]
+mod serde_impls;
+#[cfg(feature = "serde_json")]
+mod serde_value;
 mod serializer;
+mod shared;
+mod visit;
+mod writer;
 use error::*;
+pub use error::{JsonError, JsonErrorKind};
 use parser::JsonParser;
-use serializer::JsonSerializer;
+use serializer::{JsonSerializer, JsonSerializerRoot};
+pub use shared::{JsonObjectLock, JsonObjectRef, JsonObjectRefMut, JsonSharedObject};
+pub use writer::JsonWriter;
 
 mod tests;
 
+/// Remove insignificant whitespace from JSON text without parsing it to `JsonObject`.
+/// The order of keys and the text of strings and numbers are kept byte for byte.
+/// * Parameters:
+///     * `content_str` : JSON string(&str).
+/// * Return:
+///     * Minified JSON string.
+pub fn minify(content_str: &str) -> Result<String> {
+    formatter::minify(content_str)
+}
+
+/// Convert the key to snake_case, e.g. `userId` to `user_id` and `HTTPSPort` to `https_port`.
+/// Words are split at `_`, `-`, spaces and the changes of case, and digits belong to the word before them, e.g. `port8080Tcp` to `port8080_tcp`.
+/// Leading `_`s are kept.
+/// * Parameters:
+///     * `key_str` : Key.
+/// * Return:
+///     * Key in snake_case.
+pub fn to_snake_case(key_str: &str) -> String {
+    rename::to_snake_case(key_str)
+}
+
+/// Convert the key to camelCase, e.g. `user_id` to `userId` and `HTTPSPort` to `httpsPort`. Words are split like `to_snake_case`.
+/// Leading `_`s are kept.
+/// * Parameters:
+///     * `key_str` : Key.
+/// * Return:
+///     * Key in camelCase.
+pub fn to_camel_case(key_str: &str) -> String {
+    rename::to_camel_case(key_str)
+}
+
+/// Reformat JSON text with the serializer options without parsing it to `JsonObject`.
+/// The order of keys and the text of strings and numbers(e.g. `1.10`) are kept as they are.
+/// * Parameters:
+///     * `content_str` : JSON string(&str).
+///     * `options` : Serializer options. Newline, indent, colon style, compact, array style and trailing newline are used.
+/// * Return:
+///     * Reformatted JSON string.
+pub fn prettify(content_str: &str, options: &JsonSerializerOptions) -> Result<String> {
+    formatter::prettify(content_str, options)
+}
+
 /// JSON Key struct. JsonKey(String)
-#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+/// Keys are ordered like `String`, i.e. lexicographically by the bytes of UTF-8, which is the order of Unicode scalar values.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 pub struct JsonKey(pub String);
 
+impl JsonKey {
+    /// Create key.
+    /// * Parameters:
+    ///     * `key` : Text of the key.
+    /// * Return:
+    ///     * Key.
+    ///
+    /// ```
+    /// use json_parser_serializer::{JsonKey, JsonObject};
+    ///
+    /// let key = JsonKey::new("name");
+    /// assert_eq!(key.as_str(), "name");
+    /// assert_eq!(key, JsonKey::from("name"));
+    ///
+    /// let mut json_object = JsonObject::new();
+    /// json_object.members.insert(key, "value".into());
+    /// // メンバーは &str で引ける
+    /// assert!(json_object.members.contains_key("name"));
+    /// ```
+    pub fn new(key: impl Into<String>) -> JsonKey {
+        JsonKey(key.into())
+    }
+
+    /// Text of the key.
+    /// * Return:
+    ///     * Text of the key.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Members can be looked up by `&str` without allocating `JsonKey`, e.g. `members.get("key")`.
+impl std::borrow::Borrow<str> for JsonKey {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for JsonKey {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for JsonKey {
+    fn from(key_str: &str) -> Self {
+        JsonKey(key_str.to_string())
+    }
+}
+
+impl From<String> for JsonKey {
+    fn from(key_string: String) -> Self {
+        JsonKey(key_string)
+    }
+}
+
+/// Text of the key as it is, without quotes and escapes.
+impl fmt::Display for JsonKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
 /// JSON Value's enum.
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug)]
 pub enum JsonValue {
     ValueString(String),
     ValueNumber(JsonNumberType),
     ValueBool(bool),
     ValueNull,
     ValueArray(Vec<JsonValue>),
-    ValueObject(Rc<RefCell<JsonObject>>),
-}
-
-/// JSON Number Value's enum.
-#[derive(Clone, Copy, PartialEq, Debug)]
-pub enum JsonNumberType {
-    Int(i64),
-    Float(f64),
+    ValueObject(JsonSharedObject),
+    /// Object owned by the value, which is plain data: it is changed through `&mut`, copied by `clone` and never shared.
+    /// The parser makes it instead of `ValueObject` with `JsonParserOptions::owned_objects`.
+    ValueOwnedObject(Box<JsonObject>),
+    /// Serialized JSON text written as it is by the serializer. The parser never makes it.
+    ValueRaw(JsonRawValue),
 }
 
-/// JSON Object struct.
+/// JSON text that is embedded as it is when serializing, e.g. a value serialized elsewhere.
 #[derive(Clone, PartialEq, Debug)]
-pub struct JsonObject {
-    pub members: HashMap<JsonKey, JsonValue>,
+pub struct JsonRawValue(String);
+
+impl JsonRawValue {
+    /// Create raw value from JSON text. The text is checked against the grammar of JSON(one value, the order of keys, colons,
+    /// values and commas, the literals and the escapes in strings), so that it does not corrupt the document.
+    /// Whitespace around the value is removed.
+    /// * Parameters:
+    ///     * `raw_str` : JSON text of one value.
+    /// * Return:
+    ///     * Raw value.
+    pub fn new(raw_str: &str) -> Result<JsonRawValue> {
+        formatter::validate_raw_value(raw_str)?;
+        Ok(JsonRawValue(raw_str.trim().to_string()))
+    }
+
+    /// JSON text of the raw value.
+    /// * Return:
+    ///     * JSON text.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
 }
 
-impl JsonObject {
-    /// Create new empty JSON Onject.
+impl JsonValue {
+    /// Name of the type of the value used in error messages, e.g. `string`.
+    pub(crate) fn type_name(&self) -> &'static str {
+        match self {
+            JsonValue::ValueString(_) => "string",
+            JsonValue::ValueNumber(JsonNumberType::Int(_) | JsonNumberType::Uint(_)) => "integer",
+            JsonValue::ValueNumber(JsonNumberType::Float(_) | JsonNumberType::BigDecimal(_)) => "number",
+            JsonValue::ValueBool(_) => "bool",
+            JsonValue::ValueNull => "null",
+            JsonValue::ValueArray(_) => "array",
+            JsonValue::ValueObject(_) => "object",
+            JsonValue::ValueOwnedObject(_) => "owned object",
+            JsonValue::ValueRaw(_) => "raw value",
+        }
+    }
+
+    /// Get the string.
     /// * Return:
-    ///     * JSON Object struct.
-    pub fn new() -> JsonObject {
-        JsonObject { members: HashMap::new() }
+    ///     * String, or `None` if the value is not a string.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::ValueString(json_string) => Some(json_string),
+            _ => None,
+        }
     }
 
-    /// Parse JSON string to JSON Onject.
+    /// Get the integer. Floats are never converted, even if they are integral like `1.0`.
+    /// * Return:
+    ///     * Integer, or `None` if the value is not an integer(`Int`).
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            JsonValue::ValueNumber(JsonNumberType::Int(int_number)) => Some(*int_number),
+            _ => None,
+        }
+    }
+
+    /// Get the unsigned integer. Floats are never converted, even if they are integral like `1.0`.
+    /// * Return:
+    ///     * Integer, or `None` if the value is not a non-negative integer(`Int` or `Uint`).
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            JsonValue::ValueNumber(JsonNumberType::Int(int_number)) => u64::try_from(*int_number).ok(),
+            JsonValue::ValueNumber(JsonNumberType::Uint(uint_number)) => Some(*uint_number),
+            _ => None,
+        }
+    }
+
+    /// Get the float. Integers are converted if they are exactly representable(up to 2^53).
+    /// * Return:
+    ///     * Float, or `None` if the value is not a number that fits in `f64`.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            JsonValue::ValueNumber(JsonNumberType::Float(float_number)) => Some(*float_number),
+            JsonValue::ValueNumber(JsonNumberType::Int(int_number)) if int_number.unsigned_abs() <= 1 << f64::MANTISSA_DIGITS => {
+                Some(*int_number as f64)
+            }
+            _ => None,
+        }
+    }
+
+    /// Get the bool.
+    /// * Return:
+    ///     * Bool, or `None` if the value is not a bool.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            JsonValue::ValueBool(json_bool) => Some(*json_bool),
+            _ => None,
+        }
+    }
+
+    /// Get the array.
+    /// * Return:
+    ///     * Array, or `None` if the value is not an array.
+    pub fn as_array(&self) -> Option<&Vec<JsonValue>> {
+        match self {
+            JsonValue::ValueArray(json_array) => Some(json_array),
+            _ => None,
+        }
+    }
+
+    /// Get the mutable array.
+    /// * Return:
+    ///     * Array, or `None` if the value is not an array.
+    pub fn as_array_mut(&mut self) -> Option<&mut Vec<JsonValue>> {
+        match self {
+            JsonValue::ValueArray(json_array) => Some(json_array),
+            _ => None,
+        }
+    }
+
+    /// Get the object.
+    /// * Return:
+    ///     * Shared reference to the object, or `None` if the value is not an object.
+    pub fn as_object(&self) -> Option<&JsonSharedObject> {
+        match self {
+            JsonValue::ValueObject(refcell_json_object) => Some(refcell_json_object),
+            _ => None,
+        }
+    }
+
+    /// Get the owned object.
+    /// * Return:
+    ///     * Object, or `None` if the value is not an owned object.
+    pub fn as_owned_object(&self) -> Option<&JsonObject> {
+        match self {
+            JsonValue::ValueOwnedObject(json_object) => Some(json_object),
+            _ => None,
+        }
+    }
+
+    /// Get the mutable owned object.
+    /// * Return:
+    ///     * Object, or `None` if the value is not an owned object.
+    pub fn as_owned_object_mut(&mut self) -> Option<&mut JsonObject> {
+        match self {
+            JsonValue::ValueOwnedObject(json_object) => Some(json_object),
+            _ => None,
+        }
+    }
+
+    /// Convert the shared objects in the value and in its arrays and objects into owned objects.
+    /// Objects shared by several places become separate objects, one per place, and are copied unless nothing else refers to them.
+    /// References to an ancestor object(reference cycle) and objects borrowed mutably stay shared.
+    /// * Return:
+    ///     * Value with owned objects.
+    pub fn into_owned_objects(self) -> JsonValue {
+        shared::into_owned_value(self, &mut Vec::new())
+    }
+
+    /// Convert the owned objects in the value and in its arrays and objects into shared objects.
+    /// Shared objects in the value are converted in place, so the change is also seen through their other references.
+    /// Objects borrowed mutably are skipped.
+    /// * Return:
+    ///     * Value with shared objects.
+    pub fn into_shared_objects(self) -> JsonValue {
+        shared::into_shared_value(self, &mut HashSet::new())
+    }
+
+    /// Call `f` with the value and every value in it with their paths, parents before children.
+    /// Members are visited in the order of the sorted keys and elements in the order of the indexes.
+    /// Objects shared by several places are visited at each place, but reference cycles are not followed.
     /// * Parameters:
-    ///     * `content_str` : JSON string(&str).
+    ///     * `f` : Callback that takes the path and the value, and returns whether to visit the children.
+    pub fn walk<F>(&self, mut f: F)
+    where
+        F: FnMut(&JsonPath, &JsonValue) -> JsonWalkControl,
+    {
+        visit::walk_value(self, &mut f);
+    }
+
+    /// Mutable version of `walk`. `f` is called before the children are visited, so the children of the value that `f` sets are visited.
+    /// Objects that are already borrowed, e.g. by a reference cycle, are skipped.
+    /// * Parameters:
+    ///     * `f` : Callback that takes the path and the value, and returns whether to visit the children.
+    pub fn walk_mut<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&JsonPath, &mut JsonValue) -> JsonWalkControl,
+    {
+        visit::walk_value_mut(self, &mut f);
+    }
+
+    /// Apply `f` to every scalar(string, number, bool, null and raw value) in the value and rebuild the arrays and objects around the results.
+    /// Objects are rebuilt in new `Rc`s, so objects shared by several places become separate objects, one per place.
+    /// The objects of reference cycles are not followed and are kept as they are.
+    /// * Parameters:
+    ///     * `f` : Function that makes the new scalar from the scalar.
     /// * Return:
-    ///     * JSON Object struct.
-    pub fn parse(content_str: &str) -> Result<JsonObject> {
-        JsonParser::parse(content_str)
+    ///     * New value.
+    pub fn map_values<F>(self, mut f: F) -> JsonValue
+    where
+        F: FnMut(JsonValue) -> JsonValue,
+    {
+        visit::map_value(self, &mut f)
     }
 
-    /// Serialize JSON object to string.
+    /// Nesting depth of the value. Scalars are 0, and arrays and objects are 1 more than their deepest child, e.g. `[]` is 1 and `[[1]]` is 2.
+    /// A reference to an ancestor object(reference cycle) is counted like an empty object.
+    /// * Return:
+    ///     * Depth.
+    pub fn depth(&self) -> usize {
+        let mut depth = 0;
+        self.walk(|json_path, json_value| {
+            depth = depth.max(json_path.len() + usize::from(json_value.is_container()));
+            JsonWalkControl::Continue
+        });
+        depth
+    }
+
+    /// Number of the values in the value, including itself and the arrays and objects in it, e.g. `[1, [2]]` is 4.
+    /// Objects shared by several places are counted at each place. A reference to an ancestor object(reference cycle) is counted as one value.
+    /// * Return:
+    ///     * Number of the values.
+    pub fn node_count(&self) -> usize {
+        let mut node_count = 0;
+        self.walk(|_, _| {
+            node_count += 1;
+            JsonWalkControl::Continue
+        });
+        node_count
+    }
+
+    fn is_container(&self) -> bool {
+        matches!(
+            self,
+            JsonValue::ValueArray(_) | JsonValue::ValueObject(_) | JsonValue::ValueOwnedObject(_)
+        )
+    }
+
+    /// Move the value out without copying it, leaving `null` in its place.
+    /// * Return:
+    ///     * Previous value.
+    pub fn take(&mut self) -> JsonValue {
+        std::mem::take(self)
+    }
+
+    /// Hash of the contents, e.g. to find duplicated documents. The result does not depend on the order of the members,
+    /// the process or the Rust version (FNV-1a over a canonical form), so it can be stored.
+    /// Keys are hashed in sorted order and strings by their decoded contents. Integral floats in the range of `i64` or `u64`
+    /// are hashed as integers (so `1.0`, `1` and `-0.0`, `0` hash equal), other floats by their bits with NaN normalized,
+    /// and `BigDecimal` and raw values by their text.
+    /// Equal values always hash equal. A reference cycle is hashed as the distance to the referred object.
+    /// * Return:
+    ///     * 64-bit hash.
+    pub fn canonical_hash(&self) -> u64 {
+        let mut hasher = hash::JsonFnvHasher::new();
+        hash::value_hasher(self, &mut hasher);
+        hasher.finish()
+    }
+
+    /// Compare the values by their contents with the options.
+    /// * Parameters:
+    ///     * `other` : Value to compare.
+    ///     * `options` : How numbers are compared.
+    /// * Return:
+    ///     * `true` if the values are equal.
+    pub fn deep_eq(&self, other: &JsonValue, options: &JsonEqOptions) -> bool {
+        compare::value_eq(self, other, options)
+    }
+
+    /// Apply JSON Merge Patch (RFC 7386). Values other than objects in the patch replace the whole value.
+    /// The patch is deep copied, so the value never shares `Rc` with it.
+    /// * Parameters:
+    ///     * `patch` : Merge patch.
+    pub fn merge_patch(&mut self, patch: &JsonValue) {
+        merge::merge_patch_value(self, &merge::deep_copy_value(patch));
+    }
+
+    /// Serialize JSON value to string. Any value (array, string, number, bool, null or object) can be the root of the document.
     /// * Parameters:
-    ///     * `json_object` : JSON Object struct.
     ///     * `newline_kind` : Newline code(LF or CRLF) when serializing JSON.
     ///     * `indent_kind` : Indent kind(Tab of Space) when serializing JSON.
     /// * Return:
     ///     * JSON string.
     pub fn serialize(&self, newline_kind: JsonSerializerNewLineKind, indent_kind: JsonSerializerIndentKind) -> Result<String> {
-        JsonSerializer::serialize(self, newline_kind, indent_kind)
+        self.serialize_with_options(&JsonSerializerOptions::with_kinds(newline_kind, indent_kind))
+    }
+
+    /// Serialize JSON value to string with the serializer options.
+    /// * Parameters:
+    ///     * `options` : Serializer options.
+    /// * Return:
+    ///     * JSON string.
+    pub fn serialize_with_options(&self, options: &JsonSerializerOptions) -> Result<String> {
+        JsonSerializer::serialize(JsonSerializerRoot::Value(self), options)
+    }
+
+    /// Serialize JSON value to string with the default options(LF, 4 spaces, trailing newline).
+    /// * Return:
+    ///     * JSON string.
+    pub fn to_pretty_string(&self) -> Result<String> {
+        self.serialize_with_options(&JsonSerializerOptions::new())
+    }
+
+    /// Serialize JSON value to one line string without whitespace and trailing newline.
+    /// * Return:
+    ///     * JSON string.
+    pub fn to_compact_string(&self) -> Result<String> {
+        self.serialize_with_options(&JsonSerializerOptions::new().compact(true).trailing_newline(false))
+    }
+
+    /// Serialize JSON value to canonical JSON (RFC 8785 JSON Canonicalization Scheme), e.g. for signing.
+    /// * Return:
+    ///     * Canonical JSON string without a trailing newline.
+    pub fn serialize_canonical(&self) -> Result<String> {
+        JsonSerializer::serialize_canonical(JsonSerializerRoot::Value(self))
     }
 }
 
-/// Enum that specifies newline code(LF or CRLF) when serializing JSON.
-#[allow(dead_code)]
-#[derive(Clone, PartialEq)]
-pub enum JsonSerializerNewLineKind {
-    Lf,
-    CrLf,
+/// Compact JSON like `to_compact_string`. NaN and Infinity are `null`, and errors (e.g. reference cycles) are written as
+/// `<error message>` since `Display` cannot return the error of this crate.
+impl fmt::Display for JsonValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        display_serialized(self.serialize_with_options(&display_options()), f)
+    }
 }
 
-/// Enum that specifies indent kind(Tab of Space) when serializing JSON. `Space(4)` means that specifies 4 spaces as indent.
-#[allow(dead_code)]
-#[derive(Clone, PartialEq)]
-pub enum JsonSerializerIndentKind {
-    Tab,
-    Space(usize),
+/// `null`, so that structs that have `JsonValue` or `JsonObject` can derive `Default`.
+/// ```
+/// use json_parser_serializer::{JsonObject, JsonValue};
+///
+/// #[derive(Default)]
+/// struct Document {
+///     header: JsonObject,
+///     body: JsonValue,
+/// }
+///
+/// let document = Document::default();
+/// assert!(document.header.is_empty());
+/// assert_eq!(document.body, JsonValue::ValueNull);
+/// ```
+impl Default for JsonValue {
+    fn default() -> Self {
+        JsonValue::ValueNull
+    }
+}
+
+/// Values are equal if they are the same variant with equal contents. Objects are compared by their members, not by `Rc`,
+/// so a shared object and an owned object with equal members are equal. Floats are equal like `f64`, except that NaN equals NaN.
+impl PartialEq for JsonValue {
+    fn eq(&self, other: &JsonValue) -> bool {
+        match (self, other) {
+            (JsonValue::ValueString(json_string), JsonValue::ValueString(other_string)) => json_string == other_string,
+            (
+                JsonValue::ValueNumber(JsonNumberType::Float(float_number)),
+                JsonValue::ValueNumber(JsonNumberType::Float(other_float_number)),
+            ) => float_number == other_float_number || hash::float_bits(*float_number) == hash::float_bits(*other_float_number),
+            (JsonValue::ValueNumber(json_number), JsonValue::ValueNumber(other_number)) => json_number == other_number,
+            (JsonValue::ValueBool(json_bool), JsonValue::ValueBool(other_bool)) => json_bool == other_bool,
+            (JsonValue::ValueNull, JsonValue::ValueNull) => true,
+            (JsonValue::ValueArray(json_array), JsonValue::ValueArray(other_array)) => json_array == other_array,
+            (
+                JsonValue::ValueObject(_) | JsonValue::ValueOwnedObject(_),
+                JsonValue::ValueObject(_) | JsonValue::ValueOwnedObject(_),
+            ) => match (self.read_any_object(), other.read_any_object()) {
+                (Some(json_object), Some(other_object)) => *json_object == *other_object,
+                _ => unreachable!(),
+            },
+            (JsonValue::ValueRaw(json_raw_value), JsonValue::ValueRaw(other_raw_value)) => json_raw_value == other_raw_value,
+            _ => false,
+        }
+    }
+}
+
+/// Hash consistent with `==`, computed like `canonical_hash`: objects by their members in sorted order, arrays by their
+/// elements in order, and floats by `to_bits()` except that integral floats are hashed as integers (`0.0 == -0.0`).
+/// Values of different kinds of numbers (e.g. `1` and `1.0`) hash equal but are not `==`.
+/// Objects in a `HashSet` or the keys of a `HashMap` must not be changed through their `Rc`.
+impl std::hash::Hash for JsonValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash::value_hasher(self, state);
+    }
+}
+
+/// Unlike `f64`, `==` is reflexive for NaN: floats are equal if they are `==` as `f64` or have the same bits with NaN
+/// normalized, like `Hash`, so a value that contains NaN equals itself and is found in a `HashSet`.
+impl Eq for JsonValue {}
+
+impl From<&str> for JsonValue {
+    fn from(json_string_str: &str) -> Self {
+        JsonValue::ValueString(json_string_str.to_string())
+    }
+}
+
+impl From<String> for JsonValue {
+    fn from(json_string: String) -> Self {
+        JsonValue::ValueString(json_string)
+    }
+}
+
+impl From<i64> for JsonValue {
+    fn from(int_number: i64) -> Self {
+        JsonValue::ValueNumber(JsonNumberType::Int(int_number))
+    }
+}
+
+impl From<i32> for JsonValue {
+    fn from(int_number: i32) -> Self {
+        JsonValue::ValueNumber(JsonNumberType::Int(int_number as i64))
+    }
+}
+
+impl From<u32> for JsonValue {
+    fn from(int_number: u32) -> Self {
+        JsonValue::ValueNumber(JsonNumberType::Int(int_number as i64))
+    }
+}
+
+/// Values up to `i64::MAX` become `Int`. Larger values become `Uint`.
+impl From<u64> for JsonValue {
+    fn from(uint_number: u64) -> Self {
+        match i64::try_from(uint_number) {
+            Ok(int_number) => JsonValue::ValueNumber(JsonNumberType::Int(int_number)),
+            Err(_) => JsonValue::ValueNumber(JsonNumberType::Uint(uint_number)),
+        }
+    }
+}
+
+impl From<f64> for JsonValue {
+    fn from(float_number: f64) -> Self {
+        JsonValue::ValueNumber(JsonNumberType::Float(float_number))
+    }
+}
+
+impl From<bool> for JsonValue {
+    fn from(json_bool: bool) -> Self {
+        JsonValue::ValueBool(json_bool)
+    }
+}
+
+impl From<Vec<JsonValue>> for JsonValue {
+    fn from(json_array: Vec<JsonValue>) -> Self {
+        JsonValue::ValueArray(json_array)
+    }
+}
+
+/// `None` becomes `null`.
+impl<T: Into<JsonValue>> From<Option<T>> for JsonValue {
+    fn from(op_value: Option<T>) -> Self {
+        match op_value {
+            Some(value) => value.into(),
+            None => JsonValue::ValueNull,
+        }
+    }
+}
+
+/// The object is wrapped in `JsonSharedObject`.
+impl From<JsonObject> for JsonValue {
+    fn from(json_object: JsonObject) -> Self {
+        JsonValue::ValueObject(shared::new_shared_object(json_object))
+    }
+}
+
+/// The object is owned by the value.
+impl From<Box<JsonObject>> for JsonValue {
+    fn from(json_object: Box<JsonObject>) -> Self {
+        JsonValue::ValueOwnedObject(json_object)
+    }
+}
+
+/// The object is shared with the `JsonSharedObject`, not copied.
+impl From<JsonSharedObject> for JsonValue {
+    fn from(json_object_rc: JsonSharedObject) -> Self {
+        JsonValue::ValueObject(json_object_rc)
+    }
+}
+
+/// Collect values into an array, e.g. `vec![1, 2, 3].into_iter().collect::<JsonValue>()`.
+impl<T: Into<JsonValue>> FromIterator<T> for JsonValue {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        JsonValue::ValueArray(iter.into_iter().map(Into::into).collect())
+    }
+}
+
+fn display_options() -> JsonSerializerOptions {
+    JsonSerializerOptions::new()
+        .compact(true)
+        .trailing_newline(false)
+        .non_finite_float(JsonSerializerNonFiniteFloatPolicy::Null)
+}
+
+fn display_serialized(serialized: Result<String>, f: &mut fmt::Formatter) -> fmt::Result {
+    match serialized {
+        Ok(json_string) => f.write_str(&json_string),
+        Err(err) => write!(f, "<{}>", err),
+    }
+}
+
+/// JSON Number Value's enum.
+#[derive(Clone, PartialEq, Debug)]
+pub enum JsonNumberType {
+    Int(i64),
+    /// Integer larger than `i64::MAX`. The parser uses `Int` for the integers that fit in `i64`.
+    Uint(u64),
+    Float(f64),
+    /// Number text kept as it is for arbitrary precision, made by the parser with `big_decimal_digits`.
+    /// The serializer writes it verbatim. It is equal only to `BigDecimal` with the same text, e.g. `1.0` and `1.00` are not equal,
+    /// and other conversions go through `f64`.
+    /// The text must be a valid JSON number.
+    BigDecimal(String),
+}
+
+impl JsonNumberType {
+    /// Convert to float. Ints are exact up to 2^53 in magnitude and are rounded to the nearest float beyond it.
+    /// * Return:
+    ///     * Float. `BigDecimal` is rounded to the nearest float, or Infinity if it is too large.
+    pub fn as_f64(&self) -> f64 {
+        match self {
+            JsonNumberType::Int(int_number) => *int_number as f64,
+            JsonNumberType::Uint(uint_number) => *uint_number as f64,
+            JsonNumberType::Float(float_number) => *float_number,
+            JsonNumberType::BigDecimal(number_string) => number_string.parse().unwrap_or(f64::NAN),
+        }
+    }
+
+    /// Convert to integer without loss. `-0.0` becomes `0`.
+    /// * Return:
+    ///     * Integer, or `None` if the float is not integral or out of the range of `i64`(including NaN and Infinity).
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            JsonNumberType::Int(int_number) => Some(*int_number),
+            JsonNumberType::Uint(uint_number) => i64::try_from(*uint_number).ok(),
+            JsonNumberType::BigDecimal(number_string) => number_string.parse().ok(),
+            // 2^63 は i64 を超えるが -2^63 は i64::MIN
+            JsonNumberType::Float(float_number)
+                if float_number.fract() == 0.0 && *float_number >= -(2f64.powi(63)) && *float_number < 2f64.powi(63) =>
+            {
+                Some(*float_number as i64)
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the number has no fractional part. NaN and Infinity are not integral.
+    /// * Return:
+    ///     * `true` if the number is an `Int`, a `Uint`, an integral finite `Float` or a `BigDecimal` without `.` and exponent.
+    pub fn is_integral(&self) -> bool {
+        match self {
+            JsonNumberType::Int(_) | JsonNumberType::Uint(_) => true,
+            JsonNumberType::Float(float_number) => float_number.is_finite() && float_number.fract() == 0.0,
+            JsonNumberType::BigDecimal(number_string) => !number_string.contains(['.', 'e', 'E']),
+        }
+    }
+
+    /// Convert to `i64` like `as_i64`, but with an error.
+    /// * Return:
+    ///     * Integer. `OutOfRange` error if the float is not integral or out of the range of `i64`.
+    pub fn try_into_i64(&self) -> Result<i64> {
+        self.as_i64().ok_or_else(|| {
+            JsonError::new(
+                JsonErrorKind::OutOfRange,
+                Some(format!("Number: {:?} is not an integer in the range of i64.", self)),
+            )
+        })
+    }
+
+    /// Convert to `u64` without loss. `-0.0` becomes `0`.
+    /// * Return:
+    ///     * Integer. `OutOfRange` error if the number is negative, not integral or out of the range of `u64`.
+    pub fn try_into_u64(&self) -> Result<u64> {
+        let op_uint_number = match self {
+            JsonNumberType::Int(int_number) => u64::try_from(*int_number).ok(),
+            JsonNumberType::Uint(uint_number) => Some(*uint_number),
+            JsonNumberType::BigDecimal(number_string) => number_string.parse().ok(),
+            JsonNumberType::Float(float_number)
+                if float_number.fract() == 0.0 && *float_number >= 0.0 && *float_number < 2f64.powi(64) =>
+            {
+                Some(*float_number as u64)
+            }
+            _ => None,
+        };
+        op_uint_number.ok_or_else(|| {
+            JsonError::new(
+                JsonErrorKind::OutOfRange,
+                Some(format!("Number: {:?} is not an integer in the range of u64.", self)),
+            )
+        })
+    }
+}
+
+/// Segment of the path from the root to a value: the key of a member or the index of an element.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum JsonPathSegment<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+/// Path from the root to a value reported by `walk` and `walk_mut`. The root has the empty path.
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct JsonPath {
+    segments: Vec<JsonPathOwnedSegment>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+enum JsonPathOwnedSegment {
+    Key(String),
+    Index(usize),
+}
+
+impl JsonPath {
+    pub(crate) fn push_key(&mut self, key_str: &str) {
+        self.segments.push(JsonPathOwnedSegment::Key(key_str.to_string()));
+    }
+
+    pub(crate) fn push_index(&mut self, idx: usize) {
+        self.segments.push(JsonPathOwnedSegment::Index(idx));
+    }
+
+    pub(crate) fn pop(&mut self) {
+        self.segments.pop();
+    }
+
+    /// Iterate over the segments from the root.
+    /// * Return:
+    ///     * Iterator of the keys of members and the indexes of elements.
+    pub fn segments(&self) -> impl Iterator<Item = JsonPathSegment<'_>> {
+        self.segments.iter().map(|path_segment| match path_segment {
+            JsonPathOwnedSegment::Key(key_string) => JsonPathSegment::Key(key_string),
+            JsonPathOwnedSegment::Index(idx) => JsonPathSegment::Index(*idx),
+        })
+    }
+
+    /// Number of the segments, i.e. the depth of the value.
+    /// * Return:
+    ///     * Number of the segments.
+    pub fn len(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Whether the path refers to the root.
+    /// * Return:
+    ///     * `true` if the path has no segments.
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Make JSON Pointer (RFC 6901) of the path, e.g. `/servers/0/host`.
+    /// * Return:
+    ///     * JSON Pointer string. The root is the empty string.
+    pub fn to_pointer(&self) -> String {
+        serializer::make_pointer_string(&self.segments().collect::<Vec<JsonPathSegment>>())
+    }
+}
+
+/// JSON Pointer of the path.
+impl fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.to_pointer())
+    }
+}
+
+/// Kind of the difference reported by `diff_report`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum JsonDiffKind {
+    /// Only the other object has the value.
+    Added,
+    /// Only this object has the value.
+    Removed,
+    /// The values have different types, e.g. a string and a number.
+    TypeChanged,
+    /// The values have the same type but are not equal.
+    ValueChanged,
+}
+
+/// Difference at a path reported by `diff_report`.
+/// `Display` writes a line like `/meta/id: 7 -> 8`, `/tags/2: added "c"` or `/old: removed 1`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct JsonDiffEntry {
+    /// Path of the value.
+    pub path: JsonPath,
+    pub kind: JsonDiffKind,
+    /// Value in this object. `None` for `Added`.
+    pub old_value: Option<JsonValue>,
+    /// Value in the other object. `None` for `Removed`.
+    pub new_value: Option<JsonValue>,
+}
+
+/// Differences between two objects reported by `diff_report`, sorted by the path
+/// (keys in lexicographic order and indexes in numeric order). `Display` writes one entry per line.
+#[derive(Clone, PartialEq, Default, Debug)]
+pub struct JsonDiffReport {
+    pub entries: Vec<JsonDiffEntry>,
+}
+
+impl JsonDiffReport {
+    /// Whether the objects have no differences.
+    /// * Return:
+    ///     * `true` if the report has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Number of the differences.
+    /// * Return:
+    ///     * Number of the entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Iterate over the differences in the order of the paths.
+    /// * Return:
+    ///     * Iterator of the entries.
+    pub fn iter(&self) -> std::slice::Iter<'_, JsonDiffEntry> {
+        self.entries.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a JsonDiffReport {
+    type Item = &'a JsonDiffEntry;
+    type IntoIter = std::slice::Iter<'a, JsonDiffEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
+
+impl IntoIterator for JsonDiffReport {
+    type Item = JsonDiffEntry;
+    type IntoIter = std::vec::IntoIter<JsonDiffEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+/// Enum that the callback of `walk` and `walk_mut` returns to control the traversal.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum JsonWalkControl {
+    /// Visit the children of the value.
+    Continue,
+    /// Do not visit the children of the value, but continue with its siblings.
+    SkipChildren,
+    /// End the traversal.
+    Stop,
+}
+
+/// JSON Object struct.
+/// Members should be accessed with `get`, `insert`, `remove`, `contains_key` and `len` rather than `members`,
+/// which is public for compatibility and whose map type may change.
+#[derive(Clone, PartialEq, Debug)]
+pub struct JsonObject {
+    pub members: HashMap<JsonKey, JsonValue>,
+}
+
+impl JsonObject {
+    /// Create new empty JSON Onject.
+    /// * Return:
+    ///     * JSON Object struct.
+    pub fn new() -> JsonObject {
+        JsonObject { members: HashMap::new() }
+    }
+
+    /// Wrap the object to share it by several places, e.g. as the value of several members.
+    /// * Return:
+    ///     * Shared object, which is `Rc<RefCell<JsonObject>>`, or `Arc<RwLock<JsonObject>>` with the `sync` feature.
+    pub fn into_shared(self) -> JsonSharedObject {
+        shared::new_shared_object(self)
+    }
+
+    /// Convert the shared objects in the object into owned objects like `JsonValue::into_owned_objects`.
+    /// * Return:
+    ///     * Object with owned objects.
+    pub fn into_owned_objects(self) -> JsonObject {
+        shared::into_owned_object(self, &mut Vec::new())
+    }
+
+    /// Convert the owned objects in the object into shared objects like `JsonValue::into_shared_objects`.
+    /// * Return:
+    ///     * Object with shared objects.
+    pub fn into_shared_objects(self) -> JsonObject {
+        shared::into_shared_object(self, &mut HashSet::new())
+    }
+
+    /// Insert the member. Values are converted with `Into<JsonValue>`, e.g. `json_object.insert("port", 8080)`.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    ///     * `value` : Value of the member.
+    /// * Return:
+    ///     * Previous value of the member, or `None` if the object did not have the key.
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<JsonValue>) -> Option<JsonValue> {
+        self.members.insert(JsonKey(key.into()), value.into())
+    }
+
+    /// Get the value referred by JSON Pointer (RFC 6901), e.g. `/servers/0/host`.
+    /// The value is cloned. Objects in it are shared with this object since they are `JsonSharedObject`.
+    /// * Parameters:
+    ///     * `ptr` : JSON Pointer. The empty pointer refers to the whole object.
+    /// * Return:
+    ///     * Referred value, or `None` if the pointer is invalid or does not refer to any value.
+    pub fn pointer(&self, ptr: &str) -> Option<JsonValue> {
+        pointer::with_pointer_root(self, ptr, JsonErrorKind::KeyNotFound, |root| match root {
+            JsonSerializerRoot::Object(json_object) => Ok(JsonValue::from(json_object.clone())),
+            JsonSerializerRoot::Value(json_value) => Ok(json_value.clone()),
+            JsonSerializerRoot::Projection(..) => unreachable!(),
+        })
+        .ok()
+    }
+
+    /// Set the value referred by JSON Pointer (RFC 6901). The index one past the end of an array, or `-`, appends the value.
+    /// Objects along the path are modified in place, so the change is seen through every `Rc` sharing them.
+    /// * Parameters:
+    ///     * `ptr` : JSON Pointer. It must not be empty.
+    ///     * `value` : Value to set.
+    ///     * `create_intermediate` : Whether missing members along the path are created as empty objects.
+    /// * Return:
+    ///     * Replaced value, or `None` if the value is added. `KeyNotFound` error that names the failed segment
+    ///       if the path does not exist.
+    pub fn pointer_set(&mut self, ptr: &str, value: JsonValue, create_intermediate: bool) -> Result<Option<JsonValue>> {
+        pointer::pointer_set(self, ptr, value, create_intermediate)
+    }
+
+    /// Get the value referred by the JavaScript-style path, e.g. `servers[2].tls.cert`.
+    /// Keys are separated by `.` and indexes of arrays are written as `[2]`. Keys that contain `.`, `[` or `]` are quoted
+    /// like `["weird.key"]` or `['weird.key']`, with `\` escaping the next character. The empty path refers to the whole object.
+    /// The value is cloned like `pointer`.
+    /// * Parameters:
+    ///     * `path` : Path of the value.
+    /// * Return:
+    ///     * Referred value, or `None` if the path does not refer to any value. Error if the path is malformed.
+    pub fn get_path(&self, path: &str) -> Result<Option<JsonValue>> {
+        let segments = path::parse_path(path)?;
+        let location_string = format!("path:{}", path);
+        Ok(pointer::with_segments_root(
+            self,
+            &segments,
+            &location_string,
+            JsonErrorKind::KeyNotFound,
+            |root| match root {
+                JsonSerializerRoot::Object(json_object) => Ok(JsonValue::from(json_object.clone())),
+                JsonSerializerRoot::Value(json_value) => Ok(json_value.clone()),
+                JsonSerializerRoot::Projection(..) => unreachable!(),
+            },
+        )
+        .ok())
+    }
+
+    /// Set the value referred by the JavaScript-style path like `pointer_set`. See `get_path` for the syntax.
+    /// * Parameters:
+    ///     * `path` : Path of the value. It must not be empty.
+    ///     * `value` : Value to set.
+    ///     * `create_intermediate` : Whether missing members along the path are created as empty objects.
+    /// * Return:
+    ///     * Replaced value, or `None` if the value is added. Error if the path is malformed or does not exist.
+    pub fn set_path(&mut self, path: &str, value: JsonValue, create_intermediate: bool) -> Result<Option<JsonValue>> {
+        let segments = path::parse_path(path)?;
+        pointer::set_segments(self, &segments, &format!("path:{}", path), value, create_intermediate)
+    }
+
+    /// Make a new object that has only the listed members. Keys that the object does not have are skipped.
+    /// The values are deep-copied, so the new object shares no objects with this object.
+    /// * Parameters:
+    ///     * `keys` : Keys of the top-level members.
+    /// * Return:
+    ///     * New object.
+    pub fn project(&self, keys: &[&str]) -> JsonObject {
+        project::project_keys(self, keys)
+    }
+
+    /// Make a new object that has only the values referred by the paths(like `get_path`, e.g. `items[0].name`),
+    /// with the objects and arrays around them. Arrays have only the selected elements in the order of the indexes,
+    /// e.g. `items[2]` alone makes an array of one element. Paths that refer to no value are skipped.
+    /// The values are deep-copied, so the new object shares no objects with this object.
+    /// * Parameters:
+    ///     * `paths` : Paths of the values.
+    /// * Return:
+    ///     * New object. Error if a path is malformed.
+    pub fn project_paths(&self, paths: &[&str]) -> Result<JsonObject> {
+        project::project_paths(self, paths)
+    }
+
+    /// Remove the value referred by JSON Pointer (RFC 6901), e.g. `/servers/2/tls/cert`. Later elements of an array are shifted.
+    /// Removal from a shared object is visible through all of its `Rc`s.
+    /// * Parameters:
+    ///     * `ptr` : JSON Pointer. The empty pointer(the whole object) cannot be removed.
+    /// * Return:
+    ///     * Removed value, or `None` if the parent object or array does not have the last segment.
+    ///       `KeyNotFound` error with the segment if the pointer is invalid or its parent does not exist.
+    pub fn remove_pointer(&mut self, ptr: &str) -> Result<Option<JsonValue>> {
+        let segments = pointer::parse_pointer(ptr, JsonErrorKind::KeyNotFound)?;
+        pointer::remove_segments(self, &segments, &format!("pointer:{}", ptr))
+    }
+
+    /// Remove the member.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    /// * Return:
+    ///     * Removed value, or `None` if the object did not have the key.
+    pub fn remove(&mut self, key: &str) -> Option<JsonValue> {
+        self.members.remove(key)
+    }
+
+    /// Move the member out of the object without copying it. Same as `remove`.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    /// * Return:
+    ///     * Value of the member, or `None` if the object did not have the key.
+    pub fn take(&mut self, key: &str) -> Option<JsonValue> {
+        self.members.remove(key)
+    }
+
+    /// Keep only the members for which `f` returns `true`, like `HashMap::retain`.
+    /// * Parameters:
+    ///     * `f` : Predicate that takes the key and the value of each member.
+    pub fn retain<F>(&mut self, f: F)
+    where
+        F: FnMut(&JsonKey, &mut JsonValue) -> bool,
+    {
+        self.members.retain(f);
+    }
+
+    /// Keep only the members for which `f` returns `true` in the object and the objects nested in it, including the objects in arrays.
+    /// Members are filtered before their values are visited. Objects shared by several places are visited once,
+    /// and objects that are already borrowed, e.g. by a reference cycle, are skipped.
+    /// * Parameters:
+    ///     * `f` : Predicate that takes the key and the value of each member.
+    pub fn retain_recursive<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&JsonKey, &mut JsonValue) -> bool,
+    {
+        prune::retain_recursive(self, &mut f);
+    }
+
+    /// Remove null members recursively, through nested objects and arrays.
+    /// Objects shared by several places are pruned once, and objects that are already borrowed, e.g. by a reference cycle, are skipped.
+    /// * Parameters:
+    ///     * `options` : Pruning options.
+    pub fn prune_nulls(&mut self, options: JsonPruneOptions) {
+        prune::prune_nulls(self, options);
+    }
+
+    /// Rename the member, keeping its value.
+    /// * Parameters:
+    ///     * `from` : Current key of the member.
+    ///     * `to` : New key of the member.
+    /// * Return:
+    ///     * `true` if the member is renamed, `false` if the object does not have `from`.
+    ///       `KeyAlreadyExists` error if the object already has `to`, in which case nothing is changed.
+    pub fn rename_key(&mut self, from: &str, to: &str) -> Result<bool> {
+        rename::rename_key(self, from, to)
+    }
+
+    /// Rename the members at any depth, through nested objects and arrays, keeping their values.
+    /// Objects shared by several places are renamed once, and objects that are already borrowed are skipped.
+    /// * Parameters:
+    ///     * `from` : Current key of the members.
+    ///     * `to` : New key of the members.
+    /// * Return:
+    ///     * Number of the renamed members.
+    ///       `KeyAlreadyExists` error if any object has both `from` and `to`, in which case nothing is changed.
+    pub fn rename_key_recursive(&mut self, from: &str, to: &str) -> Result<usize> {
+        rename::rename_key_recursive(self, from, to)
+    }
+
+    /// Rewrite every key at any depth, through nested objects and arrays, e.g. `json_object.transform_keys(to_snake_case)`.
+    /// Objects shared by several places are rewritten once, and objects that are already borrowed are skipped.
+    /// * Parameters:
+    ///     * `f` : Function that makes the new key from the key.
+    /// * Return:
+    ///     * `KeyAlreadyExists` error if `f` makes the same key from two keys of an object, in which case nothing is changed.
+    pub fn transform_keys<F>(&mut self, f: F) -> Result<()>
+    where
+        F: Fn(&str) -> String,
+    {
+        rename::transform_keys(self, &f)
+    }
+
+    /// Call `f` with every value in the object with their paths, like `JsonValue::walk`. The object itself is not passed to `f`.
+    /// If the object is borrowed from a shared object, a reference cycle back to it is followed once.
+    /// * Parameters:
+    ///     * `f` : Callback that takes the path and the value, and returns whether to visit the children.
+    pub fn walk<F>(&self, mut f: F)
+    where
+        F: FnMut(&JsonPath, &JsonValue) -> JsonWalkControl,
+    {
+        visit::walk_object(self, None, &mut f);
+    }
+
+    /// Call `f` with every value in the object with their paths, like `JsonValue::walk_mut`. The object itself is not passed to `f`.
+    /// * Parameters:
+    ///     * `f` : Callback that takes the path and the value, and returns whether to visit the children.
+    pub fn walk_mut<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&JsonPath, &mut JsonValue) -> JsonWalkControl,
+    {
+        visit::walk_object_mut(self, &mut f);
+    }
+
+    /// Apply `f` to every scalar(string, number, bool, null and raw value) in the object in place, through nested objects and arrays.
+    /// Changes of shared objects are visible through all of their `Rc`s. Objects shared by several places are changed once,
+    /// and objects that are already borrowed, e.g. by a reference cycle, are skipped.
+    /// * Parameters:
+    ///     * `f` : Function that makes the new scalar from the scalar.
+    pub fn map_values_in_place<F>(&mut self, mut f: F)
+    where
+        F: FnMut(JsonValue) -> JsonValue,
+    {
+        visit::map_object_in_place(self, &mut f);
+    }
+
+    /// Nesting depth of the object, like `JsonValue::depth`. The empty object is 1.
+    /// * Return:
+    ///     * Depth.
+    pub fn depth(&self) -> usize {
+        let mut depth = 1;
+        self.walk(|json_path, json_value| {
+            depth = depth.max(json_path.len() + usize::from(json_value.is_container()));
+            JsonWalkControl::Continue
+        });
+        depth
+    }
+
+    /// Number of the values in the object including itself, like `JsonValue::node_count`. The empty object is 1.
+    /// * Return:
+    ///     * Number of the values.
+    pub fn node_count(&self) -> usize {
+        let mut node_count = 1;
+        self.walk(|_, _| {
+            node_count += 1;
+            JsonWalkControl::Continue
+        });
+        node_count
+    }
+
+    /// Replace the value of the member. Unlike `insert`, nothing is inserted if the object does not have the key.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    ///     * `value` : New value of the member.
+    /// * Return:
+    ///     * Previous value of the member, or `None` if the object did not have the key.
+    pub fn replace(&mut self, key: &str, value: impl Into<JsonValue>) -> Option<JsonValue> {
+        self.members
+            .get_mut(key)
+            .map(|json_value| std::mem::replace(json_value, value.into()))
+    }
+
+    /// Whether the object has the member.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    /// * Return:
+    ///     * `true` if the object has the key.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.members.contains_key(key)
+    }
+
+    /// Number of the members.
+    /// * Return:
+    ///     * Number of the members.
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    /// Whether the object has no member.
+    /// * Return:
+    ///     * `true` if the object is empty.
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// Merge the other object into the object recursively, e.g. to layer configuration files.
+    /// Values taken from the other object are deep copies, so the object never shares `Rc` with it.
+    /// The other object must not contain reference cycles.
+    /// * Parameters:
+    ///     * `other` : Object to merge.
+    ///     * `strategy` : How values in both objects are combined.
+    pub fn merge(&mut self, other: &JsonObject, strategy: JsonMergeStrategy) {
+        merge::merge_members(self, merge::deep_copy_object(other), strategy);
+    }
+
+    /// Apply JSON Merge Patch (RFC 7386). Members of the patch replace the members, objects are patched recursively
+    /// and `null` removes the member. The patch is deep copied, so the object never shares `Rc` with it.
+    /// * Parameters:
+    ///     * `patch` : Merge patch. It must be an object, because the other values would replace the whole object.
+    /// * Return:
+    ///     * `TypeMismatch` error if the patch is not an object. The object is not changed then.
+    pub fn merge_patch(&mut self, patch: &JsonValue) -> Result<()> {
+        match merge::deep_copy_value(patch) {
+            JsonValue::ValueObject(refcell_patch_object) => {
+                merge::merge_patch_members(self, &refcell_patch_object.read_object());
+                Ok(())
+            }
+            JsonValue::ValueOwnedObject(patch_object) => {
+                merge::merge_patch_members(self, &patch_object);
+                Ok(())
+            }
+            _ => Err(JsonError::new(
+                JsonErrorKind::TypeMismatch,
+                Some(format!("Merge patch: expected object, found {}", patch.type_name())),
+            )),
+        }
+    }
+
+    /// Apply JSON Patch (RFC 6902). The operations `add`, `remove`, `replace`, `move`, `copy` and `test` are supported.
+    /// The patch is applied atomically: if any operation fails, the changes made by the previous operations are undone.
+    /// The operations change the object in place like `pointer_set`, so other places sharing the nested objects see the change.
+    /// * Parameters:
+    ///     * `patch` : Operation objects, e.g. `{"op": "add", "path": "/a/-", "value": 1}`.
+    /// * Return:
+    ///     * Error with the index and the name of the failed operation. `PatchTestFailed` if `test` fails,
+    ///       `InvalidPatch` if the operation is malformed, `KeyNotFound` if the path does not exist.
+    pub fn apply_patch(&mut self, patch: &[JsonValue]) -> Result<()> {
+        patch::apply_patch(self, patch)
+    }
+
+    /// Make JSON Patch (RFC 6902) that transforms the object into the other object, so that
+    /// `apply_patch(&json_object.diff(&other))` makes the object equal to `other`.
+    /// Only `add`, `remove` and `replace` are used. Arrays are compared index by index.
+    /// * Parameters:
+    ///     * `other` : Object after the change.
+    /// * Return:
+    ///     * Operation objects. Empty if the objects are equal.
+    pub fn diff(&self, other: &JsonObject) -> Vec<JsonValue> {
+        patch::diff_objects(self, other)
+    }
+
+    /// Make the human-readable report of the differences from this object to `other`, e.g. for test failures.
+    /// Nested objects are compared member by member and arrays element by element from the head,
+    /// so an inserted element is reported as changes of the following elements and an addition at the end.
+    /// Numbers of different kinds (e.g. `1` and `1.0`) are `ValueChanged`.
+    /// * Parameters:
+    ///     * `other` : Object after the change.
+    /// * Return:
+    ///     * Report of the differences, which is empty if the objects are equal. The values are deep-copied.
+    pub fn diff_report(&self, other: &JsonObject) -> JsonDiffReport {
+        diff::diff_report(self, other)
+    }
+
+    /// Hash of the contents, e.g. to find duplicated documents. The result does not depend on the order of the members,
+    /// the process or the Rust version (FNV-1a over a canonical form), so it can be stored.
+    /// Keys are hashed in sorted order and strings by their decoded contents. Integral floats in the range of `i64` or `u64`
+    /// are hashed as integers (so `1.0`, `1` and `-0.0`, `0` hash equal), other floats by their bits with NaN normalized,
+    /// and `BigDecimal` and raw values by their text.
+    /// Equal objects always hash equal. A reference cycle is hashed as the distance to the referred object.
+    /// A cycle back to an object borrowed from a shared object is detected one level below it,
+    /// since the borrow does not tell which shared object it is; hash `JsonValue::ValueObject` of the shared object instead.
+    /// * Return:
+    ///     * 64-bit hash.
+    pub fn canonical_hash(&self) -> u64 {
+        let mut hasher = hash::JsonFnvHasher::new();
+        hash::object_hasher(self, None, &mut hasher);
+        hasher.finish()
+    }
+
+    /// Compare the objects by their contents with the options.
+    /// * Parameters:
+    ///     * `other` : Object to compare.
+    ///     * `options` : How numbers are compared.
+    /// * Return:
+    ///     * `true` if the objects are equal.
+    pub fn deep_eq(&self, other: &JsonObject, options: &JsonEqOptions) -> bool {
+        compare::object_eq(self, other, options)
+    }
+
+    /// Flatten the nested objects and arrays into one map, e.g. `{"a": {"b": [1, 2]}}` to `{"a.b[0]": 1, "a.b[1]": 2}`.
+    /// Keys are joined with the separator and indexes are appended as `[0]`. `\`, `[`, `]` and each character of
+    /// the separator in keys are escaped with `\`, e.g. the key `weird.key` becomes `weird\.key`.
+    /// Empty objects and arrays are kept as values, so `unflatten` restores them.
+    /// * Parameters:
+    ///     * `separator` : Separator of keys, e.g. `.` or `__`. It must not be empty.
+    /// * Return:
+    ///     * Map from the flattened keys to the scalar values.
+    pub fn flatten(&self, separator: &str) -> HashMap<String, JsonValue> {
+        flatten::flatten_object(self, separator)
+    }
+
+    /// Rebuild the nested object from the map made by `flatten`. Arrays are created for `[0]`.
+    /// * Parameters:
+    ///     * `flat_map` : Map from the flattened keys to the values.
+    ///     * `separator` : Separator of keys used by `flatten`.
+    /// * Return:
+    ///     * Nested object. `ParseErrorInKey` error if a key is malformed, a key is both a leaf and a prefix of
+    ///       another key (e.g. `a` and `a.b`), keys and indexes are mixed at the same level, or an index is missing.
+    pub fn unflatten(flat_map: &HashMap<String, JsonValue>, separator: &str) -> Result<JsonObject> {
+        flatten::unflatten_object(flat_map, separator)
+    }
+
+    /// Find the values of the members with the key at any depth, through nested objects and arrays.
+    /// Reference cycles are not followed, and objects shared by several places are searched at each place.
+    /// If the object is borrowed from a shared object, a cycle back to it is followed once.
+    /// * Parameters:
+    ///     * `key` : Key of the members.
+    /// * Return:
+    ///     * JSON Pointers and values of the members, in the order of the sorted keys. The values are cloned like `pointer`.
+    pub fn find_all(&self, key: &str) -> Vec<(String, JsonValue)> {
+        search::find_all(self, None, key)
+    }
+
+    /// Query the values with JSONPath. The supported subset is `$` (the object), `.name` and `['name']` (member),
+    /// `[0]` (element, negative indexes count from the end), `.*` and `[*]` (all elements or member values),
+    /// `..` (the node and all its descendants, e.g. `$..price`), and filters of elements or member values
+    /// like `[?(@.isbn)]` (member exists) or `[?(@.price < 10)]` (`==`, `!=`, `<`, `<=`, `>`, `>=`).
+    /// Members of objects are visited in the order of the sorted keys.
+    /// * Parameters:
+    ///     * `path` : JSONPath query, e.g. `$.store.book[?(@.category == 'fiction')].title`.
+    /// * Return:
+    ///     * Values that match the query, cloned like `pointer`. `ParseErrorInKey` error with the column if the query is malformed.
+    pub fn query(&self, path: &str) -> Result<Vec<JsonValue>> {
+        jsonpath::query(self, path)
+    }
+
+    /// Validate the object with a minimal subset of JSON Schema: `type`, `enum`, `minimum`, `maximum`, `minLength`,
+    /// `maxLength`, `required`, `properties` and `items`. Unknown keywords are ignored.
+    /// * Parameters:
+    ///     * `schema` : JSON Schema of the object.
+    /// * Return:
+    ///     * All the violations if the object is invalid.
+    pub fn validate_schema(&self, schema: &JsonObject) -> std::result::Result<(), Vec<JsonSchemaViolation>> {
+        let violations = schema::validate_schema(self, schema);
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+
+    /// Iterate over the members in arbitrary order.
+    /// * Return:
+    ///     * Iterator of the keys and the values.
+    pub fn iter(&self) -> impl Iterator<Item = (&JsonKey, &JsonValue)> {
+        self.members.iter()
+    }
+
+    /// Iterate over the members with mutable values in arbitrary order.
+    /// * Return:
+    ///     * Iterator of the keys and the mutable values.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&JsonKey, &mut JsonValue)> {
+        self.members.iter_mut()
+    }
+
+    /// Iterate over the keys in arbitrary order.
+    /// * Return:
+    ///     * Iterator of the keys.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.members.keys().map(|json_key| json_key.0.as_str())
+    }
+
+    /// Keys in the order of `JsonKey`, the same order as the `sort_keys` serializer option.
+    /// * Return:
+    ///     * Sorted keys.
+    pub fn keys_sorted(&self) -> Vec<&str> {
+        let mut keys: Vec<&str> = self.keys().collect();
+        keys.sort_unstable();
+        keys
+    }
+
+    /// Iterate over the values in arbitrary order.
+    /// * Return:
+    ///     * Iterator of the values.
+    pub fn values(&self) -> impl Iterator<Item = &JsonValue> {
+        self.members.values()
+    }
+
+    /// Iterate over the mutable values in arbitrary order.
+    /// * Return:
+    ///     * Iterator of the mutable values.
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut JsonValue> {
+        self.members.values_mut()
+    }
+
+    /// Get the value of the member.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    /// * Return:
+    ///     * Value of the member, or `None` if the object does not have the key.
+    pub fn get(&self, key: &str) -> Option<&JsonValue> {
+        self.members.get(key)
+    }
+
+    /// Get the mutable value of the member.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    /// * Return:
+    ///     * Value of the member, or `None` if the object does not have the key.
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut JsonValue> {
+        self.members.get_mut(key)
+    }
+
+    /// Get the string value of the member.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    /// * Return:
+    ///     * String, or `None` if the object does not have the key or the value is not a string.
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.get(key)?.as_str()
+    }
+
+    /// Get the integer value of the member.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    /// * Return:
+    ///     * Integer, or `None` if the object does not have the key or the value is not an integer(`Int`).
+    pub fn get_i64(&self, key: &str) -> Option<i64> {
+        self.get(key)?.as_i64()
+    }
+
+    /// Get the float value of the member. Integers are converted if they are exactly representable(up to 2^53).
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    /// * Return:
+    ///     * Float, or `None` if the object does not have the key or the value is not a number that fits in `f64`.
+    pub fn get_f64(&self, key: &str) -> Option<f64> {
+        self.get(key)?.as_f64()
+    }
+
+    /// Get the number value of the member as float, whatever kind of number it is, e.g. both `5` and `5.0` are `5.0`.
+    /// Unlike `get_f64`, integers beyond 2^53 are rounded to the nearest float. See `JsonNumberType::as_f64`.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    /// * Return:
+    ///     * Float, or `None` if the object does not have the key or the value is not a number.
+    pub fn get_number_as_f64(&self, key: &str) -> Option<f64> {
+        match self.get(key)? {
+            JsonValue::ValueNumber(json_number) => Some(json_number.as_f64()),
+            _ => None,
+        }
+    }
+
+    /// Get the number value of the member as integer without loss, e.g. both `5` and `5.0` are `5`.
+    /// Unlike `get_i64`, floats are accepted if they are integral and in the range of `i64`. See `JsonNumberType::as_i64`.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    /// * Return:
+    ///     * Integer, or `None` if the object does not have the key, the value is not a number or it does not fit in `i64`.
+    pub fn get_number_as_i64(&self, key: &str) -> Option<i64> {
+        match self.get(key)? {
+            JsonValue::ValueNumber(json_number) => json_number.as_i64(),
+            _ => None,
+        }
+    }
+
+    /// Get the bool value of the member.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    /// * Return:
+    ///     * Bool, or `None` if the object does not have the key or the value is not a bool.
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        self.get(key)?.as_bool()
+    }
+
+    /// Get the array value of the member.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    /// * Return:
+    ///     * Array, or `None` if the object does not have the key or the value is not an array.
+    pub fn get_array(&self, key: &str) -> Option<&Vec<JsonValue>> {
+        self.get(key)?.as_array()
+    }
+
+    /// Get the object value of the member. Owned objects are got with `get_owned_object`.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    /// * Return:
+    ///     * Shared reference to the object, or `None` if the object does not have the key or the value is not a shared object.
+    pub fn get_object(&self, key: &str) -> Option<JsonSharedObject> {
+        self.get(key)?.as_object().cloned()
+    }
+
+    /// Get the owned object value of the member.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    /// * Return:
+    ///     * Object, or `None` if the object does not have the key or the value is not an owned object.
+    pub fn get_owned_object(&self, key: &str) -> Option<&JsonObject> {
+        self.get(key)?.as_owned_object()
+    }
+
+    /// Get the mutable owned object value of the member.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    /// * Return:
+    ///     * Object, or `None` if the object does not have the key or the value is not an owned object.
+    pub fn get_owned_object_mut(&mut self, key: &str) -> Option<&mut JsonObject> {
+        self.get_mut(key)?.as_owned_object_mut()
+    }
+
+    /// Get the string value of the member like `get_str`, but with an error that explains what is wrong.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    /// * Return:
+    ///     * String. `KeyNotFound` error if the object does not have the key, `TypeMismatch` error if the type is wrong.
+    pub fn try_get_str(&self, key: &str) -> Result<&str> {
+        self.typed_value_getter(key, "string", JsonObject::get_str)
+    }
+
+    /// Get the integer value of the member like `get_i64`, but with an error that explains what is wrong.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    /// * Return:
+    ///     * Integer. `KeyNotFound` error if the object does not have the key, `TypeMismatch` error if the type is wrong.
+    pub fn try_get_i64(&self, key: &str) -> Result<i64> {
+        self.typed_value_getter(key, "integer", JsonObject::get_i64)
+    }
+
+    /// Get the float value of the member like `get_f64`, but with an error that explains what is wrong.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    /// * Return:
+    ///     * Float. `KeyNotFound` error if the object does not have the key, `TypeMismatch` error if the type is wrong.
+    pub fn try_get_f64(&self, key: &str) -> Result<f64> {
+        self.typed_value_getter(key, "number", JsonObject::get_f64)
+    }
+
+    /// Get the number value of the member as float like `get_number_as_f64`, but with an error that explains what is wrong.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    /// * Return:
+    ///     * Float. `KeyNotFound` error if the object does not have the key, `TypeMismatch` error if the value is not a number.
+    pub fn try_get_number_as_f64(&self, key: &str) -> Result<f64> {
+        self.typed_value_getter(key, "number", JsonObject::get_number_as_f64)
+    }
+
+    /// Get the number value of the member as integer like `get_number_as_i64`, but with an error that explains what is wrong.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    /// * Return:
+    ///     * Integer. `KeyNotFound` error if the object does not have the key, `TypeMismatch` error if the value is not a number,
+    ///       `OutOfRange` error if it is not integral or does not fit in `i64`.
+    pub fn try_get_number_as_i64(&self, key: &str) -> Result<i64> {
+        self.try_get_number_as_f64(key)?;
+        match self.get(key) {
+            Some(JsonValue::ValueNumber(json_number)) => json_number.try_into_i64(),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Get the bool value of the member like `get_bool`, but with an error that explains what is wrong.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    /// * Return:
+    ///     * Bool. `KeyNotFound` error if the object does not have the key, `TypeMismatch` error if the type is wrong.
+    pub fn try_get_bool(&self, key: &str) -> Result<bool> {
+        self.typed_value_getter(key, "bool", JsonObject::get_bool)
+    }
+
+    /// Get the array value of the member like `get_array`, but with an error that explains what is wrong.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    /// * Return:
+    ///     * Array. `KeyNotFound` error if the object does not have the key, `TypeMismatch` error if the type is wrong.
+    pub fn try_get_array(&self, key: &str) -> Result<&Vec<JsonValue>> {
+        self.typed_value_getter(key, "array", JsonObject::get_array)
+    }
+
+    /// Get the object value of the member like `get_object`, but with an error that explains what is wrong.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    /// * Return:
+    ///     * Shared reference to the object. `KeyNotFound` error if the object does not have the key, `TypeMismatch` error if the type is wrong.
+    pub fn try_get_object(&self, key: &str) -> Result<JsonSharedObject> {
+        self.typed_value_getter(key, "object", JsonObject::get_object)
+    }
+
+    /// Get the owned object value of the member like `get_owned_object`, but with an error that explains what is wrong.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    /// * Return:
+    ///     * Object. `KeyNotFound` error if the object does not have the key, `TypeMismatch` error if the type is wrong.
+    pub fn try_get_owned_object(&self, key: &str) -> Result<&JsonObject> {
+        self.typed_value_getter(key, "owned object", JsonObject::get_owned_object)
+    }
+
+    /// Get the string value of the member, or the default.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    ///     * `default` : Value returned if the object does not have the key or the value is not a string.
+    /// * Return:
+    ///     * String or the default.
+    pub fn get_str_or(&self, key: &str, default: &str) -> String {
+        self.get_str(key).unwrap_or(default).to_string()
+    }
+
+    /// Get the integer value of the member like `get_i64`, or the default.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    ///     * `default` : Value returned if the object does not have the key or the value is not an integer.
+    /// * Return:
+    ///     * Integer or the default.
+    pub fn get_i64_or(&self, key: &str, default: i64) -> i64 {
+        self.get_i64(key).unwrap_or(default)
+    }
+
+    /// Get the float value of the member like `get_f64`, or the default.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    ///     * `default` : Value returned if the object does not have the key or the value is not a number that fits in `f64`.
+    /// * Return:
+    ///     * Float or the default.
+    pub fn get_f64_or(&self, key: &str, default: f64) -> f64 {
+        self.get_f64(key).unwrap_or(default)
+    }
+
+    /// Get the bool value of the member, or the default.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    ///     * `default` : Value returned if the object does not have the key or the value is not a bool.
+    /// * Return:
+    ///     * Bool or the default.
+    pub fn get_bool_or(&self, key: &str, default: bool) -> bool {
+        self.get_bool(key).unwrap_or(default)
+    }
+
+    /// Get the array value of the member, or the empty array.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    /// * Return:
+    ///     * Elements, or the empty slice if the object does not have the key or the value is not an array.
+    pub fn get_array_or_empty(&self, key: &str) -> &[JsonValue] {
+        self.get_array(key).map_or(&[], Vec::as_slice)
+    }
+
+    /// Get the string value of the member, or the default if the object does not have the key.
+    /// Unlike `get_str_or`, a value of the wrong type is an error rather than the default.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    ///     * `default` : Value returned if the object does not have the key.
+    /// * Return:
+    ///     * String or the default. `TypeMismatch` error if the value is not a string.
+    pub fn try_get_str_or(&self, key: &str, default: &str) -> Result<String> {
+        self.defaulted_getter(key, default, JsonObject::try_get_str)
+            .map(str::to_string)
+    }
+
+    /// Get the integer value of the member, or the default if the object does not have the key.
+    /// Unlike `get_i64_or`, a value of the wrong type is an error rather than the default.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    ///     * `default` : Value returned if the object does not have the key.
+    /// * Return:
+    ///     * Integer or the default. `TypeMismatch` error if the value is not an integer.
+    pub fn try_get_i64_or(&self, key: &str, default: i64) -> Result<i64> {
+        self.defaulted_getter(key, default, JsonObject::try_get_i64)
+    }
+
+    /// Get the float value of the member, or the default if the object does not have the key.
+    /// Unlike `get_f64_or`, a value of the wrong type is an error rather than the default.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    ///     * `default` : Value returned if the object does not have the key.
+    /// * Return:
+    ///     * Float or the default. `TypeMismatch` error if the value is not a number that fits in `f64`.
+    pub fn try_get_f64_or(&self, key: &str, default: f64) -> Result<f64> {
+        self.defaulted_getter(key, default, JsonObject::try_get_f64)
+    }
+
+    /// Get the bool value of the member, or the default if the object does not have the key.
+    /// Unlike `get_bool_or`, a value of the wrong type is an error rather than the default.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    ///     * `default` : Value returned if the object does not have the key.
+    /// * Return:
+    ///     * Bool or the default. `TypeMismatch` error if the value is not a bool.
+    pub fn try_get_bool_or(&self, key: &str, default: bool) -> Result<bool> {
+        self.defaulted_getter(key, default, JsonObject::try_get_bool)
+    }
+
+    /// Get the array value of the member, or the empty array if the object does not have the key.
+    /// Unlike `get_array_or_empty`, a value of the wrong type is an error rather than the empty array.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    /// * Return:
+    ///     * Elements or the empty slice. `TypeMismatch` error if the value is not an array.
+    pub fn try_get_array_or_empty(&self, key: &str) -> Result<&[JsonValue]> {
+        self.defaulted_getter(key, &[], |json_object, key| json_object.try_get_array(key).map(Vec::as_slice))
+    }
+
+    fn defaulted_getter<'a, T>(&'a self, key: &str, default: T, try_getter: fn(&'a JsonObject, &str) -> Result<T>) -> Result<T> {
+        if self.contains_key(key) {
+            try_getter(self, key)
+        } else {
+            Ok(default)
+        }
+    }
+
+    /// Get the array of strings of the member. Null elements are not skipped but are errors.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    /// * Return:
+    ///     * Strings. `KeyNotFound` error if the object does not have the key,
+    ///       `TypeMismatch` error with the index if the value is not an array or an element is not a string.
+    pub fn get_str_array(&self, key: &str) -> Result<Vec<String>> {
+        self.typed_array_getter(key, "string", |json_value| json_value.as_str().map(str::to_string))
+    }
+
+    /// Get the array of integers of the member like `get_str_array`. Elements are converted like `get_i64`.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    /// * Return:
+    ///     * Integers. `KeyNotFound` error if the object does not have the key,
+    ///       `TypeMismatch` error with the index if the value is not an array or an element is not an integer.
+    pub fn get_i64_array(&self, key: &str) -> Result<Vec<i64>> {
+        self.typed_array_getter(key, "integer", JsonValue::as_i64)
+    }
+
+    /// Get the array of floats of the member like `get_str_array`. Elements are converted like `get_f64`.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    /// * Return:
+    ///     * Floats. `KeyNotFound` error if the object does not have the key,
+    ///       `TypeMismatch` error with the index if the value is not an array or an element is not a number that fits in `f64`.
+    pub fn get_f64_array(&self, key: &str) -> Result<Vec<f64>> {
+        self.typed_array_getter(key, "number", JsonValue::as_f64)
+    }
+
+    /// Get the array of bools of the member like `get_str_array`.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    /// * Return:
+    ///     * Bools. `KeyNotFound` error if the object does not have the key,
+    ///       `TypeMismatch` error with the index if the value is not an array or an element is not a bool.
+    pub fn get_bool_array(&self, key: &str) -> Result<Vec<bool>> {
+        self.typed_array_getter(key, "bool", JsonValue::as_bool)
+    }
+
+    fn typed_array_getter<T>(&self, key: &str, expected_type_str: &str, getter: fn(&JsonValue) -> Option<T>) -> Result<Vec<T>> {
+        let json_array = self.try_get_array(key)?;
+        let mut values = Vec::with_capacity(json_array.len());
+        for (idx, json_element_value) in json_array.iter().enumerate() {
+            let value = getter(json_element_value).ok_or_else(|| {
+                JsonError::new(
+                    JsonErrorKind::TypeMismatch,
+                    Some(format!(
+                        "{:?}[{}]: expected {}, found {}",
+                        key,
+                        idx,
+                        expected_type_str,
+                        json_element_value.type_name()
+                    )),
+                )
+            })?;
+            values.push(value);
+        }
+        Ok(values)
+    }
+
+    /// Append the value to the array of the member. The array is created if the object does not have the key.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    ///     * `value` : Value to append.
+    /// * Return:
+    ///     * `TypeMismatch` error if the value of the member is not an array.
+    pub fn push_to_array(&mut self, key: &str, value: impl Into<JsonValue>) -> Result<()> {
+        let json_value = self
+            .members
+            .entry(JsonKey(key.to_string()))
+            .or_insert_with(|| JsonValue::ValueArray(Vec::new()));
+        let type_name_str = json_value.type_name();
+        let json_array = json_value.as_array_mut().ok_or_else(|| {
+            JsonError::new(
+                JsonErrorKind::TypeMismatch,
+                Some(format!("{:?}: expected array, found {}", key, type_name_str)),
+            )
+        })?;
+        json_array.push(value.into());
+        Ok(())
+    }
+
+    /// Get the length of the array of the member.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    /// * Return:
+    ///     * Length, or `None` if the object does not have the key or the value is not an array.
+    pub fn array_len(&self, key: &str) -> Option<usize> {
+        Some(self.get_array(key)?.len())
+    }
+
+    /// Get the element of the array of the member.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    ///     * `idx` : Index of the element.
+    /// * Return:
+    ///     * Element, or `None` if the object does not have the key, the value is not an array or the index is out of range.
+    pub fn array_get(&self, key: &str, idx: usize) -> Option<&JsonValue> {
+        self.get_array(key)?.get(idx)
+    }
+
+    /// Set the element of the array of the member.
+    /// * Parameters:
+    ///     * `key` : Key of the member.
+    ///     * `idx` : Index of the element.
+    ///     * `value` : New value of the element.
+    /// * Return:
+    ///     * `KeyNotFound` error if the object does not have the key, `TypeMismatch` error if the value is not an array,
+    ///       `OutOfRange` error if the index is out of range.
+    pub fn array_set(&mut self, key: &str, idx: usize, value: impl Into<JsonValue>) -> Result<()> {
+        self.try_get_array(key)?;
+        let json_array = self.get_mut(key).and_then(JsonValue::as_array_mut).unwrap();
+        let len = json_array.len();
+        let json_element_value = json_array.get_mut(idx).ok_or_else(|| {
+            JsonError::new(
+                JsonErrorKind::OutOfRange,
+                Some(format!(
+                    "{:?}: index {} is out of range for the array of length {}",
+                    key, idx, len
+                )),
+            )
+        })?;
+        *json_element_value = value.into();
+        Ok(())
+    }
+
+    fn typed_value_getter<'a, T>(
+        &'a self,
+        key: &str,
+        expected_type_str: &str,
+        getter: fn(&'a JsonObject, &str) -> Option<T>,
+    ) -> Result<T> {
+        let json_value = self
+            .get(key)
+            .ok_or_else(|| JsonError::new(JsonErrorKind::KeyNotFound, Some(format!("{:?}", key))))?;
+        getter(self, key).ok_or_else(|| {
+            JsonError::new(
+                JsonErrorKind::TypeMismatch,
+                Some(format!(
+                    "{:?}: expected {}, found {}",
+                    key,
+                    expected_type_str,
+                    json_value.type_name()
+                )),
+            )
+        })
+    }
+
+    /// Parse JSON string to JSON Onject.
+    /// * Parameters:
+    ///     * `content_str` : JSON string(&str).
+    /// * Return:
+    ///     * JSON Object struct.
+    pub fn parse(content_str: &str) -> Result<JsonObject> {
+        JsonParser::parse(content_str)
+    }
+
+    /// Parse JSON string to JSON Onject with the parser options.
+    /// * Parameters:
+    ///     * `content_str` : JSON string(&str).
+    ///     * `options` : Parser options.
+    /// * Return:
+    ///     * JSON Object struct.
+    pub fn parse_with_options(content_str: &str, options: &JsonParserOptions) -> Result<JsonObject> {
+        JsonParser::parse_with_options(content_str, options)
+    }
+
+    /// Serialize JSON object to string.
+    /// * Parameters:
+    ///     * `json_object` : JSON Object struct.
+    ///     * `newline_kind` : Newline code(LF or CRLF) when serializing JSON.
+    ///     * `indent_kind` : Indent kind(Tab of Space) when serializing JSON.
+    /// * Return:
+    ///     * JSON string.
+    pub fn serialize(&self, newline_kind: JsonSerializerNewLineKind, indent_kind: JsonSerializerIndentKind) -> Result<String> {
+        self.serialize_with_options(&JsonSerializerOptions::with_kinds(newline_kind, indent_kind))
+    }
+
+    /// Serialize JSON object to string with the serializer options.
+    /// * Parameters:
+    ///     * `options` : Serializer options.
+    /// * Return:
+    ///     * JSON string.
+    pub fn serialize_with_options(&self, options: &JsonSerializerOptions) -> Result<String> {
+        JsonSerializer::serialize(JsonSerializerRoot::Object(self), options)
+    }
+
+    /// Serialize JSON object and append it to `buf`, e.g. to reuse one buffer for many objects.
+    /// On error, `buf` is left as it was.
+    /// * Parameters:
+    ///     * `buf` : Buffer that the JSON string is appended to.
+    ///     * `options` : Serializer options.
+    pub fn serialize_into(&self, buf: &mut String, options: &JsonSerializerOptions) -> Result<()> {
+        JsonSerializer::serialize_into(JsonSerializerRoot::Object(self), buf, options)
+    }
+
+    /// Serialize only the members of `keys` of JSON object to string, without cloning the object.
+    /// Members are serialized in the order of `keys` unless `sort_keys` is set. Keys that the object does not have are skipped,
+    /// or make an error with `error_on_missing_keys`.
+    /// * Parameters:
+    ///     * `keys` : Keys of the top-level members to serialize.
+    ///     * `options` : Serializer options.
+    /// * Return:
+    ///     * JSON string.
+    pub fn serialize_only(&self, keys: &[&str], options: &JsonSerializerOptions) -> Result<String> {
+        JsonSerializer::serialize(JsonSerializerRoot::Projection(self, keys), options)
+    }
+
+    /// Serialize only the value referred by JSON Pointer (RFC 6901), e.g. `/servers/2/tls`.
+    /// * Parameters:
+    ///     * `pointer` : JSON Pointer. The empty pointer refers to the whole object.
+    ///     * `options` : Serializer options.
+    /// * Return:
+    ///     * JSON string of the referred value.
+    pub fn serialize_pointer(&self, pointer: &str, options: &JsonSerializerOptions) -> Result<String> {
+        pointer::with_pointer_root(self, pointer, JsonErrorKind::SerializeErrorInValue, |root| {
+            JsonSerializer::serialize(root, options)
+        })
+    }
+
+    /// Serialize JSON object to string with the default options(LF, 4 spaces, trailing newline).
+    /// * Return:
+    ///     * JSON string.
+    pub fn to_pretty_string(&self) -> Result<String> {
+        self.serialize_with_options(&JsonSerializerOptions::new())
+    }
+
+    /// Serialize JSON object to one line string without whitespace and trailing newline.
+    /// * Return:
+    ///     * JSON string.
+    pub fn to_compact_string(&self) -> Result<String> {
+        self.serialize_with_options(&JsonSerializerOptions::new().compact(true).trailing_newline(false))
+    }
+
+    /// Serialize JSON object to canonical JSON (RFC 8785 JSON Canonicalization Scheme), e.g. for signing.
+    /// Members are sorted by UTF-16 code units of keys, there is no whitespace, and numbers are formatted like ECMAScript.
+    /// * Return:
+    ///     * Canonical JSON string without a trailing newline.
+    pub fn serialize_canonical(&self) -> Result<String> {
+        JsonSerializer::serialize_canonical(JsonSerializerRoot::Object(self))
+    }
+
+    /// Serialize JSON object to bytes in the encoding, e.g. UTF-16LE with BOM for legacy Windows applications.
+    /// * Parameters:
+    ///     * `options` : Serializer options.
+    ///     * `encoding` : Encoding of the bytes.
+    /// * Return:
+    ///     * Encoded JSON string.
+    pub fn serialize_to_bytes(&self, options: &JsonSerializerOptions, encoding: JsonSerializerOutputEncoding) -> Result<Vec<u8>> {
+        JsonSerializer::serialize_to_bytes(JsonSerializerRoot::Object(self), options, encoding)
+    }
+
+    /// Serialize JSON object to `std::io::Write` incrementally.
+    /// * Parameters:
+    ///     * `writer` : Destination of the JSON string.
+    ///     * `newline_kind` : Newline code(LF or CRLF) when serializing JSON.
+    ///     * `indent_kind` : Indent kind(Tab of Space) when serializing JSON.
+    pub fn serialize_to_writer<W: Write>(
+        &self,
+        writer: &mut W,
+        newline_kind: JsonSerializerNewLineKind,
+        indent_kind: JsonSerializerIndentKind,
+    ) -> Result<()> {
+        JsonSerializer::serialize_to_writer(
+            JsonSerializerRoot::Object(self),
+            writer,
+            &JsonSerializerOptions::with_kinds(newline_kind, indent_kind),
+        )
+    }
+
+    /// Serialize JSON object in chunks, e.g. to send a huge array over the network without holding the whole string.
+    /// `sink` is called whenever the buffer reaches `chunk_size` bytes and once at the end with the rest.
+    /// The concatenation of the chunks is the same as `serialize_with_options`. An error of `sink` aborts serializing.
+    /// * Parameters:
+    ///     * `chunk_size` : Size of the buffer in bytes(e.g. 64 KiB). 0 is an error.
+    ///     * `options` : Serializer options.
+    ///     * `sink` : Function called with each chunk.
+    pub fn serialize_to_chunks<F: FnMut(&str) -> Result<()>>(
+        &self,
+        chunk_size: usize,
+        options: &JsonSerializerOptions,
+        sink: F,
+    ) -> Result<()> {
+        JsonSerializer::serialize_to_chunks(JsonSerializerRoot::Object(self), chunk_size, sink, options)
+    }
+
+    /// Serialize JSON object to the file. The file is replaced atomically, so a crash mid-write never leaves a truncated file.
+    /// * Parameters:
+    ///     * `path` : Path of the JSON file.
+    ///     * `newline_kind` : Newline code(LF or CRLF) when serializing JSON.
+    ///     * `indent_kind` : Indent kind(Tab of Space) when serializing JSON.
+    pub fn serialize_to_file<P: AsRef<Path>>(
+        &self,
+        path: P,
+        newline_kind: JsonSerializerNewLineKind,
+        indent_kind: JsonSerializerIndentKind,
+    ) -> Result<()> {
+        JsonSerializer::serialize_to_file(
+            JsonSerializerRoot::Object(self),
+            path,
+            &JsonSerializerOptions::with_kinds(newline_kind, indent_kind),
+        )
+    }
+}
+
+/// `for (json_key, json_value) in &json_object` iterates like `iter`.
+impl<'a> IntoIterator for &'a JsonObject {
+    type Item = (&'a JsonKey, &'a JsonValue);
+    type IntoIter = std::collections::hash_map::Iter<'a, JsonKey, JsonValue>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.members.iter()
+    }
+}
+
+/// `for (json_key, json_value) in &mut json_object` iterates like `iter_mut`.
+impl<'a> IntoIterator for &'a mut JsonObject {
+    type Item = (&'a JsonKey, &'a mut JsonValue);
+    type IntoIter = std::collections::hash_map::IterMut<'a, JsonKey, JsonValue>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.members.iter_mut()
+    }
+}
+
+/// `for (json_key, json_value) in json_object` consumes the object. The order is arbitrary.
+impl IntoIterator for JsonObject {
+    type Item = (JsonKey, JsonValue);
+    type IntoIter = std::collections::hash_map::IntoIter<JsonKey, JsonValue>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.members.into_iter()
+    }
+}
+
+/// `null` returned by `Index` for a missing member or element.
+const NULL_VALUE: &JsonValue = &JsonValue::ValueNull;
+
+/// `json_object["key"]` is the value of the member, or `null` if the object does not have the key.
+/// Owned child objects can be indexed in a chain, e.g. `json_object["server"]["port"]`. See `Index<&str>` for `JsonValue`.
+impl std::ops::Index<&str> for JsonObject {
+    type Output = JsonValue;
+
+    fn index(&self, key: &str) -> &JsonValue {
+        self.members.get(key).unwrap_or(NULL_VALUE)
+    }
+}
+
+/// `json_object["key"] = value` inserts the member. A missing member is added as `null` first.
+impl std::ops::IndexMut<&str> for JsonObject {
+    fn index_mut(&mut self, key: &str) -> &mut JsonValue {
+        self.members.entry(JsonKey(key.to_string())).or_default()
+    }
+}
+
+/// `json_value["key"]` is the value of the member of an owned object(`ValueOwnedObject`), or `null` if the object does not have the key
+/// or the value is not an owned object.
+/// A shared object(`ValueObject`) cannot lend its members through `&JsonValue` because they are behind `JsonSharedObject`,
+/// so it reads as `null` too. Parse with `JsonParserOptions::owned_objects` for chained indexing, or use `get_path` or `pointer`.
+impl std::ops::Index<&str> for JsonValue {
+    type Output = JsonValue;
+
+    fn index(&self, key: &str) -> &JsonValue {
+        match self {
+            JsonValue::ValueOwnedObject(json_object) => &json_object[key],
+            _ => NULL_VALUE,
+        }
+    }
+}
+
+/// `json_value["key"] = value` inserts the member of an owned object(`ValueOwnedObject`). A missing member is added as `null` first.
+/// Unlike `Index`, panics if the value is not an owned object, including a shared object(`ValueObject`).
+impl std::ops::IndexMut<&str> for JsonValue {
+    fn index_mut(&mut self, key: &str) -> &mut JsonValue {
+        match self {
+            JsonValue::ValueOwnedObject(json_object) => &mut json_object[key],
+            _ => panic!("Cannot index {} with \"{}\".", self.type_name(), key),
+        }
+    }
+}
+
+/// `json_value[2]` is the element of the array, or `null` if the value is not an array or the index is out of range.
+impl std::ops::Index<usize> for JsonValue {
+    type Output = JsonValue;
+
+    fn index(&self, idx: usize) -> &JsonValue {
+        match self {
+            JsonValue::ValueArray(json_array) => json_array.get(idx).unwrap_or(NULL_VALUE),
+            _ => NULL_VALUE,
+        }
+    }
+}
+
+/// `json_value[2] = value` replaces the element of the array.
+/// Unlike `Index`, panics if the value is not an array or the index is out of range, because there is no element to return.
+impl std::ops::IndexMut<usize> for JsonValue {
+    fn index_mut(&mut self, idx: usize) -> &mut JsonValue {
+        match self {
+            JsonValue::ValueArray(json_array) => {
+                let len = json_array.len();
+                match json_array.get_mut(idx) {
+                    Some(json_value) => json_value,
+                    None => panic!("Index {} is out of range for the array of length {}.", idx, len),
+                }
+            }
+            _ => panic!("Cannot index {} with {}.", self.type_name(), idx),
+        }
+    }
+}
+
+/// Compact JSON like `to_compact_string`. See `Display` of `JsonValue`.
+impl fmt::Display for JsonObject {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        display_serialized(self.serialize_with_options(&display_options()), f)
+    }
+}
+
+/// Parse JSON string like `JsonObject::parse`, e.g. `content_str.parse::<JsonObject>()`.
+/// The error is `JsonError` itself (e.g. `ParseErrorInValue`) rather than the boxed error of `Result`,
+/// since parsing a string never fails with an IO error. `FromStr` is not implemented for `JsonValue`,
+/// because the parser only reads a document whose top-level value is an object.
+impl std::str::FromStr for JsonObject {
+    type Err = JsonError;
+
+    /// Parse JSON string like `JsonObject::parse`.
+    /// ```
+    /// use json_parser_serializer::{JsonErrorKind, JsonObject};
+    ///
+    /// let json_object: JsonObject = r#"{"port": 8080}"#.parse().unwrap();
+    /// assert_eq!(json_object.get_i64("port").unwrap(), 8080);
+    ///
+    /// let err = "{\"port\": }".parse::<JsonObject>().unwrap_err();
+    /// assert_eq!(err.err_kind, JsonErrorKind::ParseErrorInValue);
+    /// ```
+    fn from_str(content_str: &str) -> std::result::Result<JsonObject, JsonError> {
+        JsonObject::parse(content_str).map_err(|err| match err.downcast::<JsonError>() {
+            Ok(json_err) => *json_err,
+            // 文字列の解析は JsonError しか返さないが、念のため文言を残して包む
+            Err(err) => JsonError {
+                err_kind: JsonErrorKind::ParseErrorInObject,
+                op_additional_message: Some(err.to_string()),
+            },
+        })
+    }
+}
+
+/// Empty object like `new`.
+impl Default for JsonObject {
+    fn default() -> Self {
+        JsonObject::new()
+    }
+}
+
+/// Hash independent of the order of the members. See `Hash` of `JsonValue`.
+impl std::hash::Hash for JsonObject {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash::object_hasher(self, None, state);
+    }
+}
+
+/// NaN equals NaN. See `Eq` of `JsonValue`.
+impl Eq for JsonObject {}
+
+/// Options of the parser. The default options accept only strict JSON.
+#[derive(Clone, Debug, Default)]
+pub struct JsonParserOptions {
+    /// Whether a `,` after the last member of objects and the last element of arrays is accepted(JSON5). Default is `false`.
+    pub allow_trailing_commas: bool,
+    /// Whether a byte order mark(U+FEFF) at the start of the text is skipped. Default is `false`.
+    pub skip_bom: bool,
+    /// Numbers with more significant digits than this are kept as `JsonNumberType::BigDecimal` with their text,
+    /// and so are integers out of the range of `i64` and `u64`. Default is `None`(numbers are `Int`, `Uint` or `Float`).
+    pub big_decimal_digits: Option<usize>,
+    /// Whether nested objects are `JsonValue::ValueOwnedObject` instead of `JsonValue::ValueObject`. Default is `false`.
+    pub owned_objects: bool,
+}
+
+impl JsonParserOptions {
+    /// Create default parser options.
+    /// * Return:
+    ///     * Parser options struct.
+    pub fn new() -> JsonParserOptions {
+        JsonParserOptions {
+            allow_trailing_commas: false,
+            skip_bom: false,
+            big_decimal_digits: None,
+            owned_objects: false,
+        }
+    }
+
+    /// Set whether trailing commas are accepted.
+    pub fn allow_trailing_commas(mut self, allow_trailing_commas: bool) -> JsonParserOptions {
+        self.allow_trailing_commas = allow_trailing_commas;
+        self
+    }
+
+    /// Set whether a byte order mark at the start of the text is skipped.
+    pub fn skip_bom(mut self, skip_bom: bool) -> JsonParserOptions {
+        self.skip_bom = skip_bom;
+        self
+    }
+
+    /// Set the number of significant digits above which numbers are kept as `BigDecimal`.
+    pub fn big_decimal_digits(mut self, big_decimal_digits: Option<usize>) -> JsonParserOptions {
+        self.big_decimal_digits = big_decimal_digits;
+        self
+    }
+
+    /// Set whether nested objects are owned objects.
+    pub fn owned_objects(mut self, owned_objects: bool) -> JsonParserOptions {
+        self.owned_objects = owned_objects;
+        self
+    }
+}
+
+/// Enum that specifies which value is kept by `JsonObject::merge` when both objects have a non-object value for the key.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum JsonMergeConflictPolicy {
+    /// The value of the other object replaces the value.
+    OtherWins,
+    /// The value is kept.
+    SelfWins,
+}
+
+/// Enum that specifies how `JsonObject::merge` combines arrays when both objects have an array for the key.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum JsonMergeArrayPolicy {
+    /// Arrays follow `conflict_policy` like the other values.
+    Replace,
+    /// The elements of the other array are appended.
+    Concat,
+}
+
+/// Strategy of `JsonObject::merge`. Objects in both are always merged recursively.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct JsonMergeStrategy {
+    /// Which value is kept for the other values in both. Default is `OtherWins`.
+    pub conflict_policy: JsonMergeConflictPolicy,
+    /// How arrays in both are combined. Default is `Replace`.
+    pub array_policy: JsonMergeArrayPolicy,
+    /// Whether `null` in the other object is ignored instead of being set. Default is `false`.
+    pub ignore_null: bool,
+}
+
+impl JsonMergeStrategy {
+    /// Create default merge strategy.
+    /// * Return:
+    ///     * Merge strategy struct.
+    pub fn new() -> JsonMergeStrategy {
+        JsonMergeStrategy {
+            conflict_policy: JsonMergeConflictPolicy::OtherWins,
+            array_policy: JsonMergeArrayPolicy::Replace,
+            ignore_null: false,
+        }
+    }
+
+    /// Set which value is kept for the values in both.
+    pub fn conflict_policy(mut self, conflict_policy: JsonMergeConflictPolicy) -> JsonMergeStrategy {
+        self.conflict_policy = conflict_policy;
+        self
+    }
+
+    /// Set how arrays in both are combined.
+    pub fn array_policy(mut self, array_policy: JsonMergeArrayPolicy) -> JsonMergeStrategy {
+        self.array_policy = array_policy;
+        self
+    }
+
+    /// Set whether `null` in the other object is ignored.
+    pub fn ignore_null(mut self, ignore_null: bool) -> JsonMergeStrategy {
+        self.ignore_null = ignore_null;
+        self
+    }
+}
+
+impl Default for JsonMergeStrategy {
+    fn default() -> Self {
+        JsonMergeStrategy::new()
+    }
+}
+
+/// Options of `deep_eq`. The order of members never matters, and objects are compared by their contents, not by `Rc`.
+/// Unlike `==`, NaN is not equal to anything, including NaN, even with `float_epsilon`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct JsonEqOptions {
+    /// Whether `Int` and `Float` are compared as numbers, e.g. `2` equals `2.0`. Default is `false`.
+    /// `BigDecimal` is compared with the other kinds through `f64` too.
+    pub numeric_coercion: bool,
+    /// Floats whose difference is at most the epsilon are equal. `None` requires exact equality. Default is `None`.
+    /// Ints are compared with it only when `numeric_coercion` converts one of them.
+    pub float_epsilon: Option<f64>,
+}
+
+impl JsonEqOptions {
+    /// Create default equality options, which are as strict as `==`.
+    /// * Return:
+    ///     * Equality options struct.
+    pub fn new() -> JsonEqOptions {
+        JsonEqOptions {
+            numeric_coercion: false,
+            float_epsilon: None,
+        }
+    }
+
+    /// Set whether `Int` and `Float` are compared as numbers.
+    pub fn numeric_coercion(mut self, numeric_coercion: bool) -> JsonEqOptions {
+        self.numeric_coercion = numeric_coercion;
+        self
+    }
+
+    /// Set the tolerance of floats.
+    pub fn float_epsilon(mut self, float_epsilon: Option<f64>) -> JsonEqOptions {
+        self.float_epsilon = float_epsilon;
+        self
+    }
+}
+
+impl Default for JsonEqOptions {
+    fn default() -> Self {
+        JsonEqOptions::new()
+    }
+}
+
+/// Options of `JsonObject::prune_nulls`. Null members of objects are always removed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct JsonPruneOptions {
+    /// Whether null elements of arrays are removed too. Default is `false`.
+    pub in_arrays: bool,
+    /// Whether objects and arrays that become empty by the pruning are removed too, which may cascade to their parents.
+    /// Objects and arrays that are empty from the start are kept. Default is `false`.
+    pub drop_empty_containers: bool,
+}
+
+impl JsonPruneOptions {
+    /// Create default pruning options, which remove only null members of objects.
+    /// * Return:
+    ///     * Pruning options struct.
+    pub fn new() -> JsonPruneOptions {
+        JsonPruneOptions {
+            in_arrays: false,
+            drop_empty_containers: false,
+        }
+    }
+
+    /// Set whether null elements of arrays are removed.
+    pub fn in_arrays(mut self, in_arrays: bool) -> JsonPruneOptions {
+        self.in_arrays = in_arrays;
+        self
+    }
+
+    /// Set whether objects and arrays that become empty are removed.
+    pub fn drop_empty_containers(mut self, drop_empty_containers: bool) -> JsonPruneOptions {
+        self.drop_empty_containers = drop_empty_containers;
+        self
+    }
+}
+
+impl Default for JsonPruneOptions {
+    fn default() -> Self {
+        JsonPruneOptions::new()
+    }
+}
+
+/// Violation of JSON Schema reported by `JsonObject::validate_schema`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct JsonSchemaViolation {
+    /// JSON Pointer of the invalid value, e.g. `/servers/0/port`. The empty string is the object itself.
+    pub instance_path: String,
+    /// Keyword of the schema that failed, e.g. `required`.
+    pub keyword: String,
+    /// Human-readable description of the violation.
+    pub message: String,
+}
+
+/// `/servers/0/port: 70000 is greater than 65535. (maximum)`
+impl fmt::Display for JsonSchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {} ({})", self.instance_path, self.message, self.keyword)
+    }
+}
+
+/// Enum that specifies newline code(LF or CRLF) when serializing JSON.
+#[allow(dead_code)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum JsonSerializerNewLineKind {
+    Lf,
+    CrLf,
+    /// CRLF on Windows and LF on the other platforms, resolved when serializing.
+    Platform,
+}
+
+impl JsonSerializerNewLineKind {
+    /// Old name of `CrLf`.
+    #[deprecated(note = "Use `JsonSerializerNewLineKind::CrLf` instead.")]
+    #[allow(non_upper_case_globals)]
+    pub const Crlf: JsonSerializerNewLineKind = JsonSerializerNewLineKind::CrLf;
+}
+
+/// Enum that specifies indent kind(Tab of Space) when serializing JSON. `Space(4)` means that specifies 4 spaces as indent.
+/// It is not `Copy` because of `Custom`.
+#[allow(dead_code)]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum JsonSerializerIndentKind {
+    Tab,
+    Space(usize),
+    /// The string is used verbatim as the indent of each level, e.g. `" * "` for JSON embedded in comments.
+    /// Control characters other than tab are rejected. Characters other than spaces and tabs make the output invalid JSON,
+    /// so they are the caller's responsibility.
+    Custom(String),
+}
+
+/// Enum that specifies separator between key and value when serializing JSON.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum JsonSerializerColonStyle {
+    /// `"key": value`
+    SpaceAfter,
+    /// `"key" : value`
+    SpaceBoth,
+    /// `"key":value`
+    None,
+}
+
+/// Enum that specifies how NaN and Infinity are serialized, since JSON cannot represent them.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum JsonSerializerNonFiniteFloatPolicy {
+    /// Serialization fails with the path of the value.
+    Error,
+    /// `null` like `JSON.stringify`.
+    Null,
+    /// `"NaN"`, `"Infinity"` or `"-Infinity"`.
+    String,
+}
+
+/// Enum that specifies which numbers are serialized as strings, e.g. `"9007199254740993"`.
+/// This changes the JSON type of the values, and is intended for consumers such as JavaScript
+/// that cannot represent large integers exactly.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum JsonSerializerNumberStringPolicy {
+    /// All numbers are serialized as numbers.
+    Never,
+    /// Ints whose absolute value is greater than the threshold are serialized as strings.
+    /// `IntsAbove(9007199254740991)` quotes the ints that `Number` of JavaScript cannot represent exactly.
+    IntsAbove(i64),
+    /// All numbers are serialized as strings. NaN and Infinity follow `non_finite_float`.
+    All,
+}
+
+/// Enum that specifies layout of arrays when serializing JSON.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum JsonSerializerArrayStyle {
+    /// `[ 1, 2, 3]` on one line. Arrays that contain objects are still one element per line.
+    Inline,
+    /// Every element on its own indented line, like members of objects. Nested arrays are expanded too.
+    Expanded,
+}
+
+/// Enum that specifies placement of `{` of objects that are values of members when serializing JSON.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum JsonSerializerBraceStyle {
+    /// `"key": {` (K&R style)
+    SameLine,
+    /// `{` on the next line of `"key":`, with the same indent as the key (Allman style). Empty objects stay on the key line.
+    NextLine,
+}
+
+/// Enum that specifies rendering of empty objects and arrays when serializing JSON.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum JsonSerializerEmptyStyle {
+    /// `{}` or `[]`
+    Collapsed,
+    /// `{ }` or `[ ]`
+    Spaced,
+    /// `{` and `}` on two lines, the closing one aligned with the opening line.
+    Expanded,
+}
+
+/// Enum that specifies how an object shared by several places(the same `Rc`) is handled when serializing JSON.
+#[derive(Clone)]
+pub enum JsonSerializerSharedObjectPolicy {
+    /// The object is serialized at every place.
+    SerializeEachTime,
+    /// Serialization fails at the second place with its path.
+    Error,
+    /// The callback is called with the path(JSON Pointer) of the second and later places, and the object is serialized.
+    Warn(Rc<dyn Fn(&str)>),
+}
+
+/// Callback type of `transform_value`, called with the path and the value.
+pub type JsonSerializerTransformFn = dyn Fn(&[JsonPathSegment], &JsonValue) -> Option<JsonValue>;
+
+/// Predicate type of `inline_value`, called with the path.
+pub type JsonSerializerPathPredicateFn = dyn Fn(&[JsonPathSegment]) -> bool;
+
+/// Enum that specifies encoding of the bytes serialized by `serialize_to_bytes`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum JsonSerializerOutputEncoding {
+    Utf8,
+    /// UTF-8 prefixed with the BOM(`EF BB BF`).
+    Utf8WithBom,
+    /// UTF-16 little endian, always prefixed with the BOM(`FF FE`).
+    Utf16Le,
+    /// UTF-16 big endian, always prefixed with the BOM(`FE FF`).
+    Utf16Be,
+}
+
+/// Options of the serializer.
+#[derive(Clone)]
+pub struct JsonSerializerOptions {
+    /// Newline code(LF or CRLF) when serializing JSON. Default is `Lf`.
+    pub newline_kind: JsonSerializerNewLineKind,
+    /// Indent kind(Tab of Space) when serializing JSON. Default is `Space(4)`.
+    pub indent_kind: JsonSerializerIndentKind,
+    /// Whether a newline is appended at the end of the JSON string. Default is `true`.
+    pub trailing_newline: bool,
+    /// Separator between key and value. Default is `SpaceAfter`(`"key": value`).
+    pub colon_style: JsonSerializerColonStyle,
+    /// Whether every character outside U+0020..U+007E in keys and strings is escaped as `\uXXXX` to emit pure-ASCII JSON. Default is `false`.
+    pub escape_non_ascii: bool,
+    /// Whether `/` in keys and strings is escaped as `\/`, e.g. to embed JSON in `<script>` tags safely. Default is `false`.
+    pub escape_solidus: bool,
+    /// Number of digits after the decimal point of Float values. `None` means the shortest representation. Default is `None`.
+    /// `Some(0)` emits no fractional part, so such values are parsed as Int.
+    pub float_precision: Option<usize>,
+    /// Float values whose magnitude is below `.0` or at least `.1` are emitted in scientific notation (e.g. `1.5e-7`).
+    /// `None` never uses scientific notation. Default is `None`.
+    pub float_exp_thresholds: Option<(f64, f64)>,
+    /// How NaN and Infinity are serialized. Default is `Error`.
+    pub non_finite_float: JsonSerializerNonFiniteFloatPolicy,
+    /// Whether members of objects are serialized in ascending order of keys. Default is `false`(the order of `HashMap`).
+    pub sort_keys: bool,
+    /// Layout of arrays. Default is `Inline`.
+    pub array_style: JsonSerializerArrayStyle,
+    /// With `Inline` style, arrays whose line goes beyond this number of characters including the indent and the key
+    /// are expanded one element per line. The decision is made at each nesting level. `None` means no limit. Default is `None`.
+    pub max_inline_array_width: Option<usize>,
+    /// Whether the JSON string is serialized on one line without whitespace, e.g. `{"key":[1,2]}`.
+    /// `newline_kind` is used only for the trailing newline, and `indent_kind`, `colon_style` and the array options are ignored.
+    /// Default is `false`.
+    pub compact: bool,
+    /// Whether a `,` is appended after the last member of objects and the last element of multi-line arrays.
+    /// The output is JSON5, not strict JSON. Default is `false`.
+    pub trailing_commas: bool,
+    /// Rendering of empty objects. Default is `Collapsed`(`{}`).
+    pub empty_object_style: JsonSerializerEmptyStyle,
+    /// Rendering of empty arrays. Default is `Collapsed`(`[]`).
+    pub empty_array_style: JsonSerializerEmptyStyle,
+    /// How an object shared by several places is handled. Default is `SerializeEachTime`.
+    pub on_shared_object: JsonSerializerSharedObjectPolicy,
+    /// Maximum nesting depth of objects and arrays. The root object is depth 1. `None` means no limit. Default is `Some(1024)`.
+    /// The serializer does not recurse per nesting level, but deeper trees are rejected by default as a guard
+    /// against runaway documents; set `None` to serialize them.
+    pub max_depth: Option<usize>,
+    /// Whether members whose value is null are omitted from objects. Null elements of arrays are kept. Default is `false`.
+    pub skip_null_members: bool,
+    /// Which numbers are serialized as strings. Default is `Never`.
+    pub numbers_as_strings: JsonSerializerNumberStringPolicy,
+    /// Whether keys are padded with spaces so that the colons within each object are aligned vertically.
+    /// Ignored in compact mode. Default is `false`.
+    pub align_colons: bool,
+    /// Callback called with the path and the value of every member and element before it is serialized.
+    /// If it returns `Some`, the returned value is serialized instead, without calling the callback for its contents.
+    /// The source object is not modified. Default is `None`.
+    pub transform_value: Option<Rc<JsonSerializerTransformFn>>,
+    /// Arrays of more than this number of scalars are wrapped, this number of elements per line,
+    /// e.g. `[\n    1, 2, 3,\n    4\n]`. Arrays that contain objects or arrays are not wrapped. Ignored in compact mode.
+    /// Must not be `Some(0)`. Default is `None`.
+    pub array_wrap: Option<usize>,
+    /// Predicate called with the path of every member and element that is an object or an array.
+    /// If it returns `true`, the value is serialized on one line like `compact`, and the members after it return to the
+    /// surrounding style. Default is `None`.
+    pub inline_value: Option<Rc<JsonSerializerPathPredicateFn>>,
+    /// Whether keys that are identifiers(`[A-Za-z_$][A-Za-z0-9_$]*`, except reserved words) are written without quotes,
+    /// e.g. `{key: 1, "a b": 2}`. The output is JSON5, not strict JSON. Default is `false`.
+    pub unquoted_keys: bool,
+    /// Placement of `{` of objects that are values of members. Ignored in compact mode. Default is `SameLine`.
+    pub brace_style: JsonSerializerBraceStyle,
+    /// Whether `serialize_only` fails for keys that the object does not have. Default is `false`(skipped).
+    pub error_on_missing_keys: bool,
+    /// Whether DEL(U+007F) and C1 control characters(U+0080 - U+009F) in strings are escaped like `\u007f`.
+    /// Default is `false`.
+    pub escape_extended_controls: bool,
+    /// Whether the lines after the first one of multi-line raw values(`ValueRaw`) are indented to the current level.
+    /// If `false`, raw values are written as they are. Default is `false`.
+    pub reindent_raw_values: bool,
+    /// Non-empty objects whose compact rendering is at most this number of characters are serialized on one line like
+    /// `compact`, e.g. `{"x":1,"y":2}`. Arrays that contain objects still place each element on its own line.
+    /// Ignored in compact mode. Default is `None`.
+    pub inline_small_objects: Option<usize>,
+    /// Keys of members serialized first in this order at every nesting level, e.g. `$schema`, `$id` and `title`.
+    /// The other members follow in the order of `sort_keys`. Ignored by `serialize_canonical`. Default is empty.
+    pub priority_keys: Vec<String>,
+    /// Whether a byte order mark(U+FEFF) is written at the start of the output, e.g. for legacy Windows applications.
+    /// The bytes are `EF BB BF` in UTF-8. Default is `false`.
+    pub emit_bom: bool,
+}
+
+impl JsonSerializerOptions {
+    /// Create default serializer options.
+    /// * Return:
+    ///     * Serializer options struct.
+    pub fn new() -> JsonSerializerOptions {
+        JsonSerializerOptions {
+            newline_kind: JsonSerializerNewLineKind::Lf,
+            indent_kind: JsonSerializerIndentKind::Space(4),
+            trailing_newline: true,
+            colon_style: JsonSerializerColonStyle::SpaceAfter,
+            escape_non_ascii: false,
+            escape_solidus: false,
+            float_precision: None,
+            float_exp_thresholds: None,
+            non_finite_float: JsonSerializerNonFiniteFloatPolicy::Error,
+            sort_keys: false,
+            array_style: JsonSerializerArrayStyle::Inline,
+            max_inline_array_width: None,
+            compact: false,
+            trailing_commas: false,
+            empty_object_style: JsonSerializerEmptyStyle::Collapsed,
+            empty_array_style: JsonSerializerEmptyStyle::Collapsed,
+            on_shared_object: JsonSerializerSharedObjectPolicy::SerializeEachTime,
+            max_depth: Some(1024),
+            skip_null_members: false,
+            numbers_as_strings: JsonSerializerNumberStringPolicy::Never,
+            align_colons: false,
+            transform_value: None,
+            array_wrap: None,
+            inline_value: None,
+            unquoted_keys: false,
+            brace_style: JsonSerializerBraceStyle::SameLine,
+            error_on_missing_keys: false,
+            escape_extended_controls: false,
+            reindent_raw_values: false,
+            inline_small_objects: None,
+            priority_keys: Vec::new(),
+            emit_bom: false,
+        }
+    }
+
+    /// Set newline code(LF or CRLF).
+    pub fn newline(mut self, newline_kind: JsonSerializerNewLineKind) -> JsonSerializerOptions {
+        self.newline_kind = newline_kind;
+        self
+    }
+
+    /// Set indent kind(Tab of Space).
+    pub fn indent(mut self, indent_kind: JsonSerializerIndentKind) -> JsonSerializerOptions {
+        self.indent_kind = indent_kind;
+        self
+    }
+
+    /// Set whether a newline is appended at the end of the JSON string.
+    pub fn trailing_newline(mut self, trailing_newline: bool) -> JsonSerializerOptions {
+        self.trailing_newline = trailing_newline;
+        self
+    }
+
+    /// Set separator between key and value.
+    pub fn colon_style(mut self, colon_style: JsonSerializerColonStyle) -> JsonSerializerOptions {
+        self.colon_style = colon_style;
+        self
+    }
+
+    /// Set whether every character outside U+0020..U+007E is escaped as `\uXXXX`.
+    pub fn escape_non_ascii(mut self, escape_non_ascii: bool) -> JsonSerializerOptions {
+        self.escape_non_ascii = escape_non_ascii;
+        self
+    }
+
+    /// Set whether `/` is escaped as `\/`.
+    pub fn escape_solidus(mut self, escape_solidus: bool) -> JsonSerializerOptions {
+        self.escape_solidus = escape_solidus;
+        self
+    }
+
+    /// Set number of digits after the decimal point of Float values.
+    pub fn float_precision(mut self, float_precision: Option<usize>) -> JsonSerializerOptions {
+        self.float_precision = float_precision;
+        self
+    }
+
+    /// Set thresholds of magnitude for scientific notation of Float values.
+    pub fn float_exp_thresholds(mut self, float_exp_thresholds: Option<(f64, f64)>) -> JsonSerializerOptions {
+        self.float_exp_thresholds = float_exp_thresholds;
+        self
+    }
+
+    /// Set how NaN and Infinity are serialized.
+    pub fn non_finite_float(mut self, non_finite_float: JsonSerializerNonFiniteFloatPolicy) -> JsonSerializerOptions {
+        self.non_finite_float = non_finite_float;
+        self
+    }
+
+    /// Set whether members of objects are serialized in ascending order of keys.
+    pub fn sort_keys(mut self, sort_keys: bool) -> JsonSerializerOptions {
+        self.sort_keys = sort_keys;
+        self
+    }
+
+    /// Set layout of arrays.
+    pub fn array_style(mut self, array_style: JsonSerializerArrayStyle) -> JsonSerializerOptions {
+        self.array_style = array_style;
+        self
+    }
+
+    /// Set width limit of inline arrays.
+    pub fn max_inline_array_width(mut self, max_inline_array_width: Option<usize>) -> JsonSerializerOptions {
+        self.max_inline_array_width = max_inline_array_width;
+        self
+    }
+
+    /// Set whether the JSON string is serialized on one line without whitespace.
+    pub fn compact(mut self, compact: bool) -> JsonSerializerOptions {
+        self.compact = compact;
+        self
+    }
+
+    /// Set whether a `,` is appended after the last member and element(JSON5).
+    pub fn trailing_commas(mut self, trailing_commas: bool) -> JsonSerializerOptions {
+        self.trailing_commas = trailing_commas;
+        self
+    }
+
+    /// Set rendering of empty objects.
+    pub fn empty_object_style(mut self, empty_object_style: JsonSerializerEmptyStyle) -> JsonSerializerOptions {
+        self.empty_object_style = empty_object_style;
+        self
+    }
+
+    /// Set rendering of empty arrays.
+    pub fn empty_array_style(mut self, empty_array_style: JsonSerializerEmptyStyle) -> JsonSerializerOptions {
+        self.empty_array_style = empty_array_style;
+        self
+    }
+
+    /// Set how an object shared by several places is handled.
+    pub fn on_shared_object(mut self, on_shared_object: JsonSerializerSharedObjectPolicy) -> JsonSerializerOptions {
+        self.on_shared_object = on_shared_object;
+        self
+    }
+
+    /// Set maximum nesting depth of objects and arrays. `None` is needed to serialize trees deeper than the default 1024.
+    pub fn max_depth(mut self, max_depth: Option<usize>) -> JsonSerializerOptions {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Set whether members whose value is null are omitted.
+    pub fn skip_null_members(mut self, skip_null_members: bool) -> JsonSerializerOptions {
+        self.skip_null_members = skip_null_members;
+        self
+    }
+
+    /// Set which numbers are serialized as strings.
+    pub fn numbers_as_strings(mut self, numbers_as_strings: JsonSerializerNumberStringPolicy) -> JsonSerializerOptions {
+        self.numbers_as_strings = numbers_as_strings;
+        self
+    }
+
+    /// Set whether the colons within each object are aligned.
+    pub fn align_colons(mut self, align_colons: bool) -> JsonSerializerOptions {
+        self.align_colons = align_colons;
+        self
+    }
+
+    /// Set callback that replaces values while serializing.
+    pub fn transform_value<F>(mut self, transform_value: F) -> JsonSerializerOptions
+    where
+        F: Fn(&[JsonPathSegment], &JsonValue) -> Option<JsonValue> + 'static,
+    {
+        self.transform_value = Some(Rc::new(transform_value));
+        self
+    }
+
+    /// Set number of elements per line of wrapped arrays.
+    pub fn array_wrap(mut self, array_wrap: Option<usize>) -> JsonSerializerOptions {
+        self.array_wrap = array_wrap;
+        self
+    }
+
+    /// Set predicate that selects values serialized on one line.
+    pub fn inline_value<F>(mut self, inline_value: F) -> JsonSerializerOptions
+    where
+        F: Fn(&[JsonPathSegment]) -> bool + 'static,
+    {
+        self.inline_value = Some(Rc::new(inline_value));
+        self
+    }
+
+    /// Set whether identifier keys are written without quotes.
+    pub fn unquoted_keys(mut self, unquoted_keys: bool) -> JsonSerializerOptions {
+        self.unquoted_keys = unquoted_keys;
+        self
+    }
+
+    /// Set placement of `{` of objects that are values of members.
+    pub fn brace_style(mut self, brace_style: JsonSerializerBraceStyle) -> JsonSerializerOptions {
+        self.brace_style = brace_style;
+        self
+    }
+
+    /// Set whether `serialize_only` fails for missing keys.
+    pub fn error_on_missing_keys(mut self, error_on_missing_keys: bool) -> JsonSerializerOptions {
+        self.error_on_missing_keys = error_on_missing_keys;
+        self
+    }
+
+    /// Set whether DEL and C1 control characters are escaped.
+    pub fn escape_extended_controls(mut self, escape_extended_controls: bool) -> JsonSerializerOptions {
+        self.escape_extended_controls = escape_extended_controls;
+        self
+    }
+
+    /// Set whether multi-line raw values are indented to the current level.
+    pub fn reindent_raw_values(mut self, reindent_raw_values: bool) -> JsonSerializerOptions {
+        self.reindent_raw_values = reindent_raw_values;
+        self
+    }
+
+    /// Set maximum width of objects serialized on one line.
+    pub fn inline_small_objects(mut self, inline_small_objects: Option<usize>) -> JsonSerializerOptions {
+        self.inline_small_objects = inline_small_objects;
+        self
+    }
+
+    /// Set keys of members serialized first.
+    pub fn priority_keys(mut self, priority_keys: Vec<String>) -> JsonSerializerOptions {
+        self.priority_keys = priority_keys;
+        self
+    }
+
+    /// Set whether a byte order mark is written at the start of the output.
+    pub fn emit_bom(mut self, emit_bom: bool) -> JsonSerializerOptions {
+        self.emit_bom = emit_bom;
+        self
+    }
+
+    fn with_kinds(newline_kind: JsonSerializerNewLineKind, indent_kind: JsonSerializerIndentKind) -> JsonSerializerOptions {
+        JsonSerializerOptions::new().newline(newline_kind).indent(indent_kind)
+    }
+}
+
+impl Default for JsonSerializerOptions {
+    fn default() -> Self {
+        JsonSerializerOptions::new()
+    }
 }
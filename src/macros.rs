@@ -0,0 +1,66 @@
+//! Construction macros.
+
+/// Build `JsonObject` with JSON-like syntax, e.g. `json_object!{"name": "svc", "port": 8080, "tls": {"cert": null}}`.
+/// Keys are string literals. Values are `null`, arrays `[..]`, objects `{..}`, or any expression that implements
+/// `Into<JsonValue>`. Nested objects are wrapped in `JsonSharedObject`. A trailing comma is allowed.
+#[macro_export]
+macro_rules! json_object {
+    ($($members:tt)*) => {{
+        #[allow(unused_mut)]
+        let mut json_object = $crate::JsonObject::new();
+        $crate::json_internal!(@members json_object $($members)*);
+        json_object
+    }};
+}
+
+/// Build `JsonValue` with the same syntax as the values of `json_object!`, e.g. `json_value!([1, "a", {"b": null}])`.
+#[macro_export]
+macro_rules! json_value {
+    (null) => {
+        $crate::JsonValue::ValueNull
+    };
+    ([$($elements:tt)*]) => {
+        $crate::JsonValue::ValueArray($crate::json_internal!(@elements [] () $($elements)*))
+    };
+    ({$($members:tt)*}) => {
+        $crate::JsonValue::from($crate::json_object!($($members)*))
+    };
+    ($($value:tt)+) => {
+        $crate::JsonValue::from($($value)+)
+    };
+}
+
+/// Munchers of `json_object!` and `json_value!`. Tokens of a value are collected until `,` so that
+/// any expression can be a value.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! json_internal {
+    (@members $object:ident) => {};
+    (@members $object:ident $key:literal : $($rest:tt)*) => {
+        $crate::json_internal!(@member $object $key () $($rest)*);
+    };
+    // 値の終わり
+    (@member $object:ident $key:literal ($($value:tt)+) , $($rest:tt)*) => {
+        $object.insert($key, $crate::json_value!($($value)+));
+        $crate::json_internal!(@members $object $($rest)*);
+    };
+    (@member $object:ident $key:literal ($($value:tt)+)) => {
+        $object.insert($key, $crate::json_value!($($value)+));
+    };
+    (@member $object:ident $key:literal ($($value:tt)*) $next:tt $($rest:tt)*) => {
+        $crate::json_internal!(@member $object $key ($($value)* $next) $($rest)*);
+    };
+
+    (@elements [$($done:expr,)*] ()) => {
+        ::std::vec![$($done,)*]
+    };
+    (@elements [$($done:expr,)*] ($($value:tt)+) , $($rest:tt)*) => {
+        $crate::json_internal!(@elements [$($done,)* $crate::json_value!($($value)+),] () $($rest)*)
+    };
+    (@elements [$($done:expr,)*] ($($value:tt)+)) => {
+        ::std::vec![$($done,)* $crate::json_value!($($value)+)]
+    };
+    (@elements [$($done:expr,)*] ($($value:tt)*) $next:tt $($rest:tt)*) => {
+        $crate::json_internal!(@elements [$($done,)*] ($($value)* $next) $($rest)*)
+    };
+}
@@ -0,0 +1,102 @@
+//! Deep merge and JSON Merge Patch (RFC 7386) module.
+use super::shared::unwrap_or_clone_object;
+use super::*;
+
+/// Copy the value including the objects in it, so the copy shares no `Rc` with the original.
+pub(crate) fn deep_copy_value(json_value: &JsonValue) -> JsonValue {
+    match json_value {
+        JsonValue::ValueArray(json_array) => JsonValue::ValueArray(json_array.iter().map(deep_copy_value).collect()),
+        JsonValue::ValueObject(refcell_json_object) => JsonValue::from(deep_copy_object(&refcell_json_object.read_object())),
+        JsonValue::ValueOwnedObject(json_object) => JsonValue::ValueOwnedObject(Box::new(deep_copy_object(json_object))),
+        _ => json_value.clone(),
+    }
+}
+
+/// Copy the object including the objects in it.
+pub(crate) fn deep_copy_object(json_object: &JsonObject) -> JsonObject {
+    JsonObject {
+        members: json_object
+            .members
+            .iter()
+            .map(|(json_key, json_value)| (json_key.clone(), deep_copy_value(json_value)))
+            .collect(),
+    }
+}
+
+/// Merge the members of `other_object` into `self_object`. `other_object` must be a deep copy, so its values can be moved.
+pub(crate) fn merge_members(self_object: &mut JsonObject, other_object: JsonObject, strategy: JsonMergeStrategy) {
+    for (json_key, other_value) in other_object.members {
+        if other_value == JsonValue::ValueNull && strategy.ignore_null {
+            continue;
+        }
+        match self_object.members.get_mut(&json_key) {
+            Some(self_value) => merge_value(self_value, other_value, strategy),
+            None => {
+                self_object.members.insert(json_key, other_value);
+            }
+        }
+    }
+}
+
+fn merge_value(self_value: &mut JsonValue, other_value: JsonValue, strategy: JsonMergeStrategy) {
+    match (self_value, other_value) {
+        (
+            self_value @ (JsonValue::ValueObject(_) | JsonValue::ValueOwnedObject(_)),
+            other_value @ (JsonValue::ValueObject(_) | JsonValue::ValueOwnedObject(_)),
+        ) => {
+            // deep copy 済みなので共有されていない
+            let other_child_object = match other_value {
+                JsonValue::ValueObject(other_child_object) => unwrap_or_clone_object(other_child_object),
+                JsonValue::ValueOwnedObject(other_child_object) => *other_child_object,
+                _ => unreachable!(),
+            };
+            if let Some(mut self_child_object) = self_value.try_write_any_object() {
+                merge_members(&mut self_child_object, other_child_object, strategy);
+            }
+        }
+        (JsonValue::ValueArray(self_array), JsonValue::ValueArray(other_array))
+            if strategy.array_policy == JsonMergeArrayPolicy::Concat =>
+        {
+            self_array.extend(other_array);
+        }
+        (self_value, other_value) => {
+            if strategy.conflict_policy == JsonMergeConflictPolicy::OtherWins {
+                *self_value = other_value;
+            }
+        }
+    }
+}
+
+/// Apply JSON Merge Patch (RFC 7386) to the value. `patch_value` must not share `Rc` with `target_value`.
+pub(crate) fn merge_patch_value(target_value: &mut JsonValue, patch_value: &JsonValue) {
+    match patch_value {
+        JsonValue::ValueObject(_) | JsonValue::ValueOwnedObject(_) => {
+            // オブジェクトでなければ、パッチと同じ形の空オブジェクトにする
+            if !matches!(target_value, JsonValue::ValueObject(_) | JsonValue::ValueOwnedObject(_)) {
+                *target_value = match patch_value {
+                    JsonValue::ValueOwnedObject(_) => JsonValue::ValueOwnedObject(Box::default()),
+                    _ => JsonValue::from(JsonObject::new()),
+                };
+            }
+            if let (Some(mut target_object), Some(patch_object)) =
+                (target_value.try_write_any_object(), patch_value.read_any_object())
+            {
+                merge_patch_members(&mut target_object, &patch_object);
+            }
+        }
+        _ => *target_value = patch_value.clone(),
+    }
+}
+
+/// Apply the members of the patch object. `null` removes the member.
+pub(crate) fn merge_patch_members(target_object: &mut JsonObject, patch_object: &JsonObject) {
+    for (json_key, patch_value) in &patch_object.members {
+        if *patch_value == JsonValue::ValueNull {
+            target_object.members.remove(json_key);
+        } else {
+            // 無いメンバーは undefined として扱う
+            let target_value = target_object.members.entry(json_key.clone()).or_default();
+            merge_patch_value(target_value, patch_value);
+        }
+    }
+}
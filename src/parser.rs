@@ -1,18 +1,17 @@
 //! JSON Parser module.
-use std::cell::RefCell;
-use std::rc::Rc;
-
-use super::{JsonKey, JsonNumberType, JsonObject, JsonValue};
+use super::serializer::BOM_CHAR;
+use super::shared::new_shared_object;
+use super::{JsonKey, JsonNumberType, JsonObject, JsonParserOptions, JsonValue};
 
 use super::error::*;
 
-fn parse_error(
+pub(crate) fn parse_error(
     kind: JsonErrorKind,
     detail_str: &str,
     char_position: &CharPosition,
 ) -> Box<dyn std::error::Error + Send + Sync + 'static> {
     let (line, column) = char_position.get_position();
-    return JsonError::new(kind, Some(format!("{} | line:{} column:{}", detail_str, line, column)));
+    JsonError::new(kind, Some(format!("{} | line:{} column:{}", detail_str, line, column)))
 }
 
 #[derive(Clone, PartialEq)]
@@ -56,7 +55,7 @@ pub(crate) struct CharPosition {
 }
 
 impl CharPosition {
-    fn new() -> CharPosition {
+    pub(crate) fn new() -> CharPosition {
         CharPosition {
             idx: 0,
             line: 0,
@@ -64,7 +63,7 @@ impl CharPosition {
         }
     }
 
-    fn increment(&mut self, unicode_char: &char) {
+    pub(crate) fn increment(&mut self, unicode_char: &char) {
         self.idx += 1;
         if *unicode_char == '\n' {
             self.line += 1;
@@ -87,22 +86,66 @@ impl CharPosition {
 pub(crate) struct JsonParser {
     content_chars: Vec<char>,
     char_position: CharPosition,
+    options: JsonParserOptions,
 }
 
 impl JsonParser {
     /// Parse JSON function.
     #[allow(dead_code)]
     pub fn parse(content_str: &str) -> Result<JsonObject> {
-        let mut json_parser = JsonParser::new(content_str);
+        JsonParser::parse_with_options(content_str, &JsonParserOptions::new())
+    }
+
+    /// Parse JSON function with the parser options.
+    pub fn parse_with_options(content_str: &str, options: &JsonParserOptions) -> Result<JsonObject> {
+        let mut json_parser = JsonParser::new(content_str, options);
         let res_json_object = json_parser.object_parser();
         json_parser.content_chars.clear();
         res_json_object
     }
 
-    fn new(content_str: &str) -> JsonParser {
+    fn new(content_str: &str, options: &JsonParserOptions) -> JsonParser {
+        let content_str = match content_str.strip_prefix(BOM_CHAR) {
+            Some(bomless_str) if options.skip_bom => bomless_str,
+            _ => content_str,
+        };
         JsonParser {
             content_chars: content_str.chars().collect(),
             char_position: CharPosition::new(),
+            options: options.clone(),
+        }
+    }
+
+    /// Nested object value in the form chosen by the options.
+    fn object_value(&self, json_object: JsonObject) -> JsonValue {
+        if self.options.owned_objects {
+            JsonValue::ValueOwnedObject(Box::new(json_object))
+        } else {
+            JsonValue::ValueObject(new_shared_object(json_object))
+        }
+    }
+
+    /// Whether `close_char` follows the ',' just read, i.e. the ',' is a trailing comma allowed by the options.
+    /// The blanks before `close_char` are skipped in that case.
+    fn trailing_comma_parser(&mut self, close_char: char) -> bool {
+        if !self.options.allow_trailing_commas {
+            return false;
+        }
+        let close_idx = self
+            .content_chars
+            .iter()
+            .skip(self.char_position.get_idx())
+            .position(|unicode_char| !matches!(unicode_char, ' ' | '\t' | '\n' | '\r'))
+            .map(|offset| self.char_position.get_idx() + offset);
+        match close_idx {
+            Some(close_idx) if self.content_chars[close_idx] == close_char => {
+                while self.char_position.get_idx() < close_idx {
+                    self.char_position
+                        .increment(&self.content_chars[self.char_position.get_idx()]);
+                }
+                true
+            }
+            _ => false,
         }
     }
 
@@ -140,6 +183,10 @@ impl JsonParser {
                         // (なお、"}," は '}' でオブジェクト終了したのち、 ',' で次のメンバー、という処理になる)
                         match self.end_member_parser()? {
                             EndMemberKind::EndMember => {
+                                if self.trailing_comma_parser('}') {
+                                    // '}' は次の end_member_parser で読み込む
+                                    continue 'in_member_loop;
+                                }
                                 status = MemberParserStatus::Key;
                                 break 'in_member_loop;
                             }
@@ -203,11 +250,11 @@ impl JsonParser {
                 }
             }
         }
-        return Err(parse_error(
+        Err(parse_error(
             JsonErrorKind::ParseErrorInObject,
             "StartObject: Object is not closed.",
             &self.char_position,
-        ));
+        ))
     }
 
     fn key_parser(&mut self) -> Result<JsonKey> {
@@ -228,11 +275,11 @@ impl JsonParser {
                 }
             }
         }
-        return Err(parse_error(
+        Err(parse_error(
             JsonErrorKind::ParseErrorInKey,
             "Key: Object is not closed.",
             &self.char_position,
-        ));
+        ))
     }
 
     fn coron_parser(&mut self) -> Result<()> {
@@ -254,15 +301,16 @@ impl JsonParser {
                 }
             }
         }
-        return Err(parse_error(
+        Err(parse_error(
             JsonErrorKind::ParseErrorInObject,
             "Comma: Object is not closed.",
             &self.char_position,
-        ));
+        ))
     }
 
     fn value_parser(&mut self) -> Result<JsonValue> {
         // self.char_idx を更新しながらループを回すための2重ループ(loop、for)
+        #[allow(clippy::never_loop)]
         loop {
             for unicode_char in self.content_chars.iter().skip(self.char_position.get_idx()) {
                 match unicode_char {
@@ -283,7 +331,8 @@ impl JsonParser {
                         return Ok(JsonValue::ValueArray(self.array_parser()?));
                     }
                     '{' => {
-                        return Ok(JsonValue::ValueObject(Rc::new(RefCell::new(self.object_parser()?))));
+                        let json_object = self.object_parser()?;
+                        return Ok(self.object_value(json_object));
                     }
                     ' ' | '\t' | '\n' | '\r' => {
                         self.blank_parser()?;
@@ -324,11 +373,11 @@ impl JsonParser {
                 }
             }
         }
-        return Err(parse_error(
+        Err(parse_error(
             JsonErrorKind::ParseErrorInObject,
             "EndMember: Object is not closed.",
             &self.char_position,
-        ));
+        ))
     }
 
     // 連続で空白を処理するので、char_idxがその分増える。その前提で使う。
@@ -341,11 +390,11 @@ impl JsonParser {
                 _ => return Ok(()),
             }
         }
-        return Err(parse_error(
+        Err(parse_error(
             JsonErrorKind::ParseErrorInObject,
             "Blank: Object is not closed.",
             &self.char_position,
-        ));
+        ))
     }
 
     fn string_parser(&mut self) -> Result<String> {
@@ -370,6 +419,7 @@ impl JsonParser {
             .increment(&self.content_chars[self.char_position.get_idx()]);
 
         // self.char_idx を更新しながらループを回すための2重ループ(loop、for)
+        #[allow(clippy::never_loop)]
         loop {
             for unicode_char in self.content_chars.iter().skip(self.char_position.get_idx()) {
                 match unicode_char {
@@ -456,11 +506,11 @@ impl JsonParser {
                 }
             }
         }
-        return Err(parse_error(
+        Err(parse_error(
             JsonErrorKind::ParseErrorInString,
             "EscapeString: Object is not closed.",
             &self.char_position,
-        ));
+        ))
     }
 
     fn escape_string_utf16(&mut self) -> Result<char> {
@@ -469,7 +519,7 @@ impl JsonParser {
         loop {
             let mut unicode_hex: String = String::new();
 
-            if utf16_vec.len() > 0 {
+            if !utf16_vec.is_empty() {
                 if self.char_position.get_idx() + 1 >= self.content_chars.len() {
                     return Err(parse_error(
                         JsonErrorKind::ParseErrorInString,
@@ -509,7 +559,7 @@ impl JsonParser {
                 if unicode_hex.len() == 4 {
                     if let Ok(u16_char_code) = u16::from_str_radix(&unicode_hex, 16) {
                         utf16_vec.push(u16_char_code);
-                        if 0xD800 <= u16_char_code && u16_char_code <= 0xDBFF {
+                        if (0xD800..=0xDBFF).contains(&u16_char_code) {
                             // サロゲートペアの処理のため一度ブレークして次の"\uxxxx"を取得する。
                             break;
                         }
@@ -610,27 +660,7 @@ impl JsonParser {
                     }
                 }
                 ' ' | '\t' | '\n' | '\r' | ',' | '}' | ']' => {
-                    if decimal_point_existed || is_exp_notation {
-                        if let Ok(float_number) = number_string.parse::<f64>() {
-                            return Ok(JsonNumberType::Float(float_number));
-                        } else {
-                            return Err(parse_error(
-                                JsonErrorKind::ParseErrorInNumber,
-                                "Number: Number string could not be parsed to \"f64\".",
-                                &self.char_position,
-                            ));
-                        }
-                    } else {
-                        if let Ok(int_number) = number_string.parse::<i64>() {
-                            return Ok(JsonNumberType::Int(int_number));
-                        } else {
-                            return Err(parse_error(
-                                JsonErrorKind::ParseErrorInNumber,
-                                "Number: Number string could not be parsed to \"i64\".",
-                                &self.char_position,
-                            ));
-                        }
-                    }
+                    return self.number_converter(&number_string, decimal_point_existed || is_exp_notation);
                 }
                 _ => {
                     return Err(parse_error(
@@ -641,11 +671,44 @@ impl JsonParser {
                 }
             }
         }
-        return Err(parse_error(
+        Err(parse_error(
             JsonErrorKind::ParseErrorInNumber,
             "Number:  Object is not closed.",
             &self.char_position,
-        ));
+        ))
+    }
+
+    /// Convert the number string, which is already validated, to the number.
+    fn number_converter(&self, number_str: &str, is_float: bool) -> Result<JsonNumberType> {
+        if let Some(big_decimal_digits) = self.options.big_decimal_digits {
+            if significant_digits(number_str) > big_decimal_digits {
+                return Ok(JsonNumberType::BigDecimal(number_str.to_string()));
+            }
+        }
+        if is_float {
+            if let Ok(float_number) = number_str.parse::<f64>() {
+                return Ok(JsonNumberType::Float(float_number));
+            }
+            return Err(parse_error(
+                JsonErrorKind::ParseErrorInNumber,
+                "Number: Number string could not be parsed to \"f64\".",
+                &self.char_position,
+            ));
+        }
+        if let Ok(int_number) = number_str.parse::<i64>() {
+            Ok(JsonNumberType::Int(int_number))
+        } else if let Ok(uint_number) = number_str.parse::<u64>() {
+            // 符号がなく i64 に収まらない整数
+            Ok(JsonNumberType::Uint(uint_number))
+        } else if self.options.big_decimal_digits.is_some() {
+            Ok(JsonNumberType::BigDecimal(number_str.to_string()))
+        } else {
+            Err(parse_error(
+                JsonErrorKind::ParseErrorInNumber,
+                "Number: Number string could not be parsed to \"i64\" or \"u64\".",
+                &self.char_position,
+            ))
+        }
     }
 
     fn bool_parser(&mut self) -> Result<bool> {
@@ -693,11 +756,11 @@ impl JsonParser {
                 }
             }
         }
-        return Err(parse_error(
+        Err(parse_error(
             JsonErrorKind::ParseErrorInBool,
             "Bool:  Object is not closed.",
             &self.char_position,
-        ));
+        ))
     }
 
     fn null_parser(&mut self) -> Result<()> {
@@ -728,11 +791,11 @@ impl JsonParser {
                 }
             }
         }
-        return Err(parse_error(
+        Err(parse_error(
             JsonErrorKind::ParseErrorInNull,
             "Null:  Object is not closed.",
             &self.char_position,
-        ));
+        ))
     }
 
     fn array_parser(&mut self) -> Result<Vec<JsonValue>> {
@@ -773,6 +836,7 @@ impl JsonParser {
         }
 
         // self.char_idx を更新しながらループを回すための2重ループ(loop、for)
+        #[allow(clippy::never_loop)]
         'in_array_loop: loop {
             for unicode_char in self.content_chars.iter().skip(self.char_position.get_idx()) {
                 match unicode_char {
@@ -798,7 +862,8 @@ impl JsonParser {
                         break;
                     }
                     '{' => {
-                        object_array.push(JsonValue::ValueObject(Rc::new(RefCell::new(self.object_parser()?))));
+                        let json_object = self.object_parser()?;
+                        object_array.push(self.object_value(json_object));
                         break;
                     }
                     ' ' | '\t' | '\n' | '\r' => {
@@ -818,7 +883,11 @@ impl JsonParser {
                 object_array_len = object_array.len();
                 match self.array_separator_parser()? {
                     ArraySeparatorKind::EndElement => {
-                        // Nothing to do (Go to Next element)
+                        if self.trailing_comma_parser(']') {
+                            self.char_position
+                                .increment(&self.content_chars[self.char_position.get_idx()]);
+                            return Ok(object_array);
+                        }
                     }
                     ArraySeparatorKind::EndArray => {
                         return Ok(object_array);
@@ -829,11 +898,11 @@ impl JsonParser {
                 break 'in_array_loop;
             }
         }
-        return Err(parse_error(
+        Err(parse_error(
             JsonErrorKind::ParseErrorInArray,
             "Null:  Object is not closed.",
             &self.char_position,
-        ));
+        ))
     }
 
     fn array_separator_parser(&mut self) -> Result<ArraySeparatorKind> {
@@ -859,10 +928,21 @@ impl JsonParser {
                 }
             }
         }
-        return Err(parse_error(
+        Err(parse_error(
             JsonErrorKind::ParseErrorInObject,
             "Array: Object is not closed.",
             &self.char_position,
-        ));
+        ))
     }
 }
+
+/// Number of the significant digits of the number string, i.e. the digits of the mantissa without leading zeros.
+/// Trailing zeros are significant, e.g. `1.50` has 3 digits.
+fn significant_digits(number_str: &str) -> usize {
+    let mantissa_str = number_str.split(['e', 'E']).next().unwrap_or_default();
+    mantissa_str
+        .chars()
+        .filter(char::is_ascii_digit)
+        .skip_while(|digit_char| *digit_char == '0')
+        .count()
+}
@@ -0,0 +1,418 @@
+//! JSON Patch (RFC 6902) module.
+use super::merge::{deep_copy_object, deep_copy_value};
+use super::pointer::{self, JsonParentMut};
+use super::serializer::JsonSerializerRoot;
+use super::shared::{ptr_eq, JsonObjectLock};
+use super::{JsonObject, JsonValue};
+
+use super::error::*;
+
+fn patch_error(kind: JsonErrorKind, detail_str: &str, location_str: &str) -> Box<dyn std::error::Error + Send + Sync + 'static> {
+    JsonError::new(kind, Some(format!("{} | {}", detail_str, location_str)))
+}
+
+/// Add the operation to the error, e.g. `... | operation:2:remove`.
+fn operation_error(
+    err: Box<dyn std::error::Error + Send + Sync + 'static>,
+    op_idx: usize,
+    op_str: &str,
+) -> Box<dyn std::error::Error + Send + Sync + 'static> {
+    let Some(json_error) = err.downcast_ref::<JsonError>() else {
+        return err;
+    };
+    let operation_string = if op_str.is_empty() {
+        format!("operation:{}", op_idx)
+    } else {
+        format!("operation:{}:{}", op_idx, op_str)
+    };
+    let detail_string = match &json_error.op_additional_message {
+        Some(additional_message) => format!("{} | {}", additional_message, operation_string),
+        None => operation_string,
+    };
+    JsonError::new(json_error.err_kind.clone(), Some(detail_string))
+}
+
+/// Change made by an operation, kept to undo the patch if a later operation fails.
+enum PatchUndo {
+    // 追加した値を取り除き、置き換えたメンバーがあれば戻す。配列の "-" は追加した位置に解決済み
+    Added {
+        segments: Vec<String>,
+        op_replaced_value: Option<JsonValue>,
+    },
+    // 取り除いた値を戻す。move では、後の Added を戻したときに取り除いた値を戻す
+    Removed {
+        segments: Vec<String>,
+        op_json_value: Option<JsonValue>,
+    },
+    Replaced {
+        segments: Vec<String>,
+        json_value: JsonValue,
+    },
+    // ルートに移動した共有オブジェクトは、取り除いた位置に同じオブジェクトを戻すために覚えておく
+    RootReplaced {
+        json_root_object: JsonObject,
+        op_json_value: Option<JsonValue>,
+    },
+}
+
+/// Apply the operations to the object in place, so that objects nested in it and shared with other places see the change.
+/// If an operation fails, the changes made by the previous operations are undone, so the object is not changed.
+pub(crate) fn apply_patch(json_object: &mut JsonObject, patch: &[JsonValue]) -> Result<()> {
+    let mut undos = Vec::new();
+    for (op_idx, json_op_value) in patch.iter().enumerate() {
+        let res = match json_op_value.read_any_object() {
+            Some(op_object) => {
+                let op_str = op_object.get_str("op").unwrap_or_default();
+                operation_applier(json_object, &op_object, &mut undos).map_err(|err| operation_error(err, op_idx, op_str))
+            }
+            None => Err(operation_error(
+                JsonError::new(
+                    JsonErrorKind::InvalidPatch,
+                    Some("Patch: Operation is not an object.".to_string()),
+                ),
+                op_idx,
+                "",
+            )),
+        };
+        if let Err(err) = res {
+            patch_undoer(json_object, undos);
+            return Err(err);
+        }
+    }
+    Ok(())
+}
+
+/// Undo the changes in reverse order. Each change is undone on the state right after it was made, so undoing never fails.
+fn patch_undoer(json_object: &mut JsonObject, undos: Vec<PatchUndo>) {
+    // move で移動した値。Added を戻すと取り出され、その前の Removed で元の位置に戻る
+    let mut op_moved_value = None;
+    for undo in undos.into_iter().rev() {
+        let _ = match undo {
+            PatchUndo::Added {
+                segments,
+                op_replaced_value,
+            } => pointer::remove_segments(json_object, &segments, "").and_then(|op_json_value| {
+                op_moved_value = op_json_value;
+                match op_replaced_value {
+                    Some(json_replaced_value) => value_adder(json_object, &segments, "", json_replaced_value).map(|_| ()),
+                    None => Ok(()),
+                }
+            }),
+            PatchUndo::Removed { segments, op_json_value } => match op_json_value.or_else(|| op_moved_value.take()) {
+                Some(json_value) => value_adder(json_object, &segments, "", json_value).map(|_| ()),
+                None => Ok(()),
+            },
+            PatchUndo::Replaced { segments, json_value } => value_replacer(json_object, &segments, "", json_value).map(|_| ()),
+            PatchUndo::RootReplaced {
+                json_root_object,
+                op_json_value,
+            } => {
+                let json_replaced_object = std::mem::replace(json_object, json_root_object);
+                op_moved_value = Some(op_json_value.unwrap_or_else(|| JsonValue::from(json_replaced_object)));
+                Ok(())
+            }
+        };
+    }
+}
+
+fn member_str<'a>(op_object: &'a JsonObject, key_str: &str) -> Result<&'a str> {
+    op_object.get_str(key_str).ok_or_else(|| {
+        JsonError::new(
+            JsonErrorKind::InvalidPatch,
+            Some(format!("Patch: Operation does not have the string \"{}\".", key_str)),
+        )
+    })
+}
+
+fn member_value<'a>(op_object: &'a JsonObject, key_str: &str) -> Result<&'a JsonValue> {
+    op_object.get(key_str).ok_or_else(|| {
+        JsonError::new(
+            JsonErrorKind::InvalidPatch,
+            Some(format!("Patch: Operation does not have \"{}\".", key_str)),
+        )
+    })
+}
+
+/// Apply the operation and push the changes it made to `undos`.
+fn operation_applier(json_object: &mut JsonObject, op_object: &JsonObject, undos: &mut Vec<PatchUndo>) -> Result<()> {
+    let op_str = member_str(op_object, "op")?;
+    let path_str = member_str(op_object, "path")?;
+    let segments = pointer::parse_pointer(path_str, JsonErrorKind::InvalidPatch)?;
+    let location_string = format!("pointer:{}", path_str);
+    match op_str {
+        "add" => {
+            let json_value = deep_copy_value(member_value(op_object, "value")?);
+            undos.push(value_adder(json_object, &segments, &location_string, json_value)?);
+            Ok(())
+        }
+        "remove" => {
+            let json_value = value_remover(json_object, &segments, &location_string)?;
+            undos.push(PatchUndo::Removed {
+                segments,
+                op_json_value: Some(json_value),
+            });
+            Ok(())
+        }
+        "replace" => {
+            let json_value = deep_copy_value(member_value(op_object, "value")?);
+            undos.push(value_replacer(json_object, &segments, &location_string, json_value)?);
+            Ok(())
+        }
+        "move" => {
+            let from_str = member_str(op_object, "from")?;
+            let from_segments = pointer::parse_pointer(from_str, JsonErrorKind::InvalidPatch)?;
+            // 自分の子孫には移動できない
+            if from_segments.len() < segments.len() && segments.starts_with(&from_segments) {
+                return Err(patch_error(
+                    JsonErrorKind::InvalidPatch,
+                    "Patch: Value cannot be moved into itself.",
+                    &location_string,
+                ));
+            }
+            let json_value = value_remover(json_object, &from_segments, &format!("pointer:{}", from_str))?;
+            undos.push(PatchUndo::Removed {
+                segments: from_segments,
+                op_json_value: None,
+            });
+            undos.push(value_adder(json_object, &segments, &location_string, json_value)?);
+            Ok(())
+        }
+        "copy" => {
+            let from_str = member_str(op_object, "from")?;
+            let from_segments = pointer::parse_pointer(from_str, JsonErrorKind::InvalidPatch)?;
+            let json_value = value_getter(json_object, &from_segments, &format!("pointer:{}", from_str))?;
+            undos.push(value_adder(json_object, &segments, &location_string, json_value)?);
+            Ok(())
+        }
+        "test" => {
+            let json_expected_value = member_value(op_object, "value")?;
+            if value_getter(json_object, &segments, &location_string)? != *json_expected_value {
+                return Err(patch_error(
+                    JsonErrorKind::PatchTestFailed,
+                    "Patch: Value is not equal.",
+                    &location_string,
+                ));
+            }
+            Ok(())
+        }
+        _ => Err(JsonError::new(
+            JsonErrorKind::InvalidPatch,
+            Some(format!("Patch: Operation \"{}\" is unknown.", op_str)),
+        )),
+    }
+}
+
+/// Deep copy of the referred value.
+fn value_getter(json_object: &JsonObject, segments: &[String], location_str: &str) -> Result<JsonValue> {
+    pointer::with_segments_root(
+        json_object,
+        segments,
+        location_str,
+        JsonErrorKind::KeyNotFound,
+        |root| match root {
+            JsonSerializerRoot::Object(json_object) => Ok(JsonValue::from(deep_copy_object(json_object))),
+            JsonSerializerRoot::Value(json_value) => Ok(deep_copy_value(json_value)),
+            JsonSerializerRoot::Projection(..) => unreachable!(),
+        },
+    )
+}
+
+/// The whole document can be replaced only with an object.
+fn root_replacer(json_object: &mut JsonObject, json_value: JsonValue, location_str: &str) -> Result<PatchUndo> {
+    let (json_new_object, op_json_value) = match json_value {
+        JsonValue::ValueObject(refcell_json_object) => {
+            let json_new_object = refcell_json_object.read_object().clone();
+            (json_new_object, Some(JsonValue::ValueObject(refcell_json_object)))
+        }
+        JsonValue::ValueOwnedObject(json_owned_object) => (*json_owned_object, None),
+        _ => {
+            return Err(patch_error(
+                JsonErrorKind::TypeMismatch,
+                &format!("Patch: Root must be an object, found {}.", json_value.type_name()),
+                location_str,
+            ));
+        }
+    };
+    Ok(PatchUndo::RootReplaced {
+        json_root_object: std::mem::replace(json_object, json_new_object),
+        op_json_value,
+    })
+}
+
+fn value_adder(
+    json_object: &mut JsonObject,
+    segments: &[String],
+    location_str: &str,
+    json_value: JsonValue,
+) -> Result<PatchUndo> {
+    if segments.is_empty() {
+        return root_replacer(json_object, json_value, location_str);
+    }
+    pointer::with_parent_mut(json_object, segments, location_str, |parent, segment_str| match parent {
+        JsonParentMut::Object(json_parent_object) => {
+            let op_replaced_value = json_parent_object.insert(segment_str, json_value);
+            Ok(PatchUndo::Added {
+                segments: segments.to_vec(),
+                op_replaced_value,
+            })
+        }
+        JsonParentMut::Array(json_array) => {
+            // "-" は末尾に追加、それ以外は挿入
+            let idx = if segment_str == "-" {
+                Some(json_array.len())
+            } else {
+                pointer::parse_index(segment_str)
+            };
+            match idx {
+                Some(idx) if idx <= json_array.len() => {
+                    json_array.insert(idx, json_value);
+                    let mut added_segments = segments.to_vec();
+                    *added_segments.last_mut().unwrap() = idx.to_string();
+                    Ok(PatchUndo::Added {
+                        segments: added_segments,
+                        op_replaced_value: None,
+                    })
+                }
+                _ => Err(pointer::pointer_error(
+                    JsonErrorKind::KeyNotFound,
+                    "Path: Array does not have the index.",
+                    segment_str,
+                    location_str,
+                )),
+            }
+        }
+    })
+}
+
+fn value_remover(json_object: &mut JsonObject, segments: &[String], location_str: &str) -> Result<JsonValue> {
+    if segments.is_empty() {
+        return Err(patch_error(
+            JsonErrorKind::InvalidPatch,
+            "Patch: Root object cannot be removed.",
+            location_str,
+        ));
+    }
+    pointer::remove_segments(json_object, segments, location_str)?.ok_or_else(|| {
+        pointer::pointer_error(
+            JsonErrorKind::KeyNotFound,
+            "Path: Value does not exist.",
+            segments.last().unwrap(),
+            location_str,
+        )
+    })
+}
+
+fn value_replacer(
+    json_object: &mut JsonObject,
+    segments: &[String],
+    location_str: &str,
+    json_value: JsonValue,
+) -> Result<PatchUndo> {
+    if segments.is_empty() {
+        return root_replacer(json_object, json_value, location_str);
+    }
+    pointer::with_parent_mut(json_object, segments, location_str, |parent, segment_str| {
+        let json_target_value = match parent {
+            JsonParentMut::Object(json_parent_object) => json_parent_object.get_mut(segment_str),
+            JsonParentMut::Array(json_array) => pointer::parse_index(segment_str).and_then(|idx| json_array.get_mut(idx)),
+        };
+        match json_target_value {
+            Some(json_target_value) => Ok(PatchUndo::Replaced {
+                segments: segments.to_vec(),
+                json_value: std::mem::replace(json_target_value, json_value),
+            }),
+            None => Err(pointer::pointer_error(
+                JsonErrorKind::KeyNotFound,
+                "Path: Value does not exist.",
+                segment_str,
+                location_str,
+            )),
+        }
+    })
+}
+
+fn operation_value(op_str: &str, pointer_str: &str, op_json_value: Option<&JsonValue>) -> JsonValue {
+    let mut op_object = JsonObject::new();
+    op_object.insert("op", op_str);
+    op_object.insert("path", pointer_str);
+    if let Some(json_value) = op_json_value {
+        op_object.insert("value", deep_copy_value(json_value));
+    }
+    JsonValue::from(op_object)
+}
+
+/// Operations that transform `json_object` into `json_other_object`. Keys are visited in sorted order,
+/// so the same objects always give the same patch.
+pub(crate) fn diff_objects(json_object: &JsonObject, json_other_object: &JsonObject) -> Vec<JsonValue> {
+    let mut operations = Vec::new();
+    object_differ(json_object, json_other_object, "", &mut operations);
+    operations
+}
+
+fn object_differ(json_object: &JsonObject, json_other_object: &JsonObject, pointer_str: &str, operations: &mut Vec<JsonValue>) {
+    for key_str in json_object.keys_sorted() {
+        let member_pointer_string = format!("{}/{}", pointer_str, pointer::escape_token(key_str));
+        match json_other_object.get(key_str) {
+            Some(json_other_value) => {
+                value_differ(&json_object[key_str], json_other_value, &member_pointer_string, operations);
+            }
+            None => operations.push(operation_value("remove", &member_pointer_string, None)),
+        }
+    }
+    let mut added_keys: Vec<&str> = json_other_object
+        .keys()
+        .filter(|key_str| !json_object.contains_key(key_str))
+        .collect();
+    added_keys.sort_unstable();
+    for key_str in added_keys {
+        let member_pointer_string = format!("{}/{}", pointer_str, pointer::escape_token(key_str));
+        operations.push(operation_value(
+            "add",
+            &member_pointer_string,
+            Some(&json_other_object[key_str]),
+        ));
+    }
+}
+
+fn value_differ(json_value: &JsonValue, json_other_value: &JsonValue, pointer_str: &str, operations: &mut Vec<JsonValue>) {
+    match (json_value, json_other_value) {
+        (JsonValue::ValueObject(refcell_json_object), JsonValue::ValueObject(refcell_other_object))
+            if ptr_eq(refcell_json_object, refcell_other_object) => {}
+        (
+            JsonValue::ValueObject(_) | JsonValue::ValueOwnedObject(_),
+            JsonValue::ValueObject(_) | JsonValue::ValueOwnedObject(_),
+        ) => {
+            if let (Some(json_object), Some(json_other_object)) =
+                (json_value.read_any_object(), json_other_value.read_any_object())
+            {
+                object_differ(&json_object, &json_other_object, pointer_str, operations);
+            }
+        }
+        (JsonValue::ValueArray(json_array), JsonValue::ValueArray(json_other_array)) => {
+            // 先頭から要素ごとに比べ、余りを追加または末尾から削除する
+            for (idx, (json_element_value, json_other_element_value)) in json_array.iter().zip(json_other_array).enumerate() {
+                value_differ(
+                    json_element_value,
+                    json_other_element_value,
+                    &format!("{}/{}", pointer_str, idx),
+                    operations,
+                );
+            }
+            for (idx, json_other_element_value) in json_other_array.iter().enumerate().skip(json_array.len()) {
+                operations.push(operation_value(
+                    "add",
+                    &format!("{}/{}", pointer_str, idx),
+                    Some(json_other_element_value),
+                ));
+            }
+            for idx in (json_other_array.len()..json_array.len()).rev() {
+                operations.push(operation_value("remove", &format!("{}/{}", pointer_str, idx), None));
+            }
+        }
+        _ => {
+            if json_value != json_other_value {
+                operations.push(operation_value("replace", pointer_str, Some(json_other_value)));
+            }
+        }
+    }
+}
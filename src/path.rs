@@ -0,0 +1,95 @@
+//! Dot and bracket path module, e.g. `servers[2].tls.cert` or `["weird.key"]`.
+use super::error::*;
+
+fn path_error(detail_str: &str, char_idx: usize, path_str: &str) -> Box<dyn std::error::Error + Send + Sync + 'static> {
+    // 文字数は1始まり
+    JsonError::new(
+        JsonErrorKind::ParseErrorInKey,
+        Some(format!("{} | column:{} | path:{}", detail_str, char_idx + 1, path_str)),
+    )
+}
+
+/// Split the path into keys and indexes. The empty path refers to the whole document.
+/// Keys are separated by `.`, indexes are written as `[3]`, and keys that contain `.`, `[` or `]` are quoted as `["a.b"]`.
+pub(crate) fn parse_path(path_str: &str) -> Result<Vec<String>> {
+    let path_chars: Vec<char> = path_str.chars().collect();
+    let mut segments = Vec::new();
+    let mut char_idx = 0;
+    while char_idx < path_chars.len() {
+        if path_chars[char_idx] == '[' {
+            let (segment_string, next_char_idx) = bracket_parser(&path_chars, char_idx, path_str)?;
+            segments.push(segment_string);
+            char_idx = next_char_idx;
+        } else {
+            // 先頭以外のキーは "." で始まる
+            if !segments.is_empty() {
+                if path_chars[char_idx] != '.' {
+                    return Err(path_error("Path: Expected '.' or '[' after the segment.", char_idx, path_str));
+                }
+                char_idx += 1;
+            }
+            let start_char_idx = char_idx;
+            while char_idx < path_chars.len() && !matches!(path_chars[char_idx], '.' | '[') {
+                if matches!(path_chars[char_idx], ']' | '\"' | '\'') {
+                    return Err(path_error("Path: Key contains a bracket or a quote.", char_idx, path_str));
+                }
+                char_idx += 1;
+            }
+            if char_idx == start_char_idx {
+                return Err(path_error("Path: Key is empty.", char_idx, path_str));
+            }
+            segments.push(path_chars[start_char_idx..char_idx].iter().collect());
+        }
+    }
+    Ok(segments)
+}
+
+/// Parse `[3]` or `["key"]` from `[` at `open_char_idx`. Returns the segment and the index of the character after `]`.
+fn bracket_parser(path_chars: &[char], open_char_idx: usize, path_str: &str) -> Result<(String, usize)> {
+    let mut char_idx = open_char_idx + 1;
+    let mut segment_string = String::new();
+    match path_chars.get(char_idx) {
+        Some(&quote_char) if quote_char == '\"' || quote_char == '\'' => {
+            let quote_char_idx = char_idx;
+            char_idx += 1;
+            loop {
+                match path_chars.get(char_idx) {
+                    Some(&unicode_char) if unicode_char == quote_char => break,
+                    Some('\\') => {
+                        // エスケープされた文字はそのまま使う
+                        char_idx += 1;
+                        match path_chars.get(char_idx) {
+                            Some(&unicode_char) => segment_string.push(unicode_char),
+                            None => return Err(path_error("Path: Quote is not closed.", quote_char_idx, path_str)),
+                        }
+                    }
+                    Some(&unicode_char) => segment_string.push(unicode_char),
+                    None => return Err(path_error("Path: Quote is not closed.", quote_char_idx, path_str)),
+                }
+                char_idx += 1;
+            }
+            char_idx += 1;
+        }
+        _ => {
+            while let Some(&unicode_char) = path_chars.get(char_idx) {
+                if !unicode_char.is_ascii_digit() {
+                    break;
+                }
+                segment_string.push(unicode_char);
+                char_idx += 1;
+            }
+            if segment_string.is_empty() {
+                match path_chars.get(char_idx) {
+                    Some(']') => return Err(path_error("Path: Index is empty.", char_idx, path_str)),
+                    Some(_) => return Err(path_error("Path: Index is not a number.", char_idx, path_str)),
+                    None => {}
+                }
+            }
+        }
+    }
+    match path_chars.get(char_idx) {
+        Some(']') => Ok((segment_string, char_idx + 1)),
+        Some(_) => Err(path_error("Path: Expected ']'.", char_idx, path_str)),
+        None => Err(path_error("Path: Bracket is not closed.", open_char_idx, path_str)),
+    }
+}
@@ -0,0 +1,369 @@
+//! JSON Pointer (RFC 6901) module.
+use super::serializer::JsonSerializerRoot;
+use super::{JsonObject, JsonValue};
+
+use super::error::*;
+
+/// `location_str` is the pointer or the path with its kind, e.g. `pointer:/a/0` or `path:a[0]`.
+pub(crate) fn pointer_error(
+    kind: JsonErrorKind,
+    detail_str: &str,
+    segment_str: &str,
+    location_str: &str,
+) -> Box<dyn std::error::Error + Send + Sync + 'static> {
+    JsonError::new(
+        kind,
+        Some(format!("{} | segment:{} | {}", detail_str, segment_str, location_str)),
+    )
+}
+
+/// Split JSON Pointer into the unescaped reference tokens. The empty pointer refers to the whole document.
+pub(crate) fn parse_pointer(pointer_str: &str, kind: JsonErrorKind) -> Result<Vec<String>> {
+    if pointer_str.is_empty() {
+        return Ok(Vec::new());
+    }
+    let Some(tokens_str) = pointer_str.strip_prefix('/') else {
+        return Err(pointer_error(
+            kind,
+            "Pointer: Pointer does not start with '/'.",
+            "",
+            &format!("pointer:{}", pointer_str),
+        ));
+    };
+    let mut segments = Vec::new();
+    for token_str in tokens_str.split('/') {
+        // "~1" を先に戻すと "~01" が "/" になってしまうので、"~" の後の文字ごとに戻す
+        let mut segment_string = String::with_capacity(token_str.len());
+        let mut token_chars = token_str.chars();
+        while let Some(unicode_char) = token_chars.next() {
+            if unicode_char != '~' {
+                segment_string.push(unicode_char);
+                continue;
+            }
+            match token_chars.next() {
+                Some('0') => segment_string.push('~'),
+                Some('1') => segment_string.push('/'),
+                _ => {
+                    return Err(pointer_error(
+                        kind,
+                        "Pointer: '~' is not followed by '0' or '1'.",
+                        token_str,
+                        &format!("pointer:{}", pointer_str),
+                    ));
+                }
+            }
+        }
+        segments.push(segment_string);
+    }
+    Ok(segments)
+}
+
+/// Escape the key as a reference token of JSON Pointer.
+pub(crate) fn escape_token(key_str: &str) -> String {
+    key_str.replace('~', "~0").replace('/', "~1")
+}
+
+/// Index of an array from the reference token. Leading zeros and `-` are not allowed.
+pub(crate) fn parse_index(segment_str: &str) -> Option<usize> {
+    if segment_str.is_empty()
+        || (segment_str.len() > 1 && segment_str.starts_with('0'))
+        || !segment_str.bytes().all(|byte| byte.is_ascii_digit())
+    {
+        return None;
+    }
+    segment_str.parse::<usize>().ok()
+}
+
+/// Resolve the pointer from the root object, and call `f` with the referenced value.
+/// Nested objects stay borrowed while `f` is called.
+pub(crate) fn with_pointer_root<T, F>(json_object: &JsonObject, pointer_str: &str, kind: JsonErrorKind, f: F) -> Result<T>
+where
+    F: FnOnce(JsonSerializerRoot) -> Result<T>,
+{
+    let segments = parse_pointer(pointer_str, kind.clone())?;
+    with_segments_root(json_object, &segments, &format!("pointer:{}", pointer_str), kind, f)
+}
+
+/// Resolve the segments from the root object, and call `f` with the referenced value.
+pub(crate) fn with_segments_root<T, F>(
+    json_object: &JsonObject,
+    segments: &[String],
+    location_str: &str,
+    kind: JsonErrorKind,
+    f: F,
+) -> Result<T>
+where
+    F: FnOnce(JsonSerializerRoot) -> Result<T>,
+{
+    object_resolver(json_object, segments, location_str, kind, f)
+}
+
+fn object_resolver<T, F>(
+    json_object: &JsonObject,
+    segments: &[String],
+    location_str: &str,
+    kind: JsonErrorKind,
+    f: F,
+) -> Result<T>
+where
+    F: FnOnce(JsonSerializerRoot) -> Result<T>,
+{
+    let Some((segment_string, rest_segments)) = segments.split_first() else {
+        return f(JsonSerializerRoot::Object(json_object));
+    };
+    match json_object.get(segment_string) {
+        Some(json_value) => value_resolver(json_value, rest_segments, location_str, kind, f),
+        None => Err(pointer_error(
+            kind,
+            "Path: Object does not have the key.",
+            segment_string,
+            location_str,
+        )),
+    }
+}
+
+fn value_resolver<T, F>(json_value: &JsonValue, segments: &[String], location_str: &str, kind: JsonErrorKind, f: F) -> Result<T>
+where
+    F: FnOnce(JsonSerializerRoot) -> Result<T>,
+{
+    let Some((segment_string, rest_segments)) = segments.split_first() else {
+        return f(JsonSerializerRoot::Value(json_value));
+    };
+    match json_value {
+        JsonValue::ValueObject(_) | JsonValue::ValueOwnedObject(_) => match json_value.try_read_any_object() {
+            Some(json_object) => object_resolver(&json_object, segments, location_str, kind, f),
+            None => Err(pointer_error(
+                kind,
+                "Path: Object is mutably borrowed.",
+                segment_string,
+                location_str,
+            )),
+        },
+        JsonValue::ValueArray(json_array) => match parse_index(segment_string).and_then(|idx| json_array.get(idx)) {
+            Some(json_element_value) => value_resolver(json_element_value, rest_segments, location_str, kind, f),
+            None => Err(pointer_error(
+                kind,
+                "Path: Array does not have the index.",
+                segment_string,
+                location_str,
+            )),
+        },
+        _ => Err(pointer_error(
+            kind,
+            "Path: Value is not an object or an array.",
+            segment_string,
+            location_str,
+        )),
+    }
+}
+
+/// Object or array that contains the value referred by the last segment.
+pub(crate) enum JsonParentMut<'a> {
+    Object(&'a mut JsonObject),
+    Array(&'a mut Vec<JsonValue>),
+}
+
+/// Resolve the parent of the value referred by the segments, and call `f` with the parent and the last segment.
+/// The value itself need not exist. `segments` must not be empty.
+pub(crate) fn with_parent_mut<T, F>(json_object: &mut JsonObject, segments: &[String], location_str: &str, f: F) -> Result<T>
+where
+    F: FnOnce(JsonParentMut, &str) -> Result<T>,
+{
+    let (segment_string, rest_segments) = segments.split_first().unwrap();
+    if rest_segments.is_empty() {
+        return f(JsonParentMut::Object(json_object), segment_string);
+    }
+    match json_object.get_mut(segment_string) {
+        Some(json_child_value) => parent_value_resolver(json_child_value, rest_segments, location_str, f),
+        None => Err(pointer_error(
+            JsonErrorKind::KeyNotFound,
+            "Path: Object does not have the key.",
+            segment_string,
+            location_str,
+        )),
+    }
+}
+
+fn parent_value_resolver<T, F>(json_value: &mut JsonValue, segments: &[String], location_str: &str, f: F) -> Result<T>
+where
+    F: FnOnce(JsonParentMut, &str) -> Result<T>,
+{
+    let (segment_string, rest_segments) = segments.split_first().unwrap();
+    match json_value {
+        JsonValue::ValueObject(_) | JsonValue::ValueOwnedObject(_) => match json_value.try_write_any_object() {
+            Some(mut json_object) => with_parent_mut(&mut json_object, segments, location_str, f),
+            None => Err(pointer_error(
+                JsonErrorKind::KeyNotFound,
+                "Path: Object is borrowed.",
+                segment_string,
+                location_str,
+            )),
+        },
+        JsonValue::ValueArray(json_array) => {
+            if rest_segments.is_empty() {
+                return f(JsonParentMut::Array(json_array), segment_string);
+            }
+            match parse_index(segment_string).and_then(|idx| json_array.get_mut(idx)) {
+                Some(json_element_value) => parent_value_resolver(json_element_value, rest_segments, location_str, f),
+                None => Err(pointer_error(
+                    JsonErrorKind::KeyNotFound,
+                    "Path: Array does not have the index.",
+                    segment_string,
+                    location_str,
+                )),
+            }
+        }
+        _ => Err(pointer_error(
+            JsonErrorKind::KeyNotFound,
+            "Path: Value is not an object or an array.",
+            segment_string,
+            location_str,
+        )),
+    }
+}
+
+/// Remove the value referred by the segments from its parent. Later elements of an array are shifted.
+/// * Return:
+///     * Removed value, or `None` if the parent does not have the last segment. Error if the parent cannot be resolved.
+pub(crate) fn remove_segments(
+    json_object: &mut JsonObject,
+    segments: &[String],
+    location_str: &str,
+) -> Result<Option<JsonValue>> {
+    if segments.is_empty() {
+        return Err(pointer_error(
+            JsonErrorKind::KeyNotFound,
+            "Path: Root object cannot be removed.",
+            "",
+            location_str,
+        ));
+    }
+    with_parent_mut(json_object, segments, location_str, |parent, segment_str| match parent {
+        JsonParentMut::Object(json_parent_object) => Ok(json_parent_object.remove(segment_str)),
+        JsonParentMut::Array(json_array) => match parse_index(segment_str) {
+            Some(idx) if idx < json_array.len() => Ok(Some(json_array.remove(idx))),
+            Some(_) => Ok(None),
+            None => Err(pointer_error(
+                JsonErrorKind::KeyNotFound,
+                "Path: Segment is not an array index.",
+                segment_str,
+                location_str,
+            )),
+        },
+    })
+}
+
+/// Set the value referred by the pointer, and return the replaced value.
+/// With `create_intermediate`, missing members along the path are created as objects.
+/// The index one past the end of an array, or `-`, appends the value.
+pub(crate) fn pointer_set(
+    json_object: &mut JsonObject,
+    pointer_str: &str,
+    json_value: JsonValue,
+    create_intermediate: bool,
+) -> Result<Option<JsonValue>> {
+    let segments = parse_pointer(pointer_str, JsonErrorKind::KeyNotFound)?;
+    set_segments(
+        json_object,
+        &segments,
+        &format!("pointer:{}", pointer_str),
+        json_value,
+        create_intermediate,
+    )
+}
+
+/// Set the value referred by the segments, and return the replaced value. See `pointer_set`.
+pub(crate) fn set_segments(
+    json_object: &mut JsonObject,
+    segments: &[String],
+    location_str: &str,
+    json_value: JsonValue,
+    create_intermediate: bool,
+) -> Result<Option<JsonValue>> {
+    let kind = JsonErrorKind::KeyNotFound;
+    if segments.is_empty() {
+        return Err(pointer_error(kind, "Path: Root object cannot be replaced.", "", location_str));
+    }
+    let setter = PointerSetter {
+        location_str,
+        kind,
+        create_intermediate,
+    };
+    setter.object_setter(json_object, segments, json_value)
+}
+
+struct PointerSetter<'a> {
+    location_str: &'a str,
+    kind: JsonErrorKind,
+    create_intermediate: bool,
+}
+
+impl PointerSetter<'_> {
+    fn error(&self, detail_str: &str, segment_str: &str) -> Box<dyn std::error::Error + Send + Sync + 'static> {
+        pointer_error(self.kind.clone(), detail_str, segment_str, self.location_str)
+    }
+
+    /// `segments` must not be empty.
+    fn object_setter(
+        &self,
+        json_object: &mut JsonObject,
+        segments: &[String],
+        json_value: JsonValue,
+    ) -> Result<Option<JsonValue>> {
+        let (segment_string, rest_segments) = segments.split_first().unwrap();
+        if rest_segments.is_empty() {
+            return Ok(json_object.insert(segment_string.as_str(), json_value));
+        }
+        if self.create_intermediate && !json_object.contains_key(segment_string) {
+            json_object.insert(segment_string.as_str(), JsonObject::new());
+        }
+        match json_object.get_mut(segment_string) {
+            Some(json_child_value) => self.value_setter(json_child_value, rest_segments, json_value),
+            None => Err(self.error("Path: Object does not have the key.", segment_string)),
+        }
+    }
+
+    /// `segments` must not be empty.
+    fn value_setter(
+        &self,
+        json_parent_value: &mut JsonValue,
+        segments: &[String],
+        json_value: JsonValue,
+    ) -> Result<Option<JsonValue>> {
+        let (segment_string, rest_segments) = segments.split_first().unwrap();
+        match json_parent_value {
+            JsonValue::ValueObject(_) | JsonValue::ValueOwnedObject(_) => match json_parent_value.try_write_any_object() {
+                Some(mut json_object) => self.object_setter(&mut json_object, segments, json_value),
+                None => Err(self.error("Path: Object is borrowed.", segment_string)),
+            },
+            JsonValue::ValueArray(json_array) => {
+                // "-" は末尾の次の要素
+                let idx = if segment_string == "-" {
+                    Some(json_array.len())
+                } else {
+                    parse_index(segment_string)
+                };
+                let idx = match idx {
+                    Some(idx) if idx <= json_array.len() => idx,
+                    _ => return Err(self.error("Path: Array does not have the index.", segment_string)),
+                };
+                if idx < json_array.len() {
+                    if rest_segments.is_empty() {
+                        return Ok(Some(std::mem::replace(&mut json_array[idx], json_value)));
+                    }
+                    return self.value_setter(&mut json_array[idx], rest_segments, json_value);
+                }
+                if rest_segments.is_empty() {
+                    json_array.push(json_value);
+                    return Ok(None);
+                }
+                if !self.create_intermediate {
+                    return Err(self.error("Path: Array does not have the index.", segment_string));
+                }
+                json_array.push(JsonValue::from(JsonObject::new()));
+                self.value_setter(&mut json_array[idx], rest_segments, json_value)
+            }
+            _ => Err(self.error("Path: Value is not an object or an array.", segment_string)),
+        }
+    }
+}
@@ -0,0 +1,103 @@
+//! Projection module, which extracts the selected members from an object.
+use std::collections::HashMap;
+
+use super::merge::{deep_copy_object, deep_copy_value};
+use super::path::parse_path;
+use super::pointer::parse_index;
+use super::JsonObjectLock;
+use super::{JsonKey, JsonObject, JsonValue};
+
+use super::error::*;
+
+pub(crate) fn project_keys(json_object: &JsonObject, keys: &[&str]) -> JsonObject {
+    let mut json_projected_object = JsonObject::new();
+    for key_str in keys {
+        if let Some(json_value) = json_object.get(key_str) {
+            json_projected_object
+                .members
+                .insert(JsonKey(key_str.to_string()), deep_copy_value(json_value));
+        }
+    }
+    json_projected_object
+}
+
+/// Tree of the selected paths.
+enum Selection {
+    /// 値全体を選ぶ
+    All,
+    Children(HashMap<String, Selection>),
+}
+
+impl Selection {
+    fn insert(&mut self, segments: &[String]) {
+        let Selection::Children(child_selections) = self else {
+            return;
+        };
+        match segments.split_first() {
+            None => *self = Selection::All,
+            Some((segment_string, rest_segments)) => child_selections
+                .entry(segment_string.clone())
+                .or_insert_with(|| Selection::Children(HashMap::new()))
+                .insert(rest_segments),
+        }
+    }
+}
+
+pub(crate) fn project_paths(json_object: &JsonObject, paths: &[&str]) -> Result<JsonObject> {
+    let mut root_selection = Selection::Children(HashMap::new());
+    for path_str in paths {
+        root_selection.insert(&parse_path(path_str)?);
+    }
+    Ok(object_projector(json_object, &root_selection).unwrap_or_default())
+}
+
+/// Project the object. `None` if nothing is selected, so that the parent does not have the member.
+fn object_projector(json_object: &JsonObject, selection: &Selection) -> Option<JsonObject> {
+    let child_selections = match selection {
+        Selection::All => return Some(deep_copy_object(json_object)),
+        Selection::Children(child_selections) => child_selections,
+    };
+    let mut json_projected_object = JsonObject::new();
+    for (key_string, child_selection) in child_selections {
+        let Some(json_value) = json_object.get(key_string) else {
+            continue;
+        };
+        if let Some(json_projected_value) = value_projector(json_value, child_selection) {
+            json_projected_object
+                .members
+                .insert(JsonKey(key_string.clone()), json_projected_value);
+        }
+    }
+    (!json_projected_object.is_empty() || child_selections.is_empty()).then_some(json_projected_object)
+}
+
+fn value_projector(json_value: &JsonValue, selection: &Selection) -> Option<JsonValue> {
+    let child_selections = match selection {
+        Selection::All => return Some(deep_copy_value(json_value)),
+        Selection::Children(child_selections) => child_selections,
+    };
+    match json_value {
+        JsonValue::ValueObject(refcell_json_object) => {
+            let json_object = refcell_json_object.try_read_object()?;
+            object_projector(&json_object, selection).map(JsonValue::from)
+        }
+        JsonValue::ValueOwnedObject(json_object) => {
+            object_projector(json_object, selection).map(|json_object| JsonValue::ValueOwnedObject(Box::new(json_object)))
+        }
+        JsonValue::ValueArray(json_array) => {
+            // 選んだ要素だけをインデックス順に詰める
+            let mut selected_elements: Vec<(usize, &Selection)> = child_selections
+                .iter()
+                .filter_map(|(segment_string, child_selection)| Some((parse_index(segment_string)?, child_selection)))
+                .collect();
+            selected_elements.sort_unstable_by_key(|(idx, _)| *idx);
+            let json_projected_array: Vec<JsonValue> = selected_elements
+                .into_iter()
+                .filter_map(|(idx, child_selection)| value_projector(json_array.get(idx)?, child_selection))
+                .collect();
+            (!json_projected_array.is_empty()).then_some(JsonValue::ValueArray(json_projected_array))
+        }
+        // 子を選んでいるのに値がスカラーなら、何も選ばれていない
+        _ => None,
+    }
+}
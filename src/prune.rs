@@ -0,0 +1,80 @@
+//! Pruning module, which removes members and elements from nested objects and arrays.
+use std::collections::HashMap;
+
+use super::shared::{object_ptr, JsonObjectPtr};
+use super::visit;
+use super::JsonObjectLock;
+use super::{JsonKey, JsonObject, JsonPruneOptions, JsonValue};
+
+/// Keep the members for which `f` returns `true` in the object and the objects nested in it, including the objects in arrays.
+pub(crate) fn retain_recursive<F>(json_object: &mut JsonObject, f: &mut F)
+where
+    F: FnMut(&JsonKey, &mut JsonValue) -> bool,
+{
+    // 先に絞り込むので、取り除いたメンバーの中はたどらない
+    visit::visit_objects_mut(json_object, &mut |json_object: &mut JsonObject| {
+        json_object.members.retain(|json_key, json_value| f(json_key, json_value));
+    });
+}
+
+pub(crate) fn prune_nulls(json_object: &mut JsonObject, options: JsonPruneOptions) {
+    let mut pruner = NullPruner {
+        options,
+        emptied_ptrs: HashMap::new(),
+    };
+    pruner.object_pruner(json_object);
+}
+
+struct NullPruner {
+    options: JsonPruneOptions,
+    /// 訪れたオブジェクトと、刈り込みで空になったかどうか
+    emptied_ptrs: HashMap<JsonObjectPtr, bool>,
+}
+
+impl NullPruner {
+    fn object_pruner(&mut self, json_object: &mut JsonObject) {
+        json_object.members.retain(|_, json_value| !self.value_pruner(json_value));
+    }
+
+    /// Prune the value.
+    /// * Return:
+    ///     * `true` if the value should be removed from its parent.
+    fn value_pruner(&mut self, json_value: &mut JsonValue) -> bool {
+        match json_value {
+            JsonValue::ValueNull => true,
+            JsonValue::ValueObject(refcell_json_object) => {
+                // 共有されているオブジェクトは 1 回だけ刈り込み、2 回目以降は結果だけ使う
+                if let Some(is_emptied) = self.emptied_ptrs.get(&(object_ptr(refcell_json_object))) {
+                    return *is_emptied && self.options.drop_empty_containers;
+                }
+                self.emptied_ptrs.insert(object_ptr(refcell_json_object), false);
+                let Some(mut json_object) = refcell_json_object.try_write_object() else {
+                    return false;
+                };
+                let was_empty = json_object.is_empty();
+                self.object_pruner(&mut json_object);
+                let is_emptied = !was_empty && json_object.is_empty();
+                self.emptied_ptrs.insert(object_ptr(refcell_json_object), is_emptied);
+                is_emptied && self.options.drop_empty_containers
+            }
+            JsonValue::ValueOwnedObject(json_object) => {
+                let was_empty = json_object.is_empty();
+                self.object_pruner(json_object);
+                !was_empty && json_object.is_empty() && self.options.drop_empty_containers
+            }
+            JsonValue::ValueArray(json_array) => {
+                let was_empty = json_array.is_empty();
+                if self.options.in_arrays {
+                    json_array.retain_mut(|json_element_value| !self.value_pruner(json_element_value));
+                } else {
+                    // 要素を取り除かないので、インデックスは変わらない
+                    for json_element_value in json_array.iter_mut() {
+                        self.value_pruner(json_element_value);
+                    }
+                }
+                !was_empty && json_array.is_empty() && self.options.drop_empty_containers
+            }
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! Key renaming module.
+use std::collections::HashSet;
+
+use super::visit;
+use super::{JsonKey, JsonObject};
+
+use super::error::*;
+
+fn collision_error(to_str: &str) -> Box<dyn std::error::Error + Send + Sync + 'static> {
+    JsonError::new(
+        JsonErrorKind::KeyAlreadyExists,
+        Some(format!("Rename: Key {:?} already exists.", to_str)),
+    )
+}
+
+pub(crate) fn rename_key(json_object: &mut JsonObject, from_str: &str, to_str: &str) -> Result<bool> {
+    if !json_object.contains_key(from_str) || from_str == to_str {
+        return Ok(json_object.contains_key(from_str));
+    }
+    if json_object.contains_key(to_str) {
+        return Err(collision_error(to_str));
+    }
+    let json_value = json_object.members.remove(from_str).unwrap();
+    json_object.members.insert(JsonKey(to_str.to_string()), json_value);
+    Ok(true)
+}
+
+pub(crate) fn rename_key_recursive(json_object: &mut JsonObject, from_str: &str, to_str: &str) -> Result<usize> {
+    // 途中で失敗して一部だけ変わることがないように、先に衝突を調べる
+    let mut is_collided = false;
+    visit::visit_objects(json_object, None, &mut |json_object: &JsonObject| {
+        is_collided |= from_str != to_str && json_object.contains_key(from_str) && json_object.contains_key(to_str);
+    });
+    if is_collided {
+        return Err(collision_error(to_str));
+    }
+    let mut renamed_count = 0;
+    visit::visit_objects_mut(json_object, &mut |json_object: &mut JsonObject| {
+        if let Ok(true) = rename_key(json_object, from_str, to_str) {
+            renamed_count += 1;
+        }
+    });
+    Ok(renamed_count)
+}
+
+pub(crate) fn transform_keys<F>(json_object: &mut JsonObject, f: &F) -> Result<()>
+where
+    F: Fn(&str) -> String,
+{
+    // 途中で失敗して一部だけ変わることがないように、先に衝突を調べる
+    let mut op_collided_key_string = None;
+    visit::visit_objects(json_object, None, &mut |json_object: &JsonObject| {
+        let mut new_key_strings = HashSet::new();
+        for key_str in json_object.keys_sorted() {
+            let new_key_string = f(key_str);
+            if !new_key_strings.insert(new_key_string.clone()) && op_collided_key_string.is_none() {
+                op_collided_key_string = Some(new_key_string);
+            }
+        }
+    });
+    if let Some(collided_key_string) = op_collided_key_string {
+        return Err(collision_error(&collided_key_string));
+    }
+    visit::visit_objects_mut(json_object, &mut |json_object: &mut JsonObject| {
+        let members = std::mem::take(&mut json_object.members);
+        json_object.members = members
+            .into_iter()
+            .map(|(json_key, json_value)| (JsonKey(f(&json_key.0)), json_value))
+            .collect();
+    });
+    Ok(())
+}
+
+/// Split the key into words at `_`, `-`, spaces and the changes of case. Digits belong to the word before them.
+fn split_words(key_str: &str) -> Vec<String> {
+    let key_chars: Vec<char> = key_str.chars().collect();
+    let mut words: Vec<String> = Vec::new();
+    let mut word_string = String::new();
+    for (idx, unicode_char) in key_chars.iter().enumerate() {
+        if matches!(unicode_char, '_' | '-' | ' ') {
+            if !word_string.is_empty() {
+                words.push(std::mem::take(&mut word_string));
+            }
+            continue;
+        }
+        if unicode_char.is_uppercase() && !word_string.is_empty() {
+            let prev_char = key_chars[idx - 1];
+            let is_next_lowercase = key_chars.get(idx + 1).is_some_and(|next_char| next_char.is_lowercase());
+            // "fooBar" の "B" と、"HTTPSPort" の "P" で区切る
+            if prev_char.is_lowercase() || prev_char.is_ascii_digit() || (prev_char.is_uppercase() && is_next_lowercase) {
+                words.push(std::mem::take(&mut word_string));
+            }
+        }
+        word_string.push(*unicode_char);
+    }
+    if !word_string.is_empty() {
+        words.push(word_string);
+    }
+    words
+}
+
+/// Leading `_`s, e.g. of `_id`, are kept as they are.
+fn leading_underscores(key_str: &str) -> &str {
+    &key_str[..key_str.len() - key_str.trim_start_matches('_').len()]
+}
+
+pub(crate) fn to_snake_case(key_str: &str) -> String {
+    let words: Vec<String> = split_words(key_str).iter().map(|word_str| word_str.to_lowercase()).collect();
+    format!("{}{}", leading_underscores(key_str), words.join("_"))
+}
+
+pub(crate) fn to_camel_case(key_str: &str) -> String {
+    let mut camel_string = leading_underscores(key_str).to_string();
+    for (idx, word_string) in split_words(key_str).iter().enumerate() {
+        let lower_word_string = word_string.to_lowercase();
+        let mut word_chars = lower_word_string.chars();
+        if idx == 0 {
+            camel_string.push_str(&lower_word_string);
+        } else if let Some(first_char) = word_chars.next() {
+            camel_string.extend(first_char.to_uppercase());
+            camel_string.push_str(word_chars.as_str());
+        }
+    }
+    camel_string
+}
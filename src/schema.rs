@@ -0,0 +1,156 @@
+//! JSON Schema module. A minimal subset: `type`, `enum`, `minimum`, `maximum`, `minLength`, `maxLength`,
+//! `required`, `properties` and `items`.
+use super::pointer::escape_token;
+use super::{JsonEqOptions, JsonObject, JsonSchemaViolation, JsonValue};
+
+struct SchemaValidator {
+    violations: Vec<JsonSchemaViolation>,
+}
+
+pub(crate) fn validate_schema(json_object: &JsonObject, schema_object: &JsonObject) -> Vec<JsonSchemaViolation> {
+    let mut validator = SchemaValidator { violations: Vec::new() };
+    validator.value_validator(&JsonValue::from(json_object.clone()), schema_object, "");
+    validator.violations
+}
+
+/// Whether the value is an instance of the JSON Schema type. Integral floats like `1.0` are integers.
+fn is_type(json_value: &JsonValue, type_str: &str) -> bool {
+    match type_str {
+        "object" => matches!(json_value, JsonValue::ValueObject(_) | JsonValue::ValueOwnedObject(_)),
+        "array" => matches!(json_value, JsonValue::ValueArray(_)),
+        "string" => matches!(json_value, JsonValue::ValueString(_)),
+        "number" => matches!(json_value, JsonValue::ValueNumber(_)),
+        "integer" => matches!(json_value, JsonValue::ValueNumber(json_number) if json_number.is_integral()),
+        "boolean" => matches!(json_value, JsonValue::ValueBool(_)),
+        "null" => matches!(json_value, JsonValue::ValueNull),
+        _ => false,
+    }
+}
+
+fn number_as_f64(json_value: &JsonValue) -> Option<f64> {
+    match json_value {
+        JsonValue::ValueNumber(json_number) => Some(json_number.as_f64()),
+        _ => None,
+    }
+}
+
+impl SchemaValidator {
+    fn violation(&mut self, pointer_str: &str, keyword_str: &str, message_string: String) {
+        self.violations.push(JsonSchemaViolation {
+            instance_path: pointer_str.to_string(),
+            keyword: keyword_str.to_string(),
+            message: message_string,
+        });
+    }
+
+    /// Keywords whose value has the wrong type are ignored like unknown keywords.
+    fn value_validator(&mut self, json_value: &JsonValue, schema_object: &JsonObject, pointer_str: &str) {
+        self.type_validator(json_value, schema_object, pointer_str);
+        if let Some(json_enum_array) = schema_object.get_array("enum") {
+            let eq_options = JsonEqOptions::new().numeric_coercion(true);
+            if !json_enum_array
+                .iter()
+                .any(|json_enum_value| json_value.deep_eq(json_enum_value, &eq_options))
+            {
+                let enum_strings: Vec<String> = json_enum_array
+                    .iter()
+                    .map(|json_enum_value| json_enum_value.to_string())
+                    .collect();
+                self.violation(
+                    pointer_str,
+                    "enum",
+                    format!("{} is not one of {}.", json_value, enum_strings.join(", ")),
+                );
+            }
+        }
+        if let Some(float_number) = number_as_f64(json_value) {
+            if let Some(minimum) = schema_object.get("minimum").and_then(number_as_f64) {
+                if float_number < minimum {
+                    self.violation(pointer_str, "minimum", format!("{} is less than {}.", json_value, minimum));
+                }
+            }
+            if let Some(maximum) = schema_object.get("maximum").and_then(number_as_f64) {
+                if float_number > maximum {
+                    self.violation(pointer_str, "maximum", format!("{} is greater than {}.", json_value, maximum));
+                }
+            }
+        }
+        if let JsonValue::ValueString(json_string) = json_value {
+            // 長さは文字数で数える
+            let len = json_string.chars().count();
+            if let Some(min_len) = schema_object.get_i64("minLength") {
+                if (len as i64) < min_len {
+                    self.violation(
+                        pointer_str,
+                        "minLength",
+                        format!("String of {} characters is shorter than {}.", len, min_len),
+                    );
+                }
+            }
+            if let Some(max_len) = schema_object.get_i64("maxLength") {
+                if (len as i64) > max_len {
+                    self.violation(
+                        pointer_str,
+                        "maxLength",
+                        format!("String of {} characters is longer than {}.", len, max_len),
+                    );
+                }
+            }
+        }
+        match json_value {
+            JsonValue::ValueObject(_) | JsonValue::ValueOwnedObject(_) => {
+                self.object_validator(&json_value.read_any_object().unwrap(), schema_object, pointer_str);
+            }
+            JsonValue::ValueArray(json_array) => {
+                if let Some(items_object) = schema_object.get("items").and_then(JsonValue::read_any_object) {
+                    for (idx, json_element_value) in json_array.iter().enumerate() {
+                        self.value_validator(json_element_value, &items_object, &format!("{}/{}", pointer_str, idx));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn type_validator(&mut self, json_value: &JsonValue, schema_object: &JsonObject, pointer_str: &str) {
+        // "type" は文字列か文字列の配列
+        let type_strs: Vec<&str> = match schema_object.get("type") {
+            Some(JsonValue::ValueString(type_string)) => vec![type_string.as_str()],
+            Some(JsonValue::ValueArray(json_type_array)) => json_type_array.iter().filter_map(JsonValue::as_str).collect(),
+            _ => return,
+        };
+        if !type_strs.iter().any(|type_str| is_type(json_value, type_str)) {
+            self.violation(
+                pointer_str,
+                "type",
+                format!("Expected {}, found {}.", type_strs.join(" or "), json_value.type_name()),
+            );
+        }
+    }
+
+    fn object_validator(&mut self, json_object: &JsonObject, schema_object: &JsonObject, pointer_str: &str) {
+        if let Some(json_required_array) = schema_object.get_array("required") {
+            for required_key_str in json_required_array.iter().filter_map(JsonValue::as_str) {
+                if !json_object.contains_key(required_key_str) {
+                    self.violation(
+                        pointer_str,
+                        "required",
+                        format!("Required member {:?} is missing.", required_key_str),
+                    );
+                }
+            }
+        }
+        if let Some(properties_object) = schema_object.get("properties").and_then(JsonValue::read_any_object) {
+            for key_str in properties_object.keys_sorted() {
+                let (Some(json_member_value), Some(property_object)) = (
+                    json_object.get(key_str),
+                    properties_object.get(key_str).and_then(JsonValue::read_any_object),
+                ) else {
+                    continue;
+                };
+                let member_pointer_string = format!("{}/{}", pointer_str, escape_token(key_str));
+                self.value_validator(json_member_value, &property_object, &member_pointer_string);
+            }
+        }
+    }
+}
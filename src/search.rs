@@ -0,0 +1,65 @@
+//! Search module.
+use super::pointer::escape_token;
+use super::shared::{object_ptr, JsonObjectPtr, JsonSharedObject};
+use super::JsonObjectLock;
+use super::{JsonObject, JsonValue};
+
+/// Collect the values of the members named `key_str` with their JSON Pointers. Keys are visited in sorted order.
+/// `op_root_object` is the shared object that the root is borrowed from, so that a reference cycle to the root is not followed.
+pub(crate) fn find_all(
+    json_object: &JsonObject,
+    op_root_object: Option<&JsonSharedObject>,
+    key_str: &str,
+) -> Vec<(String, JsonValue)> {
+    let mut finder = KeyFinder {
+        key_str,
+        ancestor_ptrs: op_root_object.map(object_ptr).into_iter().collect(),
+        found_values: Vec::new(),
+    };
+    finder.object_finder(json_object, "");
+    finder.found_values
+}
+
+struct KeyFinder<'a> {
+    key_str: &'a str,
+    /// 循環を検出するため、たどっている途中のオブジェクト
+    ancestor_ptrs: Vec<JsonObjectPtr>,
+    found_values: Vec<(String, JsonValue)>,
+}
+
+impl KeyFinder<'_> {
+    fn object_finder(&mut self, json_object: &JsonObject, pointer_str: &str) {
+        for member_key_str in json_object.keys_sorted() {
+            let member_pointer_string = format!("{}/{}", pointer_str, escape_token(member_key_str));
+            let json_value = &json_object[member_key_str];
+            if member_key_str == self.key_str {
+                self.found_values.push((member_pointer_string.clone(), json_value.clone()));
+            }
+            self.value_finder(json_value, &member_pointer_string);
+        }
+    }
+
+    fn value_finder(&mut self, json_value: &JsonValue, pointer_str: &str) {
+        match json_value {
+            JsonValue::ValueObject(refcell_json_object) => {
+                // 祖先と同じオブジェクトは循環なのでたどらない。共有されているだけなら、それぞれの場所でたどる
+                if self.ancestor_ptrs.contains(&(object_ptr(refcell_json_object))) {
+                    return;
+                }
+                let Some(json_object) = refcell_json_object.try_read_object() else {
+                    return;
+                };
+                self.ancestor_ptrs.push(object_ptr(refcell_json_object));
+                self.object_finder(&json_object, pointer_str);
+                self.ancestor_ptrs.pop();
+            }
+            JsonValue::ValueOwnedObject(json_object) => self.object_finder(json_object, pointer_str),
+            JsonValue::ValueArray(json_array) => {
+                for (idx, json_element_value) in json_array.iter().enumerate() {
+                    self.value_finder(json_element_value, &format!("{}/{}", pointer_str, idx));
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,389 @@
+//! serde `Serialize` and `Deserialize` for `JsonValue` and `JsonObject`, enabled with the `serde` feature.
+//! Human-readable formats(e.g. JSON) get the natural data model: strings, numbers, bools, unit for null, sequences for arrays
+//! and maps for objects. The other formats(e.g. postcard and bincode) cannot tell the kind of the next value without it,
+//! so values are enum variants of `JsonValue` there.
+use std::fmt;
+
+use serde::de::{self, EnumAccess, MapAccess, SeqAccess, VariantAccess, Visitor};
+use serde::ser::{self, SerializeMap, SerializeSeq};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::shared::{object_ptr, JsonObjectPtr};
+use super::{JsonKey, JsonNumberType, JsonObject, JsonRawValue, JsonValue};
+
+const VALUE_VARIANTS: [&str; 10] = [
+    "String",
+    "Int",
+    "Uint",
+    "Float",
+    "BigDecimal",
+    "Bool",
+    "Null",
+    "Array",
+    "Object",
+    "Raw",
+];
+
+/// Shared object being serialized and its parent, to detect reference cycles.
+struct Ancestor<'a> {
+    json_object_ptr: JsonObjectPtr,
+    parent: Option<&'a Ancestor<'a>>,
+}
+
+fn is_ancestor(mut ancestor: Option<&Ancestor>, json_object_ptr: JsonObjectPtr) -> bool {
+    while let Some(Ancestor {
+        json_object_ptr: ancestor_ptr,
+        parent,
+    }) = ancestor
+    {
+        if *ancestor_ptr == json_object_ptr {
+            return true;
+        }
+        ancestor = *parent;
+    }
+    false
+}
+
+/// Enum variant of `JsonValue` for the formats that are not human-readable.
+fn variant_serializer<S: Serializer, T: Serialize + ?Sized>(
+    serializer: S,
+    variant_idx: u32,
+    value: &T,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_newtype_variant("JsonValue", variant_idx, VALUE_VARIANTS[variant_idx as usize], value)
+}
+
+/// `BigDecimal` is an integer if its text is, and `f64` otherwise, in human-readable formats.
+fn number_serializer<S: Serializer>(json_number: &JsonNumberType, serializer: S) -> Result<S::Ok, S::Error> {
+    let is_human_readable = serializer.is_human_readable();
+    match json_number {
+        JsonNumberType::Int(int_number) if is_human_readable => serializer.serialize_i64(*int_number),
+        JsonNumberType::Int(int_number) => variant_serializer(serializer, 1, int_number),
+        JsonNumberType::Uint(uint_number) if is_human_readable => serializer.serialize_u64(*uint_number),
+        JsonNumberType::Uint(uint_number) => variant_serializer(serializer, 2, uint_number),
+        JsonNumberType::Float(float_number) if is_human_readable => serializer.serialize_f64(*float_number),
+        JsonNumberType::Float(float_number) => variant_serializer(serializer, 3, float_number),
+        JsonNumberType::BigDecimal(number_string) if is_human_readable => {
+            if let Ok(int_number) = number_string.parse::<i64>() {
+                serializer.serialize_i64(int_number)
+            } else if let Ok(uint_number) = number_string.parse::<u64>() {
+                serializer.serialize_u64(uint_number)
+            } else {
+                serializer.serialize_f64(json_number.as_f64())
+            }
+        }
+        JsonNumberType::BigDecimal(number_string) => variant_serializer(serializer, 4, number_string.as_str()),
+    }
+}
+
+struct ValueSerializer<'a> {
+    json_value: &'a JsonValue,
+    ancestor: Option<&'a Ancestor<'a>>,
+}
+
+impl Serialize for ValueSerializer<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let is_human_readable = serializer.is_human_readable();
+        match self.json_value {
+            JsonValue::ValueString(json_string) if is_human_readable => serializer.serialize_str(json_string),
+            JsonValue::ValueString(json_string) => variant_serializer(serializer, 0, json_string.as_str()),
+            JsonValue::ValueNumber(json_number) => number_serializer(json_number, serializer),
+            JsonValue::ValueBool(json_bool) if is_human_readable => serializer.serialize_bool(*json_bool),
+            JsonValue::ValueBool(json_bool) => variant_serializer(serializer, 5, json_bool),
+            JsonValue::ValueNull if is_human_readable => serializer.serialize_unit(),
+            JsonValue::ValueNull => serializer.serialize_unit_variant("JsonValue", 6, VALUE_VARIANTS[6]),
+            JsonValue::ValueArray(json_array) => {
+                let array_serializer = ArraySerializer {
+                    json_array,
+                    ancestor: self.ancestor,
+                };
+                if is_human_readable {
+                    array_serializer.serialize(serializer)
+                } else {
+                    variant_serializer(serializer, 7, &array_serializer)
+                }
+            }
+            JsonValue::ValueObject(_) | JsonValue::ValueOwnedObject(_) => {
+                // 所有オブジェクトは循環しないので、共有オブジェクトだけを祖先として覚える
+                let json_object_ptr = self.json_value.as_object().map(object_ptr);
+                if json_object_ptr.is_some_and(|json_object_ptr| is_ancestor(self.ancestor, json_object_ptr)) {
+                    return Err(ser::Error::custom("Object: Object contains itself (reference cycle)."));
+                }
+                let Some(json_object) = self.json_value.try_read_any_object() else {
+                    return Err(ser::Error::custom("Object: Object is mutably borrowed."));
+                };
+                let ancestor = json_object_ptr.map(|json_object_ptr| Ancestor {
+                    json_object_ptr,
+                    parent: self.ancestor,
+                });
+                let object_serializer = ObjectSerializer {
+                    json_object: &json_object,
+                    ancestor: ancestor.as_ref().or(self.ancestor),
+                };
+                if is_human_readable {
+                    object_serializer.serialize(serializer)
+                } else {
+                    variant_serializer(serializer, 8, &object_serializer)
+                }
+            }
+            JsonValue::ValueRaw(json_raw_value) if is_human_readable => {
+                // 生の値はメンバーとしてパースしてからシリアライズする
+                let json_wrapper_object =
+                    JsonObject::parse(&format!("{{\"v\": {}}}", json_raw_value.as_str())).map_err(ser::Error::custom)?;
+                ValueSerializer {
+                    json_value: &json_wrapper_object["v"],
+                    ancestor: None,
+                }
+                .serialize(serializer)
+            }
+            JsonValue::ValueRaw(json_raw_value) => variant_serializer(serializer, 9, json_raw_value.as_str()),
+        }
+    }
+}
+
+struct ArraySerializer<'a> {
+    json_array: &'a [JsonValue],
+    ancestor: Option<&'a Ancestor<'a>>,
+}
+
+impl Serialize for ArraySerializer<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.json_array.len()))?;
+        for json_element_value in self.json_array {
+            seq.serialize_element(&ValueSerializer {
+                json_value: json_element_value,
+                ancestor: self.ancestor,
+            })?;
+        }
+        seq.end()
+    }
+}
+
+struct ObjectSerializer<'a> {
+    json_object: &'a JsonObject,
+    ancestor: Option<&'a Ancestor<'a>>,
+}
+
+impl Serialize for ObjectSerializer<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // 出力を決定的にするため、キーの順に並べる
+        let mut members: Vec<(&JsonKey, &JsonValue)> = self.json_object.members.iter().collect();
+        members.sort_unstable_by(|(json_key_a, _), (json_key_b, _)| json_key_a.0.cmp(&json_key_b.0));
+        let mut map = serializer.serialize_map(Some(members.len()))?;
+        for (json_key, json_value) in members {
+            map.serialize_entry(
+                &json_key.0,
+                &ValueSerializer {
+                    json_value,
+                    ancestor: self.ancestor,
+                },
+            )?;
+        }
+        map.end()
+    }
+}
+
+/// Members are serialized in the order of the sorted keys. Owned objects are serialized like shared objects.
+/// Raw values are parsed and serialized as values in human-readable formats, and kept as text in the other formats.
+/// * Return:
+///     * Error of the serializer if the value contains a reference cycle, a mutably borrowed object or a raw value that is not valid JSON.
+impl Serialize for JsonValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ValueSerializer {
+            json_value: self,
+            ancestor: None,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Serialized as a map like the object in `JsonValue`.
+impl Serialize for JsonObject {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ObjectSerializer {
+            json_object: self,
+            ancestor: None,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Variant of `JsonValue` in the formats that are not human-readable.
+enum ValueTag {
+    String,
+    Int,
+    Uint,
+    Float,
+    BigDecimal,
+    Bool,
+    Null,
+    Array,
+    Object,
+    Raw,
+}
+
+const VALUE_TAGS: [ValueTag; 10] = [
+    ValueTag::String,
+    ValueTag::Int,
+    ValueTag::Uint,
+    ValueTag::Float,
+    ValueTag::BigDecimal,
+    ValueTag::Bool,
+    ValueTag::Null,
+    ValueTag::Array,
+    ValueTag::Object,
+    ValueTag::Raw,
+];
+
+struct ValueTagVisitor;
+
+impl Visitor<'_> for ValueTagVisitor {
+    type Value = ValueTag;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a variant of JsonValue")
+    }
+
+    fn visit_u64<E: de::Error>(self, variant_idx: u64) -> Result<ValueTag, E> {
+        VALUE_TAGS
+            .into_iter()
+            .nth(variant_idx as usize)
+            .ok_or_else(|| E::invalid_value(de::Unexpected::Unsigned(variant_idx), &self))
+    }
+
+    fn visit_str<E: de::Error>(self, variant_str: &str) -> Result<ValueTag, E> {
+        match VALUE_VARIANTS
+            .iter()
+            .position(|value_variant_str| *value_variant_str == variant_str)
+        {
+            Some(variant_idx) => self.visit_u64(variant_idx as u64),
+            None => Err(E::unknown_variant(variant_str, &VALUE_VARIANTS)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ValueTag {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_identifier(ValueTagVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = JsonValue;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a JSON value")
+    }
+
+    fn visit_bool<E: de::Error>(self, json_bool: bool) -> Result<JsonValue, E> {
+        Ok(JsonValue::ValueBool(json_bool))
+    }
+
+    fn visit_i64<E: de::Error>(self, int_number: i64) -> Result<JsonValue, E> {
+        Ok(JsonValue::ValueNumber(JsonNumberType::Int(int_number)))
+    }
+
+    fn visit_u64<E: de::Error>(self, uint_number: u64) -> Result<JsonValue, E> {
+        // パーサーと同じく i64 に入らない整数だけを Uint にする
+        Ok(JsonValue::ValueNumber(match i64::try_from(uint_number) {
+            Ok(int_number) => JsonNumberType::Int(int_number),
+            Err(_) => JsonNumberType::Uint(uint_number),
+        }))
+    }
+
+    fn visit_f64<E: de::Error>(self, float_number: f64) -> Result<JsonValue, E> {
+        Ok(JsonValue::ValueNumber(JsonNumberType::Float(float_number)))
+    }
+
+    fn visit_str<E: de::Error>(self, json_str: &str) -> Result<JsonValue, E> {
+        Ok(JsonValue::ValueString(json_str.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, json_string: String) -> Result<JsonValue, E> {
+        Ok(JsonValue::ValueString(json_string))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<JsonValue, E> {
+        Ok(JsonValue::ValueNull)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<JsonValue, E> {
+        Ok(JsonValue::ValueNull)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<JsonValue, D::Error> {
+        JsonValue::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<JsonValue, A::Error> {
+        let mut json_array = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(json_element_value) = seq.next_element()? {
+            json_array.push(json_element_value);
+        }
+        Ok(JsonValue::ValueArray(json_array))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<JsonValue, A::Error> {
+        ObjectVisitor.visit_map(map).map(JsonValue::from)
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<JsonValue, A::Error> {
+        let (value_tag, variant) = data.variant()?;
+        Ok(match value_tag {
+            ValueTag::String => JsonValue::ValueString(variant.newtype_variant()?),
+            ValueTag::Int => JsonValue::ValueNumber(JsonNumberType::Int(variant.newtype_variant()?)),
+            ValueTag::Uint => JsonValue::ValueNumber(JsonNumberType::Uint(variant.newtype_variant()?)),
+            ValueTag::Float => JsonValue::ValueNumber(JsonNumberType::Float(variant.newtype_variant()?)),
+            ValueTag::BigDecimal => JsonValue::ValueNumber(JsonNumberType::BigDecimal(variant.newtype_variant()?)),
+            ValueTag::Bool => JsonValue::ValueBool(variant.newtype_variant()?),
+            ValueTag::Null => {
+                variant.unit_variant()?;
+                JsonValue::ValueNull
+            }
+            ValueTag::Array => JsonValue::ValueArray(variant.newtype_variant()?),
+            ValueTag::Object => JsonValue::from(variant.newtype_variant::<JsonObject>()?),
+            ValueTag::Raw => {
+                JsonValue::ValueRaw(JsonRawValue::new(&variant.newtype_variant::<String>()?).map_err(de::Error::custom)?)
+            }
+        })
+    }
+}
+
+struct ObjectVisitor;
+
+impl<'de> Visitor<'de> for ObjectVisitor {
+    type Value = JsonObject;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a JSON object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<JsonObject, A::Error> {
+        let mut json_object = JsonObject::new();
+        while let Some((key_string, json_value)) = map.next_entry::<String, JsonValue>()? {
+            json_object.insert(key_string, json_value);
+        }
+        Ok(json_object)
+    }
+}
+
+/// Numbers follow the parser: integers that fit in `i64` are `Int`, larger integers are `Uint` and the others are `Float`.
+/// Objects are shared objects(`ValueObject`), and a later member replaces an earlier member with the same key.
+impl<'de> Deserialize<'de> for JsonValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(ValueVisitor)
+        } else {
+            deserializer.deserialize_enum("JsonValue", &VALUE_VARIANTS, ValueVisitor)
+        }
+    }
+}
+
+/// Deserialized from a map like the object in `JsonValue`.
+impl<'de> Deserialize<'de> for JsonObject {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(ObjectVisitor)
+    }
+}
@@ -0,0 +1,127 @@
+//! Conversions between `JsonValue` and `serde_json::Value`, enabled with the `serde_json` feature.
+use serde_json::{Map, Number, Value};
+
+use super::shared::{object_ptr, JsonObjectPtr};
+use super::{JsonKey, JsonNumberType, JsonObject, JsonValue};
+
+use super::error::*;
+
+fn number_converter(json_number: &JsonNumberType) -> Value {
+    match json_number {
+        JsonNumberType::Int(int_number) => Value::Number(Number::from(*int_number)),
+        JsonNumberType::Uint(uint_number) => Value::Number(Number::from(*uint_number)),
+        // serde_json の From<f64> と同じく NaN と Infinity は null にする
+        JsonNumberType::Float(float_number) => Number::from_f64(*float_number).map_or(Value::Null, Value::Number),
+        JsonNumberType::BigDecimal(number_string) => number_string.parse::<Number>().map_or(Value::Null, Value::Number),
+    }
+}
+
+/// Convert the value, following the objects in it.
+/// * Parameters:
+///     * `ancestor_ptrs` : Objects being converted. A reference to one of them is a reference cycle and becomes `null`.
+fn value_converter(json_value: &JsonValue, ancestor_ptrs: &mut Vec<JsonObjectPtr>) -> Value {
+    match json_value {
+        JsonValue::ValueString(json_string) => Value::String(json_string.clone()),
+        JsonValue::ValueNumber(json_number) => number_converter(json_number),
+        JsonValue::ValueBool(json_bool) => Value::Bool(*json_bool),
+        JsonValue::ValueNull => Value::Null,
+        JsonValue::ValueArray(json_array) => Value::Array(
+            json_array
+                .iter()
+                .map(|json_element_value| value_converter(json_element_value, ancestor_ptrs))
+                .collect(),
+        ),
+        JsonValue::ValueObject(_) | JsonValue::ValueOwnedObject(_) => {
+            // 所有オブジェクトは循環しないので、共有オブジェクトだけを祖先として覚える
+            let json_object_ptr = json_value.as_object().map(object_ptr);
+            if json_object_ptr.is_some_and(|json_object_ptr| ancestor_ptrs.contains(&json_object_ptr)) {
+                return Value::Null;
+            }
+            let Some(json_object) = json_value.try_read_any_object() else {
+                return Value::Null;
+            };
+            ancestor_ptrs.extend(json_object_ptr);
+            let map = object_converter(&json_object, ancestor_ptrs);
+            if json_object_ptr.is_some() {
+                ancestor_ptrs.pop();
+            }
+            Value::Object(map)
+        }
+        JsonValue::ValueRaw(json_raw_value) => serde_json::from_str(json_raw_value.as_str()).unwrap_or(Value::Null),
+    }
+}
+
+fn object_converter(json_object: &JsonObject, ancestor_ptrs: &mut Vec<JsonObjectPtr>) -> Map<String, Value> {
+    json_object
+        .members
+        .iter()
+        .map(|(json_key, json_value)| (json_key.0.clone(), value_converter(json_value, ancestor_ptrs)))
+        .collect()
+}
+
+/// Deep conversion. Numbers keep their kind: `Int` becomes an `i64` number, `Uint` a `u64` number and `Float` an `f64` number.
+/// NaN and Infinity become `null` like `serde_json::Value::from(f64)`. `BigDecimal` is parsed by serde_json, so it is rounded to `f64`
+/// unless serde_json has the `arbitrary_precision` feature.
+/// Raw values are parsed by serde_json and become `null` if serde_json does not accept them.
+/// A reference to an ancestor object(reference cycle) and an object borrowed mutably become `null`.
+impl From<&JsonValue> for Value {
+    fn from(json_value: &JsonValue) -> Self {
+        value_converter(json_value, &mut Vec::new())
+    }
+}
+
+/// Deep conversion like `From<&JsonValue> for serde_json::Value`.
+impl From<&JsonObject> for Map<String, Value> {
+    fn from(json_object: &JsonObject) -> Self {
+        object_converter(json_object, &mut Vec::new())
+    }
+}
+
+fn number_parser(number: &Number) -> Result<JsonNumberType> {
+    // パーサーと同じく i64 に入らない正の整数だけを Uint にする
+    if let Some(int_number) = number.as_i64() {
+        Ok(JsonNumberType::Int(int_number))
+    } else if let Some(uint_number) = number.as_u64() {
+        Ok(JsonNumberType::Uint(uint_number))
+    } else if let Some(float_number) = number.as_f64() {
+        Ok(JsonNumberType::Float(float_number))
+    } else {
+        Err(JsonError::new(
+            JsonErrorKind::OutOfRange,
+            Some(format!("serde_json: {} does not fit in f64.", number)),
+        ))
+    }
+}
+
+/// Deep conversion. Objects become shared objects(`ValueObject`). Numbers that fit in `i64` become `Int`,
+/// larger integers that fit in `u64` become `Uint` as the parser does, and the others become `Float`.
+/// * Return:
+///     * `OutOfRange` error if a number does not fit in `f64`, which happens only with the `arbitrary_precision` feature of serde_json.
+impl TryFrom<&Value> for JsonValue {
+    type Error = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+    fn try_from(value: &Value) -> Result<Self> {
+        Ok(match value {
+            Value::Null => JsonValue::ValueNull,
+            Value::Bool(json_bool) => JsonValue::ValueBool(*json_bool),
+            Value::Number(number) => JsonValue::ValueNumber(number_parser(number)?),
+            Value::String(json_string) => JsonValue::ValueString(json_string.clone()),
+            Value::Array(array) => JsonValue::ValueArray(array.iter().map(JsonValue::try_from).collect::<Result<_>>()?),
+            Value::Object(map) => JsonValue::from(JsonObject::try_from(map)?),
+        })
+    }
+}
+
+/// Deep conversion like `TryFrom<&serde_json::Value> for JsonValue`.
+impl TryFrom<&Map<String, Value>> for JsonObject {
+    type Error = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+    fn try_from(map: &Map<String, Value>) -> Result<Self> {
+        Ok(JsonObject {
+            members: map
+                .iter()
+                .map(|(key_string, value)| Ok((JsonKey(key_string.clone()), JsonValue::try_from(value)?)))
+                .collect::<Result<_>>()?,
+        })
+    }
+}
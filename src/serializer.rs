@@ -1,5 +1,19 @@
 //! JSON Serializer module.
-use super::{JsonKey, JsonNumberType, JsonObject, JsonSerializerIndentKind, JsonSerializerNewLineKind, JsonValue};
+use std::collections::{HashMap, HashSet};
+use std::fs::{remove_file, rename, File};
+use std::io::{BufWriter, Write};
+use std::iter::Enumerate;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::shared::{object_ptr, JsonObjectPtr};
+use super::{
+    JsonKey, JsonNumberType, JsonObject, JsonPathSegment, JsonSerializerArrayStyle, JsonSerializerBraceStyle,
+    JsonSerializerColonStyle, JsonSerializerEmptyStyle, JsonSerializerIndentKind, JsonSerializerNewLineKind,
+    JsonSerializerNonFiniteFloatPolicy, JsonSerializerNumberStringPolicy, JsonSerializerOptions, JsonSerializerOutputEncoding,
+    JsonSerializerSharedObjectPolicy, JsonValue,
+};
+use super::{JsonObjectLock, JsonObjectRef, JsonSharedObject};
 
 use super::error::*;
 
@@ -8,271 +22,1635 @@ fn serialize_error(
     detail_str: &str,
     status_str: &str,
 ) -> Box<dyn std::error::Error + Send + Sync + 'static> {
-    return JsonError::new(kind, Some(format!("{} | {}", detail_str, status_str)));
+    JsonError::new(kind, Some(format!("{} | {}", detail_str, status_str)))
 }
 
-#[derive(PartialEq)]
-enum StartObjectKind {
-    EmptyObject,
-    HasSomeMember,
+fn file_error(detail_str: &str, path: &Path, err: std::io::Error) -> Box<dyn std::error::Error + Send + Sync + 'static> {
+    serialize_error(JsonErrorKind::IoError, detail_str, &format!("{} | {}", path.display(), err))
 }
 
+// 一時ファイル名を呼び出しごとに変えるためのカウンター
+static TMP_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
 const NEWLINE_STR_CRLF: &str = "\u{000D}\u{000A}";
 const NEWLINE_STR_LF: &str = "\u{000A}";
+pub(crate) const BOM_CHAR: char = '\u{FEFF}';
+
+/// Destination of the serialized string.
+pub(crate) trait JsonSerializerOutput {
+    fn push_str(&mut self, string: &str) -> Result<()>;
+
+    fn push(&mut self, unicode_char: char) -> Result<()> {
+        self.push_str(unicode_char.encode_utf8(&mut [0; 4]))
+    }
+}
+
+impl JsonSerializerOutput for String {
+    fn push_str(&mut self, string: &str) -> Result<()> {
+        String::push_str(self, string);
+        Ok(())
+    }
+
+    fn push(&mut self, unicode_char: char) -> Result<()> {
+        String::push(self, unicode_char);
+        Ok(())
+    }
+}
+
+/// `JsonSerializerOutput` that writes the serialized string incrementally to `std::io::Write`.
+pub(crate) struct JsonWriterOutput<W: Write> {
+    writer: BufWriter<W>,
+}
+
+impl<W: Write> JsonWriterOutput<W> {
+    pub(crate) fn new(writer: W) -> JsonWriterOutput<W> {
+        JsonWriterOutput {
+            writer: BufWriter::new(writer),
+        }
+    }
+
+    pub(crate) fn flush(&mut self) -> Result<()> {
+        self.writer.flush().map_err(|err| {
+            serialize_error(
+                JsonErrorKind::IoError,
+                "Writer: Failed to flush the writer.",
+                &err.to_string(),
+            )
+        })
+    }
+
+    /// Flush the buffer and return the inner writer.
+    pub(crate) fn into_inner(self) -> Result<W> {
+        self.writer.into_inner().map_err(|err| {
+            serialize_error(
+                JsonErrorKind::IoError,
+                "Writer: Failed to flush the writer.",
+                &err.error().to_string(),
+            )
+        })
+    }
+}
+
+impl<W: Write> JsonSerializerOutput for JsonWriterOutput<W> {
+    fn push_str(&mut self, string: &str) -> Result<()> {
+        self.writer.write_all(string.as_bytes()).map_err(|err| {
+            serialize_error(
+                JsonErrorKind::IoError,
+                "Writer: Failed to write the serialized string.",
+                &err.to_string(),
+            )
+        })
+    }
+}
+
+/// `JsonSerializerOutput` that passes the serialized string to a sink in chunks.
+/// The buffer is passed to the sink when it reaches `chunk_size` bytes, so a chunk can be a little longer than `chunk_size`.
+pub(crate) struct JsonChunkOutput<F: FnMut(&str) -> Result<()>> {
+    buffer_string: String,
+    chunk_size: usize,
+    sink: F,
+}
+
+impl<F: FnMut(&str) -> Result<()>> JsonChunkOutput<F> {
+    pub(crate) fn new(chunk_size: usize, sink: F) -> JsonChunkOutput<F> {
+        JsonChunkOutput {
+            buffer_string: String::with_capacity(chunk_size),
+            chunk_size,
+            sink,
+        }
+    }
+
+    fn chunk_flusher(&mut self) -> Result<()> {
+        (self.sink)(&self.buffer_string)?;
+        self.buffer_string.clear();
+        Ok(())
+    }
+
+    /// Pass the rest of the buffer to the sink.
+    pub(crate) fn finish(mut self) -> Result<()> {
+        if self.buffer_string.is_empty() {
+            return Ok(());
+        }
+        self.chunk_flusher()
+    }
+}
+
+impl<F: FnMut(&str) -> Result<()>> JsonSerializerOutput for JsonChunkOutput<F> {
+    fn push_str(&mut self, string: &str) -> Result<()> {
+        self.buffer_string.push_str(string);
+        if self.buffer_string.len() >= self.chunk_size {
+            self.chunk_flusher()?;
+        }
+        Ok(())
+    }
+}
+
+/// `JsonSerializerOutput` that only counts the characters of the serialized string.
+/// It fails as soon as the count goes beyond `max_width`, so that the rest is not serialized.
+struct JsonWidthOutput {
+    width: usize,
+    max_width: usize,
+}
+
+impl JsonSerializerOutput for JsonWidthOutput {
+    fn push_str(&mut self, string: &str) -> Result<()> {
+        self.width += string.chars().count();
+        if self.width > self.max_width {
+            return Err(serialize_error(
+                JsonErrorKind::SerializeErrorInValue,
+                "Width: Serialized string is too wide.",
+                &format!("max_width:{}", self.max_width),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Newline code at the end of the JSON string.
+pub(crate) fn make_trailing_newline_str(options: &JsonSerializerOptions) -> &'static str {
+    match options.newline_kind {
+        JsonSerializerNewLineKind::CrLf => NEWLINE_STR_CRLF,
+        JsonSerializerNewLineKind::Lf => NEWLINE_STR_LF,
+        JsonSerializerNewLineKind::Platform => {
+            if cfg!(windows) {
+                NEWLINE_STR_CRLF
+            } else {
+                NEWLINE_STR_LF
+            }
+        }
+    }
+}
+
+/// Newline code between lines. Empty in compact mode.
+pub(crate) fn make_newline_str(options: &JsonSerializerOptions) -> &'static str {
+    // compact では改行もインデントも出力しない
+    if options.compact {
+        ""
+    } else {
+        make_trailing_newline_str(options)
+    }
+}
+
+/// Indent of one level. Empty in compact mode.
+pub(crate) fn make_indent_string(options: &JsonSerializerOptions) -> String {
+    if options.compact {
+        return String::new();
+    }
+    match &options.indent_kind {
+        JsonSerializerIndentKind::Tab => "\t".to_string(),
+        JsonSerializerIndentKind::Space(length) => {
+            let mut tmp_string = String::new();
+            for _ in 0..*length {
+                tmp_string.push(' ');
+            }
+            tmp_string
+        }
+        JsonSerializerIndentKind::Custom(indent_str) => indent_str.clone(),
+    }
+}
+
+/// Separator between key and value.
+pub(crate) fn make_colon_str(options: &JsonSerializerOptions) -> &'static str {
+    if options.compact {
+        return ":";
+    }
+    match options.colon_style {
+        JsonSerializerColonStyle::SpaceAfter => ": ",
+        JsonSerializerColonStyle::SpaceBoth => " : ",
+        JsonSerializerColonStyle::None => ":",
+    }
+}
+
+pub(crate) fn validate_options(options: &JsonSerializerOptions) -> Result<()> {
+    if let JsonSerializerIndentKind::Custom(indent_str) = &options.indent_kind {
+        if indent_str
+            .chars()
+            .any(|unicode_char| unicode_char.is_control() && unicode_char != '\t')
+        {
+            return Err(serialize_error(
+                JsonErrorKind::SerializeErrorInOptions,
+                "Indent: Custom indent contains a control character.",
+                &format!("{:?}", indent_str),
+            ));
+        }
+    }
+    if options.array_wrap == Some(0) {
+        return Err(serialize_error(
+            JsonErrorKind::SerializeErrorInOptions,
+            "Array wrap: Number of elements per line is 0.",
+            "array_wrap:Some(0)",
+        ));
+    }
+    Ok(())
+}
+
+/// Shortest digits of `float_number`(`0.{digits}e{point_position}`) with the tie resolved to even.
+/// When the value is exactly halfway between two shortest candidates (e.g. `1424953923781206.25`), ECMAScript and RFC 8785
+/// choose the even one, while the formatter of Rust rounds half up.
+fn even_digits_on_tie(float_number: f64, digits: String, point_position: i32) -> String {
+    let last_digit = digits.as_bytes()[digits.len() - 1] - b'0';
+    if last_digit.is_multiple_of(2) {
+        return digits;
+    }
+    for alt_last_digit in [last_digit - 1, last_digit + 1]
+        .into_iter()
+        .filter(|alt_last_digit| *alt_last_digit <= 9)
+    {
+        let alt_digits = format!("{}{}", &digits[..digits.len() - 1], alt_last_digit);
+        if format!("0.{}e{}", alt_digits, point_position).parse::<f64>() != Ok(float_number) {
+            continue;
+        }
+        // 両方とも元の値に戻る場合、真の値がちょうど中間にあるときだけ偶数側を選ぶ
+        let lower_digits = if alt_last_digit < last_digit { &alt_digits } else { &digits };
+        let midpoint_digits = format!("{}5", lower_digits);
+        let exact_string = format!("{:.*e}", 800, float_number);
+        let exact_digits: String = exact_string
+            .split('e')
+            .next()
+            .unwrap_or_default()
+            .chars()
+            .filter(|unicode_char| *unicode_char != '.')
+            .collect();
+        if exact_digits.trim_end_matches('0') == midpoint_digits {
+            return alt_digits;
+        }
+    }
+    digits
+}
+
+// ECMAScript の予約語。識別子の形でもキーはクォートする
+const RESERVED_WORDS: [&str; 46] = [
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "implements",
+    "import",
+    "in",
+    "instanceof",
+    "interface",
+    "let",
+    "new",
+    "null",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "static",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "var",
+    "void",
+    "while",
+    "with",
+    "yield",
+    "await",
+];
+
+/// Whether the key can be written without quotes in JSON5, i.e. it matches `[A-Za-z_$][A-Za-z0-9_$]*` and is not reserved.
+fn is_identifier(key_str: &str) -> bool {
+    let mut unicode_chars = key_str.chars();
+    match unicode_chars.next() {
+        Some(unicode_char) if unicode_char.is_ascii_alphabetic() || unicode_char == '_' || unicode_char == '$' => {}
+        _ => return false,
+    }
+    unicode_chars.all(|unicode_char| unicode_char.is_ascii_alphanumeric() || unicode_char == '_' || unicode_char == '$')
+        && !RESERVED_WORDS.contains(&key_str)
+}
+
+/// Make JSON Pointer (RFC 6901) string from the path. e.g. `/key/0`
+pub(crate) fn make_pointer_string(path: &[JsonPathSegment]) -> String {
+    let mut pointer_string = String::new();
+    for path_segment in path {
+        pointer_string.push('/');
+        match path_segment {
+            JsonPathSegment::Key(key_str) => pointer_string.push_str(&key_str.replace('~', "~0").replace('/', "~1")),
+            JsonPathSegment::Index(idx) => pointer_string.push_str(&idx.to_string()),
+        }
+    }
+    pointer_string
+}
+
+/// Root of the serialized document.
+#[derive(Clone, Copy)]
+pub(crate) enum JsonSerializerRoot<'a> {
+    Object(&'a JsonObject),
+    Value(&'a JsonValue),
+    // 指定したキーのメンバーだけを持つオブジェクト
+    Projection(&'a JsonObject, &'a [&'a str]),
+}
+
+/// Pre-pass over the document that collects every object reachable from the root without recursion.
+struct JsonObjectCollector {
+    // 共有・循環しているオブジェクトも一度だけ入る
+    refcell_json_objects: Vec<JsonSharedObject>,
+    object_ptrs: HashSet<JsonObjectPtr>,
+}
+
+impl JsonObjectCollector {
+    fn collect(root: JsonSerializerRoot) -> JsonObjectCollector {
+        let mut collector = JsonObjectCollector {
+            refcell_json_objects: Vec::new(),
+            object_ptrs: HashSet::new(),
+        };
+        match root {
+            JsonSerializerRoot::Object(json_object) => collector.collect_object(json_object),
+            JsonSerializerRoot::Value(json_value) => collector.collect_values(vec![json_value]),
+            JsonSerializerRoot::Projection(json_object, keys) => {
+                let json_values = keys.iter().filter_map(|key_str| json_object.members.get(*key_str)).collect();
+                collector.collect_values(json_values);
+            }
+        }
+        let mut object_idx = 0;
+        while object_idx < collector.refcell_json_objects.len() {
+            let refcell_json_object = collector.refcell_json_objects[object_idx].clone();
+            // 可変借用中のオブジェクトはここでは読み飛ばし、シリアライズ時にパス付きでエラーにする
+            if let Some(json_object) = refcell_json_object.try_read_object() {
+                collector.collect_object(&json_object);
+            }
+            object_idx += 1;
+        }
+        collector
+    }
+
+    fn collect_object(&mut self, json_object: &JsonObject) {
+        self.collect_values(json_object.members.values().collect());
+    }
+
+    fn push_object(&mut self, refcell_json_object: &JsonSharedObject) {
+        if self.object_ptrs.insert(object_ptr(refcell_json_object)) {
+            self.refcell_json_objects.push(refcell_json_object.clone());
+        }
+    }
+
+    fn collect_values(&mut self, mut json_values: Vec<&JsonValue>) {
+        while let Some(json_value) = json_values.pop() {
+            match json_value {
+                JsonValue::ValueArray(json_array) => json_values.extend(json_array.iter()),
+                JsonValue::ValueObject(refcell_json_object) => self.push_object(refcell_json_object),
+                // 所有オブジェクトは借用せずに読めるので、その場でメンバーをたどる
+                JsonValue::ValueOwnedObject(json_object) => json_values.extend(json_object.members.values()),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Borrows of all objects in the document, held while serializing so that nested objects can be visited without recursion.
+pub(crate) struct JsonObjectArena<'a> {
+    json_objects: HashMap<JsonObjectPtr, JsonObjectRef<'a>>,
+}
+
+impl<'a> JsonObjectArena<'a> {
+    fn new(collector: &'a JsonObjectCollector) -> JsonObjectArena<'a> {
+        let mut json_objects = HashMap::with_capacity(collector.refcell_json_objects.len());
+        for refcell_json_object in &collector.refcell_json_objects {
+            if let Some(json_object) = refcell_json_object.try_read_object() {
+                json_objects.insert(object_ptr(refcell_json_object), json_object);
+            }
+        }
+        JsonObjectArena { json_objects }
+    }
+
+    /// Returns `None` if the object is mutably borrowed.
+    fn get(&self, refcell_json_object: &JsonSharedObject) -> Option<&JsonObject> {
+        self.json_objects
+            .get(&object_ptr(refcell_json_object))
+            .map(|json_object| &**json_object)
+    }
+}
+
+/// Object or array whose members are being serialized.
+#[derive(Clone)]
+enum Frame<'a> {
+    Object {
+        members: std::vec::IntoIter<(&'a JsonKey, &'a JsonValue)>,
+        // align_colons で揃えるキーの幅。揃えない場合は 0
+        key_width: usize,
+        is_first: bool,
+        // ルートの JsonObject は Rc に包まれていないので None
+        object_ptr: Option<JsonObjectPtr>,
+    },
+    Array {
+        elements: Enumerate<std::slice::Iter<'a, JsonValue>>,
+        is_first: bool,
+        is_multiline: bool,
+        // array_wrap で折り返す場合の一行の要素数
+        wrap_len: Option<usize>,
+    },
+}
+
+/// How the value of a member or an element is serialized. It is decided before the colon, so that the colon follows the value actually serialized.
+enum ValueRendering {
+    // transform_value で置き換えた値
+    Replacement(JsonValue),
+    // inline_value または inline_small_objects により一行で出力する
+    Compact,
+    Plain,
+}
 
 /// JSON serializer struct.
-#[derive(Clone, Debug)]
-pub struct JsonSerializer {
+/// Nested objects and arrays are kept on `frames` instead of the call stack, so arbitrarily deep trees can be serialized.
+#[derive(Clone)]
+pub struct JsonSerializer<'a> {
+    options: &'a JsonSerializerOptions,
+    // スカラー値だけをシリアライズする場合は None
+    arena: Option<&'a JsonObjectArena<'a>>,
+    canonical: bool,
+    // 構造上の改行。compact では空
     newline_str: &'static str,
+    trailing_newline_str: &'static str,
     indent_string: String,
-    indent_level: usize,
+    pub(crate) indent_level: usize,
+    frames: Vec<Frame<'a>>,
+    path: Vec<JsonPathSegment<'a>>,
+    // 循環参照の検出用。ルートから現在の値までのパス上にあるオブジェクト
+    object_ptrs_in_path: HashSet<JsonObjectPtr>,
+    // 共有オブジェクトの検出用。シリアライズ済みのオブジェクト
+    serialized_object_ptrs: HashSet<JsonObjectPtr>,
+    // 配列が複数行になるかどうかのキャッシュ。キーは配列の先頭要素のアドレス
+    multiline_arrays: HashMap<*const JsonValue, bool>,
+    // 値が始まる桁。一行の配列の中では None
+    value_column: Option<usize>,
+    // transform_value で置き換えた値をシリアライズしている。置き換えた値の中身には transform_value を呼ばない
+    is_replacement: bool,
+    // 置き換えた値をシリアライズするときの、置き換えた位置の深さ
+    base_depth: usize,
 }
 
-impl JsonSerializer {
+impl<'a> JsonSerializer<'a> {
     /// Serialize JSON function.
     #[allow(dead_code)]
-    pub fn serialize(
-        json_object: &JsonObject,
-        newline_kind: JsonSerializerNewLineKind,
-        indent_kind: JsonSerializerIndentKind,
-    ) -> Result<String> {
-        let mut json_serializer: JsonSerializer = JsonSerializer::new(newline_kind, indent_kind);
-
+    pub(crate) fn serialize(root: JsonSerializerRoot, options: &JsonSerializerOptions) -> Result<String> {
         let mut content_string = String::new();
-        json_serializer.object_serializer(json_object, &mut content_string)?;
-        // 最後に改行する
-        content_string.push_str(&json_serializer.newline_str);
+        JsonSerializer::serialize_into(root, &mut content_string, options)?;
         Ok(content_string)
     }
 
-    fn new(newline_kind: JsonSerializerNewLineKind, indent_kind: JsonSerializerIndentKind) -> JsonSerializer {
-        let newline_str: &'static str = {
-            match newline_kind {
-                JsonSerializerNewLineKind::CrLf => NEWLINE_STR_CRLF,
-                JsonSerializerNewLineKind::Lf => NEWLINE_STR_LF,
+    /// Serialize JSON function that appends the serialized string to `content_string`.
+    /// On error, `content_string` is truncated back to the original length.
+    pub(crate) fn serialize_into(
+        root: JsonSerializerRoot,
+        content_string: &mut String,
+        options: &JsonSerializerOptions,
+    ) -> Result<()> {
+        let original_len = content_string.len();
+        let res = JsonSerializer::serialize_to_output(root, options, false, content_string);
+        if res.is_err() {
+            content_string.truncate(original_len);
+        }
+        res
+    }
+
+    /// Serialize JSON function that encodes the serialized string to bytes.
+    pub(crate) fn serialize_to_bytes(
+        root: JsonSerializerRoot,
+        options: &JsonSerializerOptions,
+        encoding: JsonSerializerOutputEncoding,
+    ) -> Result<Vec<u8>> {
+        let mut content_string = JsonSerializer::serialize(root, options)?;
+        if options.emit_bom && encoding != JsonSerializerOutputEncoding::Utf8 {
+            // BOM 付きのエンコーディングでは BOM を二重に出力しない
+            content_string.remove(0);
+        }
+        let bytes = match encoding {
+            JsonSerializerOutputEncoding::Utf8 => content_string.into_bytes(),
+            JsonSerializerOutputEncoding::Utf8WithBom => {
+                let mut bytes = Vec::with_capacity(content_string.len() + 3);
+                bytes.extend_from_slice(&[0xEF, 0xBB, 0xBF]);
+                bytes.extend_from_slice(content_string.as_bytes());
+                bytes
+            }
+            JsonSerializerOutputEncoding::Utf16Le => {
+                let mut bytes = Vec::with_capacity(content_string.len() * 2 + 2);
+                bytes.extend_from_slice(&[0xFF, 0xFE]);
+                for u16_char_code in content_string.encode_utf16() {
+                    bytes.extend_from_slice(&u16_char_code.to_le_bytes());
+                }
+                bytes
+            }
+            JsonSerializerOutputEncoding::Utf16Be => {
+                let mut bytes = Vec::with_capacity(content_string.len() * 2 + 2);
+                bytes.extend_from_slice(&[0xFE, 0xFF]);
+                for u16_char_code in content_string.encode_utf16() {
+                    bytes.extend_from_slice(&u16_char_code.to_be_bytes());
+                }
+                bytes
             }
         };
-        let indent_string = {
-            match indent_kind {
-                JsonSerializerIndentKind::Tab => "\t".to_string(),
-                JsonSerializerIndentKind::Space(length) => {
-                    let mut tmp_string = String::new();
-                    for _ in 0..length {
-                        tmp_string.push(' ');
-                    }
-                    tmp_string
+        Ok(bytes)
+    }
+
+    /// Serialize JSON function that writes the serialized string to `writer` incrementally.
+    #[allow(dead_code)]
+    pub(crate) fn serialize_to_writer<W: Write>(
+        root: JsonSerializerRoot,
+        writer: &mut W,
+        options: &JsonSerializerOptions,
+    ) -> Result<()> {
+        let mut output = JsonWriterOutput::new(writer);
+        JsonSerializer::serialize_to_output(root, options, false, &mut output)?;
+        output.flush()
+    }
+
+    /// Serialize JSON function that passes the serialized string to `sink` in chunks of about `chunk_size` bytes.
+    pub(crate) fn serialize_to_chunks<F: FnMut(&str) -> Result<()>>(
+        root: JsonSerializerRoot,
+        chunk_size: usize,
+        sink: F,
+        options: &JsonSerializerOptions,
+    ) -> Result<()> {
+        if chunk_size == 0 {
+            return Err(serialize_error(
+                JsonErrorKind::SerializeErrorInOptions,
+                "Chunk: Chunk size is 0.",
+                "chunk_size:0",
+            ));
+        }
+        let mut output = JsonChunkOutput::new(chunk_size, sink);
+        JsonSerializer::serialize_to_output(root, options, false, &mut output)?;
+        output.finish()
+    }
+
+    /// Serialize JSON function that writes the serialized string to the file atomically.
+    /// The string is written to a temporary file in the same directory first, then the temporary file is renamed to `path`.
+    /// The name of the temporary file is unique to each call, so threads can write the same path at the same time.
+    #[allow(dead_code)]
+    pub(crate) fn serialize_to_file<P: AsRef<Path>>(
+        root: JsonSerializerRoot,
+        path: P,
+        options: &JsonSerializerOptions,
+    ) -> Result<()> {
+        let path = path.as_ref();
+        let file_name = match path.file_name() {
+            Some(file_name) => file_name.to_string_lossy(),
+            None => {
+                return Err(serialize_error(
+                    JsonErrorKind::IoError,
+                    "File: Path does not have a file name.",
+                    &path.display().to_string(),
+                ));
+            }
+        };
+        // 同じプロセスの他のスレッドが同じパスに書き込んでも一時ファイルが重ならないようにする
+        let tmp_path = path.with_file_name(format!(
+            ".{}.{}.{}.tmp",
+            file_name,
+            std::process::id(),
+            TMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        let res = JsonSerializer::serialize_to_tmp_file(root, &tmp_path, options).and_then(|_| {
+            rename(&tmp_path, path).map_err(|err| file_error("File: Failed to rename the temporary file.", path, err))
+        });
+        if res.is_err() {
+            // 途中で失敗した場合は一時ファイルを残さない
+            let _ = remove_file(&tmp_path);
+        }
+        res
+    }
+
+    fn serialize_to_tmp_file(root: JsonSerializerRoot, tmp_path: &Path, options: &JsonSerializerOptions) -> Result<()> {
+        let mut file =
+            File::create(tmp_path).map_err(|err| file_error("File: Failed to create the temporary file.", tmp_path, err))?;
+        JsonSerializer::serialize_to_writer(root, &mut file, options).map_err(|err| {
+            // 書き込みの失敗にだけパスを加え、シリアライズのエラーはそのまま返す
+            match err.downcast_ref::<JsonError>() {
+                Some(json_err) if json_err.err_kind == JsonErrorKind::IoError => {
+                    serialize_error(JsonErrorKind::IoError, &err.to_string(), &tmp_path.display().to_string())
                 }
+                _ => err,
+            }
+        })?;
+        file.sync_all()
+            .map_err(|err| file_error("File: Failed to sync the temporary file.", tmp_path, err))
+    }
+
+    /// Serialize JSON function that makes canonical JSON (RFC 8785 JSON Canonicalization Scheme).
+    /// Members are sorted by UTF-16 code units of keys, there is no whitespace, and numbers are formatted like ECMAScript.
+    pub(crate) fn serialize_canonical(root: JsonSerializerRoot) -> Result<String> {
+        let options = JsonSerializerOptions::new()
+            .compact(true)
+            .trailing_newline(false)
+            .sort_keys(true);
+        let mut content_string = String::new();
+        JsonSerializer::serialize_to_output(root, &options, true, &mut content_string)?;
+        Ok(content_string)
+    }
+
+    fn serialize_to_output(
+        root: JsonSerializerRoot,
+        options: &JsonSerializerOptions,
+        canonical: bool,
+        output: &mut dyn JsonSerializerOutput,
+    ) -> Result<()> {
+        validate_options(options)?;
+        let collector = JsonObjectCollector::collect(root);
+        let arena = JsonObjectArena::new(&collector);
+        let mut json_serializer: JsonSerializer = JsonSerializer::new(options, Some(&arena));
+        json_serializer.canonical = canonical;
+        if options.emit_bom {
+            output.push(BOM_CHAR)?;
+        }
+
+        match root {
+            JsonSerializerRoot::Object(json_object) => json_serializer.start_object_serializer(json_object, None, output)?,
+            JsonSerializerRoot::Value(json_value) => json_serializer.value_serializer(json_value, output)?,
+            JsonSerializerRoot::Projection(json_object, keys) => {
+                json_serializer.start_projection_serializer(json_object, keys, output)?
             }
         };
+        json_serializer.frames_serializer(output)?;
+        if json_serializer.options.trailing_newline {
+            // 最後に改行する
+            output.push_str(json_serializer.trailing_newline_str)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn new(options: &'a JsonSerializerOptions, arena: Option<&'a JsonObjectArena<'a>>) -> JsonSerializer<'a> {
         JsonSerializer {
-            newline_str: newline_str,
-            indent_string: indent_string,
+            options,
+            arena,
+            canonical: false,
+            newline_str: make_newline_str(options),
+            trailing_newline_str: make_trailing_newline_str(options),
+            indent_string: make_indent_string(options),
             indent_level: 0,
+            frames: Vec::new(),
+            path: Vec::new(),
+            object_ptrs_in_path: HashSet::new(),
+            serialized_object_ptrs: HashSet::new(),
+            multiline_arrays: HashMap::new(),
+            value_column: Some(0),
+            is_replacement: false,
+            base_depth: 0,
+        }
+    }
+
+    /// Object of the value: the borrowed shared object in the arena, or the owned object.
+    /// `None` if the value is not an object or the shared object is mutably borrowed.
+    fn get_object<'b>(&self, json_value: &'b JsonValue) -> Option<&'b JsonObject>
+    where
+        'a: 'b,
+    {
+        match json_value {
+            JsonValue::ValueObject(refcell_json_object) => self.arena.and_then(|arena| arena.get(refcell_json_object)),
+            JsonValue::ValueOwnedObject(json_object) => Some(json_object),
+            _ => None,
         }
     }
 
-    fn make_indent_string(&self) -> String {
-        let mut indent_string = String::new();
+    /// Width of the current indent in characters.
+    fn indent_width(&self) -> usize {
+        self.indent_level * self.indent_string.chars().count()
+    }
+
+    /// Width of the serialized string in characters.
+    fn string_width(&self, json_string_str: &str) -> usize {
+        let mut content_string = String::new();
+        // String への出力は失敗しない
+        let _ = self.string_serializer(json_string_str, &mut content_string);
+        content_string.chars().count()
+    }
+
+    fn indent_serializer(&self, output: &mut dyn JsonSerializerOutput) -> Result<()> {
+        if self.indent_string.is_empty() {
+            return Ok(());
+        }
         for _ in 0..self.indent_level {
-            indent_string.push_str(&self.indent_string);
+            output.push_str(&self.indent_string)?;
         }
-        indent_string
+        Ok(())
     }
 
-    fn object_serializer(&mut self, json_object: &JsonObject, content_string: &mut String) -> Result<()> {
-        match self.start_object_serializer(json_object, content_string)? {
-            StartObjectKind::EmptyObject => {
-                return Ok(());
-            }
-            StartObjectKind::HasSomeMember => {
-                let mut member_count: usize = 0;
-                for (json_key, json_value) in &json_object.members {
-                    self.key_serializer(json_key, content_string)?;
-                    self.coron_serializer(content_string)?;
-                    self.value_serializer(json_value, content_string)?;
-                    if member_count < json_object.members.len() - 1 {
-                        self.end_member_serializer(content_string)?
+    /// Serialize the members of the objects and arrays on `frames` until all of them are closed.
+    fn frames_serializer(&mut self, output: &mut dyn JsonSerializerOutput) -> Result<()> {
+        while let Some(frame) = self.frames.last_mut() {
+            match frame {
+                Frame::Object {
+                    members,
+                    key_width,
+                    is_first,
+                    ..
+                } => match members.next() {
+                    Some((json_key, json_value)) => {
+                        let key_width = *key_width;
+                        if !std::mem::replace(is_first, false) {
+                            self.end_member_serializer(output)?;
+                        }
+                        self.key_serializer(json_key, output)?;
+                        let padded_key_width = if key_width > 0 {
+                            let json_key_width = self.key_width(&json_key.0);
+                            for _ in json_key_width..key_width {
+                                output.push(' ')?;
+                            }
+                            key_width
+                        } else if self.options.max_inline_array_width.is_some() {
+                            self.key_width(&json_key.0)
+                        } else {
+                            0
+                        };
+                        if self.options.max_inline_array_width.is_some() {
+                            self.value_column = Some(self.indent_width() + padded_key_width + self.colon_str().len());
+                        }
+                        self.path.push(JsonPathSegment::Key(&json_key.0));
+                        let value_rendering = self.value_rendering(json_value);
+                        if self.is_brace_on_next_line(json_value, &value_rendering) {
+                            // 開き括弧はキーの次の行に、キーと同じインデントで出力する
+                            output.push_str(self.colon_str().trim_end())?;
+                            output.push_str(self.newline_str)?;
+                            self.indent_serializer(output)?;
+                        } else {
+                            self.coron_serializer(output)?;
+                        }
+                        self.rendered_value_serializer(json_value, value_rendering, output)?;
                     }
-                    member_count += 1;
+                    None => self.end_object_serializer(output)?,
+                },
+                Frame::Array {
+                    elements,
+                    is_first,
+                    is_multiline,
+                    wrap_len,
+                } => match elements.next() {
+                    Some((idx, json_value)) => {
+                        let is_first = std::mem::replace(is_first, false);
+                        if let Some(wrap_len) = *wrap_len {
+                            // 一行に wrap_len 個ずつ出力する
+                            if idx % wrap_len == 0 {
+                                if !is_first {
+                                    self.end_member_serializer(output)?;
+                                }
+                                self.indent_serializer(output)?;
+                            } else {
+                                output.push_str(", ")?;
+                            }
+                            self.value_column = None;
+                        } else if *is_multiline {
+                            if !is_first {
+                                self.end_member_serializer(output)?;
+                            }
+                            self.indent_serializer(output)?;
+                            self.value_column = Some(self.indent_width());
+                        } else {
+                            if !is_first {
+                                output.push(',')?;
+                            }
+                            if !self.options.compact {
+                                output.push(' ')?;
+                            }
+                            self.value_column = None;
+                        }
+                        self.path.push(JsonPathSegment::Index(idx));
+                        let value_rendering = self.value_rendering(json_value);
+                        self.rendered_value_serializer(json_value, value_rendering, output)?;
+                    }
+                    None => self.end_array_serializer(output)?,
+                },
+            }
+        }
+        Ok(())
+    }
+
+    /// Decide how the value of a member or an element is serialized. The path segment of the value is already pushed.
+    fn value_rendering(&mut self, json_value: &'a JsonValue) -> ValueRendering {
+        let options = self.options;
+        if let Some(transform_value) = options.transform_value.as_ref().filter(|_| !self.is_replacement) {
+            if let Some(json_replacement_value) = transform_value(&self.path, json_value) {
+                return ValueRendering::Replacement(json_replacement_value);
+            }
+        }
+        if let Some(inline_value) = &options.inline_value {
+            if !options.compact
+                && matches!(
+                    json_value,
+                    JsonValue::ValueArray(_) | JsonValue::ValueObject(_) | JsonValue::ValueOwnedObject(_)
+                )
+                && inline_value(&self.path)
+            {
+                return ValueRendering::Compact;
+            }
+        }
+        if self.is_small_object(json_value) {
+            return ValueRendering::Compact;
+        }
+        ValueRendering::Plain
+    }
+
+    /// Serialize the value of a member or an element as decided by `value_rendering`, and pop its path segment
+    /// (when the frame of the value is closed if one is pushed).
+    fn rendered_value_serializer(
+        &mut self,
+        json_value: &'a JsonValue,
+        value_rendering: ValueRendering,
+        output: &mut dyn JsonSerializerOutput,
+    ) -> Result<()> {
+        match value_rendering {
+            ValueRendering::Replacement(json_replacement_value) => {
+                self.replacement_serializer(&json_replacement_value, output)?;
+            }
+            ValueRendering::Compact => {
+                // 部分木だけ compact で出力する
+                let compact_options = self.options.clone().compact(true);
+                let arena = self.arena;
+                let is_replacement = self.is_replacement;
+                self.subtree_serializer(json_value, &compact_options, arena, is_replacement, output)?;
+            }
+            ValueRendering::Plain => {
+                if self.value_serializer(json_value, output)? {
+                    return Ok(());
                 }
-                self.end_object_serializer(content_string)?
             }
         }
+        self.path.pop();
         Ok(())
     }
 
-    fn start_object_serializer(&mut self, json_object: &JsonObject, content_string: &mut String) -> Result<StartObjectKind> {
-        if json_object.members.len() == 0 {
-            content_string.push_str("{}");
-            return Ok(StartObjectKind::EmptyObject);
+    /// Whether the value is a non-empty object that is serialized on one line with `inline_small_objects`.
+    /// The object is serialized in compact mode to measure the width, which stops as soon as it goes beyond the limit.
+    fn is_small_object(&mut self, json_value: &'a JsonValue) -> bool {
+        let Some(max_width) = self.options.inline_small_objects.filter(|_| !self.options.compact) else {
+            return false;
+        };
+        if !matches!(json_value, JsonValue::ValueObject(_) | JsonValue::ValueOwnedObject(_))
+            || self
+                .get_object(json_value)
+                .is_none_or(|json_object| self.is_empty_object(json_object))
+        {
+            return false;
+        }
+        // 計測では共有オブジェクトを記録しない
+        let measure_options = self
+            .options
+            .clone()
+            .compact(true)
+            .on_shared_object(JsonSerializerSharedObjectPolicy::SerializeEachTime);
+        let arena = self.arena;
+        let is_replacement = self.is_replacement;
+        let mut width_output = JsonWidthOutput { width: 0, max_width };
+        // 幅を超えた場合の他に、循環参照などのエラーも通常のシリアライズで報告させる
+        self.subtree_serializer(json_value, &measure_options, arena, is_replacement, &mut width_output)
+            .is_ok()
+    }
+
+    /// Serialize the value returned by `transform_value` in place of the original value.
+    fn replacement_serializer(&mut self, json_value: &JsonValue, output: &mut dyn JsonSerializerOutput) -> Result<()> {
+        match json_value {
+            JsonValue::ValueString(json_string) => self.string_serializer(json_string, output),
+            JsonValue::ValueNumber(json_number) => self.number_serializer(json_number, output),
+            JsonValue::ValueBool(json_bool) => self.bool_serializer(json_bool, output),
+            JsonValue::ValueNull => self.null_serializer(output),
+            JsonValue::ValueRaw(json_raw_value) => self.raw_serializer(json_raw_value.as_str(), output),
+            JsonValue::ValueArray(_) | JsonValue::ValueObject(_) | JsonValue::ValueOwnedObject(_) => {
+                // 置き換えた値は元の木に含まれないので、置き換えた値のオブジェクトを借用し直す
+                let collector = JsonObjectCollector::collect(JsonSerializerRoot::Value(json_value));
+                let arena = JsonObjectArena::new(&collector);
+                let options = self.options;
+                self.subtree_serializer(json_value, options, Some(&arena), true, output)
+            }
+        }
+    }
+
+    /// Serialize the value with another serializer that continues from the current position, e.g. with other options.
+    fn subtree_serializer<'b>(
+        &mut self,
+        json_value: &'b JsonValue,
+        options: &'b JsonSerializerOptions,
+        arena: Option<&'b JsonObjectArena<'b>>,
+        is_replacement: bool,
+        output: &mut dyn JsonSerializerOutput,
+    ) -> Result<()>
+    where
+        'a: 'b,
+    {
+        let mut json_serializer = JsonSerializer::new(options, arena);
+        json_serializer.canonical = self.canonical;
+        json_serializer.is_replacement = is_replacement;
+        json_serializer.base_depth = self.base_depth + self.frames.len();
+        json_serializer.indent_level = self.indent_level;
+        json_serializer.path = self.path.clone();
+        json_serializer.object_ptrs_in_path = std::mem::take(&mut self.object_ptrs_in_path);
+        json_serializer.serialized_object_ptrs = std::mem::take(&mut self.serialized_object_ptrs);
+        json_serializer.value_column = self.value_column;
+        let res = json_serializer
+            .value_serializer(json_value, output)
+            .and_then(|_| json_serializer.frames_serializer(output));
+        // 途中で失敗した場合は、閉じていないオブジェクトをパスから外す
+        for frame in &json_serializer.frames {
+            if let Frame::Object {
+                object_ptr: Some(object_ptr),
+                ..
+            } = frame
+            {
+                json_serializer.object_ptrs_in_path.remove(object_ptr);
+            }
+        }
+        self.object_ptrs_in_path = json_serializer.object_ptrs_in_path;
+        self.serialized_object_ptrs = json_serializer.serialized_object_ptrs;
+        res
+    }
+
+    /// Returns whether a frame is pushed. In that case the path segment of the value is popped when the frame is closed.
+    fn start_object_serializer(
+        &mut self,
+        json_object: &'a JsonObject,
+        object_ptr: Option<JsonObjectPtr>,
+        output: &mut dyn JsonSerializerOutput,
+    ) -> Result<bool> {
+        self.depth_checker(JsonErrorKind::SerializeErrorInObject)?;
+        let members = self.ordered_members(json_object.members.iter().collect());
+        self.start_members_serializer(members, object_ptr, output)
+    }
+
+    /// Start the root object that has only the members of `keys`. Returns whether a frame is pushed.
+    fn start_projection_serializer(
+        &mut self,
+        json_object: &'a JsonObject,
+        keys: &[&str],
+        output: &mut dyn JsonSerializerOutput,
+    ) -> Result<bool> {
+        self.depth_checker(JsonErrorKind::SerializeErrorInObject)?;
+        let mut members: Vec<(&JsonKey, &JsonValue)> = Vec::with_capacity(keys.len());
+        for key_str in keys {
+            match json_object.members.get_key_value(*key_str) {
+                Some(member) => {
+                    if !members.iter().any(|(json_key, _)| json_key.0 == *key_str) {
+                        members.push(member);
+                    }
+                }
+                None => {
+                    if self.options.error_on_missing_keys {
+                        return Err(serialize_error(
+                            JsonErrorKind::SerializeErrorInKey,
+                            "Key: Object does not have the key.",
+                            &format!("key:{}", key_str),
+                        ));
+                    }
+                }
+            }
+        }
+        let members = self.ordered_members(members);
+        self.start_members_serializer(members, None, output)
+    }
+
+    fn start_members_serializer(
+        &mut self,
+        members: Vec<(&'a JsonKey, &'a JsonValue)>,
+        object_ptr: Option<JsonObjectPtr>,
+        output: &mut dyn JsonSerializerOutput,
+    ) -> Result<bool> {
+        if members.is_empty() {
+            self.empty_serializer('{', '}', self.options.empty_object_style, output)?;
+            Ok(false)
         } else {
-            content_string.push_str("{");
-            content_string.push_str(&self.newline_str);
+            if let Some(object_ptr) = object_ptr {
+                self.object_ptrs_in_path.insert(object_ptr);
+            }
+            output.push('{')?;
+            output.push_str(self.newline_str)?;
             self.indent_level += 1;
-            return Ok(StartObjectKind::HasSomeMember);
+            let key_width = if self.options.align_colons && !self.options.compact {
+                members
+                    .iter()
+                    .map(|(json_key, _)| self.key_width(&json_key.0))
+                    .max()
+                    .unwrap_or(0)
+            } else {
+                0
+            };
+            self.frames.push(Frame::Object {
+                members: members.into_iter(),
+                key_width,
+                is_first: true,
+                object_ptr,
+            });
+            Ok(true)
         }
     }
 
-    fn key_serializer(&self, json_key: &JsonKey, content_string: &mut String) -> Result<()> {
-        content_string.push_str(&self.make_indent_string());
-        self.string_serializer(&json_key.0, content_string)?;
-        Ok(())
+    /// Whether the object is serialized as an empty object.
+    fn is_empty_object(&self, json_object: &JsonObject) -> bool {
+        if self.options.skip_null_members {
+            json_object
+                .members
+                .values()
+                .all(|json_value| matches!(json_value, JsonValue::ValueNull))
+        } else {
+            json_object.members.is_empty()
+        }
     }
 
-    fn coron_serializer(&self, content_string: &mut String) -> Result<()> {
-        content_string.push_str(" : ");
+    /// Check that the object or array to be started does not go beyond `max_depth`.
+    fn depth_checker(&self, kind: JsonErrorKind) -> Result<()> {
+        // 空のオブジェクトや配列はフレームを積まないが、深さには数える
+        let depth = self.base_depth + self.frames.len() + 1;
+        match self.options.max_depth {
+            Some(max_depth) if depth > max_depth => Err(serialize_error(
+                kind,
+                "Depth: Nesting is too deep.",
+                &format!("depth:{} | path:{}", depth, make_pointer_string(&self.path)),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Members in the order of serialization. Null members are excluded with `skip_null_members`,
+    /// and members of `priority_keys` come first.
+    fn ordered_members(&self, mut members: Vec<(&'a JsonKey, &'a JsonValue)>) -> Vec<(&'a JsonKey, &'a JsonValue)> {
+        if self.options.skip_null_members {
+            members.retain(|(_, json_value)| !matches!(json_value, JsonValue::ValueNull));
+        }
+        if self.canonical {
+            // RFC 8785 では UTF-16 のコード単位で比較する
+            members.sort_unstable_by(|(json_key_a, _), (json_key_b, _)| {
+                json_key_a.0.encode_utf16().cmp(json_key_b.0.encode_utf16())
+            });
+        } else if self.options.sort_keys {
+            members.sort_unstable_by_key(|(json_key, _)| *json_key);
+        }
+        if !self.canonical && !self.options.priority_keys.is_empty() {
+            // 安定ソートなので、優先キー以外のメンバーの順は変わらない
+            members.sort_by_key(|(json_key, _)| {
+                self.options
+                    .priority_keys
+                    .iter()
+                    .position(|priority_key| *priority_key == json_key.0)
+                    .unwrap_or(usize::MAX)
+            });
+        }
+        members
+    }
+
+    fn empty_serializer(
+        &self,
+        open_char: char,
+        close_char: char,
+        empty_style: JsonSerializerEmptyStyle,
+        output: &mut dyn JsonSerializerOutput,
+    ) -> Result<()> {
+        output.push(open_char)?;
+        match empty_style {
+            JsonSerializerEmptyStyle::Collapsed => {}
+            JsonSerializerEmptyStyle::Spaced => {
+                if !self.options.compact {
+                    output.push(' ')?;
+                }
+            }
+            JsonSerializerEmptyStyle::Expanded => {
+                // 閉じ括弧は開き括弧の行と同じインデント
+                output.push_str(self.newline_str)?;
+                self.indent_serializer(output)?;
+            }
+        }
+        output.push(close_char)
+    }
+
+    /// Width of the empty object or array in the inline rendering. `None` if it spans multiple lines.
+    fn empty_width(&self, empty_style: JsonSerializerEmptyStyle) -> Option<usize> {
+        match empty_style {
+            JsonSerializerEmptyStyle::Collapsed => Some(2),
+            JsonSerializerEmptyStyle::Spaced => Some(3),
+            JsonSerializerEmptyStyle::Expanded => None,
+        }
+    }
+
+    fn key_serializer(&self, json_key: &JsonKey, output: &mut dyn JsonSerializerOutput) -> Result<()> {
+        self.indent_serializer(output)?;
+        self.key_str_serializer(&json_key.0, output)?;
         Ok(())
     }
 
-    fn value_serializer(&mut self, json_value: &JsonValue, content_string: &mut String) -> Result<()> {
+    /// Serialize the key without the indent. The key is not quoted if `unquoted_keys` is true and it is an identifier.
+    pub(crate) fn key_str_serializer(&self, key_str: &str, output: &mut dyn JsonSerializerOutput) -> Result<()> {
+        if self.options.unquoted_keys && is_identifier(key_str) {
+            output.push_str(key_str)
+        } else {
+            self.string_serializer(key_str, output)
+        }
+    }
+
+    /// Width of the serialized key in characters.
+    fn key_width(&self, key_str: &str) -> usize {
+        if self.options.unquoted_keys && is_identifier(key_str) {
+            key_str.len()
+        } else {
+            self.string_width(key_str)
+        }
+    }
+
+    /// Whether the value of the member is serialized as a multi-line non-empty object whose `{` is put on the next line of the key.
+    fn is_brace_on_next_line(&self, json_value: &JsonValue, value_rendering: &ValueRendering) -> bool {
+        if self.options.brace_style != JsonSerializerBraceStyle::NextLine || self.options.compact {
+            return false;
+        }
+        match value_rendering {
+            ValueRendering::Replacement(json_replacement_value) => json_replacement_value
+                .try_read_any_object()
+                .is_some_and(|json_object| !self.is_empty_object(&json_object)),
+            ValueRendering::Compact => false,
+            ValueRendering::Plain => self
+                .get_object(json_value)
+                .is_some_and(|json_object| !self.is_empty_object(json_object)),
+        }
+    }
+
+    fn colon_str(&self) -> &'static str {
+        make_colon_str(self.options)
+    }
+
+    fn coron_serializer(&self, output: &mut dyn JsonSerializerOutput) -> Result<()> {
+        output.push_str(self.colon_str())
+    }
+
+    /// Returns whether a frame is pushed, i.e. the value is a non-empty object or array.
+    fn value_serializer(&mut self, json_value: &'a JsonValue, output: &mut dyn JsonSerializerOutput) -> Result<bool> {
         match json_value {
             JsonValue::ValueString(json_string) => {
-                self.string_serializer(json_string, content_string)?;
+                self.string_serializer(json_string, output)?;
             }
             JsonValue::ValueNumber(json_number) => {
-                self.number_serializer(json_number, content_string)?;
+                self.number_serializer(json_number, output)?;
             }
             JsonValue::ValueBool(json_bool) => {
-                self.bool_serializer(json_bool, content_string)?;
+                self.bool_serializer(json_bool, output)?;
             }
             JsonValue::ValueNull => {
-                self.null_serializer(content_string)?;
+                self.null_serializer(output)?;
+            }
+            JsonValue::ValueRaw(json_raw_value) => {
+                self.raw_serializer(json_raw_value.as_str(), output)?;
             }
-            JsonValue::ValueArray(json_array) => self.array_serializer(json_array, content_string)?,
+            JsonValue::ValueArray(json_array) => return self.start_array_serializer(json_array, output),
             JsonValue::ValueObject(refcell_json_object) => {
-                let json_object = refcell_json_object.borrow();
-                self.object_serializer(&json_object, content_string)?
+                let json_object_ptr = object_ptr(refcell_json_object);
+                if self.object_ptrs_in_path.contains(&json_object_ptr) {
+                    return Err(serialize_error(
+                        JsonErrorKind::SerializeErrorInObject,
+                        "Object: Object contains itself (reference cycle).",
+                        &format!("path:{}", make_pointer_string(&self.path)),
+                    ));
+                }
+                self.shared_object_checker(json_object_ptr)?;
+                let json_object = self.get_object(json_value).ok_or_else(|| {
+                    serialize_error(
+                        JsonErrorKind::SerializeErrorInObject,
+                        "Object: Object is mutably borrowed.",
+                        &format!("path:{}", make_pointer_string(&self.path)),
+                    )
+                })?;
+                return self.start_object_serializer(json_object, Some(json_object_ptr), output);
+            }
+            // 所有オブジェクトは共有も循環もしない
+            JsonValue::ValueOwnedObject(json_object) => return self.start_object_serializer(json_object, None, output),
+        }
+        Ok(false)
+    }
+
+    fn shared_object_checker(&mut self, object_ptr: JsonObjectPtr) -> Result<()> {
+        match &self.options.on_shared_object {
+            JsonSerializerSharedObjectPolicy::SerializeEachTime => {}
+            JsonSerializerSharedObjectPolicy::Error => {
+                if !self.serialized_object_ptrs.insert(object_ptr) {
+                    return Err(serialize_error(
+                        JsonErrorKind::SerializeErrorInObject,
+                        "Object: Object is shared by several places.",
+                        &format!("path:{}", make_pointer_string(&self.path)),
+                    ));
+                }
+            }
+            JsonSerializerSharedObjectPolicy::Warn(callback) => {
+                if !self.serialized_object_ptrs.insert(object_ptr) {
+                    callback(&make_pointer_string(&self.path));
+                }
             }
         }
         Ok(())
     }
 
-    fn end_member_serializer(&self, content_string: &mut String) -> Result<()> {
-        content_string.push_str(",");
-        content_string.push_str(&self.newline_str);
+    fn end_member_serializer(&self, output: &mut dyn JsonSerializerOutput) -> Result<()> {
+        output.push(',')?;
+        output.push_str(self.newline_str)?;
         Ok(())
     }
 
-    fn end_object_serializer(&mut self, content_string: &mut String) -> Result<()> {
+    fn end_object_serializer(&mut self, output: &mut dyn JsonSerializerOutput) -> Result<()> {
+        if let Some(Frame::Object {
+            object_ptr: Some(object_ptr),
+            ..
+        }) = self.frames.pop()
+        {
+            self.object_ptrs_in_path.remove(&object_ptr);
+        }
+        self.path.pop();
         self.indent_level -= 1;
-        content_string.push_str(&self.newline_str);
-        content_string.push_str(&self.make_indent_string());
-        content_string.push_str("}");
+        if self.options.trailing_commas {
+            output.push(',')?;
+        }
+        output.push_str(self.newline_str)?;
+        self.indent_serializer(output)?;
+        output.push('}')?;
         Ok(())
     }
 
-    fn string_serializer(&self, json_string_str: &str, content_string: &mut String) -> Result<()> {
-        content_string.push_str("\"");
-        for unicode_char in json_string_str.chars() {
-            match unicode_char {
-                '\"' => {
-                    content_string.push_str("\\\"");
-                }
-                '\\' => {
-                    content_string.push_str("\\\\");
-                }
-                '\r' => {
-                    content_string.push_str("\\r");
-                }
-                '\n' => {
-                    content_string.push_str("\\n");
-                }
-                '\t' => {
-                    content_string.push_str("\\t");
-                }
-                '\u{0008}' => {
-                    content_string.push_str("\\b");
+    /// Serialize the string with quotes. Runs of characters that need no escaping are pushed as slices.
+    pub(crate) fn string_serializer(&self, json_string_str: &str, output: &mut dyn JsonSerializerOutput) -> Result<()> {
+        output.push('\"')?;
+        // エスケープしない文字が続く間はまとめて出力する
+        let mut run_start_idx = 0;
+        if !self.options.escape_non_ascii && !self.options.escape_extended_controls {
+            // エスケープする文字が ASCII だけなので、バイト単位で探す
+            for (byte_idx, byte) in json_string_str.bytes().enumerate() {
+                if !byte.is_ascii() || !self.is_escaped_char(byte as char) {
+                    continue;
                 }
-                '\u{000C}' => {
-                    content_string.push_str("\\f");
-                }
-                ('\u{0000}'..='\u{0007}') | '\u{000B}' | ('\u{000E}'..='\u{0001F}') => {
-                    let u32_code_point = unicode_char as u32;
-                    content_string.push_str(&format!("\\u{:04x}", u32_code_point));
-                }
-                _ => {
-                    content_string.push(unicode_char);
+                output.push_str(&json_string_str[run_start_idx..byte_idx])?;
+                self.escaped_char_serializer(byte as char, output)?;
+                run_start_idx = byte_idx + 1;
+            }
+        } else {
+            for (byte_idx, unicode_char) in json_string_str.char_indices() {
+                if !self.is_escaped_char(unicode_char) {
+                    continue;
                 }
+                output.push_str(&json_string_str[run_start_idx..byte_idx])?;
+                self.escaped_char_serializer(unicode_char, output)?;
+                run_start_idx = byte_idx + unicode_char.len_utf8();
             }
         }
-        content_string.push_str("\"");
+        output.push_str(&json_string_str[run_start_idx..])?;
+        output.push('\"')?;
         Ok(())
     }
 
-    fn number_serializer(&self, json_number: &JsonNumberType, content_string: &mut String) -> Result<()> {
-        match json_number {
-            JsonNumberType::Int(int_number) => {
-                content_string.push_str(&format!("{}", int_number));
+    /// Whether the character in strings is escaped.
+    fn is_escaped_char(&self, unicode_char: char) -> bool {
+        match unicode_char {
+            '\"' | '\\' | ('\u{0000}'..='\u{001F}') => true,
+            '/' => self.options.escape_solidus,
+            ('\u{007F}'..='\u{009F}') if self.options.escape_extended_controls => true,
+            _ => self.options.escape_non_ascii && !(' '..='~').contains(&unicode_char),
+        }
+    }
+
+    /// Serialize the character for which `is_escaped_char` is true.
+    fn escaped_char_serializer(&self, unicode_char: char, output: &mut dyn JsonSerializerOutput) -> Result<()> {
+        match unicode_char {
+            '\"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '/' => output.push_str("\\/"),
+            '\r' => output.push_str("\\r"),
+            '\n' => output.push_str("\\n"),
+            '\t' => output.push_str("\\t"),
+            '\u{0008}' => output.push_str("\\b"),
+            '\u{000C}' => output.push_str("\\f"),
+            // その他の制御文字、DEL と C1 制御文字、非 ASCII 文字
+            _ => {
+                // U+FFFF を超える文字はサロゲートペアで出力する
+                for u16_char_code in unicode_char.encode_utf16(&mut [0; 2]) {
+                    output.push_str(&format!("\\u{:04x}", u16_char_code))?;
+                }
+                Ok(())
             }
+        }
+    }
+
+    pub(crate) fn number_serializer(&self, json_number: &JsonNumberType, output: &mut dyn JsonSerializerOutput) -> Result<()> {
+        if self.canonical {
+            return self.canonical_number_serializer(json_number, output);
+        }
+        let is_quoted = match (self.options.numbers_as_strings, json_number) {
+            (JsonSerializerNumberStringPolicy::Never, _) => false,
+            (JsonSerializerNumberStringPolicy::IntsAbove(threshold), JsonNumberType::Int(int_number)) => {
+                threshold < 0 || int_number.unsigned_abs() > threshold as u64
+            }
+            (JsonSerializerNumberStringPolicy::IntsAbove(threshold), JsonNumberType::Uint(uint_number)) => {
+                threshold < 0 || *uint_number > threshold as u64
+            }
+            (JsonSerializerNumberStringPolicy::IntsAbove(_), JsonNumberType::Float(_)) => false,
+            // 精度を保てないので常に文字列にする
+            (JsonSerializerNumberStringPolicy::IntsAbove(_), JsonNumberType::BigDecimal(_)) => true,
+            (JsonSerializerNumberStringPolicy::All, _) => true,
+        };
+        let number_string = match json_number {
+            JsonNumberType::Int(int_number) => format!("{}", int_number),
+            JsonNumberType::Uint(uint_number) => format!("{}", uint_number),
+            JsonNumberType::BigDecimal(number_string) => number_string.clone(),
             JsonNumberType::Float(float_number) => {
                 if float_number.is_nan() || float_number.is_infinite() {
-                    return Err(serialize_error(
-                        JsonErrorKind::SerializeErrorInNumber,
-                        "Number:  Number is NaN or Infinite.",
-                        &format!("{}", float_number),
-                    ));
+                    return self.non_finite_float_serializer(*float_number, output);
                 }
-                content_string.push_str(&format!("{}", float_number));
+                self.float_to_string(*float_number)
             }
+        };
+        if is_quoted {
+            output.push('\"')?;
+            output.push_str(&number_string)?;
+            output.push('\"')?;
+        } else {
+            output.push_str(&number_string)?;
         }
         Ok(())
     }
 
-    fn bool_serializer(&self, json_bool: &bool, content_string: &mut String) -> Result<()> {
-        if *json_bool {
-            content_string.push_str("true");
+    fn non_finite_float_serializer(&self, float_number: f64, output: &mut dyn JsonSerializerOutput) -> Result<()> {
+        match self.options.non_finite_float {
+            JsonSerializerNonFiniteFloatPolicy::Error => Err(serialize_error(
+                JsonErrorKind::SerializeErrorInNumber,
+                "Number:  Number is NaN or Infinite.",
+                &format!("{} | path:{}", float_number, make_pointer_string(&self.path)),
+            )),
+            JsonSerializerNonFiniteFloatPolicy::Null => self.null_serializer(output),
+            JsonSerializerNonFiniteFloatPolicy::String => {
+                if float_number.is_nan() {
+                    self.string_serializer("NaN", output)
+                } else if float_number.is_sign_positive() {
+                    self.string_serializer("Infinity", output)
+                } else {
+                    self.string_serializer("-Infinity", output)
+                }
+            }
+        }
+    }
+
+    /// Number in the format of ECMAScript `Number.prototype.toString`, which RFC 8785 requires. Int and Uint are treated as a double too.
+    fn canonical_number_serializer(&self, json_number: &JsonNumberType, output: &mut dyn JsonSerializerOutput) -> Result<()> {
+        let float_number = json_number.as_f64();
+        if float_number.is_nan() || float_number.is_infinite() {
+            return Err(serialize_error(
+                JsonErrorKind::SerializeErrorInNumber,
+                "Number:  Number is NaN or Infinite.",
+                &format!("{} | path:{}", float_number, make_pointer_string(&self.path)),
+            ));
+        }
+        if float_number == 0.0 {
+            // -0 も "0"
+            return output.push_str("0");
+        }
+        if float_number < 0.0 {
+            output.push('-')?;
+        }
+        // 最短表現の仮数部の数字列と指数から ECMAScript の規則で組み立てる
+        let exp_string = format!("{:e}", float_number.abs());
+        let (mantissa_str, exp_str) = exp_string.split_once('e').unwrap_or((&exp_string, "0"));
+        let digits: String = mantissa_str.chars().filter(|unicode_char| *unicode_char != '.').collect();
+        let digit_count = digits.len() as i32;
+        let point_position = exp_str.parse::<i32>().unwrap_or(0) + 1;
+        let digits = even_digits_on_tie(float_number.abs(), digits, point_position);
+        if digit_count <= point_position && point_position <= 21 {
+            output.push_str(&digits)?;
+            output.push_str(&"0".repeat((point_position - digit_count) as usize))?;
+        } else if 0 < point_position && point_position <= 21 {
+            output.push_str(&digits[..point_position as usize])?;
+            output.push('.')?;
+            output.push_str(&digits[point_position as usize..])?;
+        } else if -6 < point_position && point_position <= 0 {
+            output.push_str("0.")?;
+            output.push_str(&"0".repeat(-point_position as usize))?;
+            output.push_str(&digits)?;
         } else {
-            content_string.push_str("false");
+            output.push_str(&digits[..1])?;
+            if digit_count > 1 {
+                output.push('.')?;
+                output.push_str(&digits[1..])?;
+            }
+            output.push('e')?;
+            if point_position > 0 {
+                output.push('+')?;
+            }
+            output.push_str(&(point_position - 1).to_string())?;
         }
         Ok(())
     }
 
-    fn null_serializer(&self, content_string: &mut String) -> Result<()> {
-        content_string.push_str("null");
+    fn float_to_string(&self, float_number: f64) -> String {
+        let use_exp_notation = match self.options.float_exp_thresholds {
+            Some((small_threshold, large_threshold)) => {
+                float_number != 0.0 && (float_number.abs() < small_threshold || float_number.abs() >= large_threshold)
+            }
+            None => false,
+        };
+        match (use_exp_notation, self.options.float_precision) {
+            (true, Some(precision)) => format!("{:.*e}", precision, float_number),
+            (true, None) => format!("{:e}", float_number),
+            (false, Some(precision)) => format!("{:.*}", precision, float_number),
+            (false, None) => {
+                let mut float_string = format!("{}", float_number);
+                if !float_string.contains(['.', 'e', 'E']) {
+                    // 整数値の Float は ".0" を付けて Int と区別する
+                    float_string.push_str(".0");
+                }
+                float_string
+            }
+        }
+    }
+
+    pub(crate) fn bool_serializer(&self, json_bool: &bool, output: &mut dyn JsonSerializerOutput) -> Result<()> {
+        if *json_bool {
+            output.push_str("true")?;
+        } else {
+            output.push_str("false")?;
+        }
         Ok(())
     }
 
-    fn array_serializer(&mut self, json_array: &Vec<JsonValue>, content_string: &mut String) -> Result<()> {
-        content_string.push_str("[");
+    pub(crate) fn null_serializer(&self, output: &mut dyn JsonSerializerOutput) -> Result<()> {
+        output.push_str("null")?;
+        Ok(())
+    }
 
-        for (idx, json_value) in json_array.iter().enumerate() {
-            content_string.push_str(" ");
-            match json_value {
-                JsonValue::ValueString(json_string) => {
-                    self.string_serializer(json_string, content_string)?;
+    /// Write the raw JSON text. With `reindent_raw_values`, the lines after the first one are indented to the current level.
+    pub(crate) fn raw_serializer(&self, raw_str: &str, output: &mut dyn JsonSerializerOutput) -> Result<()> {
+        if !self.options.reindent_raw_values {
+            return output.push_str(raw_str);
+        }
+        for (line_idx, line_str) in raw_str.split('\n').enumerate() {
+            if line_idx > 0 {
+                output.push('\n')?;
+                // 空行にはインデントを付けない
+                if !line_str.trim_end_matches('\r').is_empty() {
+                    self.indent_serializer(output)?;
                 }
+            }
+            output.push_str(line_str)?;
+        }
+        Ok(())
+    }
+
+    /// Returns whether a frame is pushed, i.e. the array is not empty.
+    fn start_array_serializer(&mut self, json_array: &'a [JsonValue], output: &mut dyn JsonSerializerOutput) -> Result<bool> {
+        self.depth_checker(JsonErrorKind::SerializeErrorInArray)?;
+        if json_array.is_empty() {
+            self.empty_serializer('[', ']', self.options.empty_array_style, output)?;
+            return Ok(false);
+        }
+        output.push('[')?;
+        let wrap_len = self.wrap_len(json_array);
+        let is_multiline = !self.options.compact
+            && (wrap_len.is_some()
+                || self.options.array_style == JsonSerializerArrayStyle::Expanded
+                || self.is_multiline_array(json_array)
+                || self.exceeds_inline_array_width(json_array));
+        if is_multiline {
+            // オブジェクトを含む配列は一要素一行で出力する
+            output.push_str(self.newline_str)?;
+            self.indent_level += 1;
+        }
+        self.frames.push(Frame::Array {
+            elements: json_array.iter().enumerate(),
+            is_first: true,
+            is_multiline,
+            wrap_len,
+        });
+        Ok(true)
+    }
+
+    fn end_array_serializer(&mut self, output: &mut dyn JsonSerializerOutput) -> Result<()> {
+        if let Some(Frame::Array { is_multiline: true, .. }) = self.frames.pop() {
+            self.indent_level -= 1;
+            if self.options.trailing_commas {
+                output.push(',')?;
+            }
+            output.push_str(self.newline_str)?;
+            self.indent_serializer(output)?;
+        }
+        self.path.pop();
+        output.push(']')?;
+        Ok(())
+    }
+
+    /// Number of elements per line with `array_wrap`. `None` if the array is not wrapped,
+    /// i.e. it fits on one line or it contains objects or arrays.
+    fn wrap_len(&self, json_array: &[JsonValue]) -> Option<usize> {
+        match self.options.array_wrap {
+            Some(wrap_len)
+                if !self.options.compact
+                    && json_array.len() > wrap_len
+                    && json_array.iter().all(|json_value| {
+                        !matches!(
+                            json_value,
+                            JsonValue::ValueArray(_)
+                                | JsonValue::ValueObject(_)
+                                | JsonValue::ValueOwnedObject(_)
+                                | JsonValue::ValueRaw(_)
+                        )
+                    }) =>
+            {
+                Some(wrap_len)
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the inline rendering of the array goes beyond `max_inline_array_width` from the current column.
+    fn exceeds_inline_array_width(&self, json_array: &[JsonValue]) -> bool {
+        match (self.options.max_inline_array_width, self.value_column) {
+            (Some(max_width), Some(value_column)) => {
+                max_width < value_column || self.inline_array_width(json_array, max_width - value_column).is_none()
+            }
+            _ => false,
+        }
+    }
+
+    /// Width of the inline rendering of the array, e.g. `[ 1, [ 2]]`. Returns `None` as soon as it goes beyond `max_width`.
+    /// The array must not contain non-empty objects.
+    fn inline_array_width(&self, json_array: &[JsonValue], max_width: usize) -> Option<usize> {
+        let empty_array_width = self.empty_width(self.options.empty_array_style)?;
+        let bracket_width = |json_array: &[JsonValue]| {
+            if json_array.is_empty() {
+                empty_array_width
+            } else {
+                json_array.len() * 2 + 1
+            }
+        };
+        let mut width = bracket_width(json_array);
+        let mut json_values: Vec<&JsonValue> = json_array.iter().collect();
+        while let Some(json_value) = json_values.pop() {
+            width += match json_value {
+                JsonValue::ValueString(json_string) => self.string_width(json_string),
                 JsonValue::ValueNumber(json_number) => {
-                    self.number_serializer(json_number, content_string)?;
+                    let mut content_string = String::new();
+                    // NaN などでエラーになる値は、実際のシリアライズ時にパス付きでエラーにする
+                    self.number_serializer(json_number, &mut content_string).ok()?;
+                    content_string.chars().count()
                 }
-                JsonValue::ValueBool(json_bool) => {
-                    self.bool_serializer(json_bool, content_string)?;
+                JsonValue::ValueBool(true) => 4,
+                JsonValue::ValueBool(false) => 5,
+                JsonValue::ValueNull => 4,
+                JsonValue::ValueRaw(json_raw_value) => json_raw_value.as_str().chars().count(),
+                JsonValue::ValueArray(json_child_array) => {
+                    json_values.extend(json_child_array.iter());
+                    bracket_width(json_child_array)
                 }
-                JsonValue::ValueNull => {
-                    self.null_serializer(content_string)?;
+                JsonValue::ValueObject(_) | JsonValue::ValueOwnedObject(_) => {
+                    self.empty_width(self.options.empty_object_style)?
                 }
-                JsonValue::ValueArray(json_array) => {
-                    self.array_serializer(json_array, content_string)?;
-                }
-                JsonValue::ValueObject(refcell_json_object) => {
-                    content_string.push_str(self.newline_str);
-                    if idx == 0 {
-                        self.indent_level += 1;
-                    }
-                    content_string.push_str(&self.make_indent_string());
-                    let json_object = refcell_json_object.borrow();
-                    self.object_serializer(&json_object, content_string)?;
-                    if idx == json_array.len() - 1 {
-                        self.indent_level -= 1;
-                        content_string.push_str(self.newline_str);
-                        content_string.push_str(&self.make_indent_string());
+            };
+            if width > max_width {
+                return None;
+            }
+        }
+        if width > max_width {
+            None
+        } else {
+            Some(width)
+        }
+    }
+
+    /// Whether the array spans multiple lines, i.e. it contains a non-empty object directly or in a nested array.
+    /// Nested arrays are evaluated children first with an explicit stack, and the results are cached.
+    fn is_multiline_array(&mut self, json_array: &[JsonValue]) -> bool {
+        let is_empty_object_multiline = self.options.empty_object_style == JsonSerializerEmptyStyle::Expanded;
+        let is_empty_array_multiline = self.options.empty_array_style == JsonSerializerEmptyStyle::Expanded;
+        let mut json_arrays: Vec<(&[JsonValue], bool)> = vec![(json_array, false)];
+        while let Some((json_current_array, are_children_evaluated)) = json_arrays.pop() {
+            if json_current_array.is_empty() || self.multiline_arrays.contains_key(&json_current_array.as_ptr()) {
+                continue;
+            }
+            if !are_children_evaluated {
+                json_arrays.push((json_current_array, true));
+                for json_value in json_current_array {
+                    if let JsonValue::ValueArray(json_child_array) = json_value {
+                        json_arrays.push((json_child_array, false));
                     }
                 }
+                continue;
             }
-            if idx < json_array.len() - 1 {
-                content_string.push_str(",");
-            }
+            let is_multiline = json_current_array.iter().any(|json_value| match json_value {
+                // 可変借用中のオブジェクトはシリアライズ時にエラーになるので、ここでは中身があるものとして扱う
+                JsonValue::ValueObject(_) | JsonValue::ValueOwnedObject(_) => self
+                    .get_object(json_value)
+                    .is_none_or(|json_object| !self.is_empty_object(json_object) || is_empty_object_multiline),
+                JsonValue::ValueArray(json_child_array) if json_child_array.is_empty() => is_empty_array_multiline,
+                JsonValue::ValueArray(json_child_array) => {
+                    self.multiline_arrays.get(&json_child_array.as_ptr()) == Some(&true)
+                        || self.wrap_len(json_child_array).is_some()
+                }
+                _ => false,
+            });
+            self.multiline_arrays.insert(json_current_array.as_ptr(), is_multiline);
         }
-        content_string.push_str("]");
-        Ok(())
+        self.multiline_arrays.get(&json_array.as_ptr()) == Some(&true)
     }
 }
@@ -0,0 +1,330 @@
+//! Shared object module. Nested objects are `Rc<RefCell<JsonObject>>`, or `Arc<RwLock<JsonObject>>` with the `sync` feature.
+#[cfg(not(feature = "sync"))]
+use std::cell::{Ref, RefCell, RefMut};
+#[cfg(not(feature = "sync"))]
+use std::rc::Rc;
+#[cfg(feature = "sync")]
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard, TryLockError};
+
+use std::collections::HashSet;
+use std::ops::{Deref, DerefMut};
+
+use super::{JsonObject, JsonValue};
+
+/// Nested object in `JsonValue::ValueObject`, which can be shared by several places.
+/// It is `Rc<RefCell<JsonObject>>` by default, and `Arc<RwLock<JsonObject>>` with the `sync` feature,
+/// which makes the values `Send` and `Sync`.
+#[cfg(not(feature = "sync"))]
+pub type JsonSharedObject = Rc<RefCell<JsonObject>>;
+/// Nested object in `JsonValue::ValueObject`, which can be shared by several places.
+/// It is `Rc<RefCell<JsonObject>>` by default, and `Arc<RwLock<JsonObject>>` with the `sync` feature,
+/// which makes the values `Send` and `Sync`.
+#[cfg(feature = "sync")]
+pub type JsonSharedObject = Arc<RwLock<JsonObject>>;
+
+/// Shared borrow of `JsonSharedObject`: `Ref` or `RwLockReadGuard`.
+#[cfg(not(feature = "sync"))]
+pub type JsonObjectRef<'a> = Ref<'a, JsonObject>;
+/// Shared borrow of `JsonSharedObject`: `Ref` or `RwLockReadGuard`.
+#[cfg(feature = "sync")]
+pub type JsonObjectRef<'a> = RwLockReadGuard<'a, JsonObject>;
+
+/// Mutable borrow of `JsonSharedObject`: `RefMut` or `RwLockWriteGuard`.
+#[cfg(not(feature = "sync"))]
+pub type JsonObjectRefMut<'a> = RefMut<'a, JsonObject>;
+/// Mutable borrow of `JsonSharedObject`: `RefMut` or `RwLockWriteGuard`.
+#[cfg(feature = "sync")]
+pub type JsonObjectRefMut<'a> = RwLockWriteGuard<'a, JsonObject>;
+
+/// Access to `JsonSharedObject` that works with and without the `sync` feature.
+/// Without the feature, these are `borrow`, `borrow_mut`, `try_borrow` and `try_borrow_mut` of `RefCell`, which panic on a conflict.
+/// With the feature, these lock the `RwLock` and ignore poisoning; a conflict in the same thread may deadlock instead of panicking.
+pub trait JsonObjectLock {
+    /// Borrow the object.
+    /// * Return:
+    ///     * Shared borrow of the object.
+    fn read_object(&self) -> JsonObjectRef<'_>;
+
+    /// Borrow the object mutably.
+    /// * Return:
+    ///     * Mutable borrow of the object.
+    fn write_object(&self) -> JsonObjectRefMut<'_>;
+
+    /// Borrow the object if it is not borrowed mutably.
+    /// * Return:
+    ///     * Shared borrow of the object, or `None` if it is borrowed mutably.
+    fn try_read_object(&self) -> Option<JsonObjectRef<'_>>;
+
+    /// Borrow the object mutably if it is not borrowed.
+    /// * Return:
+    ///     * Mutable borrow of the object, or `None` if it is borrowed.
+    fn try_write_object(&self) -> Option<JsonObjectRefMut<'_>>;
+}
+
+#[cfg(not(feature = "sync"))]
+impl JsonObjectLock for JsonSharedObject {
+    fn read_object(&self) -> JsonObjectRef<'_> {
+        self.borrow()
+    }
+
+    fn write_object(&self) -> JsonObjectRefMut<'_> {
+        self.borrow_mut()
+    }
+
+    fn try_read_object(&self) -> Option<JsonObjectRef<'_>> {
+        self.try_borrow().ok()
+    }
+
+    fn try_write_object(&self) -> Option<JsonObjectRefMut<'_>> {
+        self.try_borrow_mut().ok()
+    }
+}
+
+#[cfg(feature = "sync")]
+impl JsonObjectLock for JsonSharedObject {
+    fn read_object(&self) -> JsonObjectRef<'_> {
+        self.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write_object(&self) -> JsonObjectRefMut<'_> {
+        self.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn try_read_object(&self) -> Option<JsonObjectRef<'_>> {
+        match self.try_read() {
+            Ok(json_object) => Some(json_object),
+            Err(TryLockError::Poisoned(poison_err)) => Some(poison_err.into_inner()),
+            Err(TryLockError::WouldBlock) => None,
+        }
+    }
+
+    fn try_write_object(&self) -> Option<JsonObjectRefMut<'_>> {
+        match self.try_write() {
+            Ok(json_object) => Some(json_object),
+            Err(TryLockError::Poisoned(poison_err)) => Some(poison_err.into_inner()),
+            Err(TryLockError::WouldBlock) => None,
+        }
+    }
+}
+
+#[cfg(not(feature = "sync"))]
+pub(crate) fn new_shared_object(json_object: JsonObject) -> JsonSharedObject {
+    Rc::new(RefCell::new(json_object))
+}
+
+#[cfg(feature = "sync")]
+pub(crate) fn new_shared_object(json_object: JsonObject) -> JsonSharedObject {
+    Arc::new(RwLock::new(json_object))
+}
+
+/// Address that identifies the shared object without borrowing it, e.g. to detect reference cycles.
+#[cfg(not(feature = "sync"))]
+pub(crate) type JsonObjectPtr = *const RefCell<JsonObject>;
+/// Address that identifies the shared object without borrowing it, e.g. to detect reference cycles.
+#[cfg(feature = "sync")]
+pub(crate) type JsonObjectPtr = *const RwLock<JsonObject>;
+
+/// `Rc::as_ptr` or `Arc::as_ptr` of the shared object. A root borrowed from a shared object has no such address,
+/// so the traversals that detect reference cycles take the shared object of the root separately.
+#[cfg(not(feature = "sync"))]
+pub(crate) fn object_ptr(json_shared_object: &JsonSharedObject) -> JsonObjectPtr {
+    Rc::as_ptr(json_shared_object)
+}
+
+#[cfg(feature = "sync")]
+pub(crate) fn object_ptr(json_shared_object: &JsonSharedObject) -> JsonObjectPtr {
+    Arc::as_ptr(json_shared_object)
+}
+
+#[cfg(not(feature = "sync"))]
+pub(crate) fn ptr_eq(json_shared_object: &JsonSharedObject, json_other_shared_object: &JsonSharedObject) -> bool {
+    Rc::ptr_eq(json_shared_object, json_other_shared_object)
+}
+
+#[cfg(feature = "sync")]
+pub(crate) fn ptr_eq(json_shared_object: &JsonSharedObject, json_other_shared_object: &JsonSharedObject) -> bool {
+    Arc::ptr_eq(json_shared_object, json_other_shared_object)
+}
+
+/// Move the object out if nothing else shares it.
+/// * Return:
+///     * Object, or the shared object as it is if it is shared.
+#[cfg(not(feature = "sync"))]
+pub(crate) fn try_unwrap_object(json_shared_object: JsonSharedObject) -> Result<JsonObject, JsonSharedObject> {
+    Rc::try_unwrap(json_shared_object).map(RefCell::into_inner)
+}
+
+#[cfg(feature = "sync")]
+pub(crate) fn try_unwrap_object(json_shared_object: JsonSharedObject) -> Result<JsonObject, JsonSharedObject> {
+    Arc::try_unwrap(json_shared_object).map(|json_object| json_object.into_inner().unwrap_or_else(PoisonError::into_inner))
+}
+
+/// Move the object out if nothing else shares it, or clone it.
+pub(crate) fn unwrap_or_clone_object(json_shared_object: JsonSharedObject) -> JsonObject {
+    try_unwrap_object(json_shared_object).unwrap_or_else(|json_shared_object| json_shared_object.read_object().clone())
+}
+
+/// Borrow of the object of `ValueObject` or `ValueOwnedObject`.
+pub(crate) enum AnyObjectRef<'a> {
+    Shared(JsonObjectRef<'a>),
+    Owned(&'a JsonObject),
+}
+
+impl Deref for AnyObjectRef<'_> {
+    type Target = JsonObject;
+
+    fn deref(&self) -> &JsonObject {
+        match self {
+            AnyObjectRef::Shared(json_object) => json_object,
+            AnyObjectRef::Owned(json_object) => json_object,
+        }
+    }
+}
+
+/// Mutable borrow of the object of `ValueObject` or `ValueOwnedObject`.
+pub(crate) enum AnyObjectRefMut<'a> {
+    Shared(JsonObjectRefMut<'a>),
+    Owned(&'a mut JsonObject),
+}
+
+impl Deref for AnyObjectRefMut<'_> {
+    type Target = JsonObject;
+
+    fn deref(&self) -> &JsonObject {
+        match self {
+            AnyObjectRefMut::Shared(json_object) => json_object,
+            AnyObjectRefMut::Owned(json_object) => json_object,
+        }
+    }
+}
+
+impl DerefMut for AnyObjectRefMut<'_> {
+    fn deref_mut(&mut self) -> &mut JsonObject {
+        match self {
+            AnyObjectRefMut::Shared(json_object) => json_object,
+            AnyObjectRefMut::Owned(json_object) => json_object,
+        }
+    }
+}
+
+impl JsonValue {
+    /// Borrow the object whether it is shared or owned. The shared object is borrowed with `read_object`.
+    /// * Return:
+    ///     * Borrow of the object, or `None` if the value is not an object.
+    pub(crate) fn read_any_object(&self) -> Option<AnyObjectRef<'_>> {
+        match self {
+            JsonValue::ValueObject(refcell_json_object) => Some(AnyObjectRef::Shared(refcell_json_object.read_object())),
+            JsonValue::ValueOwnedObject(json_object) => Some(AnyObjectRef::Owned(json_object)),
+            _ => None,
+        }
+    }
+
+    /// Borrow the object whether it is shared or owned.
+    /// * Return:
+    ///     * Borrow of the object, or `None` if the value is not an object or the shared object is borrowed mutably.
+    pub(crate) fn try_read_any_object(&self) -> Option<AnyObjectRef<'_>> {
+        match self {
+            JsonValue::ValueObject(refcell_json_object) => refcell_json_object.try_read_object().map(AnyObjectRef::Shared),
+            JsonValue::ValueOwnedObject(json_object) => Some(AnyObjectRef::Owned(json_object)),
+            _ => None,
+        }
+    }
+
+    /// Borrow the object mutably whether it is shared or owned.
+    /// * Return:
+    ///     * Mutable borrow of the object, or `None` if the value is not an object or the shared object is borrowed.
+    pub(crate) fn try_write_any_object(&mut self) -> Option<AnyObjectRefMut<'_>> {
+        match self {
+            JsonValue::ValueObject(refcell_json_object) => refcell_json_object.try_write_object().map(AnyObjectRefMut::Shared),
+            JsonValue::ValueOwnedObject(json_object) => Some(AnyObjectRefMut::Owned(json_object)),
+            _ => None,
+        }
+    }
+}
+
+/// Convert the shared objects in the value into owned objects, parents before children.
+/// * Parameters:
+///     * `ancestor_ptrs` : Shared objects being converted, whose references are reference cycles and stay shared.
+pub(crate) fn into_owned_value(json_value: JsonValue, ancestor_ptrs: &mut Vec<JsonObjectPtr>) -> JsonValue {
+    match json_value {
+        JsonValue::ValueArray(json_array) => JsonValue::ValueArray(
+            json_array
+                .into_iter()
+                .map(|json_element_value| into_owned_value(json_element_value, ancestor_ptrs))
+                .collect(),
+        ),
+        JsonValue::ValueObject(refcell_json_object) => {
+            let json_object_ptr = object_ptr(&refcell_json_object);
+            if ancestor_ptrs.contains(&json_object_ptr) {
+                return JsonValue::ValueObject(refcell_json_object);
+            }
+            // 他からも参照されていればコピーし、可変借用中ならそのまま残す
+            let json_object = match try_unwrap_object(refcell_json_object) {
+                Ok(json_object) => json_object,
+                Err(refcell_json_object) => {
+                    let json_cloned_object = refcell_json_object.try_read_object().map(|json_object| json_object.clone());
+                    match json_cloned_object {
+                        Some(json_object) => json_object,
+                        None => return JsonValue::ValueObject(refcell_json_object),
+                    }
+                }
+            };
+            ancestor_ptrs.push(json_object_ptr);
+            let json_owned_object = into_owned_object(json_object, ancestor_ptrs);
+            ancestor_ptrs.pop();
+            JsonValue::ValueOwnedObject(Box::new(json_owned_object))
+        }
+        JsonValue::ValueOwnedObject(json_object) => {
+            JsonValue::ValueOwnedObject(Box::new(into_owned_object(*json_object, ancestor_ptrs)))
+        }
+        _ => json_value,
+    }
+}
+
+pub(crate) fn into_owned_object(json_object: JsonObject, ancestor_ptrs: &mut Vec<JsonObjectPtr>) -> JsonObject {
+    JsonObject {
+        members: json_object
+            .members
+            .into_iter()
+            .map(|(json_key, json_value)| (json_key, into_owned_value(json_value, ancestor_ptrs)))
+            .collect(),
+    }
+}
+
+/// Convert the owned objects in the value into shared objects. Shared objects in the value are converted in place,
+/// once even if they are referred by several places or by a reference cycle. Objects borrowed mutably are skipped.
+/// * Parameters:
+///     * `visited_ptrs` : Shared objects already converted.
+pub(crate) fn into_shared_value(json_value: JsonValue, visited_ptrs: &mut HashSet<JsonObjectPtr>) -> JsonValue {
+    match json_value {
+        JsonValue::ValueArray(json_array) => JsonValue::ValueArray(
+            json_array
+                .into_iter()
+                .map(|json_element_value| into_shared_value(json_element_value, visited_ptrs))
+                .collect(),
+        ),
+        JsonValue::ValueObject(refcell_json_object) => {
+            if visited_ptrs.insert(object_ptr(&refcell_json_object)) {
+                if let Some(mut json_object) = refcell_json_object.try_write_object() {
+                    let json_shared_object = into_shared_object(std::mem::take(&mut *json_object), visited_ptrs);
+                    *json_object = json_shared_object;
+                }
+            }
+            JsonValue::ValueObject(refcell_json_object)
+        }
+        JsonValue::ValueOwnedObject(json_object) => {
+            JsonValue::ValueObject(new_shared_object(into_shared_object(*json_object, visited_ptrs)))
+        }
+        _ => json_value,
+    }
+}
+
+pub(crate) fn into_shared_object(json_object: JsonObject, visited_ptrs: &mut HashSet<JsonObjectPtr>) -> JsonObject {
+    JsonObject {
+        members: json_object
+            .members
+            .into_iter()
+            .map(|(json_key, json_value)| (json_key, into_shared_value(json_value, visited_ptrs)))
+            .collect(),
+    }
+}
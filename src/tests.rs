@@ -1,11 +1,12 @@
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests {
 
     use super::super::error::*;
-    use crate::{JsonKey, JsonNumberType, JsonObject, JsonSerializerNewLineKind, JsonValue};
+    use crate::*;
 
     use std::cell::RefCell;
-    use std::fs::{create_dir, File};
+    use std::fs::{create_dir_all, File};
     use std::io::prelude::*;
     use std::path::Path;
     use std::rc::Rc;
@@ -154,29 +155,29 @@ mod tests {
         if let JsonValue::ValueArray(json_array) = json_object.members.get(&JsonKey("array_object".to_string())).unwrap() {
             if let JsonValue::ValueObject(child_json_object) = &json_array[0] {
                 member_assert_eq(
-                    &child_json_object.borrow(),
+                    &child_json_object.read_object(),
                     "array_object_string",
                     &JsonValue::ValueString("array_objct_string1".to_string()),
                 );
                 member_assert_eq(
-                    &child_json_object.borrow(),
+                    &child_json_object.read_object(),
                     "array_object_number",
                     &JsonValue::ValueNumber(JsonNumberType::Float(1.0)),
                 );
-                assert_eq!(child_json_object.borrow().members.len(), 2);
+                assert_eq!(child_json_object.read_object().members.len(), 2);
             }
             if let JsonValue::ValueObject(child_json_object) = &json_array[1] {
                 member_assert_eq(
-                    &child_json_object.borrow(),
+                    &child_json_object.read_object(),
                     "array_object_string",
                     &JsonValue::ValueString("array_objct_string2".to_string()),
                 );
                 member_assert_eq(
-                    &child_json_object.borrow(),
+                    &child_json_object.read_object(),
                     "array_object_number",
                     &JsonValue::ValueNumber(JsonNumberType::Float(2.0)),
                 );
-                assert_eq!(child_json_object.borrow().members.len(), 2);
+                assert_eq!(child_json_object.read_object().members.len(), 2);
             }
         } else {
             panic!();
@@ -196,25 +197,25 @@ mod tests {
         dbg!(json_object.clone());
         if let JsonValue::ValueObject(child_json_object) = json_object.members.get(&JsonKey("empty_object".to_string())).unwrap()
         {
-            assert_eq!(child_json_object.borrow().members.len(), 0);
+            assert_eq!(child_json_object.read_object().members.len(), 0);
         } else {
             panic!();
         }
         if let JsonValue::ValueObject(child_json_object) = json_object.members.get(&JsonKey("object".to_string())).unwrap() {
             member_assert_eq(
-                &child_json_object.borrow(),
+                &child_json_object.read_object(),
                 "object_string",
                 &JsonValue::ValueString("object_string".to_string()),
             );
             member_assert_eq(
-                &child_json_object.borrow(),
+                &child_json_object.read_object(),
                 "object_number",
                 &JsonValue::ValueNumber(JsonNumberType::Int(1)),
             );
-            member_assert_eq(&child_json_object.borrow(), "object_bool", &JsonValue::ValueBool(true));
-            member_assert_eq(&child_json_object.borrow(), "object_null", &JsonValue::ValueNull);
+            member_assert_eq(&child_json_object.read_object(), "object_bool", &JsonValue::ValueBool(true));
+            member_assert_eq(&child_json_object.read_object(), "object_null", &JsonValue::ValueNull);
             member_assert_eq(
-                &child_json_object.borrow(),
+                &child_json_object.read_object(),
                 "object_array",
                 &JsonValue::ValueArray(vec![
                     JsonValue::ValueNumber(JsonNumberType::Int(1)),
@@ -223,24 +224,24 @@ mod tests {
                 ]),
             );
             if let JsonValue::ValueObject(grand_child_json_object) = &child_json_object
-                .borrow()
+                .read_object()
                 .members
                 .get(&JsonKey("object_object".to_string()))
                 .unwrap()
             {
                 member_assert_eq(
-                    &grand_child_json_object.borrow(),
+                    &grand_child_json_object.read_object(),
                     "object_object_string",
                     &JsonValue::ValueString("object_object_string".to_string()),
                 );
                 member_assert_eq(
-                    &grand_child_json_object.borrow(),
+                    &grand_child_json_object.read_object(),
                     "object_object_number",
                     &JsonValue::ValueNumber(JsonNumberType::Int(1)),
                 );
-                assert_eq!(grand_child_json_object.borrow().members.len(), 2);
+                assert_eq!(grand_child_json_object.read_object().members.len(), 2);
             }
-            assert_eq!(child_json_object.borrow().members.len(), 6);
+            assert_eq!(child_json_object.read_object().members.len(), 6);
         } else {
             panic!();
         }
@@ -263,7 +264,7 @@ mod tests {
             } else {
                 members_lines
                     .iter()
-                    .position(|&expect_line| &(expect_line.to_string() + ",") == trimed_line)
+                    .position(|&expect_line| expect_line.to_string() + "," == trimed_line)
                     .unwrap();
             }
         }
@@ -272,7 +273,7 @@ mod tests {
     #[test]
     fn serialize_string() -> Result<()> {
         let object_brackets = ("{", "}");
-        let member_lines = [r#"    "string" : "string""#, r#"    "escape_string" : "string""#];
+        let member_lines = [r#"    "string": "string""#, r#"    "escape_string": "string""#];
 
         let mut json_object = JsonObject::new();
 
@@ -296,10 +297,10 @@ mod tests {
     fn serialize_number() -> Result<()> {
         let object_brackets = ("{", "}");
         let member_lines = [
-            r#"    "int" : 1"#,
-            r#"    "minus_int" : -1"#,
-            r#"    "float" : 0.1"#,
-            r#"    "minus_float" : -0.1"#,
+            r#"    "int": 1"#,
+            r#"    "minus_int": -1"#,
+            r#"    "float": 0.1"#,
+            r#"    "minus_float": -0.1"#,
         ];
 
         let mut json_object = JsonObject::new();
@@ -331,7 +332,7 @@ mod tests {
     #[test]
     fn serialize_bool_null() -> Result<()> {
         let object_brackets = ("{", "}");
-        let member_lines = [r#"    "true" : true"#, r#"    "false" : false"#, r#"    "null" : null"#];
+        let member_lines = [r#"    "true": true"#, r#"    "false": false"#, r#"    "null": null"#];
 
         let mut json_object = JsonObject::new();
 
@@ -359,11 +360,11 @@ mod tests {
     fn serialize_array() -> Result<()> {
         let object_brackets = ("{", "}");
         let member_lines = [
-            r#"    "empty_array" : []"#,
-            r#"    "array_string" : [ "string1", "string2", "string3"]"#,
-            r#"    "array_number" : [ 1, 2, 3, 4, 5]"#,
-            r#"    "array_bool_null" : [ true, false, null]"#,
-            r#"    "array_array" : [ [ "0,0", "0,1"], [ "1,0", "1,1"]]"#,
+            r#"    "empty_array": []"#,
+            r#"    "array_string": [ "string1", "string2", "string3"]"#,
+            r#"    "array_number": [ 1, 2, 3, 4, 5]"#,
+            r#"    "array_bool_null": [ true, false, null]"#,
+            r#"    "array_array": [ [ "0,0", "0,1"], [ "1,0", "1,1"]]"#,
         ];
 
         let mut json_object = JsonObject::new();
@@ -423,12 +424,12 @@ mod tests {
     fn serialize_object() -> Result<()> {
         // empty object
         let object_brackets = ("{", "}");
-        let member_lines = [r#"    "empty_object" : {}"#];
+        let member_lines = [r#"    "empty_object": {}"#];
 
         let mut json_object = JsonObject::new();
 
         let json_key = JsonKey("empty_object".to_string());
-        let json_value = JsonValue::ValueObject(Rc::new(RefCell::new(JsonObject::new())));
+        let json_value = JsonValue::ValueObject(JsonObject::new().into_shared());
         json_object.members.insert(json_key, json_value);
 
         let serialized_string = json_object
@@ -446,7 +447,7 @@ mod tests {
         let mut json_object = JsonObject::new();
 
         let json_key = JsonKey("object_object".to_string());
-        let json_value = JsonValue::ValueObject(Rc::new(RefCell::new(json_child_object)));
+        let json_value = JsonValue::ValueObject(json_child_object.into_shared());
         json_object.members.insert(json_key, json_value);
 
         let serialized_string = json_object
@@ -455,8 +456,8 @@ mod tests {
 
         let mut serialized_lines = serialized_string.lines();
         assert_eq!("{", serialized_lines.next().unwrap());
-        assert_eq!(r#"    "object_object" : {"#, serialized_lines.next().unwrap());
-        assert_eq!(r#"        "object_number" : 1"#, serialized_lines.next().unwrap());
+        assert_eq!(r#"    "object_object": {"#, serialized_lines.next().unwrap());
+        assert_eq!(r#"        "object_number": 1"#, serialized_lines.next().unwrap());
         assert_eq!("    }", serialized_lines.next().unwrap());
         assert_eq!("}", serialized_lines.next().unwrap());
 
@@ -470,26 +471,26 @@ mod tests {
         let mut json_object = JsonObject::new();
 
         let json_key = JsonKey("Lf".to_string());
-        let json_value = JsonValue::ValueObject(Rc::new(RefCell::new(JsonObject::new())));
+        let json_value = JsonValue::ValueObject(JsonObject::new().into_shared());
         json_object.members.insert(json_key, json_value);
 
         let serialized_string = json_object
             .serialize(JsonSerializerNewLineKind::Lf, crate::JsonSerializerIndentKind::Space(4))
             .unwrap();
-        assert_eq!("{\u{000a}    \"Lf\" : {}\u{000a}}\u{000a}", serialized_string);
+        assert_eq!("{\u{000a}    \"Lf\": {}\u{000a}}\u{000a}", serialized_string);
 
         // CrLf
         let mut json_object = JsonObject::new();
 
         let json_key = JsonKey("CrLf".to_string());
-        let json_value = JsonValue::ValueObject(Rc::new(RefCell::new(JsonObject::new())));
+        let json_value = JsonValue::ValueObject(JsonObject::new().into_shared());
         json_object.members.insert(json_key, json_value);
 
         let serialized_string = json_object
             .serialize(JsonSerializerNewLineKind::CrLf, crate::JsonSerializerIndentKind::Space(4))
             .unwrap();
         assert_eq!(
-            "{\u{000d}\u{000a}    \"CrLf\" : {}\u{000d}\u{000a}}\u{000d}\u{000a}",
+            "{\u{000d}\u{000a}    \"CrLf\": {}\u{000d}\u{000a}}\u{000d}\u{000a}",
             serialized_string
         );
         Ok(())
@@ -501,45 +502,43 @@ mod tests {
         let mut json_object = JsonObject::new();
 
         let json_key = JsonKey("Tab".to_string());
-        let json_value = JsonValue::ValueObject(Rc::new(RefCell::new(JsonObject::new())));
+        let json_value = JsonValue::ValueObject(JsonObject::new().into_shared());
         json_object.members.insert(json_key, json_value);
 
         let serialized_string = json_object
             .serialize(JsonSerializerNewLineKind::Lf, crate::JsonSerializerIndentKind::Tab)
             .unwrap();
-        assert_eq!("{\u{000a}\u{0009}\"Tab\" : {}\u{000a}}\u{000a}", serialized_string);
+        assert_eq!("{\u{000a}\u{0009}\"Tab\": {}\u{000a}}\u{000a}", serialized_string);
 
         // Space(0)
         let mut json_object = JsonObject::new();
 
         let json_key = JsonKey("Space(0)".to_string());
-        let json_value = JsonValue::ValueObject(Rc::new(RefCell::new(JsonObject::new())));
+        let json_value = JsonValue::ValueObject(JsonObject::new().into_shared());
         json_object.members.insert(json_key, json_value);
 
         let serialized_string = json_object
             .serialize(JsonSerializerNewLineKind::Lf, crate::JsonSerializerIndentKind::Space(0))
             .unwrap();
-        assert_eq!("{\u{000a}\"Space(0)\" : {}\u{000a}}\u{000a}", serialized_string);
+        assert_eq!("{\u{000a}\"Space(0)\": {}\u{000a}}\u{000a}", serialized_string);
 
         // Space(10)
         let mut json_object = JsonObject::new();
 
         let json_key = JsonKey("Space(10)".to_string());
-        let json_value = JsonValue::ValueObject(Rc::new(RefCell::new(JsonObject::new())));
+        let json_value = JsonValue::ValueObject(JsonObject::new().into_shared());
         json_object.members.insert(json_key, json_value);
 
         let serialized_string = json_object
             .serialize(JsonSerializerNewLineKind::Lf, crate::JsonSerializerIndentKind::Space(10))
             .unwrap();
-        assert_eq!("{\u{000a}          \"Space(10)\" : {}\u{000a}}\u{000a}", serialized_string);
+        assert_eq!("{\u{000a}          \"Space(10)\": {}\u{000a}}\u{000a}", serialized_string);
         Ok(())
     }
 
     #[test]
     fn read_write() -> Result<()> {
-        if !Path::new("./for_test/output/").exists() {
-            create_dir(Path::new("./for_test/output/")).unwrap();
-        }
+        create_dir_all(Path::new("./for_test/output/"))?;
 
         let test_path = Path::new("./for_test/read_test1.json");
         let mut file = File::open(test_path)?;
@@ -570,4 +569,4475 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn serialize_root_value() -> Result<()> {
+        // array
+        let json_value = JsonValue::ValueArray(vec![
+            JsonValue::ValueNumber(JsonNumberType::Int(1)),
+            JsonValue::ValueString("string".to_string()),
+            JsonValue::ValueArray(vec![JsonValue::ValueBool(true), JsonValue::ValueNull]),
+        ]);
+        let serialized_string = json_value
+            .serialize(JsonSerializerNewLineKind::Lf, crate::JsonSerializerIndentKind::Space(4))
+            .unwrap();
+        assert_eq!("[ 1, \"string\", [ true, null]]\u{000a}", serialized_string);
+
+        // array of objects
+        let mut json_child_object = JsonObject::new();
+        json_child_object.insert("object_number", 1);
+        let json_array_value = JsonValue::from(vec![JsonValue::from(json_child_object)]);
+        let serialized_string = json_array_value
+            .serialize(JsonSerializerNewLineKind::Lf, crate::JsonSerializerIndentKind::Space(4))
+            .unwrap();
+        assert_eq!(
+            "[\u{000a}    {\u{000a}        \"object_number\": 1\u{000a}    }\u{000a}]\u{000a}",
+            serialized_string
+        );
+
+        // scalars
+        let serialized_string = JsonValue::ValueString("escape\"string".to_string())
+            .serialize(JsonSerializerNewLineKind::Lf, crate::JsonSerializerIndentKind::Space(4))
+            .unwrap();
+        assert_eq!("\"escape\\\"string\"\u{000a}", serialized_string);
+        let serialized_string = JsonValue::ValueNumber(JsonNumberType::Float(-0.5))
+            .serialize(JsonSerializerNewLineKind::CrLf, crate::JsonSerializerIndentKind::Space(4))
+            .unwrap();
+        assert_eq!("-0.5\u{000d}\u{000a}", serialized_string);
+        let serialized_string = JsonValue::ValueBool(false)
+            .serialize(JsonSerializerNewLineKind::Lf, crate::JsonSerializerIndentKind::Tab)
+            .unwrap();
+        assert_eq!("false\u{000a}", serialized_string);
+        let serialized_string = JsonValue::ValueNull
+            .serialize(JsonSerializerNewLineKind::Lf, crate::JsonSerializerIndentKind::Tab)
+            .unwrap();
+        assert_eq!("null\u{000a}", serialized_string);
+
+        // round trip (top-level values are parsed as a member value)
+        let serialized_string = json_value
+            .serialize(JsonSerializerNewLineKind::Lf, crate::JsonSerializerIndentKind::Space(4))
+            .unwrap();
+        let json_object = JsonObject::parse(&format!("{{ \"root\": {} }}", serialized_string)).unwrap();
+        member_assert_eq(&json_object, "root", &json_value);
+        let serialized_string = json_array_value
+            .serialize(JsonSerializerNewLineKind::Lf, crate::JsonSerializerIndentKind::Space(4))
+            .unwrap();
+        let json_object = JsonObject::parse(&format!("{{ \"root\": {} }}", serialized_string)).unwrap();
+        member_assert_eq(&json_object, "root", &json_array_value);
+        Ok(())
+    }
+
+    #[test]
+    fn serialize_to_writer() -> Result<()> {
+        let test_path = Path::new("./for_test/read_test1.json");
+        let mut file = File::open(test_path)?;
+        let mut content_string = String::new();
+        file.read_to_string(&mut content_string)?;
+        let json_object = JsonObject::parse(&content_string).unwrap();
+
+        let serialized_string = json_object
+            .serialize(JsonSerializerNewLineKind::CrLf, crate::JsonSerializerIndentKind::Space(4))
+            .unwrap();
+        let mut serialized_bytes: Vec<u8> = Vec::new();
+        json_object
+            .serialize_to_writer(
+                &mut serialized_bytes,
+                JsonSerializerNewLineKind::CrLf,
+                crate::JsonSerializerIndentKind::Space(4),
+            )
+            .unwrap();
+        assert_eq!(serialized_string.as_bytes(), serialized_bytes.as_slice());
+        Ok(())
+    }
+
+    #[test]
+    fn serialize_to_writer_io_error() -> Result<()> {
+        struct FailWriter;
+        impl Write for FailWriter {
+            fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other("disk full"))
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Err(std::io::Error::other("disk full"))
+            }
+        }
+
+        let mut json_object = JsonObject::new();
+        json_object
+            .members
+            .insert(JsonKey("string".to_string()), JsonValue::ValueString("string".to_string()));
+        let err = json_object
+            .serialize_to_writer(
+                &mut FailWriter,
+                JsonSerializerNewLineKind::Lf,
+                crate::JsonSerializerIndentKind::Space(4),
+            )
+            .unwrap_err();
+        let json_error = err.downcast_ref::<JsonError>().unwrap();
+        assert_eq!(json_error.err_kind, JsonErrorKind::IoError);
+        assert!(err.to_string().contains("disk full"));
+        Ok(())
+    }
+
+    #[test]
+    fn serialize_to_file() -> Result<()> {
+        create_dir_all(Path::new("./for_test/output/"))?;
+
+        let test_path = Path::new("./for_test/parse_test_object.json");
+        let mut file = File::open(test_path)?;
+        let mut content_string = String::new();
+        file.read_to_string(&mut content_string)?;
+        let json_object = JsonObject::parse(&content_string).unwrap();
+
+        let json_path = Path::new("./for_test/output/serialize_to_file_test.json");
+        json_object
+            .serialize_to_file(json_path, JsonSerializerNewLineKind::Lf, crate::JsonSerializerIndentKind::Tab)
+            .unwrap();
+        let mut file = File::open(json_path)?;
+        let mut content_string = String::new();
+        file.read_to_string(&mut content_string)?;
+        assert_eq!(JsonObject::parse(&content_string).unwrap(), json_object);
+        assert!(!tmp_file_exists("serialize_to_file_test.json")?);
+
+        // The directory does not exist.
+        let err = json_object
+            .serialize_to_file(
+                "./for_test/output/not_exist_dir/serialize_to_file_test.json",
+                JsonSerializerNewLineKind::Lf,
+                crate::JsonSerializerIndentKind::Tab,
+            )
+            .unwrap_err();
+        assert_eq!(err.downcast_ref::<JsonError>().unwrap().err_kind, JsonErrorKind::IoError);
+        assert!(err.to_string().contains("not_exist_dir"));
+
+        // シリアライズのエラーは serialize_to_writer と同じ種類のまま返す
+        let mut json_nan_object = JsonObject::new();
+        json_nan_object.insert("nan", f64::NAN);
+        let err = json_nan_object
+            .serialize_to_file(json_path, JsonSerializerNewLineKind::Lf, crate::JsonSerializerIndentKind::Tab)
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<JsonError>().unwrap().err_kind,
+            JsonErrorKind::SerializeErrorInNumber
+        );
+        assert!(!tmp_file_exists("serialize_to_file_test.json")?);
+        assert_eq!(JsonObject::parse(&std::fs::read_to_string(json_path)?)?, json_object);
+
+        // 同じパスに複数のスレッドから書き込む
+        let handles: Vec<_> = (0..8)
+            .map(|thread_idx| {
+                std::thread::spawn(move || {
+                    let mut json_object = JsonObject::new();
+                    json_object.insert("thread", thread_idx);
+                    json_object
+                        .serialize_to_file(
+                            "./for_test/output/serialize_to_file_threads_test.json",
+                            JsonSerializerNewLineKind::Lf,
+                            crate::JsonSerializerIndentKind::Tab,
+                        )
+                        .is_ok()
+                })
+            })
+            .collect();
+        for handle in handles {
+            assert!(handle.join().unwrap());
+        }
+        let json_thread_object = JsonObject::parse(&std::fs::read_to_string(
+            "./for_test/output/serialize_to_file_threads_test.json",
+        )?)?;
+        assert!(json_thread_object
+            .get_i64("thread")
+            .is_some_and(|thread_idx| (0..8).contains(&thread_idx)));
+        assert!(!tmp_file_exists("serialize_to_file_threads_test.json")?);
+        Ok(())
+    }
+
+    /// Whether a temporary file of `serialize_to_file` for the file name is left in `for_test/output`.
+    fn tmp_file_exists(file_name: &str) -> Result<bool> {
+        for dir_entry in std::fs::read_dir("./for_test/output/")? {
+            let entry_file_name = dir_entry?.file_name();
+            let entry_file_name = entry_file_name.to_string_lossy();
+            if entry_file_name.starts_with(&format!(".{}.", file_name)) && entry_file_name.ends_with(".tmp") {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    #[test]
+    fn trailing_newline() -> Result<()> {
+        let mut json_object = JsonObject::new();
+        json_object.members.insert(JsonKey("Lf".to_string()), JsonValue::ValueNull);
+
+        // default: with trailing newline
+        let options = JsonSerializerOptions::new();
+        assert!(options.trailing_newline);
+        let serialized_string = json_object.serialize_with_options(&options).unwrap();
+        assert_eq!("{\u{000a}    \"Lf\": null\u{000a}}\u{000a}", serialized_string);
+
+        // without trailing newline
+        let options = JsonSerializerOptions {
+            trailing_newline: false,
+            ..JsonSerializerOptions::new()
+        };
+        let serialized_string = json_object.serialize_with_options(&options).unwrap();
+        assert_eq!("{\u{000a}    \"Lf\": null\u{000a}}", serialized_string);
+
+        let options = JsonSerializerOptions {
+            newline_kind: JsonSerializerNewLineKind::CrLf,
+            trailing_newline: false,
+            ..JsonSerializerOptions::new()
+        };
+        let serialized_string = JsonValue::ValueBool(true).serialize_with_options(&options).unwrap();
+        assert_eq!("true", serialized_string);
+        Ok(())
+    }
+
+    #[test]
+    fn colon_style() -> Result<()> {
+        let mut json_object = JsonObject::new();
+        json_object
+            .members
+            .insert(JsonKey("key".to_string()), JsonValue::ValueBool(true));
+
+        // default: SpaceAfter
+        assert_eq!(JsonSerializerOptions::new().colon_style, JsonSerializerColonStyle::SpaceAfter);
+        let serialized_string = json_object.serialize_with_options(&JsonSerializerOptions::new()).unwrap();
+        assert_eq!("{\u{000a}    \"key\": true\u{000a}}\u{000a}", serialized_string);
+
+        let options = JsonSerializerOptions {
+            colon_style: JsonSerializerColonStyle::SpaceBoth,
+            ..JsonSerializerOptions::new()
+        };
+        let serialized_string = json_object.serialize_with_options(&options).unwrap();
+        assert_eq!("{\u{000a}    \"key\" : true\u{000a}}\u{000a}", serialized_string);
+
+        let options = JsonSerializerOptions {
+            colon_style: JsonSerializerColonStyle::None,
+            ..JsonSerializerOptions::new()
+        };
+        let serialized_string = json_object.serialize_with_options(&options).unwrap();
+        assert_eq!("{\u{000a}    \"key\":true\u{000a}}\u{000a}", serialized_string);
+        assert_eq!(JsonObject::parse(&serialized_string).unwrap(), json_object);
+        Ok(())
+    }
+
+    #[test]
+    fn escape_non_ascii() -> Result<()> {
+        let mut json_object = JsonObject::new();
+        json_object.members.insert(
+            JsonKey("キー".to_string()),
+            JsonValue::ValueString("A あ GlowingStar:🌟 é DEL:\u{007f}".to_string()),
+        );
+        let options = JsonSerializerOptions {
+            escape_non_ascii: true,
+            ..JsonSerializerOptions::new()
+        };
+        let serialized_string = json_object.serialize_with_options(&options).unwrap();
+        assert_eq!(
+            "{\u{000a}    \"\\u30ad\\u30fc\": \"A \\u3042 GlowingStar:\\ud83c\\udf1f \\u00e9 DEL:\\u007f\"\u{000a}}\u{000a}",
+            serialized_string
+        );
+        assert!(serialized_string.is_ascii());
+        assert_eq!(JsonObject::parse(&serialized_string).unwrap(), json_object);
+
+        // default: non-ASCII characters are emitted as is.
+        let serialized_string = json_object.serialize_with_options(&JsonSerializerOptions::new()).unwrap();
+        assert!(serialized_string.contains("キー"));
+        assert!(serialized_string.contains("🌟"));
+        Ok(())
+    }
+
+    #[test]
+    fn escape_solidus() -> Result<()> {
+        let mut json_object = JsonObject::new();
+        json_object.members.insert(
+            JsonKey("</key>".to_string()),
+            JsonValue::ValueString("<script>alert(1)</script>".to_string()),
+        );
+        let options = JsonSerializerOptions {
+            escape_solidus: true,
+            ..JsonSerializerOptions::new()
+        };
+        let serialized_string = json_object.serialize_with_options(&options).unwrap();
+        assert!(!serialized_string.contains("</"));
+        assert_eq!(
+            "{\u{000a}    \"<\\/key>\": \"<script>alert(1)<\\/script>\"\u{000a}}\u{000a}",
+            serialized_string
+        );
+        assert_eq!(JsonObject::parse(&serialized_string).unwrap(), json_object);
+
+        // default: '/' is emitted as is.
+        let serialized_string = json_object.serialize_with_options(&JsonSerializerOptions::new()).unwrap();
+        assert!(serialized_string.contains("</script>"));
+        Ok(())
+    }
+
+    #[test]
+    fn serialize_integral_float() -> Result<()> {
+        for (float_number, expect_str) in [(1.0, "1.0"), (-3.0, "-3.0"), (1e3, "1000.0"), (0.0, "0.0"), (-0.5, "-0.5")] {
+            let json_value = JsonValue::ValueNumber(JsonNumberType::Float(float_number));
+            let options = JsonSerializerOptions {
+                trailing_newline: false,
+                ..JsonSerializerOptions::new()
+            };
+            assert_eq!(expect_str, json_value.serialize_with_options(&options).unwrap());
+
+            // round trip: Float stays Float
+            let mut json_object = JsonObject::new();
+            json_object.members.insert(JsonKey("float".to_string()), json_value.clone());
+            let serialized_string = json_object.serialize_with_options(&options).unwrap();
+            member_assert_eq(&JsonObject::parse(&serialized_string).unwrap(), "float", &json_value);
+        }
+
+        // 1e3 in the JSON text is parsed as Float and stays Float.
+        let json_object = JsonObject::parse("{\"exp\": 1e3}").unwrap();
+        let serialized_string = json_object.serialize_with_options(&JsonSerializerOptions::new()).unwrap();
+        member_assert_eq(
+            &JsonObject::parse(&serialized_string).unwrap(),
+            "exp",
+            &JsonValue::ValueNumber(JsonNumberType::Float(1000.0)),
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn float_format() -> Result<()> {
+        fn serialize_float(float_number: f64, options: &JsonSerializerOptions) -> String {
+            JsonValue::ValueNumber(JsonNumberType::Float(float_number))
+                .serialize_with_options(options)
+                .unwrap()
+        }
+
+        // precision
+        let options = JsonSerializerOptions {
+            trailing_newline: false,
+            float_precision: Some(2),
+            ..JsonSerializerOptions::new()
+        };
+        assert_eq!("2.50", serialize_float(2.5, &options));
+        assert_eq!("1.23", serialize_float(1.23456, &options));
+        assert_eq!("3.00", serialize_float(2.999, &options));
+        assert_eq!("-0.01", serialize_float(-0.005001, &options));
+
+        // exponent thresholds
+        let options = JsonSerializerOptions {
+            trailing_newline: false,
+            float_exp_thresholds: Some((1e-3, 1e6)),
+            ..JsonSerializerOptions::new()
+        };
+        assert_eq!("1.5e20", serialize_float(1.5e20, &options));
+        assert_eq!("1e6", serialize_float(1e6, &options));
+        assert_eq!("999999.5", serialize_float(999999.5, &options));
+        assert_eq!("-2.5e-7", serialize_float(-2.5e-7, &options));
+        assert_eq!("0.001", serialize_float(0.001, &options));
+        assert_eq!("0.0", serialize_float(0.0, &options));
+
+        // precision and exponent thresholds
+        let options = JsonSerializerOptions {
+            trailing_newline: false,
+            float_precision: Some(3),
+            float_exp_thresholds: Some((1e-3, 1e6)),
+            ..JsonSerializerOptions::new()
+        };
+        assert_eq!("1.235e300", serialize_float(1.23456e300, &options));
+        assert_eq!("5.000e-300", serialize_float(5e-300, &options));
+        assert_eq!("12.346", serialize_float(12.3456, &options));
+
+        // The output is valid JSON and stays Float.
+        for float_number in [1.5e20, 5e-300, 1e6] {
+            let mut json_object = JsonObject::new();
+            json_object.members.insert(
+                JsonKey("float".to_string()),
+                JsonValue::ValueNumber(JsonNumberType::Float(float_number)),
+            );
+            let serialized_string = json_object.serialize_with_options(&options).unwrap();
+            let json_value = JsonObject::parse(&serialized_string).unwrap().members[&JsonKey("float".to_string())].clone();
+            if let JsonValue::ValueNumber(JsonNumberType::Float(parsed_number)) = json_value {
+                assert!((parsed_number - float_number).abs() <= float_number.abs() * 1e-3);
+            } else {
+                panic!();
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn non_finite_float() -> Result<()> {
+        let mut json_child_object = JsonObject::new();
+        json_child_object.members.insert(
+            JsonKey("values".to_string()),
+            JsonValue::ValueArray(vec![
+                JsonValue::ValueNumber(JsonNumberType::Float(f64::NAN)),
+                JsonValue::ValueNumber(JsonNumberType::Float(f64::INFINITY)),
+                JsonValue::ValueNumber(JsonNumberType::Float(f64::NEG_INFINITY)),
+            ]),
+        );
+        let mut json_object = JsonObject::new();
+        json_object.members.insert(
+            JsonKey("sensor/1".to_string()),
+            JsonValue::ValueObject(json_child_object.into_shared()),
+        );
+
+        // default: Error with the path of the value
+        let err = json_object.serialize_with_options(&JsonSerializerOptions::new()).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<JsonError>().unwrap().err_kind,
+            JsonErrorKind::SerializeErrorInNumber
+        );
+        assert!(err.to_string().contains("path:/sensor~11/values/0"));
+
+        let mut json_member_object = JsonObject::new();
+        json_member_object.members.insert(
+            JsonKey("inf".to_string()),
+            JsonValue::ValueNumber(JsonNumberType::Float(f64::INFINITY)),
+        );
+        let err = json_member_object
+            .serialize_with_options(&JsonSerializerOptions::new())
+            .unwrap_err();
+        assert!(err.to_string().contains("path:/inf"));
+
+        // Null
+        let options = JsonSerializerOptions {
+            non_finite_float: JsonSerializerNonFiniteFloatPolicy::Null,
+            ..JsonSerializerOptions::new()
+        };
+        let serialized_string = json_object.serialize_with_options(&options).unwrap();
+        assert!(serialized_string.contains(r#""values": [ null, null, null]"#));
+        let serialized_string = json_member_object.serialize_with_options(&options).unwrap();
+        assert_eq!("{\u{000a}    \"inf\": null\u{000a}}\u{000a}", serialized_string);
+
+        // String
+        let options = JsonSerializerOptions {
+            non_finite_float: JsonSerializerNonFiniteFloatPolicy::String,
+            ..JsonSerializerOptions::new()
+        };
+        let serialized_string = json_object.serialize_with_options(&options).unwrap();
+        assert!(serialized_string.contains(r#""values": [ "NaN", "Infinity", "-Infinity"]"#));
+        let serialized_string = json_member_object.serialize_with_options(&options).unwrap();
+        assert_eq!("{\u{000a}    \"inf\": \"Infinity\"\u{000a}}\u{000a}", serialized_string);
+        Ok(())
+    }
+
+    #[test]
+    fn serialize_mixed_array() -> Result<()> {
+        fn object_value(key: &str, number: i64) -> JsonValue {
+            let mut json_object = JsonObject::new();
+            json_object
+                .members
+                .insert(JsonKey(key.to_string()), JsonValue::ValueNumber(JsonNumberType::Int(number)));
+            JsonValue::ValueObject(json_object.into_shared())
+        }
+        fn int_value(number: i64) -> JsonValue {
+            JsonValue::ValueNumber(JsonNumberType::Int(number))
+        }
+        fn serialize_member(json_value: JsonValue) -> String {
+            let mut json_object = JsonObject::new();
+            json_object.members.insert(JsonKey("array".to_string()), json_value);
+            json_object.serialize_with_options(&JsonSerializerOptions::new()).unwrap()
+        }
+
+        // [1, {..}]
+        let serialized_string = serialize_member(JsonValue::ValueArray(vec![int_value(1), object_value("a", 1)]));
+        assert_eq!(
+            concat!(
+                "{\n",
+                "    \"array\": [\n",
+                "        1,\n",
+                "        {\n",
+                "            \"a\": 1\n",
+                "        }\n",
+                "    ]\n",
+                "}\n"
+            ),
+            serialized_string
+        );
+
+        // [{..}, 1]
+        let serialized_string = serialize_member(JsonValue::ValueArray(vec![object_value("a", 1), int_value(1)]));
+        assert_eq!(
+            concat!(
+                "{\n",
+                "    \"array\": [\n",
+                "        {\n",
+                "            \"a\": 1\n",
+                "        },\n",
+                "        1\n",
+                "    ]\n",
+                "}\n"
+            ),
+            serialized_string
+        );
+
+        // [{..}, {..}, 1, {..}]
+        let serialized_string = serialize_member(JsonValue::ValueArray(vec![
+            object_value("a", 1),
+            object_value("b", 2),
+            int_value(3),
+            object_value("c", 4),
+        ]));
+        assert_eq!(
+            concat!(
+                "{\n",
+                "    \"array\": [\n",
+                "        {\n",
+                "            \"a\": 1\n",
+                "        },\n",
+                "        {\n",
+                "            \"b\": 2\n",
+                "        },\n",
+                "        3,\n",
+                "        {\n",
+                "            \"c\": 4\n",
+                "        }\n",
+                "    ]\n",
+                "}\n"
+            ),
+            serialized_string
+        );
+
+        // nested: [[1, {..}], [2, 3], {"o": [{..}, 4]}, {}]
+        let mut json_child_object = JsonObject::new();
+        json_child_object.members.insert(
+            JsonKey("o".to_string()),
+            JsonValue::ValueArray(vec![object_value("d", 5), int_value(4)]),
+        );
+        let serialized_string = serialize_member(JsonValue::ValueArray(vec![
+            JsonValue::ValueArray(vec![int_value(1), object_value("a", 1)]),
+            JsonValue::ValueArray(vec![int_value(2), int_value(3)]),
+            json_child_object.into(),
+            JsonObject::new().into(),
+        ]));
+        assert_eq!(
+            concat!(
+                "{\n",
+                "    \"array\": [\n",
+                "        [\n",
+                "            1,\n",
+                "            {\n",
+                "                \"a\": 1\n",
+                "            }\n",
+                "        ],\n",
+                "        [ 2, 3],\n",
+                "        {\n",
+                "            \"o\": [\n",
+                "                {\n",
+                "                    \"d\": 5\n",
+                "                },\n",
+                "                4\n",
+                "            ]\n",
+                "        },\n",
+                "        {}\n",
+                "    ]\n",
+                "}\n"
+            ),
+            serialized_string
+        );
+        assert_eq!(
+            JsonObject::parse(&serialized_string)
+                .unwrap()
+                .serialize_with_options(&JsonSerializerOptions::new())
+                .unwrap(),
+            serialized_string
+        );
+
+        // Arrays without objects stay on one line.
+        let serialized_string = serialize_member(JsonValue::ValueArray(vec![
+            int_value(1),
+            JsonValue::ValueObject(JsonObject::new().into_shared()),
+            JsonValue::ValueArray(vec![int_value(2)]),
+        ]));
+        assert_eq!("{\n    \"array\": [ 1, {}, [ 2]]\n}\n", serialized_string);
+        Ok(())
+    }
+
+    #[test]
+    fn serialize_reference_cycle() -> Result<()> {
+        // direct cycle: object contains itself
+        let refcell_json_object = JsonObject::new().into_shared();
+        refcell_json_object.write_object().members.insert(
+            JsonKey("self".to_string()),
+            JsonValue::ValueObject(refcell_json_object.clone()),
+        );
+        let err = JsonValue::ValueObject(refcell_json_object.clone())
+            .serialize_with_options(&JsonSerializerOptions::new())
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<JsonError>().unwrap().err_kind,
+            JsonErrorKind::SerializeErrorInObject
+        );
+        assert!(err.to_string().contains("path:/self"));
+        let err = refcell_json_object
+            .read_object()
+            .serialize_with_options(&JsonSerializerOptions::new())
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<JsonError>().unwrap().err_kind,
+            JsonErrorKind::SerializeErrorInObject
+        );
+        refcell_json_object.write_object().members.clear();
+
+        // indirect cycle: parent -> array -> child -> parent
+        let refcell_parent_object = JsonObject::new().into_shared();
+        let refcell_child_object = JsonObject::new().into_shared();
+        refcell_parent_object.write_object().members.insert(
+            JsonKey("children".to_string()),
+            JsonValue::ValueArray(vec![JsonValue::ValueObject(refcell_child_object.clone())]),
+        );
+        refcell_child_object.write_object().members.insert(
+            JsonKey("parent".to_string()),
+            JsonValue::ValueObject(refcell_parent_object.clone()),
+        );
+        let err = JsonValue::ValueObject(refcell_parent_object.clone())
+            .serialize_with_options(&JsonSerializerOptions::new())
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<JsonError>().unwrap().err_kind,
+            JsonErrorKind::SerializeErrorInObject
+        );
+        assert!(err.to_string().contains("path:/children/0/parent"));
+        refcell_child_object.write_object().members.clear();
+
+        // The same object in two places is not a cycle.
+        let refcell_shared_object = JsonObject::new().into_shared();
+        let mut json_object = JsonObject::new();
+        json_object.members.insert(
+            JsonKey("a".to_string()),
+            JsonValue::ValueObject(refcell_shared_object.clone()),
+        );
+        json_object.members.insert(
+            JsonKey("b".to_string()),
+            JsonValue::ValueObject(refcell_shared_object.clone()),
+        );
+        assert!(json_object.serialize_with_options(&JsonSerializerOptions::new()).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn serialize_deep_object() -> Result<()> {
+        const DEPTH: usize = 50000;
+        let mut refcell_json_object = JsonObject::new().into_shared();
+        refcell_json_object
+            .write_object()
+            .members
+            .insert(JsonKey("leaf".to_string()), JsonValue::ValueNumber(JsonNumberType::Int(0)));
+        for _ in 1..DEPTH {
+            let refcell_parent_object = JsonObject::new().into_shared();
+            refcell_parent_object
+                .write_object()
+                .members
+                .insert(JsonKey("a".to_string()), JsonValue::ValueObject(refcell_json_object));
+            refcell_json_object = refcell_parent_object;
+        }
+
+        let mut options = JsonSerializerOptions::new();
+        options.indent_kind = JsonSerializerIndentKind::Space(0);
+        // 既定では max_depth を超える
+        let res = JsonValue::ValueObject(refcell_json_object.clone()).serialize_with_options(&options);
+        assert_eq!(
+            res.unwrap_err().downcast_ref::<JsonError>().unwrap().err_kind,
+            JsonErrorKind::SerializeErrorInObject
+        );
+
+        options.max_depth = None;
+        let serialized_string = JsonValue::ValueObject(refcell_json_object.clone()).serialize_with_options(&options)?;
+        assert!(serialized_string.starts_with("{\n\"a\": {\n\"a\": {\n"));
+        assert!(serialized_string.contains("{\n\"leaf\": 0\n}\n}"));
+        assert_eq!(serialized_string.matches('{').count(), DEPTH);
+        assert_eq!(serialized_string.matches('}').count(), DEPTH);
+
+        // Drop もネストの深さだけ再帰するので、外側から順に切り離す
+        let mut refcell_next_object = Some(refcell_json_object);
+        while let Some(refcell_current_object) = refcell_next_object.take() {
+            if let Some(JsonValue::ValueObject(refcell_child_object)) = refcell_current_object
+                .write_object()
+                .members
+                .remove(&JsonKey("a".to_string()))
+            {
+                refcell_next_object = Some(refcell_child_object);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn serializer_options_builder() -> Result<()> {
+        let json_object = JsonObject::parse("{\"b\": 1, \"c\": {\"z\": true, \"y\": null}, \"a\": [ 2, 3]}")?;
+
+        let options = JsonSerializerOptions::new()
+            .newline(JsonSerializerNewLineKind::CrLf)
+            .indent(JsonSerializerIndentKind::Space(2))
+            .sort_keys(true);
+        assert_eq!(
+            json_object.serialize_with_options(&options)?,
+            "{\r\n  \"a\": [ 2, 3],\r\n  \"b\": 1,\r\n  \"c\": {\r\n    \"y\": null,\r\n    \"z\": true\r\n  }\r\n}\r\n"
+        );
+
+        // The two-argument serialize is the same as the default options with the kinds.
+        let options = JsonSerializerOptions::new()
+            .newline(JsonSerializerNewLineKind::Lf)
+            .indent(JsonSerializerIndentKind::Tab);
+        assert_eq!(
+            json_object.serialize(JsonSerializerNewLineKind::Lf, JsonSerializerIndentKind::Tab)?,
+            json_object.serialize_with_options(&options)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn custom_indent() -> Result<()> {
+        let json_object = JsonObject::parse("{\"a\": {\"b\": [ 1]}}")?;
+        let options = JsonSerializerOptions::new().indent(JsonSerializerIndentKind::Custom("  ".to_string()));
+        assert_eq!(
+            json_object.serialize_with_options(&options)?,
+            "{\n  \"a\": {\n    \"b\": [ 1]\n  }\n}\n"
+        );
+
+        let options = JsonSerializerOptions::new().indent(JsonSerializerIndentKind::Custom(String::new()));
+        assert_eq!(
+            json_object.serialize_with_options(&options)?,
+            "{\n\"a\": {\n\"b\": [ 1]\n}\n}\n"
+        );
+
+        let options = JsonSerializerOptions::new().indent(JsonSerializerIndentKind::Custom(" * ".to_string()));
+        assert_eq!(
+            json_object.serialize_with_options(&options)?,
+            "{\n * \"a\": {\n *  * \"b\": [ 1]\n * }\n}\n"
+        );
+
+        let options = JsonSerializerOptions::new().indent(JsonSerializerIndentKind::Custom("\n".to_string()));
+        let err = json_object.serialize_with_options(&options).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<JsonError>().unwrap().err_kind,
+            JsonErrorKind::SerializeErrorInOptions
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn array_style_expanded() -> Result<()> {
+        let options = JsonSerializerOptions::new().array_style(JsonSerializerArrayStyle::Expanded);
+        let serialize_member =
+            |json_content_str: &str| -> Result<String> { JsonObject::parse(json_content_str)?.serialize_with_options(&options) };
+
+        assert_eq!(
+            serialize_member("{\"array\": [1, 2.5, -3]}")?,
+            "{\n    \"array\": [\n        1,\n        2.5,\n        -3\n    ]\n}\n"
+        );
+        assert_eq!(
+            serialize_member("{\"array\": [\"a\", \"b\"]}")?,
+            "{\n    \"array\": [\n        \"a\",\n        \"b\"\n    ]\n}\n"
+        );
+        assert_eq!(
+            serialize_member("{\"array\": [{\"a\": 1}, {}]}")?,
+            "{\n    \"array\": [\n        {\n            \"a\": 1\n        },\n        {}\n    ]\n}\n"
+        );
+        // Nested arrays inherit the style, empty arrays stay "[]".
+        assert_eq!(
+            serialize_member("{\"array\": [[1, 2], []]}")?,
+            "{\n    \"array\": [\n        [\n            1,\n            2\n        ],\n        []\n    ]\n}\n"
+        );
+
+        // Default is Inline.
+        assert_eq!(
+            JsonObject::parse("{\"array\": [1, 2]}")?.serialize_with_options(&JsonSerializerOptions::new())?,
+            "{\n    \"array\": [ 1, 2]\n}\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn max_inline_array_width() -> Result<()> {
+        let json_object = JsonObject::parse("{\"array\": [1, 2, 3]}")?;
+        // `    "array": [ 1, 2, 3]` is 23 characters.
+        let options = JsonSerializerOptions::new().max_inline_array_width(Some(23));
+        assert_eq!(
+            json_object.serialize_with_options(&options)?,
+            "{\n    \"array\": [ 1, 2, 3]\n}\n"
+        );
+        let options = JsonSerializerOptions::new().max_inline_array_width(Some(22));
+        assert_eq!(
+            json_object.serialize_with_options(&options)?,
+            "{\n    \"array\": [\n        1,\n        2,\n        3\n    ]\n}\n"
+        );
+
+        // Each nesting level is decided independently.
+        let json_object = JsonObject::parse("{\"a\": [[1, 2], [3, 4]]}")?;
+        let options = JsonSerializerOptions::new().max_inline_array_width(Some(20));
+        assert_eq!(
+            json_object.serialize_with_options(&options)?,
+            "{\n    \"a\": [\n        [ 1, 2],\n        [ 3, 4]\n    ]\n}\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn compact() -> Result<()> {
+        let json_object = JsonObject::parse("{\"a\": {\"b\": [1, [2, 3], {\"c\": null}]}}")?;
+        let options = JsonSerializerOptions::new().compact(true);
+        assert_eq!(
+            json_object.serialize_with_options(&options)?,
+            "{\"a\":{\"b\":[1,[2,3],{\"c\":null}]}}\n"
+        );
+        let options = options.trailing_newline(false);
+        assert_eq!(
+            json_object.serialize_with_options(&options)?,
+            "{\"a\":{\"b\":[1,[2,3],{\"c\":null}]}}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn serialize_canonical() -> Result<()> {
+        // RFC 8785 3.2.2
+        let json_object = JsonObject::parse(
+            "{\n  \"numbers\": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],\n  \
+             \"string\": \"\\u20ac$\\u000F\\u000aA'\\u0042\\u0022\\u005c\\\\\\\"\\/\",\n  \
+             \"literals\": [null, true, false]\n}",
+        )?;
+        assert_eq!(
+            json_object.serialize_canonical()?,
+            "{\"literals\":[null,true,false],\"numbers\":[333333333.3333333,1e+30,4.5,0.002,1e-27],\
+             \"string\":\"€$\\u000f\\nA'B\\\"\\\\\\\\\\\"/\"}"
+        );
+
+        // RFC 8785 3.2.3: keys are sorted by UTF-16 code units.
+        let json_object = JsonObject::parse(
+            "{\"\\u20ac\": \"Euro Sign\", \"\\r\": \"Carriage Return\", \"\\ufb33\": \"Hebrew Letter Dalet With Dagesh\", \
+             \"1\": \"One\", \"\\ud83d\\ude00\": \"Emoji: Grinning Face\", \"\\u0080\": \"Control\", \
+             \"\\u00f6\": \"Latin Small Letter O With Diaeresis\"}",
+        )?;
+        assert_eq!(
+            json_object.serialize_canonical()?,
+            "{\"\\r\":\"Carriage Return\",\"1\":\"One\",\"\u{0080}\":\"Control\",\"ö\":\"Latin Small Letter O With Diaeresis\",\
+             \"€\":\"Euro Sign\",\"😀\":\"Emoji: Grinning Face\",\"\u{fb33}\":\"Hebrew Letter Dalet With Dagesh\"}"
+        );
+
+        // RFC 8785 Appendix B
+        let number_vectors: [(u64, &str); 24] = [
+            (0x0000000000000000, "0"),
+            (0x8000000000000000, "0"),
+            (0x0000000000000001, "5e-324"),
+            (0x8000000000000001, "-5e-324"),
+            (0x7fefffffffffffff, "1.7976931348623157e+308"),
+            (0xffefffffffffffff, "-1.7976931348623157e+308"),
+            (0x4340000000000000, "9007199254740992"),
+            (0xc340000000000000, "-9007199254740992"),
+            (0x4430000000000000, "295147905179352830000"),
+            (0x44b52d02c7e14af5, "9.999999999999997e+22"),
+            (0x44b52d02c7e14af6, "1e+23"),
+            (0x44b52d02c7e14af7, "1.0000000000000001e+23"),
+            (0x444b1ae4d6e2ef4e, "999999999999999700000"),
+            (0x444b1ae4d6e2ef4f, "999999999999999900000"),
+            (0x444b1ae4d6e2ef50, "1e+21"),
+            (0x3eb0c6f7a0b5ed8c, "9.999999999999997e-7"),
+            (0x3eb0c6f7a0b5ed8d, "0.000001"),
+            (0x41b3de4355555553, "333333333.3333332"),
+            (0x41b3de4355555554, "333333333.33333325"),
+            (0x41b3de4355555555, "333333333.3333333"),
+            (0x41b3de4355555556, "333333333.3333334"),
+            (0x41b3de4355555557, "333333333.33333343"),
+            (0xbecbf647612f3696, "-0.0000033333333333333333"),
+            (0x43143ff3c1cb0959, "1424953923781206.2"),
+        ];
+        for (bits, expected_str) in number_vectors {
+            let json_value = JsonValue::ValueNumber(JsonNumberType::Float(f64::from_bits(bits)));
+            assert_eq!(json_value.serialize_canonical()?, expected_str);
+        }
+        assert_eq!(JsonValue::ValueNumber(JsonNumberType::Int(-42)).serialize_canonical()?, "-42");
+        for bits in [0x7fffffffffffffff_u64, 0x7ff0000000000000] {
+            let json_value = JsonValue::ValueNumber(JsonNumberType::Float(f64::from_bits(bits)));
+            let err = json_value.serialize_canonical().unwrap_err();
+            assert_eq!(
+                err.downcast_ref::<JsonError>().unwrap().err_kind,
+                JsonErrorKind::SerializeErrorInNumber
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn to_pretty_and_compact_string() -> Result<()> {
+        let json_object = JsonObject::parse("{\"a\": [1, \"x\"]}")?;
+        assert_eq!(json_object.to_pretty_string()?, "{\n    \"a\": [ 1, \"x\"]\n}\n");
+        assert_eq!(json_object.to_compact_string()?, "{\"a\":[1,\"x\"]}");
+
+        let json_value = json_object.members[&JsonKey("a".to_string())].clone();
+        assert_eq!(json_value.to_pretty_string()?, "[ 1, \"x\"]\n");
+        assert_eq!(json_value.to_compact_string()?, "[1,\"x\"]");
+        Ok(())
+    }
+
+    #[test]
+    fn display() -> Result<()> {
+        let json_object = JsonObject::parse("{\"a\": {\"b\": [1, true, null]}, \"s\": \"x\\\"\\n\\u0001\"}")?;
+        let json_string = format!("{}", json_object);
+        assert!(
+            json_string == "{\"a\":{\"b\":[1,true,null]},\"s\":\"x\\\"\\n\\u0001\"}"
+                || json_string == "{\"s\":\"x\\\"\\n\\u0001\",\"a\":{\"b\":[1,true,null]}}"
+        );
+        assert_eq!(
+            JsonValue::ValueArray(vec![
+                JsonValue::ValueNumber(JsonNumberType::Float(f64::NAN)),
+                JsonValue::ValueString("/".to_string()),
+            ])
+            .to_string(),
+            "[null,\"/\"]"
+        );
+
+        // Errors are written in the string instead of panicking.
+        let refcell_json_object = JsonObject::new().into_shared();
+        refcell_json_object.write_object().members.insert(
+            JsonKey("self".to_string()),
+            JsonValue::ValueObject(refcell_json_object.clone()),
+        );
+        let json_string = JsonValue::ValueObject(refcell_json_object.clone()).to_string();
+        assert!(json_string.starts_with("<Serialize error in object"));
+        refcell_json_object.write_object().members.clear();
+        Ok(())
+    }
+
+    #[test]
+    fn trailing_commas() -> Result<()> {
+        let json_object = JsonObject::parse("{\"a\": {\"b\": [1, 2]}, \"c\": [{\"d\": null}]}")?;
+        let options = JsonSerializerOptions::new().trailing_commas(true).sort_keys(true);
+        let serialized_string = json_object.serialize_with_options(&options)?;
+        assert_eq!(
+            serialized_string,
+            "{\n    \"a\": {\n        \"b\": [ 1, 2],\n    },\n    \"c\": [\n        {\n            \"d\": null,\n        },\n    ],\n}\n"
+        );
+
+        // The output is not strict JSON, but the lenient parser accepts it.
+        assert!(JsonObject::parse(&serialized_string).is_err());
+        let parser_options = JsonParserOptions::new().allow_trailing_commas(true);
+        assert_eq!(
+            JsonObject::parse_with_options(&serialized_string, &parser_options)?,
+            json_object
+        );
+        assert_eq!(
+            JsonObject::parse_with_options("{\"a\": [1, 2 , ] , }", &parser_options)?,
+            JsonObject::parse("{\"a\": [1, 2]}")?
+        );
+        assert!(JsonObject::parse_with_options("{\"a\": [1, , ]}", &parser_options).is_err());
+        assert!(JsonObject::parse_with_options("{\"a\": 1, , }", &parser_options).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn serialize_to_bytes() -> Result<()> {
+        let json_object = JsonObject::parse("{\"key\": \"€😀\"}")?;
+        let options = JsonSerializerOptions::new().compact(true).trailing_newline(false);
+        let json_string = json_object.serialize_with_options(&options)?;
+
+        let bytes = json_object.serialize_to_bytes(&options, JsonSerializerOutputEncoding::Utf8)?;
+        assert_eq!(bytes, json_string.as_bytes());
+
+        let bytes = json_object.serialize_to_bytes(&options, JsonSerializerOutputEncoding::Utf8WithBom)?;
+        assert_eq!(&bytes[..3], &[0xEF, 0xBB, 0xBF]);
+        assert_eq!(String::from_utf8(bytes[3..].to_vec())?, json_string);
+
+        // '{' is U+007B.
+        let bytes = json_object.serialize_to_bytes(&options, JsonSerializerOutputEncoding::Utf16Le)?;
+        assert_eq!(&bytes[..4], &[0xFF, 0xFE, 0x7B, 0x00]);
+        let u16_char_codes: Vec<u16> = bytes[2..]
+            .chunks(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        assert_eq!(String::from_utf16(&u16_char_codes)?, json_string);
+
+        let bytes = json_object.serialize_to_bytes(&options, JsonSerializerOutputEncoding::Utf16Be)?;
+        assert_eq!(&bytes[..4], &[0xFE, 0xFF, 0x00, 0x7B]);
+        let u16_char_codes: Vec<u16> = bytes[2..]
+            .chunks(2)
+            .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+            .collect();
+        assert_eq!(String::from_utf16(&u16_char_codes)?, json_string);
+        Ok(())
+    }
+
+    #[test]
+    fn empty_style() -> Result<()> {
+        let serialize_with_styles = |json_content_str: &str, empty_style: JsonSerializerEmptyStyle| -> Result<String> {
+            let options = JsonSerializerOptions::new()
+                .empty_object_style(empty_style)
+                .empty_array_style(empty_style);
+            JsonObject::parse(json_content_str)?.serialize_with_options(&options)
+        };
+        use JsonSerializerEmptyStyle::*;
+
+        // top level
+        assert_eq!(serialize_with_styles("{}", Collapsed)?, "{}\n");
+        assert_eq!(serialize_with_styles("{}", Spaced)?, "{ }\n");
+        assert_eq!(serialize_with_styles("{}", Expanded)?, "{\n}\n");
+        let options = JsonSerializerOptions::new().empty_array_style(Spaced);
+        assert_eq!(JsonValue::ValueArray(Vec::new()).serialize_with_options(&options)?, "[ ]\n");
+        let options = JsonSerializerOptions::new().empty_array_style(Expanded);
+        assert_eq!(JsonValue::ValueArray(Vec::new()).serialize_with_options(&options)?, "[\n]\n");
+
+        // member value
+        assert_eq!(serialize_with_styles("{\"o\": {}}", Collapsed)?, "{\n    \"o\": {}\n}\n");
+        assert_eq!(serialize_with_styles("{\"o\": {}}", Spaced)?, "{\n    \"o\": { }\n}\n");
+        assert_eq!(serialize_with_styles("{\"o\": {}}", Expanded)?, "{\n    \"o\": {\n    }\n}\n");
+        assert_eq!(serialize_with_styles("{\"a\": []}", Spaced)?, "{\n    \"a\": [ ]\n}\n");
+        assert_eq!(serialize_with_styles("{\"a\": []}", Expanded)?, "{\n    \"a\": [\n    ]\n}\n");
+
+        // inside an array
+        assert_eq!(
+            serialize_with_styles("{\"a\": [1, {}, []]}", Collapsed)?,
+            "{\n    \"a\": [ 1, {}, []]\n}\n"
+        );
+        assert_eq!(
+            serialize_with_styles("{\"a\": [1, {}, []]}", Spaced)?,
+            "{\n    \"a\": [ 1, { }, [ ]]\n}\n"
+        );
+        assert_eq!(
+            serialize_with_styles("{\"a\": [1, {}, []]}", Expanded)?,
+            "{\n    \"a\": [\n        1,\n        {\n        },\n        [\n        ]\n    ]\n}\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn shared_object_policy() -> Result<()> {
+        let refcell_shared_object = JsonObject::new().into_shared();
+        refcell_shared_object
+            .write_object()
+            .members
+            .insert(JsonKey("x".to_string()), JsonValue::ValueNull);
+        let mut json_object = JsonObject::new();
+        for key_str in ["a", "b"] {
+            json_object.members.insert(
+                JsonKey(key_str.to_string()),
+                JsonValue::ValueObject(refcell_shared_object.clone()),
+            );
+        }
+
+        let options = JsonSerializerOptions::new().sort_keys(true);
+        assert_eq!(
+            json_object.serialize_with_options(&options)?,
+            "{\n    \"a\": {\n        \"x\": null\n    },\n    \"b\": {\n        \"x\": null\n    }\n}\n"
+        );
+
+        let options = options.on_shared_object(JsonSerializerSharedObjectPolicy::Error);
+        let err = json_object.serialize_with_options(&options).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<JsonError>().unwrap().err_kind,
+            JsonErrorKind::SerializeErrorInObject
+        );
+        assert!(err.to_string().contains("path:/b"));
+
+        let shared_paths: Rc<RefCell<Vec<String>>> = Rc::new(RefCell::new(Vec::new()));
+        let shared_paths_in_callback = shared_paths.clone();
+        let options = options.on_shared_object(JsonSerializerSharedObjectPolicy::Warn(Rc::new(move |path_str: &str| {
+            shared_paths_in_callback.borrow_mut().push(path_str.to_string())
+        })));
+        assert_eq!(
+            json_object.serialize_with_options(&options)?,
+            "{\n    \"a\": {\n        \"x\": null\n    },\n    \"b\": {\n        \"x\": null\n    }\n}\n"
+        );
+        assert_eq!(*shared_paths.borrow(), vec!["/b".to_string()]);
+        Ok(())
+    }
+
+    #[test]
+    fn minify_json() -> Result<()> {
+        assert_eq!(
+            minify("{\n    \"z\" : [ 1.10, -2E+3, true, null ],\r\n\t\"a\": { \"b\" : {} , \"c\": [ ] }\n}\n")?,
+            "{\"z\":[1.10,-2E+3,true,null],\"a\":{\"b\":{},\"c\":[]}}"
+        );
+        // Escaped quotes and whitespace in strings are kept.
+        assert_eq!(
+            minify("{ \"k \\\" }\" : \"a \\\\\\\" b\\u0041 \\n { ,\" }")?,
+            "{\"k \\\" }\":\"a \\\\\\\" b\\u0041 \\n { ,\"}"
+        );
+        assert_eq!(minify(" [ \"\\ud83d\\ude00\" , \"😀\" ] ")?, "[\"\\ud83d\\ude00\",\"😀\"]");
+
+        for invalid_str in [
+            "{\"a\": \"b}",
+            "{\"a\": [1}",
+            "{\"a\": 1",
+            "{\"a\": 1}}",
+            "{\"a\": #}",
+            "{\"a\" 1}",
+            "[1,,2]",
+            "",
+        ] {
+            assert!(minify(invalid_str).is_err(), "{}", invalid_str);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn prettify_json() -> Result<()> {
+        let json_content_str = "{\"z\": 1.10, \"b\": [1, 2.50, {\"y\": \"\\\"q\\\"\", \"x\": 1E2}], \"a\": {}, \"m\": [[]]}";
+        assert_eq!(
+            prettify(&minify(json_content_str)?, &JsonSerializerOptions::new())?,
+            "{\n    \"z\": 1.10,\n    \"b\": [\n        1,\n        2.50,\n        {\n            \"y\": \"\\\"q\\\"\",\n            \
+             \"x\": 1E2\n        }\n    ],\n    \"a\": {},\n    \"m\": [ []]\n}\n"
+        );
+
+        let options = JsonSerializerOptions::new()
+            .newline(JsonSerializerNewLineKind::CrLf)
+            .indent(JsonSerializerIndentKind::Tab)
+            .colon_style(JsonSerializerColonStyle::SpaceBoth)
+            .trailing_newline(false);
+        assert_eq!(
+            prettify("{\"b\":[1,[2]],\"a\":-0.0}", &options)?,
+            "{\r\n\t\"b\" : [ 1, [ 2]],\r\n\t\"a\" : -0.0\r\n}"
+        );
+
+        // The same layout as the serializer.
+        let json_object = JsonObject::parse(json_content_str)?;
+        let options = JsonSerializerOptions::new().sort_keys(true);
+        let serialized_string = json_object.serialize_with_options(&options)?;
+        assert_eq!(prettify(&minify(&serialized_string)?, &options)?, serialized_string);
+        assert_eq!(
+            prettify(&serialized_string, &options.clone().compact(true).trailing_newline(false))?,
+            minify(&serialized_string)?
+        );
+        Ok(())
+    }
+
+    fn write_json_value<W: std::io::Write>(json_writer: &mut JsonWriter<W>, json_value: &JsonValue) -> Result<()> {
+        match json_value {
+            JsonValue::ValueString(json_string) => json_writer.value_str(json_string),
+            JsonValue::ValueNumber(json_number) => json_writer.value_number(json_number.clone()),
+            JsonValue::ValueBool(json_bool) => json_writer.value_bool(*json_bool),
+            JsonValue::ValueNull => json_writer.value_null(),
+            JsonValue::ValueRaw(json_raw_value) => json_writer.value_raw(json_raw_value),
+            JsonValue::ValueArray(json_array) => {
+                json_writer.begin_array()?;
+                for json_child_value in json_array {
+                    write_json_value(json_writer, json_child_value)?;
+                }
+                json_writer.end_array()
+            }
+            JsonValue::ValueObject(_) | JsonValue::ValueOwnedObject(_) => {
+                json_writer.begin_object()?;
+                let json_object = json_value.read_any_object().unwrap();
+                let mut json_keys: Vec<&JsonKey> = json_object.members.keys().collect();
+                json_keys.sort_by(|json_key_a, json_key_b| json_key_a.0.cmp(&json_key_b.0));
+                for json_key in json_keys {
+                    json_writer.key(&json_key.0)?;
+                    write_json_value(json_writer, &json_object.members[json_key])?;
+                }
+                json_writer.end_object()
+            }
+        }
+    }
+
+    #[test]
+    fn json_writer() -> Result<()> {
+        let json_content_str = "{\"s\": \"a\\\"/é\", \"n\": [1, -2.5, 3e2], \"o\": {\"e\": {}, \"a\": [], \"b\": [[]]}, \
+            \"m\": [1, [2, {\"x\": [true, false, null]}], [3, [4]], {}], \"l\": [[1, {}], [[]]], \"z\": [{}, {\"y\": {}}]}";
+        let json_value = JsonValue::ValueObject(JsonObject::parse(json_content_str)?.into_shared());
+        let base_options = JsonSerializerOptions::new().sort_keys(true);
+        for options in [
+            base_options.clone(),
+            base_options.clone().compact(true),
+            base_options.clone().array_style(JsonSerializerArrayStyle::Expanded),
+            base_options.clone().empty_object_style(JsonSerializerEmptyStyle::Expanded),
+            base_options.clone().empty_array_style(JsonSerializerEmptyStyle::Expanded),
+            base_options
+                .clone()
+                .empty_array_style(JsonSerializerEmptyStyle::Spaced)
+                .trailing_commas(true)
+                .escape_solidus(true)
+                .escape_non_ascii(true)
+                .newline(JsonSerializerNewLineKind::CrLf)
+                .indent(JsonSerializerIndentKind::Tab)
+                .colon_style(JsonSerializerColonStyle::SpaceBoth),
+            base_options
+                .clone()
+                .indent(JsonSerializerIndentKind::Custom("  ".to_string()))
+                .float_precision(Some(2))
+                .numbers_as_strings(JsonSerializerNumberStringPolicy::All)
+                .unquoted_keys(true)
+                .escape_extended_controls(true)
+                .skip_null_members(true)
+                .trailing_newline(false),
+            // compact では無視されるレイアウトのオプション
+            base_options
+                .clone()
+                .compact(true)
+                .brace_style(JsonSerializerBraceStyle::NextLine)
+                .align_colons(true)
+                .max_inline_array_width(Some(10))
+                .array_wrap(Some(2))
+                .inline_small_objects(Some(20))
+                .inline_value(|_| true),
+        ] {
+            let mut json_writer = JsonWriter::new_string(&options)?;
+            write_json_value(&mut json_writer, &json_value)?;
+            assert_eq!(json_writer.finish_string()?, json_value.serialize_with_options(&options)?);
+        }
+
+        // 木全体が必要なオプションは黙って無視せずにエラーにする
+        for options in [
+            base_options.clone().brace_style(JsonSerializerBraceStyle::NextLine),
+            base_options.clone().align_colons(true),
+            base_options.clone().max_inline_array_width(Some(10)),
+            base_options.clone().array_wrap(Some(2)),
+            base_options.clone().inline_small_objects(Some(20)),
+            base_options.clone().inline_value(|_| true),
+            base_options.clone().compact(true).transform_value(|_, _| None),
+        ] {
+            let err = JsonWriter::new_string(&options).err().unwrap();
+            assert_eq!(
+                err.downcast_ref::<JsonError>().unwrap().err_kind,
+                JsonErrorKind::SerializeErrorInOptions
+            );
+        }
+
+        // io::Write
+        let options = JsonSerializerOptions::new();
+        let mut json_writer = JsonWriter::new(Vec::new(), &options)?;
+        json_writer.begin_array()?;
+        json_writer.value_number(JsonNumberType::Int(1))?;
+        json_writer.end_array()?;
+        assert_eq!(json_writer.finish()?, b"[ 1]\n".to_vec());
+
+        // Wrong nesting
+        let mut json_writer = JsonWriter::new_string(&options)?;
+        json_writer.begin_array()?;
+        assert!(json_writer.key("a").is_err());
+        assert!(json_writer.end_object().is_err());
+        json_writer.begin_object()?;
+        assert!(json_writer.value_null().is_err());
+        json_writer.key("a")?;
+        assert!(json_writer.key("b").is_err());
+        assert!(json_writer.end_object().is_err());
+        assert!(json_writer.value_number(JsonNumberType::Float(f64::NAN)).is_err());
+        json_writer.value_null()?;
+        assert!(json_writer.end_array().is_err());
+        json_writer.end_object()?;
+        json_writer.end_array()?;
+        assert!(json_writer.value_null().is_err());
+        assert_eq!(json_writer.finish_string()?, "[\n    {\n        \"a\": null\n    }\n]\n");
+
+        let mut json_writer = JsonWriter::new_string(&options)?;
+        json_writer.begin_object()?;
+        assert!(json_writer.finish_string().is_err());
+        assert!(JsonWriter::new_string(&options)?.finish_string().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn serialize_max_depth() -> Result<()> {
+        // {"a": [[...[1]...]]} の深さは配列の数 + 1
+        let mut json_value = JsonValue::ValueNumber(JsonNumberType::Int(1));
+        for _ in 0..2000 {
+            json_value = JsonValue::ValueArray(vec![json_value]);
+        }
+        let mut json_object = JsonObject::new();
+        json_object.members.insert(JsonKey("a".to_string()), json_value);
+
+        let err = json_object.serialize_with_options(&JsonSerializerOptions::new()).unwrap_err();
+        let json_error = err.downcast_ref::<JsonError>().unwrap();
+        assert_eq!(json_error.err_kind, JsonErrorKind::SerializeErrorInArray);
+        assert!(err.to_string().contains("depth:1025 | path:/a/0/0/"), "{}", err);
+
+        let options = JsonSerializerOptions::new().compact(true).max_depth(Some(2001));
+        assert_eq!(json_object.serialize_with_options(&options)?.matches('[').count(), 2000);
+        let options = JsonSerializerOptions::new().compact(true).max_depth(Some(2000));
+        assert!(json_object.serialize_with_options(&options).is_err());
+
+        // 空のオブジェクトも数える
+        let json_object = JsonObject::parse("{\"a\": {\"b\": {}}}")?;
+        let options = JsonSerializerOptions::new().max_depth(Some(2));
+        let err = json_object.serialize_with_options(&options).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<JsonError>().unwrap().err_kind,
+            JsonErrorKind::SerializeErrorInObject
+        );
+        assert!(err.to_string().contains("depth:3 | path:/a/b"), "{}", err);
+        assert!(json_object.serialize_with_options(&options.max_depth(Some(3))).is_ok());
+
+        let options = JsonSerializerOptions::new().max_depth(Some(1));
+        let mut json_writer = JsonWriter::new_string(&options)?;
+        json_writer.begin_object()?;
+        json_writer.key("a")?;
+        assert!(json_writer.begin_array().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn skip_null_members() -> Result<()> {
+        let json_object =
+            JsonObject::parse("{\"a\": null, \"b\": [null, 1], \"c\": {\"x\": null}, \"d\": [{\"y\": null}], \"e\": 0}")?;
+        let options = JsonSerializerOptions::new().sort_keys(true).skip_null_members(true);
+        // 全メンバーが null のオブジェクトは空オブジェクトになり、それを含む配列は一行になる
+        assert_eq!(
+            json_object.serialize_with_options(&options)?,
+            "{\n    \"b\": [ null, 1],\n    \"c\": {},\n    \"d\": [ {}],\n    \"e\": 0\n}\n"
+        );
+        assert_eq!(
+            JsonObject::parse("{\"a\": null}")?.serialize_with_options(&options.clone().compact(true))?,
+            "{}\n"
+        );
+
+        let mut json_writer = JsonWriter::new_string(&options)?;
+        write_json_value(&mut json_writer, &JsonValue::ValueObject(json_object.into_shared()))?;
+        assert_eq!(
+            json_writer.finish_string()?,
+            "{\n    \"b\": [ null, 1],\n    \"c\": {},\n    \"d\": [ {}],\n    \"e\": 0\n}\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn numbers_as_strings() -> Result<()> {
+        let json_object = JsonObject::parse(
+            "{\"a\": 9007199254740991, \"b\": 9007199254740992, \"c\": -9007199254740992, \"d\": 1, \"e\": 1.5}",
+        )?;
+        let options = JsonSerializerOptions::new()
+            .sort_keys(true)
+            .compact(true)
+            .trailing_newline(false)
+            .numbers_as_strings(JsonSerializerNumberStringPolicy::IntsAbove(9007199254740991));
+        assert_eq!(
+            json_object.serialize_with_options(&options)?,
+            "{\"a\":9007199254740991,\"b\":\"9007199254740992\",\"c\":\"-9007199254740992\",\"d\":1,\"e\":1.5}"
+        );
+
+        let options = options.numbers_as_strings(JsonSerializerNumberStringPolicy::All);
+        assert_eq!(
+            json_object.serialize_with_options(&options)?,
+            "{\"a\":\"9007199254740991\",\"b\":\"9007199254740992\",\"c\":\"-9007199254740992\",\"d\":\"1\",\"e\":\"1.5\"}"
+        );
+
+        let options = options.numbers_as_strings(JsonSerializerNumberStringPolicy::Never);
+        assert_eq!(
+            json_object.serialize_with_options(&options)?,
+            "{\"a\":9007199254740991,\"b\":9007199254740992,\"c\":-9007199254740992,\"d\":1,\"e\":1.5}"
+        );
+
+        // NaN は non_finite_float に従う
+        let json_value = JsonValue::ValueArray(vec![JsonValue::ValueNumber(JsonNumberType::Float(f64::NAN))]);
+        let options = options
+            .numbers_as_strings(JsonSerializerNumberStringPolicy::All)
+            .non_finite_float(JsonSerializerNonFiniteFloatPolicy::Null);
+        assert_eq!(json_value.serialize_with_options(&options)?, "[null]");
+        Ok(())
+    }
+
+    #[test]
+    fn align_colons() -> Result<()> {
+        let json_object =
+            JsonObject::parse("{\"a\": 1, \"bbb\": {\"x\": true, \"yy\": [1, 2]}, \"q\\\"\\n\": null, \"é\": \"e\"}")?;
+        let options = JsonSerializerOptions::new().sort_keys(true).align_colons(true);
+        // エスケープ後の幅で揃える。ネストしたオブジェクトは独立に揃える
+        assert_eq!(
+            json_object.serialize_with_options(&options)?,
+            "{\n    \"a\"    : 1,\n    \"bbb\"  : {\n        \"x\" : true,\n        \"yy\": [ 1, 2]\n    },\n    \
+             \"q\\\"\\n\": null,\n    \"é\"    : \"e\"\n}\n"
+        );
+        assert_eq!(
+            json_object.serialize_with_options(&options.clone().colon_style(JsonSerializerColonStyle::SpaceBoth))?,
+            "{\n    \"a\"     : 1,\n    \"bbb\"   : {\n        \"x\"  : true,\n        \"yy\" : [ 1, 2]\n    },\n    \
+             \"q\\\"\\n\" : null,\n    \"é\"     : \"e\"\n}\n"
+        );
+        assert_eq!(
+            json_object.serialize_with_options(&options.clone().compact(true).trailing_newline(false))?,
+            "{\"a\":1,\"bbb\":{\"x\":true,\"yy\":[1,2]},\"q\\\"\\n\":null,\"é\":\"e\"}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn transform_value() -> Result<()> {
+        let json_object = JsonObject::parse(
+            "{\"password\": \"p0\", \"db\": {\"password\": \"p1\", \"port\": 5432}, \
+             \"users\": [{\"name\": \"a\", \"token\": \"t0\"}, {\"name\": \"b\", \"token\": \"t1\"}], \"token\": \"keep\"}",
+        )?;
+        let options = JsonSerializerOptions::new()
+            .sort_keys(true)
+            .compact(true)
+            .trailing_newline(false)
+            .transform_value(|path, _| match path {
+                [.., JsonPathSegment::Key("password")]
+                | [JsonPathSegment::Key("users"), JsonPathSegment::Index(_), JsonPathSegment::Key("token")] => {
+                    Some(JsonValue::ValueString("***".to_string()))
+                }
+                _ => None,
+            });
+        assert_eq!(
+            json_object.serialize_with_options(&options)?,
+            "{\"db\":{\"password\":\"***\",\"port\":5432},\"password\":\"***\",\"token\":\"keep\",\
+             \"users\":[{\"name\":\"a\",\"token\":\"***\"},{\"name\":\"b\",\"token\":\"***\"}]}"
+        );
+        // 元のオブジェクトは変更されない
+        assert_eq!(
+            json_object.members.get(&JsonKey("password".to_string())),
+            Some(&JsonValue::ValueString("p0".to_string()))
+        );
+
+        // 置き換えた値がオブジェクトの場合は、その位置のインデントで出力し、中身には callback を呼ばない
+        let options = JsonSerializerOptions::new()
+            .sort_keys(true)
+            .transform_value(|path, json_value| match path {
+                [JsonPathSegment::Key("db")] => Some(JsonValue::ValueArray(vec![json_value.clone()])),
+                _ => None,
+            });
+        assert_eq!(
+            JsonObject::parse("{\"db\": {\"port\": 5432}}")?.serialize_with_options(&options)?,
+            "{\n    \"db\": [\n        {\n            \"port\": 5432\n        }\n    ]\n}\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn array_wrap() -> Result<()> {
+        let json_object =
+            JsonObject::parse("{\"m\": [1, 2, 3, 4, 5, 6, 7, 8, 9, 10], \"s\": [1, 2, 3], \"x\": [1, 2, 3, 4, {}]}")?;
+        let options = JsonSerializerOptions::new().sort_keys(true).array_wrap(Some(3));
+        assert_eq!(
+            json_object.serialize_with_options(&options)?,
+            "{\n    \"m\": [\n        1, 2, 3,\n        4, 5, 6,\n        7, 8, 9,\n        10\n    ],\n    \
+             \"s\": [ 1, 2, 3],\n    \"x\": [ 1, 2, 3, 4, {}]\n}\n"
+        );
+
+        // 折り返す配列を含む配列は一要素一行
+        let json_value = JsonValue::ValueArray(vec![
+            JsonValue::ValueNull,
+            JsonValue::ValueArray((0..4).map(|idx| JsonValue::ValueNumber(JsonNumberType::Int(idx))).collect()),
+        ]);
+        assert_eq!(
+            json_value.serialize_with_options(&options.clone().trailing_commas(true))?,
+            "[\n    null,\n    [\n        0, 1, 2,\n        3,\n    ],\n]\n"
+        );
+        assert_eq!(
+            json_value.serialize_with_options(&options.clone().compact(true).trailing_newline(false))?,
+            "[null,[0,1,2,3]]"
+        );
+
+        let err = json_value.serialize_with_options(&options.array_wrap(Some(0))).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<JsonError>().unwrap().err_kind,
+            JsonErrorKind::SerializeErrorInOptions
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn array_wrap_owned_objects() -> Result<()> {
+        // 所有オブジェクトを含む配列も共有オブジェクトと同じく一要素一行
+        let content_str = "{\"a\": [{\"x\": 1}, {\"x\": 2}, {\"x\": 3}]}";
+        let json_owned_object = JsonParser::parse_with_options(content_str, &JsonParserOptions::new().owned_objects(true))?;
+        let options = JsonSerializerOptions::new().array_wrap(Some(2));
+        assert_eq!(
+            json_owned_object.serialize_with_options(&options)?,
+            "{\n    \"a\": [\n        {\n            \"x\": 1\n        },\n        {\n            \"x\": 2\n        },\n        \
+             {\n            \"x\": 3\n        }\n    ]\n}\n"
+        );
+        assert_eq!(
+            json_owned_object.serialize_with_options(&options)?,
+            JsonObject::parse(content_str)?.serialize_with_options(&options)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn inline_value() -> Result<()> {
+        let json_object = JsonObject::parse(
+            "{\"name\": \"n\", \"view\": {\"matrix\": [[1, 0], [0, 1]], \"palette\": {\"bg\": \"#000\", \"fg\": \"#fff\"}, \
+             \"size\": {\"w\": 1}}}",
+        )?;
+        let options = JsonSerializerOptions::new().sort_keys(true).inline_value(|path| {
+            matches!(
+                path.last(),
+                Some(JsonPathSegment::Key("matrix")) | Some(JsonPathSegment::Key("palette"))
+            )
+        });
+        assert_eq!(
+            json_object.serialize_with_options(&options)?,
+            "{\n    \"name\": \"n\",\n    \"view\": {\n        \"matrix\": [[1,0],[0,1]],\n        \
+             \"palette\": {\"bg\":\"#000\",\"fg\":\"#fff\"},\n        \"size\": {\n            \"w\": 1\n        }\n    }\n}\n"
+        );
+
+        // 所有オブジェクトも部分木だけ compact で出力する
+        let json_owned_object =
+            JsonParser::parse_with_options(&json_object.to_string(), &JsonParserOptions::new().owned_objects(true))?;
+        assert_eq!(
+            json_owned_object.serialize_with_options(&options)?,
+            json_object.serialize_with_options(&options)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn unquoted_keys() -> Result<()> {
+        let json_object = JsonObject::parse(
+            "{\"simple_$1\": 1, \"a b\": 2, \"q\\\"\": 3, \"1st\": 4, \"null\": 5, \"é\": 6, \"_\": {\"x\": []}}",
+        )?;
+        let options = JsonSerializerOptions::new().sort_keys(true).unquoted_keys(true);
+        assert_eq!(
+            json_object.serialize_with_options(&options)?,
+            "{\n    \"1st\": 4,\n    _: {\n        x: []\n    },\n    \"a b\": 2,\n    \"null\": 5,\n    \
+             \"q\\\"\": 3,\n    simple_$1: 1,\n    \"é\": 6\n}\n"
+        );
+        assert_eq!(
+            json_object.serialize_with_options(&options.compact(true).trailing_newline(false))?,
+            "{\"1st\":4,_:{x:[]},\"a b\":2,\"null\":5,\"q\\\"\":3,simple_$1:1,\"é\":6}"
+        );
+        assert_eq!(
+            JsonObject::parse("{\"ab\": 1, \"a b\": 2}")?.serialize_with_options(
+                &JsonSerializerOptions::new()
+                    .sort_keys(true)
+                    .unquoted_keys(true)
+                    .align_colons(true)
+            )?,
+            "{\n    \"a b\": 2,\n    ab   : 1\n}\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn brace_style() -> Result<()> {
+        let json_object = JsonObject::parse("{\"a\": {\"b\": {\"c\": 1}, \"e\": {}}, \"l\": [{\"d\": 2}], \"n\": 0}")?;
+        let options = JsonSerializerOptions::new().sort_keys(true);
+        assert_eq!(
+            json_object.serialize_with_options(&options.clone().brace_style(JsonSerializerBraceStyle::SameLine))?,
+            "{\n    \"a\": {\n        \"b\": {\n            \"c\": 1\n        },\n        \"e\": {}\n    },\n    \
+             \"l\": [\n        {\n            \"d\": 2\n        }\n    ],\n    \"n\": 0\n}\n"
+        );
+        assert_eq!(
+            json_object.serialize_with_options(&options.clone().brace_style(JsonSerializerBraceStyle::NextLine))?,
+            "{\n    \"a\":\n    {\n        \"b\":\n        {\n            \"c\": 1\n        },\n        \"e\": {}\n    },\n    \
+             \"l\": [\n        {\n            \"d\": 2\n        }\n    ],\n    \"n\": 0\n}\n"
+        );
+        let options = options
+            .brace_style(JsonSerializerBraceStyle::NextLine)
+            .colon_style(JsonSerializerColonStyle::SpaceBoth)
+            .empty_object_style(JsonSerializerEmptyStyle::Expanded);
+        assert_eq!(
+            JsonObject::parse("{\"a\": {\"b\": {}}}")?.serialize_with_options(&options)?,
+            "{\n    \"a\" :\n    {\n        \"b\" : {\n        }\n    }\n}\n"
+        );
+
+        // 開き括弧の位置は置き換えた値と一行にした値で決める
+        let json_object = JsonObject::parse("{\"n\": 0, \"secret\": {\"a\": 1}, \"view\": {\"a\": 1}}")?;
+        let options = JsonSerializerOptions::new()
+            .sort_keys(true)
+            .brace_style(JsonSerializerBraceStyle::NextLine)
+            .transform_value(|path, _| match path {
+                [JsonPathSegment::Key("secret")] => Some(JsonValue::from("***")),
+                [JsonPathSegment::Key("n")] => Some(json_value!({"b": 2})),
+                _ => None,
+            });
+        assert_eq!(
+            json_object.serialize_with_options(&options)?,
+            "{\n    \"n\":\n    {\n        \"b\": 2\n    },\n    \"secret\": \"***\",\n    \"view\":\n    {\n        \"a\": 1\n    }\n}\n"
+        );
+        let options = options.inline_value(|path| path == [JsonPathSegment::Key("view")]);
+        assert_eq!(
+            json_object.serialize_with_options(&options)?,
+            "{\n    \"n\":\n    {\n        \"b\": 2\n    },\n    \"secret\": \"***\",\n    \"view\": {\"a\":1}\n}\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn serialize_only() -> Result<()> {
+        let json_object =
+            JsonObject::parse("{\"id\": 1, \"name\": \"n\", \"secret\": \"s\", \"profile\": {\"b\": [1], \"a\": null}}")?;
+        let options = JsonSerializerOptions::new().compact(true).trailing_newline(false);
+        // キーの順に出力する
+        assert_eq!(
+            json_object.serialize_only(&["name", "id", "missing", "name"], &options)?,
+            "{\"name\":\"n\",\"id\":1}"
+        );
+        assert_eq!(
+            json_object.serialize_only(&["profile", "id"], &options.clone().sort_keys(true))?,
+            "{\"id\":1,\"profile\":{\"a\":null,\"b\":[1]}}"
+        );
+        assert_eq!(json_object.serialize_only(&[], &options)?, "{}");
+        assert_eq!(
+            json_object.serialize_only(&["id"], &JsonSerializerOptions::new())?,
+            "{\n    \"id\": 1\n}\n"
+        );
+
+        let err = json_object
+            .serialize_only(&["id", "missing"], &options.error_on_missing_keys(true))
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<JsonError>().unwrap().err_kind,
+            JsonErrorKind::SerializeErrorInKey
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn escape_extended_controls() -> Result<()> {
+        let json_string = "a\u{7f}\u{80}\u{85}\u{9f}\u{a0}é~".to_string();
+        let mut json_object = JsonObject::new();
+        json_object
+            .members
+            .insert(JsonKey("k".to_string()), JsonValue::ValueString(json_string.clone()));
+
+        let options = JsonSerializerOptions::new().compact(true).trailing_newline(false);
+        assert_eq!(
+            json_object.serialize_with_options(&options)?,
+            format!("{{\"k\":\"{}\"}}", json_string)
+        );
+
+        let options = options.escape_extended_controls(true);
+        let serialized_string = json_object.serialize_with_options(&options)?;
+        assert_eq!(serialized_string, "{\"k\":\"a\\u007f\\u0080\\u0085\\u009f\u{a0}é~\"}");
+        assert_eq!(JsonObject::parse(&serialized_string)?, json_object);
+        Ok(())
+    }
+
+    #[test]
+    fn raw_value() -> Result<()> {
+        let json_raw_value = JsonRawValue::new(" {\n  \"b\": [1, 2.50],\n\n  \"a\": \"}\"\n}\n")?;
+        assert_eq!(json_raw_value.as_str(), "{\n  \"b\": [1, 2.50],\n\n  \"a\": \"}\"\n}");
+        let mut json_object = JsonObject::new();
+        json_object
+            .members
+            .insert(JsonKey("data".to_string()), JsonValue::ValueRaw(json_raw_value.clone()));
+        json_object.members.insert(
+            JsonKey("list".to_string()),
+            JsonValue::ValueArray(vec![JsonValue::ValueRaw(JsonRawValue::new("true")?)]),
+        );
+
+        let options = JsonSerializerOptions::new().sort_keys(true);
+        let serialized_string = json_object.serialize_with_options(&options)?;
+        assert_eq!(
+            serialized_string,
+            "{\n    \"data\": {\n  \"b\": [1, 2.50],\n\n  \"a\": \"}\"\n},\n    \"list\": [ true]\n}\n"
+        );
+        let json_parsed_object = JsonObject::parse(&serialized_string)?;
+        assert_eq!(
+            json_parsed_object.members[&JsonKey("list".to_string())],
+            JsonValue::ValueArray(vec![JsonValue::ValueBool(true)])
+        );
+
+        let options = options.reindent_raw_values(true);
+        let serialized_string = json_object.serialize_with_options(&options)?;
+        assert_eq!(
+            serialized_string,
+            "{\n    \"data\": {\n      \"b\": [1, 2.50],\n\n      \"a\": \"}\"\n    },\n    \"list\": [ true]\n}\n"
+        );
+        assert!(JsonObject::parse(&serialized_string).is_ok());
+
+        let mut json_writer = JsonWriter::new_string(&options)?;
+        write_json_value(&mut json_writer, &JsonValue::ValueObject(json_object.into_shared()))?;
+        assert_eq!(json_writer.finish_string()?, serialized_string);
+
+        for invalid_str in ["", " ", "{\"a\": 1", "[1]]", "\"a", "1, 2", "{} []"] {
+            assert!(JsonRawValue::new(invalid_str).is_err(), "{}", invalid_str);
+        }
+        // 括弧が対応していても JSON の文法に従わない値
+        for invalid_str in [
+            "{\"a\" 1}",
+            "{:}",
+            "tru",
+            "[1,,2]",
+            "{\"a\":}",
+            "\"\\x\"",
+            "[1,]",
+            "{\"a\": 1,}",
+            "{1: 2}",
+            "{\"a\", \"b\"}",
+            "[\"a\": 1]",
+            "01",
+            "1.",
+            "-",
+            "1e",
+            "+1",
+            "NaN",
+            "\"\\u00g0\"",
+            "\"\\ud800\"",
+            "\"\\udc00\"",
+            "\"a\tb\"",
+        ] {
+            assert!(JsonRawValue::new(invalid_str).is_err(), "{}", invalid_str);
+        }
+        let mut json_object = JsonObject::new();
+        for (idx, valid_str) in [
+            "-0.5e+10",
+            "0",
+            "1E2",
+            "\"\\\"\\/\\b\\f\\n\\r\\t\\u00e9\\ud83d\\ude00\"",
+            "[]",
+            "{}",
+            "[null, true, false, {\"a\": [1, {\"b\": \"c\"}]}]",
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            json_object.insert(idx.to_string(), JsonValue::ValueRaw(JsonRawValue::new(valid_str)?));
+        }
+        assert!(JsonObject::parse(&json_object.serialize_with_options(&JsonSerializerOptions::new())?).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn serialize_pointer() -> Result<()> {
+        let json_object = JsonObject::parse(
+            "{\"servers\": [{\"name\": \"a\"}, {\"name\": \"b\", \"tls\": {\"on\": true}}], \"a/b\": {\"m~n\": 1}, \"\": 0}",
+        )?;
+        let options = JsonSerializerOptions::new().sort_keys(true);
+        assert_eq!(
+            json_object.serialize_pointer("/servers/1/tls", &options)?,
+            "{\n    \"on\": true\n}\n"
+        );
+        assert_eq!(json_object.serialize_pointer("/servers/0/name", &options)?, "\"a\"\n");
+        assert_eq!(json_object.serialize_pointer("/a~1b/m~0n", &options)?, "1\n");
+        assert_eq!(json_object.serialize_pointer("/", &options)?, "0\n");
+        assert_eq!(
+            json_object.serialize_pointer("", &options)?,
+            json_object.serialize_with_options(&options)?
+        );
+
+        for (pointer_str, segment_str) in [
+            ("/servers/2", "segment:2 |"),
+            ("/servers/01", "segment:01 |"),
+            ("/servers/-", "segment:- |"),
+            ("/servers/0/name/x", "segment:x |"),
+            ("/missing", "segment:missing |"),
+            ("/a~2b", "segment:a~2b |"),
+            ("servers", "segment: |"),
+        ] {
+            let err = json_object.serialize_pointer(pointer_str, &options).unwrap_err();
+            assert_eq!(
+                err.downcast_ref::<JsonError>().unwrap().err_kind,
+                JsonErrorKind::SerializeErrorInValue
+            );
+            assert!(err.to_string().contains(segment_str), "{}", err);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn serialize_to_chunks() -> Result<()> {
+        let mut json_object = JsonObject::new();
+        json_object.members.insert(
+            JsonKey("array".to_string()),
+            JsonValue::ValueArray(
+                (0..1000)
+                    .map(|idx| JsonValue::ValueNumber(JsonNumberType::Int(idx)))
+                    .collect(),
+            ),
+        );
+        json_object
+            .members
+            .insert(JsonKey("string".to_string()), JsonValue::ValueString("日本語".repeat(100)));
+        let options = JsonSerializerOptions::new().sort_keys(true);
+        let serialized_string = json_object.serialize_with_options(&options)?;
+
+        let mut chunks: Vec<String> = Vec::new();
+        json_object.serialize_to_chunks(64, &options, |chunk_str| {
+            chunks.push(chunk_str.to_string());
+            Ok(())
+        })?;
+        assert!(chunks.len() > 1);
+        assert!(chunks[..chunks.len() - 1].iter().all(|chunk_string| chunk_string.len() >= 64));
+        assert_eq!(chunks.concat(), serialized_string);
+
+        // sink のエラーで中断する
+        let mut call_count = 0;
+        let err = json_object
+            .serialize_to_chunks(64, &options, |_| {
+                call_count += 1;
+                Err(JsonError::new(JsonErrorKind::IoError, Some("connection reset".to_string())))
+            })
+            .unwrap_err();
+        assert_eq!(call_count, 1);
+        assert_eq!(err.downcast_ref::<JsonError>().unwrap().err_kind, JsonErrorKind::IoError);
+
+        let err = json_object.serialize_to_chunks(0, &options, |_| Ok(())).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<JsonError>().unwrap().err_kind,
+            JsonErrorKind::SerializeErrorInOptions
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn inline_small_objects() -> Result<()> {
+        let json_object =
+            JsonObject::parse(r#"{"point": {"x": 1, "y": 2}, "points": [{"x": 1, "y": 2}, {"x": 10, "y": 20}], "empty": {}}"#)?;
+        // {"x":1,"y":2} は 13 文字、{"x":10,"y":20} は 15 文字
+        let options = JsonSerializerOptions::new().sort_keys(true).inline_small_objects(Some(13));
+        assert_eq!(
+            json_object.serialize_with_options(&options)?,
+            concat!(
+                "{\n",
+                "    \"empty\": {},\n",
+                "    \"point\": {\"x\":1,\"y\":2},\n",
+                "    \"points\": [\n",
+                "        {\"x\":1,\"y\":2},\n",
+                "        {\n",
+                "            \"x\": 10,\n",
+                "            \"y\": 20\n",
+                "        }\n",
+                "    ]\n",
+                "}\n"
+            )
+        );
+        let options = options.inline_small_objects(Some(12));
+        assert_eq!(
+            json_object.serialize_with_options(&options)?,
+            json_object.serialize_with_options(&JsonSerializerOptions::new().sort_keys(true))?
+        );
+        let options = options
+            .inline_small_objects(Some(15))
+            .brace_style(JsonSerializerBraceStyle::NextLine);
+        assert_eq!(
+            json_object.serialize_with_options(&options)?,
+            concat!(
+                "{\n",
+                "    \"empty\": {},\n",
+                "    \"point\": {\"x\":1,\"y\":2},\n",
+                "    \"points\": [\n",
+                "        {\"x\":1,\"y\":2},\n",
+                "        {\"x\":10,\"y\":20}\n",
+                "    ]\n",
+                "}\n"
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn priority_keys() -> Result<()> {
+        let json_object = JsonObject::parse(
+            r#"{"type": "object", "title": "Config", "$schema": "https://json-schema.org/draft/2020-12/schema",
+                "properties": {"name": {"type": "string"}, "version": {"type": "integer"}, "arch": {"type": "string"}}}"#,
+        )?;
+        let options = JsonSerializerOptions::new()
+            .compact(true)
+            .trailing_newline(false)
+            .sort_keys(true)
+            .priority_keys(vec![
+                "$schema".to_string(),
+                "$id".to_string(),
+                "title".to_string(),
+                "version".to_string(),
+            ]);
+        assert_eq!(
+            json_object.serialize_with_options(&options)?,
+            concat!(
+                r#"{"$schema":"https://json-schema.org/draft/2020-12/schema","title":"Config","#,
+                r#""properties":{"version":{"type":"integer"},"arch":{"type":"string"},"name":{"type":"string"}},"#,
+                r#""type":"object"}"#
+            )
+        );
+        // serialize_canonical では無視する
+        assert!(json_object
+            .serialize_canonical()?
+            .starts_with(r#"{"$schema":"https://json-schema.org/draft/2020-12/schema","properties""#));
+        Ok(())
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn newline_kind_platform() -> Result<()> {
+        let mut json_object = JsonObject::new();
+        json_object.members.insert(JsonKey("key".to_string()), JsonValue::ValueNull);
+
+        // 旧名は CrLf と同じ
+        assert_eq!(JsonSerializerNewLineKind::Crlf, JsonSerializerNewLineKind::CrLf);
+        assert!(matches!(JsonSerializerNewLineKind::CrLf, JsonSerializerNewLineKind::Crlf));
+        assert_eq!(
+            json_object.serialize(JsonSerializerNewLineKind::Crlf, JsonSerializerIndentKind::Space(4))?,
+            "{\r\n    \"key\": null\r\n}\r\n"
+        );
+
+        let newline_kind = JsonSerializerNewLineKind::Platform;
+        let copied_newline_kind = newline_kind;
+        assert_eq!(format!("{:?}", copied_newline_kind), "Platform");
+        let serialized_string = json_object.serialize(newline_kind, JsonSerializerIndentKind::Space(4))?;
+        #[cfg(windows)]
+        assert_eq!(serialized_string, "{\r\n    \"key\": null\r\n}\r\n");
+        #[cfg(not(windows))]
+        assert_eq!(serialized_string, "{\n    \"key\": null\n}\n");
+
+        assert_eq!(format!("{:?}", JsonSerializerIndentKind::Space(2)), "Space(2)");
+        Ok(())
+    }
+
+    #[test]
+    fn emit_bom() -> Result<()> {
+        let mut json_object = JsonObject::new();
+        json_object
+            .members
+            .insert(JsonKey("key".to_string()), JsonValue::ValueString("値".to_string()));
+        let options = JsonSerializerOptions::new().emit_bom(true).trailing_newline(false);
+        let serialized_string = json_object.serialize_with_options(&options)?;
+        assert_eq!(serialized_string, "\u{FEFF}{\n    \"key\": \"値\"\n}");
+
+        // BOM を読み飛ばさないとエラーになる
+        assert!(JsonObject::parse(&serialized_string).is_err());
+        let parsed_object = JsonObject::parse_with_options(&serialized_string, &JsonParserOptions::new().skip_bom(true))?;
+        assert_eq!(parsed_object, json_object);
+
+        // バイト列でも BOM は一度だけ
+        let bytes = json_object.serialize_to_bytes(&options, JsonSerializerOutputEncoding::Utf8)?;
+        assert_eq!(&bytes[..4], &[0xEF, 0xBB, 0xBF, b'{']);
+        assert_eq!(
+            json_object.serialize_to_bytes(&options, JsonSerializerOutputEncoding::Utf8WithBom)?,
+            bytes
+        );
+        let bytes = json_object.serialize_to_bytes(&options, JsonSerializerOutputEncoding::Utf16Le)?;
+        assert_eq!(&bytes[..4], &[0xFF, 0xFE, b'{', 0x00]);
+
+        let mut json_writer = JsonWriter::new(Vec::new(), &options)?;
+        json_writer.begin_object()?;
+        json_writer.key("key")?;
+        json_writer.value_str("値")?;
+        json_writer.end_object()?;
+        assert_eq!(json_writer.finish()?, serialized_string.into_bytes());
+        Ok(())
+    }
+
+    #[test]
+    fn serialize_into() -> Result<()> {
+        let options = JsonSerializerOptions::new().compact(true).trailing_newline(false);
+        let mut buf = String::new();
+        for idx in 0..3 {
+            let mut json_object = JsonObject::new();
+            json_object
+                .members
+                .insert(JsonKey("idx".to_string()), JsonValue::ValueNumber(JsonNumberType::Int(idx)));
+            buf.clear();
+            json_object.serialize_into(&mut buf, &options)?;
+            assert_eq!(buf, format!("{{\"idx\":{}}}", idx));
+        }
+
+        // 追記する。エラーの場合は元の長さに戻す
+        let mut json_object = JsonObject::new();
+        json_object
+            .members
+            .insert(JsonKey("a".to_string()), JsonValue::ValueNumber(JsonNumberType::Int(1)));
+        json_object.members.insert(
+            JsonKey("b".to_string()),
+            JsonValue::ValueNumber(JsonNumberType::Float(f64::NAN)),
+        );
+        let options = options.sort_keys(true);
+        let err = json_object.serialize_into(&mut buf, &options).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<JsonError>().unwrap().err_kind,
+            JsonErrorKind::SerializeErrorInNumber
+        );
+        assert_eq!(buf, "{\"idx\":2}");
+        json_object.members.remove(&JsonKey("b".to_string()));
+        json_object.serialize_into(&mut buf, &options)?;
+        assert_eq!(buf, "{\"idx\":2}{\"a\":1}");
+        Ok(())
+    }
+
+    #[test]
+    fn serialize_long_strings() -> Result<()> {
+        let long_str = "abcdefghij".repeat(500);
+        let json_string = format!("{}\"日本語\\{}\u{0001}😀\n{}", long_str, long_str, long_str);
+        let mut json_object = JsonObject::new();
+        json_object.members.insert(
+            JsonKey(format!("{}\t{}", long_str, long_str)),
+            JsonValue::ValueString(json_string),
+        );
+        let options = JsonSerializerOptions::new().compact(true).trailing_newline(false);
+        assert_eq!(
+            json_object.serialize_with_options(&options)?,
+            format!(
+                "{{\"{}\\t{}\":\"{}\\\"日本語\\\\{}\\u0001😀\\n{}\"}}",
+                long_str, long_str, long_str, long_str, long_str
+            )
+        );
+        let options = options.escape_non_ascii(true).escape_solidus(true);
+        let json_value = JsonValue::ValueString(format!("{}/日\u{007F}😀{}", long_str, long_str));
+        assert_eq!(
+            json_value.serialize_with_options(&options)?,
+            format!("\"{}\\/\\u65e5\\u007f\\ud83d\\ude00{}\"", long_str, long_str)
+        );
+
+        // 往復して元の値に戻る
+        let serialized_string = json_object.serialize_with_options(&JsonSerializerOptions::new())?;
+        assert_eq!(JsonObject::parse(&serialized_string)?, json_object);
+        Ok(())
+    }
+
+    #[test]
+    fn get_member() -> Result<()> {
+        let mut json_object = JsonObject::parse(r#"{"name": "json", "日本語": 1, "quote\"\n": true}"#)?;
+        assert_eq!(json_object.get("name"), Some(&JsonValue::ValueString("json".to_string())));
+        assert_eq!(
+            json_object.get("日本語"),
+            Some(&JsonValue::ValueNumber(JsonNumberType::Int(1)))
+        );
+        assert_eq!(json_object.get("quote\"\n"), Some(&JsonValue::ValueBool(true)));
+        assert_eq!(json_object.get("none"), None);
+
+        *json_object.get_mut("日本語").unwrap() = JsonValue::ValueNull;
+        assert!(json_object.get_mut("none").is_none());
+        // members への直接のアクセスも変わらない
+        assert_eq!(
+            json_object.members.get(&JsonKey("日本語".to_string())),
+            Some(&JsonValue::ValueNull)
+        );
+        assert_eq!(json_object.members.len(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn typed_getters() -> Result<()> {
+        let json_object = JsonObject::parse(
+            r#"{"str": "text", "int": -3, "big": 9007199254740993, "float": 1.5, "bool": false, "array": [1], "object": {"a": null}}"#,
+        )?;
+        assert_eq!(json_object.get_str("str"), Some("text"));
+        assert_eq!(json_object.get_i64("int"), Some(-3));
+        assert_eq!(json_object.get_f64("float"), Some(1.5));
+        assert_eq!(json_object.get_f64("int"), Some(-3.0));
+        // f64 で正確に表せない整数は変換しない
+        assert_eq!(json_object.get_f64("big"), None);
+        assert_eq!(json_object.get_bool("bool"), Some(false));
+        assert_eq!(
+            json_object.get_array("array"),
+            Some(&vec![JsonValue::ValueNumber(JsonNumberType::Int(1))])
+        );
+        let refcell_json_object = json_object.get_object("object").unwrap();
+        assert_eq!(refcell_json_object.read_object().get("a"), Some(&JsonValue::ValueNull));
+
+        // 型が違う場合とキーがない場合
+        assert_eq!(json_object.get_str("int"), None);
+        assert_eq!(json_object.get_i64("float"), None);
+        assert_eq!(json_object.get_f64("str"), None);
+        assert_eq!(json_object.get_bool("str"), None);
+        assert_eq!(json_object.get_array("object"), None);
+        assert!(json_object.get_object("array").is_none());
+        assert_eq!(json_object.get_str("none"), None);
+        assert!(json_object.get_object("none").is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn try_typed_getters() -> Result<()> {
+        let json_object = JsonObject::parse(
+            r#"{"timeout": "30s", "retry": 3, "ratio": 0.5, "debug": true, "hosts": ["a"], "tls": {}, "none": null}"#,
+        )?;
+        assert_eq!(json_object.try_get_str("timeout")?, "30s");
+        assert_eq!(json_object.try_get_i64("retry")?, 3);
+        assert_eq!(json_object.try_get_f64("ratio")?, 0.5);
+        assert_eq!(json_object.try_get_f64("retry")?, 3.0);
+        assert!(json_object.try_get_bool("debug")?);
+        assert_eq!(json_object.try_get_array("hosts")?.len(), 1);
+        assert!(json_object.try_get_object("tls")?.read_object().members.is_empty());
+
+        let err = json_object.try_get_i64("timeout").unwrap_err();
+        assert_eq!(err.downcast_ref::<JsonError>().unwrap().err_kind, JsonErrorKind::TypeMismatch);
+        assert_eq!(err.to_string(), "Type mismatch : \"timeout\": expected integer, found string");
+        let err = json_object.try_get_f64("none").unwrap_err();
+        assert_eq!(err.to_string(), "Type mismatch : \"none\": expected number, found null");
+        let err = json_object.try_get_str("ratio").unwrap_err();
+        assert_eq!(err.to_string(), "Type mismatch : \"ratio\": expected string, found number");
+        let err = json_object.try_get_bool("retry").unwrap_err();
+        assert_eq!(err.to_string(), "Type mismatch : \"retry\": expected bool, found integer");
+        let err = json_object.try_get_array("tls").unwrap_err();
+        assert_eq!(err.to_string(), "Type mismatch : \"tls\": expected array, found object");
+        let err = json_object.try_get_object("hosts").unwrap_err();
+        assert_eq!(err.to_string(), "Type mismatch : \"hosts\": expected object, found array");
+
+        let err = json_object.try_get_str("port").unwrap_err();
+        assert_eq!(err.downcast_ref::<JsonError>().unwrap().err_kind, JsonErrorKind::KeyNotFound);
+        assert_eq!(err.to_string(), "Key not found : \"port\"");
+        Ok(())
+    }
+
+    #[test]
+    fn insert_member() -> Result<()> {
+        let mut json_child_object = JsonObject::new();
+        let json_key = JsonKey("object_number".to_string());
+        let json_value = JsonValue::ValueNumber(JsonNumberType::Int(1));
+        json_child_object.members.insert(json_key, json_value);
+
+        let mut json_object = JsonObject::new();
+        let json_key = JsonKey("object_object".to_string());
+        let json_value = JsonValue::ValueObject(json_child_object.into_shared());
+        json_object.members.insert(json_key, json_value);
+        let json_key = JsonKey("array".to_string());
+        let json_value = JsonValue::ValueArray(vec![
+            JsonValue::ValueString("string".to_string()),
+            JsonValue::ValueNumber(JsonNumberType::Float(1.5)),
+            JsonValue::ValueBool(true),
+        ]);
+        json_object.members.insert(json_key, json_value);
+        let json_key = JsonKey("name".to_string());
+        let json_value = JsonValue::ValueString("json".to_string());
+        json_object.members.insert(json_key, json_value);
+
+        // insert で同じオブジェクトを作る
+        let mut json_child_object = JsonObject::new();
+        json_child_object.insert("object_number", 1);
+        let mut inserted_object = JsonObject::new();
+        inserted_object.insert("object_object", json_child_object);
+        inserted_object.insert("array", vec!["string".into(), 1.5.into(), true.into()]);
+        assert_eq!(inserted_object.insert("name", "old".to_string()), None);
+        assert_eq!(inserted_object.insert("name".to_string(), "json"), Some("old".into()));
+
+        let options = JsonSerializerOptions::new().sort_keys(true);
+        assert_eq!(
+            inserted_object.serialize_with_options(&options)?,
+            json_object.serialize_with_options(&options)?
+        );
+        assert_eq!(inserted_object, json_object);
+        Ok(())
+    }
+
+    #[test]
+    fn remove_member() -> Result<()> {
+        let mut json_object = JsonObject::new();
+        assert!(json_object.is_empty());
+        assert_eq!(json_object.len(), 0);
+        assert_eq!(json_object.remove("key"), None);
+
+        json_object.insert("key", "value");
+        json_object.insert("キー", true);
+        assert!(!json_object.is_empty());
+        assert_eq!(json_object.len(), 2);
+        assert!(json_object.contains_key("key"));
+        assert!(json_object.contains_key("キー"));
+        assert!(!json_object.contains_key("none"));
+
+        assert_eq!(json_object.remove("none"), None);
+        assert_eq!(json_object.len(), 2);
+        assert_eq!(json_object.remove("キー"), Some(JsonValue::ValueBool(true)));
+        assert!(!json_object.contains_key("キー"));
+        assert_eq!(json_object.remove("key"), Some(JsonValue::ValueString("value".to_string())));
+        assert!(json_object.is_empty());
+        assert_eq!(json_object.to_compact_string()?, "{}");
+        Ok(())
+    }
+
+    #[test]
+    fn pointer() -> Result<()> {
+        // RFC 6901 の例
+        let json_object = JsonObject::parse(
+            r#"{
+                "foo": ["bar", "baz"],
+                "": 0,
+                "a/b": 1,
+                "c%d": 2,
+                "e^f": 3,
+                "g|h": 4,
+                "i\\j": 5,
+                "k\"l": 6,
+                " ": 7,
+                "m~n": 8
+            }"#,
+        )?;
+        assert_eq!(json_object.pointer(""), Some(JsonValue::from(json_object.clone())));
+        assert_eq!(
+            json_object.pointer("/foo"),
+            Some(JsonValue::from(vec!["bar".into(), "baz".into()]))
+        );
+        assert_eq!(json_object.pointer("/foo/0"), Some(JsonValue::from("bar")));
+        assert_eq!(json_object.pointer("/"), Some(JsonValue::from(0)));
+        assert_eq!(json_object.pointer("/a~1b"), Some(JsonValue::from(1)));
+        assert_eq!(json_object.pointer("/c%d"), Some(JsonValue::from(2)));
+        assert_eq!(json_object.pointer("/e^f"), Some(JsonValue::from(3)));
+        assert_eq!(json_object.pointer("/g|h"), Some(JsonValue::from(4)));
+        assert_eq!(json_object.pointer("/i\\j"), Some(JsonValue::from(5)));
+        assert_eq!(json_object.pointer("/k\"l"), Some(JsonValue::from(6)));
+        assert_eq!(json_object.pointer("/ "), Some(JsonValue::from(7)));
+        assert_eq!(json_object.pointer("/m~0n"), Some(JsonValue::from(8)));
+
+        let json_object = JsonObject::parse(r#"{"a": {"b": [{"c": true}]}}"#)?;
+        assert_eq!(json_object.pointer("/a/b/0/c"), Some(JsonValue::from(true)));
+        assert_eq!(json_object.pointer("/a/b/1"), None);
+        assert_eq!(json_object.pointer("/a/b/01"), None);
+        assert_eq!(json_object.pointer("/a/b/-"), None);
+        assert_eq!(json_object.pointer("/a/x"), None);
+        assert_eq!(json_object.pointer("/a/b/0/c/d"), None);
+        assert_eq!(json_object.pointer("a"), None);
+        assert_eq!(json_object.pointer("/a~2"), None);
+        Ok(())
+    }
+
+    #[test]
+    fn pointer_set() -> Result<()> {
+        let mut json_object = JsonObject::parse(r#"{"a": {"b": 1}, "list": [1, 2], "scalar": "text"}"#)?;
+        assert_eq!(
+            json_object.pointer_set("/a/b", JsonValue::from(2), false)?,
+            Some(JsonValue::from(1))
+        );
+        assert_eq!(json_object.pointer_set("/a/c", JsonValue::from(3), false)?, None);
+        assert_eq!(json_object.pointer("/a/c"), Some(JsonValue::from(3)));
+
+        // 配列: 置き換え、末尾の次と "-" は追加、飛び越えるとエラー
+        assert_eq!(
+            json_object.pointer_set("/list/0", JsonValue::from(10), false)?,
+            Some(JsonValue::from(1))
+        );
+        assert_eq!(json_object.pointer_set("/list/2", JsonValue::from(3), false)?, None);
+        assert_eq!(json_object.pointer_set("/list/-", JsonValue::from(4), false)?, None);
+        assert_eq!(
+            json_object.pointer("/list"),
+            Some(JsonValue::from(vec![10.into(), 2.into(), 3.into(), 4.into()]))
+        );
+        let err = json_object.pointer_set("/list/6", JsonValue::from(6), true).unwrap_err();
+        assert_eq!(err.downcast_ref::<JsonError>().unwrap().err_kind, JsonErrorKind::KeyNotFound);
+        assert!(err.to_string().contains("segment:6 | pointer:/list/6"));
+
+        // 途中のオブジェクトを作る
+        let err = json_object.pointer_set("/x/y/z", JsonValue::ValueNull, false).unwrap_err();
+        assert!(err.to_string().contains("segment:x"));
+        assert_eq!(json_object.pointer_set("/x/y/z", JsonValue::ValueNull, true)?, None);
+        assert_eq!(
+            json_object.pointer("/x/y"),
+            Some(JsonValue::from(JsonObject::parse(r#"{"z": null}"#)?))
+        );
+        assert_eq!(json_object.pointer_set("/list/-/k", JsonValue::from(true), true)?, None);
+        assert_eq!(json_object.pointer("/list/4/k"), Some(JsonValue::from(true)));
+
+        // スカラーの中は指せない
+        let err = json_object.pointer_set("/scalar/0", JsonValue::ValueNull, true).unwrap_err();
+        assert!(err.to_string().contains("Value is not an object or an array."));
+        assert!(json_object.pointer_set("", JsonValue::ValueNull, true).is_err());
+
+        // 共有されたオブジェクトはどちらからも変更が見える
+        let refcell_json_object = json_object.get_object("a").unwrap();
+        let mut other_object = JsonObject::new();
+        other_object.insert("shared", JsonValue::ValueObject(refcell_json_object.clone()));
+        other_object.pointer_set("/shared/b", JsonValue::from("changed"), false)?;
+        assert_eq!(json_object.pointer("/a/b"), Some(JsonValue::from("changed")));
+        assert_eq!(refcell_json_object.read_object().get_str("b"), Some("changed"));
+        Ok(())
+    }
+
+    #[test]
+    fn get_path() -> Result<()> {
+        let mut json_object = JsonObject::parse(
+            r#"{"servers": [{}, {}, {"tls": {"cert": "c.pem"}}], "weird.key": 1, "a[b": {"c]": 2}, "it's": 3}"#,
+        )?;
+        assert_eq!(json_object.get_path("servers[2].tls.cert")?, Some(JsonValue::from("c.pem")));
+        assert_eq!(json_object.get_path("servers[3].tls")?, None);
+        assert_eq!(json_object.get_path("servers.tls")?, None);
+        assert_eq!(json_object.get_path(r#"["weird.key"]"#)?, Some(JsonValue::from(1)));
+        assert_eq!(json_object.get_path("weird.key")?, None);
+        assert_eq!(json_object.get_path(r#"['a[b']["c]"]"#)?, Some(JsonValue::from(2)));
+        assert_eq!(json_object.get_path(r#"["it's"]"#)?, Some(JsonValue::from(3)));
+        assert_eq!(json_object.get_path(r#"['it\'s']"#)?, Some(JsonValue::from(3)));
+        assert_eq!(json_object.get_path("")?, Some(JsonValue::from(json_object.clone())));
+
+        // 書式の誤りは位置付きのエラー
+        for (path, detail) in [
+            (r#"["weird.key"#, "Quote is not closed. | column:2"),
+            ("servers[2", "Bracket is not closed. | column:8"),
+            ("servers[]", "Index is empty. | column:9"),
+            ("servers[x]", "Index is not a number. | column:9"),
+            ("servers[2x]", "Expected ']'. | column:10"),
+            ("servers[2]tls", "Expected '.' or '[' after the segment. | column:11"),
+            ("servers..tls", "Key is empty. | column:9"),
+            ("a]b", "Key contains a bracket or a quote. | column:2"),
+        ] {
+            let err = json_object.get_path(path).unwrap_err();
+            assert_eq!(
+                err.downcast_ref::<JsonError>().unwrap().err_kind,
+                JsonErrorKind::ParseErrorInKey
+            );
+            assert!(err.to_string().contains(detail), "{}", err);
+        }
+
+        assert_eq!(
+            json_object.set_path("servers[2].tls.cert", JsonValue::from("d.pem"), false)?,
+            Some(JsonValue::from("c.pem"))
+        );
+        assert_eq!(json_object.set_path(r#"["new.key"].x"#, JsonValue::ValueNull, true)?, None);
+        assert_eq!(json_object.pointer("/new.key/x"), Some(JsonValue::ValueNull));
+        let err = json_object.set_path("servers[5]", JsonValue::ValueNull, true).unwrap_err();
+        assert!(err.to_string().contains("segment:5 | path:servers[5]"));
+        assert!(json_object.set_path("servers[", JsonValue::ValueNull, true).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn from_primitives() -> Result<()> {
+        assert_eq!(JsonValue::from("x"), JsonValue::ValueString("x".to_string()));
+        assert_eq!(JsonValue::from("x".to_string()), JsonValue::ValueString("x".to_string()));
+        assert_eq!(JsonValue::from(-3i64), JsonValue::ValueNumber(JsonNumberType::Int(-3)));
+        assert_eq!(JsonValue::from(-3i32), JsonValue::ValueNumber(JsonNumberType::Int(-3)));
+        assert_eq!(
+            JsonValue::from(u32::MAX),
+            JsonValue::ValueNumber(JsonNumberType::Int(u32::MAX as i64))
+        );
+        assert_eq!(
+            JsonValue::from(i64::MAX as u64),
+            JsonValue::ValueNumber(JsonNumberType::Int(i64::MAX))
+        );
+        assert_eq!(
+            JsonValue::from(u64::MAX),
+            JsonValue::ValueNumber(JsonNumberType::Uint(u64::MAX))
+        );
+        assert_eq!(JsonValue::from(-0.5), JsonValue::ValueNumber(JsonNumberType::Float(-0.5)));
+        assert_eq!(JsonValue::from(false), JsonValue::ValueBool(false));
+        assert_eq!(JsonValue::from(Some(1i32)), JsonValue::ValueNumber(JsonNumberType::Int(1)));
+        assert_eq!(JsonValue::from(Some("x")), JsonValue::ValueString("x".to_string()));
+        assert_eq!(JsonValue::from(None::<i64>), JsonValue::ValueNull);
+
+        let mut json_object = JsonObject::new();
+        json_object.insert("a", 1u32);
+        json_object.insert("b", None::<String>);
+        assert_eq!(json_object.serialize_canonical()?, r#"{"a":1,"b":null}"#);
+        Ok(())
+    }
+
+    #[test]
+    fn from_containers() -> Result<()> {
+        let json_array_value: JsonValue = vec![1, 2, 3].into_iter().collect();
+        assert_eq!(json_array_value, JsonValue::ValueArray(vec![1.into(), 2.into(), 3.into()]));
+        let json_array_value: JsonValue = ["a", "b"].into_iter().map(Some).collect();
+        assert_eq!(json_array_value.serialize_canonical()?, r#"["a","b"]"#);
+
+        // Rc を共有する
+        let json_object_rc = JsonObject::new().into_shared();
+        let json_value = JsonValue::from(json_object_rc.clone());
+        json_object_rc.write_object().insert("k", true);
+        assert_eq!(json_value.serialize_canonical()?, r#"{"k":true}"#);
+
+        let mut json_object = JsonObject::new();
+        json_object.insert("child", JsonObject::new());
+        json_object.insert("list", vec![JsonValue::from(1), JsonValue::ValueNull]);
+        assert_eq!(json_object.serialize_canonical()?, r#"{"child":{},"list":[1,null]}"#);
+        Ok(())
+    }
+
+    #[test]
+    fn index_member() -> Result<()> {
+        let mut json_object = JsonObject::parse(r#"{"port": 80, "hosts": ["a", "b"], "server": {"port": 443}}"#)?;
+        assert_eq!(json_object["port"], JsonValue::from(80));
+        assert_eq!(json_object["hosts"][1], JsonValue::from("b"));
+        // 無いものは null
+        assert_eq!(json_object["missing"], JsonValue::ValueNull);
+        assert_eq!(json_object["hosts"][2], JsonValue::ValueNull);
+        assert_eq!(json_object["port"][0], JsonValue::ValueNull);
+        assert!(!json_object.contains_key("missing"));
+
+        json_object["port"] = 8080.into();
+        json_object["new"] = "x".into();
+        json_object["hosts"][0] = "c".into();
+        assert_eq!(json_object.get_str("new"), Some("x"));
+        assert_eq!(json_object.pointer("/hosts"), Some(vec!["c", "b"].into_iter().collect()));
+        assert_eq!(json_object["port"], JsonValue::from(8080));
+
+        // 子オブジェクトは JsonSharedObject 越しに変更する
+        if let JsonValue::ValueObject(json_child_object) = &json_object["server"] {
+            json_child_object.write_object()["port"] = 8443.into();
+        }
+        assert_eq!(json_object.get_path("server.port")?, Some(JsonValue::from(8443)));
+        // 共有オブジェクトのメンバーは JsonValue 越しには null
+        assert_eq!(json_object["server"]["port"], JsonValue::ValueNull);
+
+        // 所有オブジェクトは連鎖して参照、変更できる
+        let mut json_owned_object = JsonParser::parse_with_options(
+            r#"{"server": {"port": 443, "tls": {"cert": "a.pem"}}, "hosts": [{"name": "a"}]}"#,
+            &JsonParserOptions::new().owned_objects(true),
+        )?;
+        assert_eq!(json_owned_object["server"]["port"], JsonValue::from(443));
+        assert_eq!(json_owned_object["server"]["tls"]["cert"], JsonValue::from("a.pem"));
+        assert_eq!(json_owned_object["hosts"][0]["name"], JsonValue::from("a"));
+        assert_eq!(json_owned_object["server"]["missing"]["port"], JsonValue::ValueNull);
+        assert_eq!(json_owned_object["hosts"]["name"], JsonValue::ValueNull);
+        json_owned_object["server"]["port"] = 8443.into();
+        json_owned_object["server"]["tls"]["key"] = "a.key".into();
+        json_owned_object["hosts"][0]["name"] = "b".into();
+        assert_eq!(json_owned_object.get_path("server.port")?, Some(JsonValue::from(8443)));
+        assert_eq!(json_owned_object.get_path("server.tls.key")?, Some(JsonValue::from("a.key")));
+        assert_eq!(json_owned_object.get_path("hosts[0].name")?, Some(JsonValue::from("b")));
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot index object with \"port\".")]
+    fn index_mut_shared_object() {
+        let mut json_object = JsonObject::parse(r#"{"server": {"port": 443}}"#).unwrap();
+        json_object["server"]["port"] = 8443.into();
+    }
+
+    #[test]
+    #[should_panic(expected = "Index 2 is out of range for the array of length 2.")]
+    fn index_mut_out_of_range() {
+        let mut json_value = JsonValue::from(vec![JsonValue::ValueNull, JsonValue::ValueNull]);
+        json_value[2] = 1.into();
+    }
+
+    #[test]
+    fn value_accessors() -> Result<()> {
+        let mut json_array_value = JsonValue::from(vec![
+            JsonValue::from("s"),
+            JsonValue::from(-2),
+            JsonValue::from(1.0),
+            JsonValue::from(true),
+            JsonValue::ValueNull,
+            JsonValue::from(JsonObject::new()),
+        ]);
+        let json_array = json_array_value.as_array().unwrap();
+        assert_eq!(json_array[0].as_str(), Some("s"));
+        assert_eq!(json_array[1].as_str(), None);
+        assert_eq!(json_array[3].as_bool(), Some(true));
+        assert_eq!(json_array[4].as_bool(), None);
+        assert!(json_array[5].as_object().unwrap().read_object().is_empty());
+        assert!(json_array[0].as_object().is_none());
+        assert!(json_array[0].as_array().is_none());
+
+        // 数値: Int -> f64 は 2^53 まで、Float -> i64 はしない
+        assert_eq!(json_array[1].as_i64(), Some(-2));
+        assert_eq!(json_array[1].as_f64(), Some(-2.0));
+        assert_eq!(json_array[2].as_f64(), Some(1.0));
+        assert_eq!(json_array[2].as_i64(), None);
+        assert_eq!(JsonValue::from(1i64 << 53).as_f64(), Some(9007199254740992.0));
+        assert_eq!(JsonValue::from((1i64 << 53) + 1).as_f64(), None);
+
+        json_array_value.as_array_mut().unwrap().push(JsonValue::from(1));
+        assert_eq!(json_array_value.as_array().unwrap().len(), 7);
+        assert!(JsonValue::ValueNull.as_array_mut().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn iterate_members() -> Result<()> {
+        let mut json_object = JsonObject::parse(r#"{"a": 1, "b": 2, "c": "x"}"#)?;
+        let mut keys: Vec<&str> = json_object.keys().collect();
+        keys.sort();
+        assert_eq!(keys, vec!["a", "b", "c"]);
+        assert_eq!(json_object.values().filter_map(JsonValue::as_i64).sum::<i64>(), 3);
+        assert_eq!(
+            json_object
+                .iter()
+                .filter(|(_, json_value)| json_value.as_str().is_some())
+                .count(),
+            1
+        );
+
+        for (json_key, json_value) in json_object.iter_mut() {
+            if json_key.0 == "a" {
+                *json_value = JsonValue::from(10);
+            }
+        }
+        for json_value in json_object.values_mut() {
+            if let Some(json_string) = json_value.as_str() {
+                *json_value = JsonValue::from(json_string.to_uppercase());
+            }
+        }
+        assert_eq!(json_object.serialize_canonical()?, r#"{"a":10,"b":2,"c":"X"}"#);
+        Ok(())
+    }
+
+    #[test]
+    fn into_iter_members() -> Result<()> {
+        let mut json_object = JsonObject::parse(r#"{"a": 1, "b": [true], "c": {"d": null}}"#)?;
+        let mut count = 0;
+        for (_, json_value) in &json_object {
+            if json_value.as_i64().is_some() {
+                count += 1;
+            }
+        }
+        assert_eq!(count, 1);
+        for (_, json_value) in &mut json_object {
+            if let Some(json_array) = json_value.as_array_mut() {
+                json_array.push(JsonValue::from(false));
+            }
+        }
+
+        let json_members: Vec<(JsonKey, JsonValue)> = json_object.clone().into_iter().collect();
+        assert_eq!(json_members.len(), 3);
+        let mut json_rebuilt_object = JsonObject::new();
+        for (json_key, json_value) in json_members {
+            json_rebuilt_object.insert(json_key.0, json_value);
+        }
+        assert_eq!(json_rebuilt_object, json_object);
+        assert_eq!(
+            json_rebuilt_object.serialize_canonical()?,
+            r#"{"a":1,"b":[true,false],"c":{"d":null}}"#
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn merge_objects() -> Result<()> {
+        // clone() は子オブジェクトを共有するので、毎回 parse する
+        let defaults_str =
+            r#"{"server": {"host": "localhost", "port": 80, "tls": {"enabled": false}}, "plugins": ["a"], "log": "info"}"#;
+        let defaults = JsonObject::parse(defaults_str)?;
+        let env = JsonObject::parse(r#"{"server": {"port": 8080, "tls": {"cert": "c.pem"}}, "plugins": ["b"], "log": null}"#)?;
+        let cli = JsonObject::parse(r#"{"server": {"tls": {"enabled": true}}, "plugins": ["c"], "debug": true}"#)?;
+
+        let mut json_object = JsonObject::parse(defaults_str)?;
+        json_object.merge(&env, JsonMergeStrategy::new());
+        json_object.merge(&cli, JsonMergeStrategy::new());
+        assert_eq!(
+            json_object.serialize_canonical()?,
+            concat!(
+                r#"{"debug":true,"log":null,"plugins":["c"],"#,
+                r#""server":{"host":"localhost","port":8080,"tls":{"cert":"c.pem","enabled":true}}}"#
+            )
+        );
+
+        let strategy = JsonMergeStrategy::new()
+            .array_policy(JsonMergeArrayPolicy::Concat)
+            .ignore_null(true);
+        let mut json_object = JsonObject::parse(defaults_str)?;
+        json_object.merge(&env, strategy);
+        json_object.merge(&cli, strategy);
+        assert_eq!(
+            json_object.serialize_canonical()?,
+            concat!(
+                r#"{"debug":true,"log":"info","plugins":["a","b","c"],"#,
+                r#""server":{"host":"localhost","port":8080,"tls":{"cert":"c.pem","enabled":true}}}"#
+            )
+        );
+
+        // SelfWins: 既存の値は残り、無いものだけ追加される
+        let mut json_object = JsonObject::parse(r#"{"server": {"tls": {"enabled": true}}, "plugins": ["c"], "debug": true}"#)?;
+        json_object.merge(
+            &defaults,
+            JsonMergeStrategy::new().conflict_policy(JsonMergeConflictPolicy::SelfWins),
+        );
+        assert_eq!(
+            json_object.serialize_canonical()?,
+            concat!(
+                r#"{"debug":true,"log":"info","plugins":["c"],"#,
+                r#""server":{"host":"localhost","port":80,"tls":{"enabled":true}}}"#
+            )
+        );
+
+        // 追加された子オブジェクトは other と共有されない
+        let mut json_object = JsonObject::new();
+        json_object.merge(&env, JsonMergeStrategy::new());
+        json_object.get_object("server").unwrap().write_object().insert("port", 1);
+        assert_eq!(env.get_path("server.port")?, Some(JsonValue::from(8080)));
+        Ok(())
+    }
+
+    #[test]
+    fn merge_patch() -> Result<()> {
+        fn value_of(json_str: &str) -> JsonValue {
+            JsonObject::parse(&format!(r#"{{"v": {}}}"#, json_str))
+                .unwrap()
+                .get("v")
+                .unwrap()
+                .clone()
+        }
+        // RFC 7386 Appendix A
+        for (target_str, patch_str, result_str) in [
+            (r#"{"a":"b"}"#, r#"{"a":"c"}"#, r#"{"a":"c"}"#),
+            (r#"{"a":"b"}"#, r#"{"b":"c"}"#, r#"{"a":"b","b":"c"}"#),
+            (r#"{"a":"b"}"#, r#"{"a":null}"#, r#"{}"#),
+            (r#"{"a":"b","b":"c"}"#, r#"{"a":null}"#, r#"{"b":"c"}"#),
+            (r#"{"a":["b"]}"#, r#"{"a":"c"}"#, r#"{"a":"c"}"#),
+            (r#"{"a":"c"}"#, r#"{"a":["b"]}"#, r#"{"a":["b"]}"#),
+            (r#"{"a":{"b":"c"}}"#, r#"{"a":{"b":"d","c":null}}"#, r#"{"a":{"b":"d"}}"#),
+            (r#"{"a":[{"b":"c"}]}"#, r#"{"a":[1]}"#, r#"{"a":[1]}"#),
+            (r#"["a","b"]"#, r#"["c","d"]"#, r#"["c","d"]"#),
+            (r#"{"a":"b"}"#, r#"["c"]"#, r#"["c"]"#),
+            (r#"{"a":"foo"}"#, r#"null"#, r#"null"#),
+            (r#"{"a":"foo"}"#, r#""bar""#, r#""bar""#),
+            (r#"{"e":null}"#, r#"{"a":1}"#, r#"{"a":1,"e":null}"#),
+            (r#"[1,2]"#, r#"{"a":"b","c":null}"#, r#"{"a":"b"}"#),
+            (r#"{}"#, r#"{"a":{"bb":{"ccc":null}}}"#, r#"{"a":{"bb":{}}}"#),
+        ] {
+            let mut json_value = value_of(target_str);
+            json_value.merge_patch(&value_of(patch_str));
+            assert_eq!(
+                json_value.serialize_canonical()?,
+                result_str,
+                "{} + {}",
+                target_str,
+                patch_str
+            );
+        }
+
+        // 手で作ったパッチ
+        let mut json_object = JsonObject::parse(
+            r#"{"title": "Goodbye!", "author": {"givenName": "John", "familyName": "Doe"}, "tags": ["example", "sample"], "content": "This will be unchanged"}"#,
+        )?;
+        let patch = value_of(
+            r#"{"title": "Hello!", "phoneNumber": "+01-123-456-7890", "author": {"familyName": null}, "tags": ["example"]}"#,
+        );
+        json_object.merge_patch(&patch)?;
+        assert_eq!(
+            json_object.serialize_canonical()?,
+            concat!(
+                r#"{"author":{"givenName":"John"},"content":"This will be unchanged","#,
+                r#""phoneNumber":"+01-123-456-7890","tags":["example"],"title":"Hello!"}"#
+            )
+        );
+        // パッチとは共有しない
+        json_object.get_object("author").unwrap().write_object().insert("x", 1);
+        if let JsonValue::ValueObject(refcell_patch_object) = &patch {
+            assert!(!refcell_patch_object
+                .read_object()
+                .get_object("author")
+                .unwrap()
+                .read_object()
+                .contains_key("x"));
+        }
+
+        let err = json_object.merge_patch(&value_of("[1]")).unwrap_err();
+        assert_eq!(err.downcast_ref::<JsonError>().unwrap().err_kind, JsonErrorKind::TypeMismatch);
+        assert_eq!(json_object.len(), 5);
+        Ok(())
+    }
+
+    #[test]
+    fn apply_patch() -> Result<()> {
+        fn patch_of(json_str: &str) -> Vec<JsonValue> {
+            JsonObject::parse(&format!(r#"{{"p": {}}}"#, json_str))
+                .unwrap()
+                .get_array("p")
+                .unwrap()
+                .clone()
+        }
+        // RFC 6902 Appendix A
+        for (target_str, patch_str, result_str) in [
+            (
+                r#"{"foo":"bar"}"#,
+                r#"[{"op":"add","path":"/baz","value":"qux"}]"#,
+                r#"{"baz":"qux","foo":"bar"}"#,
+            ),
+            (
+                r#"{"foo":["bar","baz"]}"#,
+                r#"[{"op":"add","path":"/foo/1","value":"qux"}]"#,
+                r#"{"foo":["bar","qux","baz"]}"#,
+            ),
+            (
+                r#"{"baz":"qux","foo":"bar"}"#,
+                r#"[{"op":"remove","path":"/baz"}]"#,
+                r#"{"foo":"bar"}"#,
+            ),
+            (
+                r#"{"foo":["bar","qux","baz"]}"#,
+                r#"[{"op":"remove","path":"/foo/1"}]"#,
+                r#"{"foo":["bar","baz"]}"#,
+            ),
+            (
+                r#"{"baz":"qux","foo":"bar"}"#,
+                r#"[{"op":"replace","path":"/baz","value":"boo"}]"#,
+                r#"{"baz":"boo","foo":"bar"}"#,
+            ),
+            (
+                r#"{"foo":{"bar":"baz","waldo":"fred"},"qux":{"corge":"grault"}}"#,
+                r#"[{"op":"move","from":"/foo/waldo","path":"/qux/thud"}]"#,
+                r#"{"foo":{"bar":"baz"},"qux":{"corge":"grault","thud":"fred"}}"#,
+            ),
+            (
+                r#"{"foo":["all","grass","cows","eat"]}"#,
+                r#"[{"op":"move","from":"/foo/1","path":"/foo/3"}]"#,
+                r#"{"foo":["all","cows","eat","grass"]}"#,
+            ),
+            (
+                r#"{"baz":"qux","foo":["a",2,"c"]}"#,
+                r#"[{"op":"test","path":"/baz","value":"qux"},{"op":"test","path":"/foo/1","value":2}]"#,
+                r#"{"baz":"qux","foo":["a",2,"c"]}"#,
+            ),
+            (
+                r#"{"foo":"bar"}"#,
+                r#"[{"op":"add","path":"/child","value":{"grandchild":{}}}]"#,
+                r#"{"child":{"grandchild":{}},"foo":"bar"}"#,
+            ),
+            (
+                r#"{"foo":"bar"}"#,
+                r#"[{"op":"add","path":"/baz","value":"qux","xyz":123}]"#,
+                r#"{"baz":"qux","foo":"bar"}"#,
+            ),
+            (
+                r#"{"/":9,"~1":10}"#,
+                r#"[{"op":"test","path":"/~01","value":10}]"#,
+                r#"{"/":9,"~1":10}"#,
+            ),
+            (
+                r#"{"foo":["bar"]}"#,
+                r#"[{"op":"add","path":"/foo/-","value":["abc","def"]}]"#,
+                r#"{"foo":["bar",["abc","def"]]}"#,
+            ),
+            // 配列の端
+            (
+                r#"{"a":[1,2]}"#,
+                r#"[{"op":"add","path":"/a/2","value":3}]"#,
+                r#"{"a":[1,2,3]}"#,
+            ),
+            (
+                r#"{"a":[1,2]}"#,
+                r#"[{"op":"add","path":"/a/0","value":0}]"#,
+                r#"{"a":[0,1,2]}"#,
+            ),
+            (
+                r#"{"a":[1,2]}"#,
+                r#"[{"op":"copy","from":"/a","path":"/a/-"}]"#,
+                r#"{"a":[1,2,[1,2]]}"#,
+            ),
+            (r#"{"a":1}"#, r#"[{"op":"replace","path":"","value":{"b":2}}]"#, r#"{"b":2}"#),
+        ] {
+            let mut json_object = JsonObject::parse(target_str)?;
+            json_object.apply_patch(&patch_of(patch_str))?;
+            assert_eq!(json_object.serialize_canonical()?, result_str, "{}", patch_str);
+        }
+
+        for (target_str, patch_str, err_kind, detail) in [
+            (
+                r#"{"baz":"qux"}"#,
+                r#"[{"op":"test","path":"/baz","value":"bar"}]"#,
+                JsonErrorKind::PatchTestFailed,
+                "pointer:/baz | operation:0:test",
+            ),
+            (
+                r#"{"foo":"bar"}"#,
+                r#"[{"op":"add","path":"/baz/bat","value":"qux"}]"#,
+                JsonErrorKind::KeyNotFound,
+                "segment:baz | pointer:/baz/bat",
+            ),
+            (
+                r#"{"/":9,"~1":10}"#,
+                r#"[{"op":"test","path":"/~01","value":"10"}]"#,
+                JsonErrorKind::PatchTestFailed,
+                "operation:0:test",
+            ),
+            (
+                r#"{"a":[1,2]}"#,
+                r#"[{"op":"add","path":"/a/3","value":3}]"#,
+                JsonErrorKind::KeyNotFound,
+                "segment:3",
+            ),
+            (
+                r#"{"a":[1,2]}"#,
+                r#"[{"op":"add","path":"/a/01","value":3}]"#,
+                JsonErrorKind::KeyNotFound,
+                "segment:01",
+            ),
+            (
+                r#"{"a":[1,2]}"#,
+                r#"[{"op":"remove","path":"/a/-"}]"#,
+                JsonErrorKind::KeyNotFound,
+                "segment:-",
+            ),
+            (
+                r#"{"a":[1,2]}"#,
+                r#"[{"op":"replace","path":"/a/2","value":3}]"#,
+                JsonErrorKind::KeyNotFound,
+                "segment:2",
+            ),
+            (
+                r#"{"a":{"b":1}}"#,
+                r#"[{"op":"move","from":"/a","path":"/a/b/c"}]"#,
+                JsonErrorKind::InvalidPatch,
+                "moved into itself",
+            ),
+            (
+                r#"{"a":1}"#,
+                r#"[{"op":"jump","path":"/a"}]"#,
+                JsonErrorKind::InvalidPatch,
+                "operation:0:jump",
+            ),
+            (
+                r#"{"a":1}"#,
+                r#"[{"op":"add","path":"/b"}]"#,
+                JsonErrorKind::InvalidPatch,
+                "\"value\"",
+            ),
+            (r#"{"a":1}"#, r#"[1]"#, JsonErrorKind::InvalidPatch, "operation:0"),
+            // 途中で失敗すると何も変わらない
+            (
+                r#"{"a":1}"#,
+                r#"[{"op":"add","path":"/b","value":2},{"op":"remove","path":"/c"}]"#,
+                JsonErrorKind::KeyNotFound,
+                "pointer:/c | operation:1:remove",
+            ),
+        ] {
+            let mut json_object = JsonObject::parse(target_str)?;
+            let err = json_object.apply_patch(&patch_of(patch_str)).unwrap_err();
+            assert_eq!(err.downcast_ref::<JsonError>().unwrap().err_kind, err_kind, "{}", patch_str);
+            assert!(err.to_string().contains(detail), "{}", err);
+            assert_eq!(json_object, JsonObject::parse(target_str)?);
+        }
+
+        // 共有しているオブジェクトはその場で変更されるので、他の場所からも変更が見える
+        let json_server_object = JsonObject::parse(r#"{"port": 80, "hosts": ["a"]}"#)?.into_shared();
+        let mut json_object = JsonObject::new();
+        json_object.insert("server", JsonValue::ValueObject(json_server_object.clone()));
+        json_object.insert("alias", JsonValue::ValueObject(json_server_object.clone()));
+        json_object.apply_patch(&patch_of(
+            r#"[{"op":"replace","path":"/server/port","value":443},{"op":"add","path":"/server/hosts/-","value":"b"},
+                {"op":"test","path":"/alias/port","value":443}]"#,
+        ))?;
+        assert_eq!(
+            json_server_object.read_object().serialize_canonical()?,
+            r#"{"hosts":["a","b"],"port":443}"#
+        );
+        assert!(matches!(&json_object["server"], JsonValue::ValueObject(json_child_object)
+            if crate::shared::ptr_eq(json_child_object, &json_server_object)));
+
+        // 途中で失敗すると、その場で変更したものも含めて元に戻る
+        for patch_str in [
+            r#"[{"op":"add","path":"/server/tls","value":true},{"op":"remove","path":"/server/port"},
+                {"op":"add","path":"/server/hosts/0","value":"z"},{"op":"replace","path":"/server/hosts/1","value":"y"},
+                {"op":"move","from":"/server/hosts/2","path":"/server/port"},{"op":"copy","from":"/alias","path":"/copy"},
+                {"op":"test","path":"/server/missing","value":1}]"#,
+            r#"[{"op":"move","from":"/server","path":""},{"op":"test","path":"/port","value":0}]"#,
+            r#"[{"op":"replace","path":"","value":{}},{"op":"remove","path":"/server"}]"#,
+        ] {
+            assert!(json_object.apply_patch(&patch_of(patch_str)).is_err());
+            assert_eq!(
+                json_server_object.read_object().serialize_canonical()?,
+                r#"{"hosts":["a","b"],"port":443}"#
+            );
+            assert_eq!(json_object.len(), 2);
+            for key_str in ["server", "alias"] {
+                assert!(matches!(&json_object[key_str], JsonValue::ValueObject(json_child_object)
+                    if crate::shared::ptr_eq(json_child_object, &json_server_object)));
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn diff_objects() -> Result<()> {
+        let json_object = JsonObject::parse(r#"{"a": 1, "b": {"c": [1, 2, 3]}, "d/e": "x"}"#)?;
+        let json_other_object = JsonObject::parse(r#"{"a": 2, "b": {"c": [1, 5]}, "f~": null}"#)?;
+        let patch = json_object.diff(&json_other_object);
+        let patch_strings: Vec<String> = patch
+            .iter()
+            .map(|json_op_value| json_op_value.serialize_canonical().unwrap())
+            .collect();
+        assert_eq!(
+            patch_strings,
+            vec![
+                r#"{"op":"replace","path":"/a","value":2}"#,
+                r#"{"op":"replace","path":"/b/c/1","value":5}"#,
+                r#"{"op":"remove","path":"/b/c/2"}"#,
+                r#"{"op":"remove","path":"/d~1e"}"#,
+                r#"{"op":"add","path":"/f~0","value":null}"#,
+            ]
+        );
+        assert!(json_object.diff(&json_object).is_empty());
+
+        // diff を当てると等しくなる
+        let fixtures = [
+            r#"{}"#,
+            r#"{"a": 1, "b": [1, 2, {"c": true}], "d": {"e": {"f": "g"}}}"#,
+            r#"{"a": 1.0, "b": [{"c": false}], "d": {"e": [], "h": null}, "i": {"j": 1}}"#,
+            r#"{"a": [[1], [2, 3]], "b": "b", "d": "e", "~/": {}}"#,
+            r#"{"b": [1, 2, {"c": true}, 4, 5], "d": {"e": {"f": "h", "g": {"k": [null]}}}}"#,
+        ];
+        for from_str in fixtures {
+            for to_str in fixtures {
+                let json_from_object = JsonObject::parse(from_str)?;
+                let json_to_object = JsonObject::parse(to_str)?;
+                let mut json_object = JsonObject::parse(from_str)?;
+                json_object.apply_patch(&json_from_object.diff(&json_to_object))?;
+                assert_eq!(json_object, json_to_object, "{} -> {}", from_str, to_str);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn deep_eq() -> Result<()> {
+        let strict = JsonEqOptions::new();
+        let coerced = JsonEqOptions::new().numeric_coercion(true);
+        let tolerant = JsonEqOptions::new().float_epsilon(Some(0.25));
+
+        assert!(JsonValue::from(2).deep_eq(&JsonValue::from(2), &strict));
+        assert!(!JsonValue::from(2).deep_eq(&JsonValue::from(2.0), &strict));
+        assert!(JsonValue::from(2).deep_eq(&JsonValue::from(2.0), &coerced));
+        assert!(JsonValue::from(2.0).deep_eq(&JsonValue::from(2), &coerced));
+        assert!(!JsonValue::from(2).deep_eq(&JsonValue::from(2.5), &coerced));
+        assert!(!JsonValue::from(2).deep_eq(&JsonValue::from("2"), &coerced));
+
+        // epsilon の境界は含む
+        assert!(JsonValue::from(1.0).deep_eq(&JsonValue::from(1.25), &tolerant));
+        assert!(!JsonValue::from(1.0).deep_eq(&JsonValue::from(1.375), &tolerant));
+        assert!(!JsonValue::from(1).deep_eq(&JsonValue::from(1.25), &tolerant));
+        assert!(JsonValue::from(1).deep_eq(&JsonValue::from(1.25), &tolerant.numeric_coercion(true)));
+        // Int 同士は常に厳密
+        assert!(!JsonValue::from(1).deep_eq(&JsonValue::from(2), &JsonEqOptions::new().float_epsilon(Some(10.0))));
+        let ulp_noise = 0.1 + 0.2;
+        assert!(!JsonValue::from(ulp_noise).deep_eq(&JsonValue::from(0.3), &strict));
+        assert!(
+            JsonValue::from(ulp_noise).deep_eq(&JsonValue::from(0.3), &JsonEqOptions::new().float_epsilon(Some(f64::EPSILON)))
+        );
+
+        // NaN は何とも等しくない、無限大は同じ符号なら等しい
+        assert!(!JsonValue::from(f64::NAN).deep_eq(&JsonValue::from(f64::NAN), &tolerant));
+        assert!(JsonValue::from(f64::INFINITY).deep_eq(&JsonValue::from(f64::INFINITY), &tolerant));
+        assert!(!JsonValue::from(f64::INFINITY).deep_eq(&JsonValue::from(f64::NEG_INFINITY), &tolerant));
+
+        // ネストした配列とオブジェクト、メンバーの順序は関係ない
+        let json_object = JsonObject::parse(r#"{"a": [1, {"b": 2.0, "c": [3]}], "d": {"e": null, "f": 0.5}}"#)?;
+        let json_other_object = JsonObject::parse(r#"{"d": {"f": 0.5000001, "e": null}, "a": [1.0, {"c": [3.0], "b": 2}]}"#)?;
+        assert!(!json_object.deep_eq(&json_other_object, &strict));
+        assert!(!json_object.deep_eq(&json_other_object, &coerced));
+        assert!(json_object.deep_eq(&json_other_object, &coerced.float_epsilon(Some(1e-6))));
+        assert!(json_object.deep_eq(&json_object, &strict));
+        let json_short_object = JsonObject::parse(r#"{"a": [1, {"b": 2.0, "c": [3]}]}"#)?;
+        assert!(!json_object.deep_eq(&json_short_object, &coerced));
+        assert!(!json_short_object.deep_eq(&json_object, &coerced));
+        Ok(())
+    }
+
+    #[test]
+    fn json_object_macro() -> Result<()> {
+        let port = 8000;
+        let json_object = json_object! {
+            "name": "svc",
+            "port": port + 80,
+            "ratio": -0.5,
+            "offset": -1,
+            "tags": ["a", "b", format!("{}", 'c'),],
+            "tls": { "enabled": true, "cert": null, },
+            "nested": [[], {}, [null, {"x": Some(1)}]],
+            "none": None::<i64>,
+        };
+        let json_parsed_object = JsonObject::parse(
+            r#"{"name": "svc", "port": 8080, "ratio": -0.5, "offset": -1, "tags": ["a", "b", "c"],
+                "tls": {"enabled": true, "cert": null}, "nested": [[], {}, [null, {"x": 1}]], "none": null}"#,
+        )?;
+        assert_eq!(json_object, json_parsed_object);
+        assert!(matches!(json_object.get("tls"), Some(JsonValue::ValueObject(_))));
+        assert_eq!(json_object! {}, JsonObject::new());
+        assert_eq!(
+            json_value!([1, "a"]),
+            JsonValue::from(vec![JsonValue::from(1), JsonValue::from("a")])
+        );
+        assert_eq!(json_value!(null), JsonValue::ValueNull);
+        Ok(())
+    }
+
+    #[test]
+    fn flatten_unflatten() -> Result<()> {
+        let json_object = JsonObject::parse(r#"{"a": {"b": [1, 2]}, "c": "x"}"#)?;
+        let flat_map = json_object.flatten(".");
+        let mut flat_keys: Vec<&String> = flat_map.keys().collect();
+        flat_keys.sort();
+        assert_eq!(flat_keys, vec!["a.b[0]", "a.b[1]", "c"]);
+        assert_eq!(flat_map["a.b[1]"], JsonValue::from(2));
+
+        // 往復
+        for json_str in [
+            r#"{}"#,
+            r#"{"a": {"b": [1, {"c": [[true, null], []]}], "d": {}}, "e": 1.5}"#,
+            r#"{"weird.key": {"x[0]": 1, "back\\slash": 2, "": {"]": 3}}, "list": [{"a.b": [null]}]}"#,
+        ] {
+            let json_object = JsonObject::parse(json_str)?;
+            for separator in [".", "__", "/"] {
+                let json_rebuilt_object = JsonObject::unflatten(&json_object.flatten(separator), separator)?;
+                assert_eq!(json_rebuilt_object, json_object, "{} {}", json_str, separator);
+            }
+        }
+
+        // "." を含むキーはエスケープされる
+        let json_object = JsonObject::parse(r#"{"weird.key": {"a[1]": true}}"#)?;
+        let flat_map = json_object.flatten(".");
+        assert_eq!(flat_map.keys().collect::<Vec<_>>(), vec![r"weird\.key.a\[1\]"]);
+        let flat_map = json_object.flatten("__");
+        assert_eq!(flat_map.keys().collect::<Vec<_>>(), vec![r"weird.key__a\[1\]"]);
+
+        // 区切り文字の一部を含むキーも、区切り文字の文字をすべてエスケープして往復する
+        let json_object = JsonObject::parse(r#"{"a:": {"b": 1}}"#)?;
+        let flat_map = json_object.flatten("::");
+        assert_eq!(flat_map.keys().collect::<Vec<_>>(), vec![r"a\:::b"]);
+        assert_eq!(JsonObject::unflatten(&flat_map, "::")?, json_object);
+        let json_object = JsonObject::parse(
+            r#"{"a:": {":b": 1, "c": {":": [{"::": 2}]}}, ":::": 3, "x_": {"_y": {"_": 4}}, "m:n": {"o::p": 5}}"#,
+        )?;
+        for separator in ["::", "__", ":_", "_:_"] {
+            let json_rebuilt_object = JsonObject::unflatten(&json_object.flatten(separator), separator)?;
+            assert_eq!(json_rebuilt_object, json_object, "{}", separator);
+        }
+
+        let flat_map_of = |flat_keys: &[&str]| -> HashMap<String, JsonValue> {
+            flat_keys
+                .iter()
+                .map(|flat_key| (flat_key.to_string(), JsonValue::from(1)))
+                .collect()
+        };
+        for (flat_keys, detail) in [
+            (vec!["a", "a.b"], "Key is both a leaf and a prefix. | key:a.b"),
+            (vec!["a[0]", "a[0].b"], "Key is both a leaf and a prefix. | key:a[0].b"),
+            (vec!["a[0]", "a.b"], "Keys and indexes are mixed. | key:a[0]"),
+            (vec!["a[0]", "a[2]"], "Array does not have the index 1. | key:a"),
+            (vec!["a[x]"], "Index is not a number."),
+            (vec!["a[0"], "Bracket is not closed."),
+            (vec!["a[0]b"], "Expected the separator or '[' after the index."),
+            (vec!["a]"], "']' is not escaped."),
+            (vec![r"a\"], "'\\' is not followed by a character."),
+        ] {
+            let err = JsonObject::unflatten(&flat_map_of(&flat_keys), ".").unwrap_err();
+            assert_eq!(
+                err.downcast_ref::<JsonError>().unwrap().err_kind,
+                JsonErrorKind::ParseErrorInKey
+            );
+            assert!(err.to_string().contains(detail), "{}", err);
+        }
+        assert_eq!(
+            JsonObject::unflatten(&flat_map_of(&["a[1]", "a[0].b", "c.d[0][0]"]), ".")?.serialize_canonical()?,
+            r#"{"a":[{"b":1},1],"c":{"d":[[1]]}}"#
+        );
+        assert!(JsonObject::unflatten(&flat_map_of(&["a"]), "").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn find_all() -> Result<()> {
+        let json_object = JsonObject::parse(
+            r#"{"id": 1, "a": {"id": 2, "b": {"c": {"id": 3}}}, "list": [{"id": 4}, [{"x": {"id": 5}}], "id"], "a/b": {"id": 6}}"#,
+        )?;
+        let found_values = json_object.find_all("id");
+        assert_eq!(
+            found_values,
+            vec![
+                ("/a/b/c/id".to_string(), JsonValue::from(3)),
+                ("/a/id".to_string(), JsonValue::from(2)),
+                ("/a~1b/id".to_string(), JsonValue::from(6)),
+                ("/id".to_string(), JsonValue::from(1)),
+                ("/list/0/id".to_string(), JsonValue::from(4)),
+                ("/list/1/0/x/id".to_string(), JsonValue::from(5)),
+            ]
+        );
+        for (pointer, json_value) in &found_values {
+            assert_eq!(json_object.pointer(pointer).as_ref(), Some(json_value));
+        }
+        assert!(json_object.find_all("missing").is_empty());
+
+        // 共有されたオブジェクトはそれぞれの場所で見つかり、循環は止まる
+        let json_child_object = (json_object! {"id": 7}).into_shared();
+        json_child_object.write_object().insert("loop", json_child_object.clone());
+        let mut json_object = JsonObject::new();
+        json_object.insert("first", json_child_object.clone());
+        json_object.insert("second", vec![JsonValue::from(json_child_object.clone())]);
+        let found_pointers: Vec<String> = json_object.find_all("id").into_iter().map(|(pointer, _)| pointer).collect();
+        assert_eq!(found_pointers, vec!["/first/id", "/second/0/id"]);
+        assert_eq!(json_object.find_all("loop").len(), 2);
+        // 循環を切って解放する
+        json_child_object.write_object().remove("loop");
+        Ok(())
+    }
+
+    #[test]
+    fn query_jsonpath() -> Result<()> {
+        let json_object = JsonObject::parse(
+            r#"{"store": {
+                "book": [
+                    {"category": "reference", "author": "Nigel Rees", "title": "Sayings of the Century", "price": 8.95, "available": true},
+                    {"category": "fiction", "author": "Evelyn Waugh", "title": "Sword of Honour", "price": 12.99, "available": false},
+                    {"category": "fiction", "author": "Herman Melville", "title": "Moby Dick", "isbn": "0-553-21311-3", "price": 8.99, "available": true},
+                    {"category": "fiction", "author": "J. R. R. Tolkien", "title": "The Lord of the Rings", "isbn": "0-395-19395-8", "price": 22.99, "available": true}
+                ],
+                "bicycle": {"color": "red", "price": 19.95}
+            }}"#,
+        )?;
+        let query_strings = |query_str: &str| -> Vec<String> {
+            json_object
+                .query(query_str)
+                .unwrap()
+                .iter()
+                .map(|json_value| json_value.serialize_canonical().unwrap())
+                .collect()
+        };
+        let authors = vec![
+            r#""Nigel Rees""#,
+            r#""Evelyn Waugh""#,
+            r#""Herman Melville""#,
+            r#""J. R. R. Tolkien""#,
+        ];
+        assert_eq!(query_strings("$.store.book[*].author"), authors);
+        assert_eq!(query_strings("$..author"), authors);
+        assert_eq!(query_strings("$['store'][\"book\"][*]['author']"), authors);
+        assert_eq!(query_strings("$.store.*").len(), 2);
+        assert_eq!(
+            query_strings("$.store..price"),
+            vec!["19.95", "8.95", "12.99", "8.99", "22.99"]
+        );
+        assert_eq!(query_strings("$..book[2].title"), vec![r#""Moby Dick""#]);
+        assert_eq!(query_strings("$..book[-1].title"), vec![r#""The Lord of the Rings""#]);
+        assert!(query_strings("$..book[4]").is_empty());
+        assert!(query_strings("$..book[-5]").is_empty());
+        assert_eq!(
+            query_strings("$..book[?(@.isbn)].title"),
+            vec![r#""Moby Dick""#, r#""The Lord of the Rings""#]
+        );
+        assert_eq!(query_strings("$..book[?(@.price < 10)].price"), vec!["8.95", "8.99"]);
+        assert_eq!(query_strings("$..book[?(@.price >= 12.99)].price"), vec!["12.99", "22.99"]);
+        assert_eq!(
+            query_strings("$..book[?(@.available == false)].title"),
+            vec![r#""Sword of Honour""#]
+        );
+        assert_eq!(
+            query_strings("$..book[ ?( @.category != 'fiction' ) ].title"),
+            vec![r#""Sayings of the Century""#]
+        );
+        assert_eq!(query_strings("$..[?(@.color == \"red\")].price"), vec!["19.95"]);
+        assert_eq!(
+            query_strings("$..book[0][?(@ > 'N')]"),
+            vec![r#""Nigel Rees""#, r#""reference""#, r#""Sayings of the Century""#]
+        );
+        assert_eq!(query_strings("$").len(), 1);
+        assert_eq!(query_strings("$.store.bicycle").len(), 1);
+        assert!(query_strings("$.missing.price").is_empty());
+
+        for (query_str, detail) in [
+            ("store", "Query does not start with '$'. | column:1"),
+            ("$.store[", "Bracket is not closed. | column:8"),
+            ("$.store['book", "Quote is not closed. | column:9"),
+            ("$.store[0", "Bracket is not closed. | column:8"),
+            ("$.store[0 1]", "Expected ']'. | column:11"),
+            ("$.store[x]", "Expected a name, an index, '*' or a filter. | column:9"),
+            ("$.", "Name is empty. | column:3"),
+            ("$store", "Expected '.' or '['. | column:2"),
+            ("$..book[?@.isbn]", "Expected '(' after '?'. | column:10"),
+            ("$..book[?(isbn)]", "Filter does not start with '@'. | column:11"),
+            ("$..book[?(@.price = 1)]", "Expected an operator. | column:19"),
+            (
+                "$..book[?(@.price < ten)]",
+                "Literal is not a string, a number, a bool or null. | column:21",
+            ),
+            ("$..book[?(@.price < 1]", "Expected ')'. | column:22"),
+        ] {
+            let err = json_object.query(query_str).unwrap_err();
+            assert_eq!(
+                err.downcast_ref::<JsonError>().unwrap().err_kind,
+                JsonErrorKind::ParseErrorInKey
+            );
+            assert!(err.to_string().contains(detail), "{}", err);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn validate_schema() -> Result<()> {
+        let schema = JsonObject::parse(
+            r#"{
+                "type": "object",
+                "required": ["name", "servers"],
+                "properties": {
+                    "name": {"type": "string", "minLength": 1, "maxLength": 8},
+                    "mode": {"enum": ["dev", "prod"]},
+                    "servers": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "required": ["port"],
+                            "properties": {
+                                "port": {"type": "integer", "minimum": 1, "maximum": 65535},
+                                "host": {"type": ["string", "null"]}
+                            }
+                        }
+                    },
+                    "ratio": {"type": "number", "x-unknown": true}
+                },
+                "description": "ignored"
+            }"#,
+        )?;
+        let json_object = JsonObject::parse(
+            r#"{"name": "svc", "mode": "prod", "servers": [{"port": 80, "host": null}, {"port": 443.0, "host": "a"}], "ratio": 1}"#,
+        )?;
+        assert_eq!(json_object.validate_schema(&schema), Ok(()));
+
+        let json_object = JsonObject::parse(
+            r#"{"name": "too-long-name", "mode": "test", "servers": [{"port": 70000}, {"host": 1}, "x"], "ratio": "1"}"#,
+        )?;
+        let violations = json_object.validate_schema(&schema).unwrap_err();
+        let violation_strings: Vec<String> = violations.iter().map(|violation| violation.to_string()).collect();
+        assert_eq!(
+            violation_strings,
+            vec![
+                r#"/mode: "test" is not one of "dev", "prod". (enum)"#,
+                "/name: String of 13 characters is longer than 8. (maxLength)",
+                "/ratio: Expected number, found string. (type)",
+                "/servers/0/port: 70000 is greater than 65535. (maximum)",
+                r#"/servers/1: Required member "port" is missing. (required)"#,
+                "/servers/1/host: Expected string or null, found integer. (type)",
+                "/servers/2: Expected object, found string. (type)",
+            ]
+        );
+        assert_eq!(violations[3].instance_path, "/servers/0/port");
+        assert_eq!(violations[3].keyword, "maximum");
+
+        let violations = JsonObject::new().validate_schema(&schema).unwrap_err();
+        assert_eq!(violations.len(), 2);
+        assert!(violations
+            .iter()
+            .all(|violation| violation.instance_path.is_empty() && violation.keyword == "required"));
+        assert_eq!(
+            JsonObject::parse(r#"{"name": ""}"#)?
+                .validate_schema(&JsonObject::parse(r#"{"properties": {"name": {"minLength": 1}}}"#)?)
+                .unwrap_err()[0]
+                .keyword,
+            "minLength"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn number_conversions() {
+        let two_53 = 2f64.powi(53);
+        assert_eq!(JsonNumberType::Int(1 << 53).as_f64(), two_53);
+        assert_eq!(JsonNumberType::Float(two_53).as_i64(), Some(1 << 53));
+        assert_eq!(JsonNumberType::Int(i64::MAX).as_i64(), Some(i64::MAX));
+        assert_eq!(JsonNumberType::Float(i64::MIN as f64).as_i64(), Some(i64::MIN));
+        // 2^63 は i64 に収まらない
+        assert_eq!(JsonNumberType::Float(i64::MAX as f64).as_i64(), None);
+        assert_eq!(JsonNumberType::Float(1.5).as_i64(), None);
+        assert_eq!(JsonNumberType::Float(f64::NAN).as_i64(), None);
+        assert_eq!(JsonNumberType::Float(f64::INFINITY).as_i64(), None);
+        assert_eq!(JsonNumberType::Float(-0.0).as_i64(), Some(0));
+
+        assert!(JsonNumberType::Int(-3).is_integral());
+        assert!(JsonNumberType::Float(-0.0).is_integral());
+        assert!(!JsonNumberType::Float(0.5).is_integral());
+        assert!(!JsonNumberType::Float(f64::NAN).is_integral());
+        assert!(!JsonNumberType::Float(f64::NEG_INFINITY).is_integral());
+
+        assert_eq!(JsonNumberType::Float(-4.0).try_into_i64().unwrap(), -4);
+        let err = JsonNumberType::Float(f64::NAN).try_into_i64().unwrap_err();
+        assert_eq!(err.downcast_ref::<JsonError>().unwrap().err_kind, JsonErrorKind::OutOfRange);
+
+        assert_eq!(JsonNumberType::Int(i64::MAX).try_into_u64().unwrap(), i64::MAX as u64);
+        assert_eq!(JsonNumberType::Float(i64::MAX as f64).try_into_u64().unwrap(), 1 << 63);
+        assert_eq!(JsonNumberType::Float(-0.0).try_into_u64().unwrap(), 0);
+        for json_number in [
+            JsonNumberType::Int(-1),
+            JsonNumberType::Int(i64::MIN),
+            JsonNumberType::Float(-1.0),
+            JsonNumberType::Float(2f64.powi(64)),
+            JsonNumberType::Float(f64::NAN),
+        ] {
+            let err = json_number.try_into_u64().unwrap_err();
+            assert_eq!(err.downcast_ref::<JsonError>().unwrap().err_kind, JsonErrorKind::OutOfRange);
+        }
+    }
+
+    #[test]
+    fn uint_numbers() {
+        let json_object =
+            JsonObject::parse(r#"{"max": 18446744073709551615, "above": 9223372036854775808, "i64": 9223372036854775807}"#)
+                .unwrap();
+        assert_eq!(json_object["max"], JsonValue::ValueNumber(JsonNumberType::Uint(u64::MAX)));
+        assert_eq!(
+            json_object["above"],
+            JsonValue::ValueNumber(JsonNumberType::Uint(i64::MAX as u64 + 1))
+        );
+        assert_eq!(json_object["i64"], JsonValue::ValueNumber(JsonNumberType::Int(i64::MAX)));
+        assert_eq!(json_object["max"].as_u64(), Some(u64::MAX));
+        assert_eq!(json_object["max"].as_i64(), None);
+        assert_eq!(json_object["i64"].as_u64(), Some(i64::MAX as u64));
+        let json_string = json_object.to_compact_string().unwrap();
+        assert!(json_string.contains("\"max\":18446744073709551615"));
+        assert!(json_string.contains("\"above\":9223372036854775808"));
+        assert_eq!(JsonObject::parse(&json_string).unwrap()["max"], json_object["max"]);
+
+        // u64 を超える整数と負の数はエラー
+        for json_str in [r#"{"v": 18446744073709551616}"#, r#"{"v": -9223372036854775809}"#] {
+            let err = JsonObject::parse(json_str).unwrap_err();
+            assert_eq!(
+                err.downcast_ref::<JsonError>().unwrap().err_kind,
+                JsonErrorKind::ParseErrorInNumber
+            );
+        }
+
+        let json_uint = JsonNumberType::Uint(u64::MAX);
+        assert_eq!(json_uint.as_i64(), None);
+        assert_eq!(json_uint.try_into_u64().unwrap(), u64::MAX);
+        assert!(json_uint.is_integral());
+        assert_eq!(json_uint.as_f64(), u64::MAX as f64);
+        let err = json_uint.try_into_i64().unwrap_err();
+        assert_eq!(err.downcast_ref::<JsonError>().unwrap().err_kind, JsonErrorKind::OutOfRange);
+
+        let eq_options = JsonEqOptions::new();
+        assert!(json_object["max"].deep_eq(&JsonValue::from(u64::MAX), &eq_options));
+        assert!(!json_object["above"].deep_eq(&json_object["i64"], &eq_options));
+        assert!(json_object["above"].deep_eq(&JsonValue::from(2f64.powi(63)), &JsonEqOptions::new().numeric_coercion(true)));
+    }
+
+    #[test]
+    fn big_decimal_numbers() {
+        let big_int_str = "12345678901234567890123456789012345678901234567890";
+        let small_decimal_str = "1.23456789012345678901234567890e-40";
+        let json_str = format!(
+            r#"{{"big": {}, "small": {}, "int": 42, "neg": -{}}}"#,
+            big_int_str, small_decimal_str, big_int_str
+        );
+        // 既定ではエラーになる
+        let err = JsonObject::parse(&json_str).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<JsonError>().unwrap().err_kind,
+            JsonErrorKind::ParseErrorInNumber
+        );
+
+        let options = JsonParserOptions::new().big_decimal_digits(Some(17));
+        let json_object = JsonObject::parse_with_options(&json_str, &options).unwrap();
+        assert_eq!(
+            json_object["big"],
+            JsonValue::ValueNumber(JsonNumberType::BigDecimal(big_int_str.to_string()))
+        );
+        assert_eq!(
+            json_object["small"],
+            JsonValue::ValueNumber(JsonNumberType::BigDecimal(small_decimal_str.to_string()))
+        );
+        assert_eq!(json_object["int"], JsonValue::ValueNumber(JsonNumberType::Int(42)));
+        let json_string = json_object.to_compact_string().unwrap();
+        assert!(json_string.contains(&format!("\"big\":{}", big_int_str)));
+        assert!(json_string.contains(&format!("\"small\":{}", small_decimal_str)));
+        assert!(json_string.contains(&format!("\"neg\":-{}", big_int_str)));
+        let json_reparsed_object = JsonObject::parse_with_options(&json_string, &options).unwrap();
+        assert_eq!(json_reparsed_object["big"], json_object["big"]);
+        assert_eq!(json_reparsed_object["small"], json_object["small"]);
+
+        // 先頭の 0 は有効桁に数えない
+        let json_object =
+            JsonObject::parse_with_options(r#"{"v": 0.000000000000000000001, "w": 1.000000000000000000}"#, &options).unwrap();
+        assert_eq!(json_object["v"], JsonValue::ValueNumber(JsonNumberType::Float(1e-21)));
+        assert_eq!(
+            json_object["w"],
+            JsonValue::ValueNumber(JsonNumberType::BigDecimal("1.000000000000000000".to_string()))
+        );
+
+        let json_big = JsonNumberType::BigDecimal(big_int_str.to_string());
+        assert!(json_big.is_integral());
+        assert_eq!(json_big.as_i64(), None);
+        assert_eq!(json_big.as_f64(), big_int_str.parse::<f64>().unwrap());
+        assert!(!JsonNumberType::BigDecimal(small_decimal_str.to_string()).is_integral());
+        assert_eq!(JsonNumberType::BigDecimal("123".to_string()).try_into_u64().unwrap(), 123);
+
+        let eq_options = JsonEqOptions::new();
+        let json_w = &json_object["w"];
+        assert!(!json_w.deep_eq(&JsonValue::from(1.0), &eq_options));
+        assert!(json_w.deep_eq(&JsonValue::from(1), &JsonEqOptions::new().numeric_coercion(true)));
+        assert!(!json_w.deep_eq(
+            &JsonValue::ValueNumber(JsonNumberType::BigDecimal("1.0".to_string())),
+            &JsonEqOptions::new().numeric_coercion(true)
+        ));
+    }
+
+    #[test]
+    fn json_key_api() {
+        let json_key = JsonKey::new(String::from("a/b"));
+        assert_eq!(json_key.as_str(), "a/b");
+        assert_eq!(json_key.as_ref(), "a/b");
+        assert_eq!(json_key.to_string(), "a/b");
+        assert_eq!(JsonKey::from("a/b"), json_key);
+        assert_eq!(JsonKey::from(String::from("a/b")), json_key);
+
+        let json_object = JsonObject::parse(r#"{"\"quoted\"": 1}"#).unwrap();
+        // Display はエスケープしない
+        let json_key = json_object.members.keys().next().unwrap();
+        assert_eq!(format!("{}", json_key), "\"quoted\"");
+        assert_eq!(json_object.members.get("\"quoted\""), Some(&JsonValue::from(1)));
+    }
+
+    #[test]
+    fn json_key_order() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::collections::BTreeSet;
+        use std::hash::{Hash, Hasher};
+
+        let key_strs = ["b", "あ", "B", "a", "Z", "é", "aa", "", "😀", "A"];
+        let json_keys: BTreeSet<JsonKey> = key_strs.iter().map(|key_str| JsonKey::from(*key_str)).collect();
+        let sorted_key_strs: Vec<&str> = json_keys.iter().map(JsonKey::as_str).collect();
+        assert_eq!(sorted_key_strs, ["", "A", "B", "Z", "a", "aa", "b", "é", "あ", "😀"]);
+
+        // Ord と Hash は同じ等価性を使う
+        let hash = |json_key: &JsonKey| {
+            let mut hasher = DefaultHasher::new();
+            json_key.hash(&mut hasher);
+            hasher.finish()
+        };
+        let json_key = JsonKey::new("あ");
+        let json_same_key = JsonKey::from(String::from("あ"));
+        assert_eq!(json_key.cmp(&json_same_key), std::cmp::Ordering::Equal);
+        assert_eq!(hash(&json_key), hash(&json_same_key));
+
+        let mut json_object = JsonObject::new();
+        for key_str in key_strs {
+            json_object.insert(key_str, 1);
+        }
+        assert_eq!(json_object.keys_sorted(), sorted_key_strs);
+        let json_string = json_object
+            .serialize_with_options(&JsonSerializerOptions::new().compact(true).sort_keys(true))
+            .unwrap();
+        assert_eq!(
+            json_string,
+            "{\"\":1,\"A\":1,\"B\":1,\"Z\":1,\"a\":1,\"aa\":1,\"b\":1,\"é\":1,\"あ\":1,\"😀\":1}\n"
+        );
+    }
+
+    #[test]
+    fn take_replace() {
+        let mut json_object =
+            JsonObject::parse(r#"{"src": {"a": {"b": [1, 2, {"c": 3}]}}, "dst": null, "list": [1, {"d": 4}]}"#).unwrap();
+        let refcell_src_object = json_object.get_object("src").unwrap();
+        let strong_count = JsonSharedObject::strong_count(&refcell_src_object);
+
+        let json_src_value = json_object.take("src").unwrap();
+        assert!(!json_object.contains_key("src"));
+        assert_eq!(json_object.replace("dst", json_src_value), Some(JsonValue::ValueNull));
+        // 同じ Rc が移動しただけで、コピーされていない
+        assert!(JsonSharedObject::ptr_eq(
+            json_object.get_object("dst").as_ref().unwrap(),
+            &refcell_src_object
+        ));
+        assert_eq!(JsonSharedObject::strong_count(&refcell_src_object), strong_count);
+
+        assert_eq!(json_object.replace("missing", 1), None);
+        assert!(!json_object.contains_key("missing"));
+        assert_eq!(json_object.take("missing"), None);
+
+        let json_element_value = json_object.get_mut("list").unwrap()[1].take();
+        assert_eq!(json_element_value.to_compact_string().unwrap(), r#"{"d":4}"#);
+        assert_eq!(
+            json_object["list"],
+            JsonValue::from(vec![JsonValue::from(1), JsonValue::ValueNull])
+        );
+    }
+
+    #[test]
+    fn retain_members() {
+        let mut json_object =
+            JsonObject::parse(r#"{"a": null, "x_tmp": 1, "b": {"c": null, "x_d": 2, "e": [null, {"f": null, "g": 3}]}}"#)
+                .unwrap();
+        json_object.retain(|json_key, _| !json_key.0.starts_with("x_"));
+        assert_eq!(json_object.keys_sorted(), ["a", "b"]);
+        // 入れ子のオブジェクトはそのまま
+        assert!(json_object.get_object("b").unwrap().read_object().contains_key("x_d"));
+
+        json_object.retain_recursive(|_, json_value| *json_value != JsonValue::ValueNull);
+        assert!(json_object.deep_eq(
+            &JsonObject::parse(r#"{"b": {"x_d": 2, "e": [null, {"g": 3}]}}"#).unwrap(),
+            &JsonEqOptions::new()
+        ));
+        json_object.retain_recursive(|json_key, _| !json_key.0.starts_with("x_"));
+        assert!(!json_object.get_object("b").unwrap().read_object().contains_key("x_d"));
+
+        // 共有されたオブジェクトと循環
+        let refcell_shared_object = JsonObject::parse(r#"{"n": null, "v": 1}"#).unwrap().into_shared();
+        let mut json_object = JsonObject::new();
+        json_object.insert("a", refcell_shared_object.clone());
+        json_object.insert("b", vec![JsonValue::from(refcell_shared_object.clone())]);
+        refcell_shared_object
+            .write_object()
+            .insert("self", refcell_shared_object.clone());
+        let mut visit_count = 0;
+        json_object.retain_recursive(|json_key, _| {
+            visit_count += 1;
+            json_key.0 != "n"
+        });
+        assert_eq!(visit_count, 5);
+        assert_eq!(refcell_shared_object.read_object().keys_sorted(), ["self", "v"]);
+        refcell_shared_object.write_object().remove("self");
+    }
+
+    #[test]
+    fn prune_nulls() {
+        let json_str =
+            r#"{"a": null, "b": {"c": null, "d": {"e": null}}, "f": [null, 1, {"g": null}, [null]], "h": {}, "i": []}"#;
+        let assert_pruned = |options: JsonPruneOptions, expected_str: &str| {
+            let mut json_object = JsonObject::parse(json_str).unwrap();
+            json_object.prune_nulls(options);
+            assert!(json_object.deep_eq(&JsonObject::parse(expected_str).unwrap(), &JsonEqOptions::new()));
+        };
+        assert_pruned(
+            JsonPruneOptions::new(),
+            r#"{"b": {"d": {}}, "f": [null, 1, {}, [null]], "h": {}, "i": []}"#,
+        );
+        assert_pruned(
+            JsonPruneOptions::new().in_arrays(true),
+            r#"{"b": {"d": {}}, "f": [1, {}, []], "h": {}, "i": []}"#,
+        );
+        // "b" は "d" が消えた結果として空になるので消える。元から空の "h" と "i" は残る
+        assert_pruned(
+            JsonPruneOptions::new().drop_empty_containers(true),
+            r#"{"f": [null, 1, {}, [null]], "h": {}, "i": []}"#,
+        );
+        assert_pruned(
+            JsonPruneOptions::new().in_arrays(true).drop_empty_containers(true),
+            r#"{"f": [1], "h": {}, "i": []}"#,
+        );
+        let mut json_object = JsonObject::parse(r#"{"a": [null, {"b": null}]}"#).unwrap();
+        json_object.prune_nulls(JsonPruneOptions::new().in_arrays(true).drop_empty_containers(true));
+        assert!(json_object.is_empty());
+
+        // 共有されたオブジェクトはどちらの場所からも消え、循環しても止まる
+        let refcell_shared_object = JsonObject::parse(r#"{"n": null}"#).unwrap().into_shared();
+        let mut json_object = JsonObject::new();
+        json_object.insert("a", refcell_shared_object.clone());
+        json_object.insert("b", refcell_shared_object.clone());
+        let refcell_cyclic_object = JsonObject::parse(r#"{"n": null}"#).unwrap().into_shared();
+        refcell_cyclic_object
+            .write_object()
+            .insert("self", refcell_cyclic_object.clone());
+        json_object.insert("c", refcell_cyclic_object.clone());
+        json_object.prune_nulls(JsonPruneOptions::new().drop_empty_containers(true));
+        assert_eq!(json_object.keys_sorted(), ["c"]);
+        assert_eq!(refcell_cyclic_object.read_object().keys_sorted(), ["self"]);
+        refcell_cyclic_object.write_object().remove("self");
+    }
+
+    #[test]
+    fn rename_key() {
+        let mut json_object = JsonObject::parse(r#"{"old": {"old": 1}, "list": [{"old": 2}, {"new": 3}], "other": 4}"#).unwrap();
+        assert!(json_object.rename_key("old", "new").unwrap());
+        assert!(!json_object.rename_key("missing", "new2").unwrap());
+        assert!(json_object.rename_key("other", "other").unwrap());
+        assert_eq!(json_object.keys_sorted(), ["list", "new", "other"]);
+        // 入れ子のメンバーはそのまま
+        assert!(json_object.get_object("new").unwrap().read_object().contains_key("old"));
+
+        // 衝突
+        let err = json_object.rename_key("other", "list").unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<JsonError>().unwrap().err_kind,
+            JsonErrorKind::KeyAlreadyExists
+        );
+        assert_eq!(json_object["other"], JsonValue::from(4));
+        assert_eq!(json_object.keys_sorted(), ["list", "new", "other"]);
+
+        let mut json_object =
+            JsonObject::parse(r#"{"old": {"old": 1}, "list": [{"old": 2}, {"new": 3}, [{"old": 5}]]}"#).unwrap();
+        assert_eq!(json_object.rename_key_recursive("old", "new").unwrap(), 4);
+        assert!(json_object.deep_eq(
+            &JsonObject::parse(r#"{"new": {"new": 1}, "list": [{"new": 2}, {"new": 3}, [{"new": 5}]]}"#).unwrap(),
+            &JsonEqOptions::new()
+        ));
+
+        // 深いところで衝突すると何も変わらない
+        let json_str = r#"{"a": 1, "b": {"c": [{"a": 2, "z": 3}]}}"#;
+        let mut json_object = JsonObject::parse(json_str).unwrap();
+        let err = json_object.rename_key_recursive("a", "z").unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<JsonError>().unwrap().err_kind,
+            JsonErrorKind::KeyAlreadyExists
+        );
+        assert!(json_object.deep_eq(&JsonObject::parse(json_str).unwrap(), &JsonEqOptions::new()));
+    }
+
+    #[test]
+    fn transform_keys() {
+        for (key_str, snake_str, camel_str) in [
+            ("userId", "user_id", "userId"),
+            ("user_id", "user_id", "userId"),
+            ("HTTPSPort", "https_port", "httpsPort"),
+            ("getHTTPResponse2", "get_http_response2", "getHttpResponse2"),
+            ("port8080Tcp", "port8080_tcp", "port8080Tcp"),
+            ("UserName", "user_name", "userName"),
+            ("kebab-case key", "kebab_case_key", "kebabCaseKey"),
+            ("_id", "_id", "_id"),
+            ("_privateValue", "_private_value", "_privateValue"),
+            ("ID", "id", "id"),
+            ("", "", ""),
+        ] {
+            assert_eq!(to_snake_case(key_str), snake_str, "{}", key_str);
+            assert_eq!(to_camel_case(key_str), camel_str, "{}", key_str);
+        }
+
+        let camel_str =
+            r#"{"userId": 1, "userProfile": {"displayName": "a", "addressList": [{"zipCode": "1"}, [{"lineNo": 2}]]}}"#;
+        let mut json_object = JsonObject::parse(camel_str).unwrap();
+        json_object.transform_keys(to_snake_case).unwrap();
+        assert!(json_object.deep_eq(
+            &JsonObject::parse(
+                r#"{"user_id": 1, "user_profile": {"display_name": "a", "address_list": [{"zip_code": "1"}, [{"line_no": 2}]]}}"#
+            )
+            .unwrap(),
+            &JsonEqOptions::new()
+        ));
+        json_object.transform_keys(to_camel_case).unwrap();
+        assert!(json_object.deep_eq(&JsonObject::parse(camel_str).unwrap(), &JsonEqOptions::new()));
+
+        // 衝突すると何も変わらない
+        let json_str = r#"{"a": {"userId": 1, "b": [{"user_id": 2, "userId": 3}]}}"#;
+        let mut json_object = JsonObject::parse(json_str).unwrap();
+        let err = json_object.transform_keys(to_snake_case).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<JsonError>().unwrap().err_kind,
+            JsonErrorKind::KeyAlreadyExists
+        );
+        assert!(json_object.deep_eq(&JsonObject::parse(json_str).unwrap(), &JsonEqOptions::new()));
+    }
+
+    #[test]
+    fn walk_values() {
+        let json_object = JsonObject::parse(r#"{"b": [1, {"c": {"d/e": [true]}}], "a": null, "z": {"y": 2}}"#).unwrap();
+        let mut pointer_strings = Vec::new();
+        json_object.walk(|json_path, _| {
+            pointer_strings.push(json_path.to_pointer());
+            JsonWalkControl::Continue
+        });
+        assert_eq!(
+            pointer_strings,
+            [
+                "/a",
+                "/b",
+                "/b/0",
+                "/b/1",
+                "/b/1/c",
+                "/b/1/c/d~1e",
+                "/b/1/c/d~1e/0",
+                "/z",
+                "/z/y"
+            ]
+        );
+
+        let mut json_paths = Vec::new();
+        JsonValue::from(json_object.clone()).walk(|json_path, json_value| {
+            json_paths.push(json_path.clone());
+            match json_value {
+                JsonValue::ValueArray(_) => JsonWalkControl::SkipChildren,
+                _ => JsonWalkControl::Continue,
+            }
+        });
+        assert!(json_paths[0].is_empty());
+        let pointer_strings: Vec<String> = json_paths.iter().map(|json_path| json_path.to_string()).collect();
+        assert_eq!(pointer_strings, ["", "/a", "/b", "/z", "/z/y"]);
+        assert_eq!(json_paths[4].len(), 2);
+        assert_eq!(
+            json_paths[4].segments().collect::<Vec<JsonPathSegment>>(),
+            [JsonPathSegment::Key("z"), JsonPathSegment::Key("y")]
+        );
+
+        let mut visit_count = 0;
+        json_object.walk(|json_path, _| {
+            visit_count += 1;
+            if json_path
+                .segments()
+                .any(|path_segment| path_segment == JsonPathSegment::Index(1))
+            {
+                JsonWalkControl::Stop
+            } else {
+                JsonWalkControl::Continue
+            }
+        });
+        // "/a", "/b", "/b/0", "/b/1" で止まる
+        assert_eq!(visit_count, 4);
+
+        // 循環はたどらない
+        let refcell_cyclic_object = JsonObject::parse(r#"{"v": 1}"#).unwrap().into_shared();
+        refcell_cyclic_object
+            .write_object()
+            .insert("self", refcell_cyclic_object.clone());
+        let mut pointer_strings = Vec::new();
+        JsonValue::from(refcell_cyclic_object.clone()).walk(|json_path, _| {
+            pointer_strings.push(json_path.to_pointer());
+            JsonWalkControl::Continue
+        });
+        assert_eq!(pointer_strings, ["", "/self", "/v"]);
+        refcell_cyclic_object.write_object().walk_mut(|_, json_value| {
+            if let JsonValue::ValueNumber(_) = json_value {
+                *json_value = JsonValue::from(2);
+            }
+            JsonWalkControl::Continue
+        });
+        assert_eq!(refcell_cyclic_object.read_object()["v"], JsonValue::from(2));
+        refcell_cyclic_object.write_object().remove("self");
+
+        // walk_mut は置き換えた値の子もたどる
+        let mut json_object = JsonObject::parse(r#"{"a": 1, "b": [2, 3]}"#).unwrap();
+        json_object.walk_mut(|json_path, json_value| {
+            if json_path.to_pointer() == "/a" {
+                *json_value = JsonValue::from(vec![JsonValue::from(10)]);
+            } else if let JsonValue::ValueNumber(JsonNumberType::Int(int_number)) = json_value {
+                *int_number += 1;
+            }
+            JsonWalkControl::Continue
+        });
+        assert!(json_object.deep_eq(
+            &JsonObject::parse(r#"{"a": [11], "b": [3, 4]}"#).unwrap(),
+            &JsonEqOptions::new()
+        ));
+    }
+
+    #[test]
+    fn map_values() {
+        let json_str = r#"{"name": "alice", "tags": ["a", {"t": "b"}], "size": 2.0, "ratio": 0.5, "n": 3, "ok": true}"#;
+        let uppercase = |json_value: JsonValue| match json_value {
+            JsonValue::ValueString(json_string) => JsonValue::from(json_string.to_uppercase()),
+            _ => json_value,
+        };
+        let json_value = JsonValue::from(JsonObject::parse(json_str).unwrap()).map_values(uppercase);
+        let options = JsonSerializerOptions::new()
+            .compact(true)
+            .sort_keys(true)
+            .trailing_newline(false);
+        assert_eq!(
+            json_value.serialize_with_options(&options).unwrap(),
+            r#"{"n":3,"name":"ALICE","ok":true,"ratio":0.5,"size":2.0,"tags":["A",{"t":"B"}]}"#
+        );
+
+        let integral_to_int = |json_value: JsonValue| match json_value {
+            JsonValue::ValueNumber(JsonNumberType::Float(float_number)) if float_number.fract() == 0.0 => {
+                JsonValue::from(float_number as i64)
+            }
+            _ => json_value,
+        };
+        let mut json_object = JsonObject::parse(json_str).unwrap();
+        json_object.map_values_in_place(integral_to_int);
+        assert_eq!(
+            json_object.serialize_with_options(&options).unwrap(),
+            r#"{"n":3,"name":"alice","ok":true,"ratio":0.5,"size":2,"tags":["a",{"t":"b"}]}"#
+        );
+
+        // map_values は共有されたオブジェクトを場所ごとに作り直し、元のオブジェクトは変えない
+        let refcell_shared_object = JsonObject::parse(r#"{"v": 1}"#).unwrap().into_shared();
+        let mut json_object = JsonObject::new();
+        json_object.insert("a", refcell_shared_object.clone());
+        json_object.insert("b", refcell_shared_object.clone());
+        let increment = |json_value: JsonValue| match json_value {
+            JsonValue::ValueNumber(JsonNumberType::Int(int_number)) => JsonValue::from(int_number + 1),
+            _ => json_value,
+        };
+        let json_mapped_value = JsonValue::from(json_object.clone()).map_values(increment);
+        let refcell_mapped_object = json_mapped_value.as_object().unwrap();
+        assert_eq!(refcell_shared_object.read_object()["v"], JsonValue::from(1));
+        assert_eq!(
+            refcell_mapped_object.read_object().get_object("a").unwrap().read_object()["v"],
+            JsonValue::from(2)
+        );
+        assert!(!JsonSharedObject::ptr_eq(
+            &refcell_mapped_object.read_object().get_object("a").unwrap(),
+            &refcell_mapped_object.read_object().get_object("b").unwrap()
+        ));
+        // map_values_in_place は共有されたオブジェクトを 1 回だけ変える
+        json_object.map_values_in_place(increment);
+        assert_eq!(refcell_shared_object.read_object()["v"], JsonValue::from(2));
+
+        // 循環はそのまま残る
+        refcell_shared_object
+            .write_object()
+            .insert("self", refcell_shared_object.clone());
+        let json_mapped_value = JsonValue::from(refcell_shared_object.clone()).map_values(increment);
+        let refcell_mapped_object = json_mapped_value.as_object().unwrap();
+        assert_eq!(refcell_mapped_object.read_object()["v"], JsonValue::from(3));
+        assert!(JsonSharedObject::ptr_eq(
+            &refcell_mapped_object.read_object().get_object("self").unwrap(),
+            &refcell_shared_object
+        ));
+        refcell_shared_object.write_object().remove("self");
+    }
+
+    #[test]
+    fn depth_node_count() -> Result<()> {
+        for (file_str, depth, node_count) in [
+            ("./for_test/parse_test_array.json", 3, 30),
+            ("./for_test/parse_test_object.json", 3, 14),
+            ("./for_test/parse_test_number.json", 1, 9),
+        ] {
+            let mut content_string = String::new();
+            File::open(Path::new(file_str))?.read_to_string(&mut content_string)?;
+            let json_object = JsonObject::parse(&content_string)?;
+            assert_eq!(json_object.depth(), depth, "{}", file_str);
+            assert_eq!(json_object.node_count(), node_count, "{}", file_str);
+            let json_value = JsonValue::from(json_object);
+            assert_eq!(json_value.depth(), depth, "{}", file_str);
+            assert_eq!(json_value.node_count(), node_count, "{}", file_str);
+        }
+        assert_eq!(JsonObject::new().depth(), 1);
+        assert_eq!(JsonObject::new().node_count(), 1);
+        assert_eq!(JsonValue::from(1).depth(), 0);
+        assert_eq!(JsonValue::from(1).node_count(), 1);
+        assert_eq!(JsonValue::from(Vec::new()).depth(), 1);
+
+        // 共有されたオブジェクトは場所ごとに数え、循環は 1 つに数える
+        let refcell_shared_object = JsonObject::parse(r#"{"v": [1]}"#)?.into_shared();
+        let mut json_object = JsonObject::new();
+        json_object.insert("a", refcell_shared_object.clone());
+        json_object.insert("b", vec![JsonValue::from(refcell_shared_object.clone())]);
+        assert_eq!(json_object.node_count(), 1 + 3 + 1 + 3);
+        assert_eq!(json_object.depth(), 4);
+        refcell_shared_object
+            .write_object()
+            .insert("self", refcell_shared_object.clone());
+        assert_eq!(json_object.node_count(), 1 + 4 + 1 + 4);
+        assert_eq!(json_object.depth(), 4);
+        refcell_shared_object.write_object().remove("self");
+        Ok(())
+    }
+
+    #[test]
+    fn remove_pointer() {
+        let mut json_object =
+            JsonObject::parse(r#"{"servers": [{"tls": {"cert": "a", "key": "b"}}, 1, 2], "a/b": {"~": 3}}"#).unwrap();
+        let refcell_tls_object = json_object.pointer("/servers/0/tls").unwrap().as_object().unwrap().clone();
+        assert_eq!(
+            json_object.remove_pointer("/servers/0/tls/cert").unwrap(),
+            Some(JsonValue::from("a"))
+        );
+        // 共有している Rc からも見える
+        assert!(!refcell_tls_object.read_object().contains_key("cert"));
+
+        assert_eq!(json_object.remove_pointer("/servers/1").unwrap(), Some(JsonValue::from(1)));
+        assert_eq!(json_object.pointer("/servers/1"), Some(JsonValue::from(2)));
+        assert_eq!(json_object.remove_pointer("/a~1b/~0").unwrap(), Some(JsonValue::from(3)));
+
+        // 最後のセグメントがなければ None
+        assert_eq!(json_object.remove_pointer("/servers/0/tls/missing").unwrap(), None);
+        assert_eq!(json_object.remove_pointer("/servers/5").unwrap(), None);
+        assert_eq!(json_object.remove_pointer("/missing").unwrap(), None);
+
+        for (ptr_str, message_str) in [
+            ("/missing/key", "segment:missing"),
+            ("/servers/9/tls", "segment:9"),
+            ("/servers/x", "segment:x"),
+            ("/servers/1/x", "segment:x"),
+            ("", "Root object"),
+            ("servers", "does not start with '/'"),
+        ] {
+            let err = json_object.remove_pointer(ptr_str).unwrap_err();
+            assert_eq!(err.downcast_ref::<JsonError>().unwrap().err_kind, JsonErrorKind::KeyNotFound);
+            assert!(err.to_string().contains(message_str), "{}: {}", ptr_str, err);
+        }
+        assert!(json_object.deep_eq(
+            &JsonObject::parse(r#"{"servers": [{"tls": {"key": "b"}}, 2], "a/b": {}}"#).unwrap(),
+            &JsonEqOptions::new()
+        ));
+    }
+
+    #[test]
+    fn array_helpers() {
+        let mut json_object = JsonObject::new();
+        assert_eq!(json_object.array_len("list"), None);
+        json_object.push_to_array("list", 1).unwrap();
+        json_object.push_to_array("list", "two").unwrap();
+        json_object.push_to_array("list", JsonValue::ValueNull).unwrap();
+        assert_eq!(json_object.array_len("list"), Some(3));
+        assert_eq!(json_object.array_get("list", 1), Some(&JsonValue::from("two")));
+        assert_eq!(json_object.array_get("list", 3), None);
+        json_object.array_set("list", 2, vec![JsonValue::from(true)]).unwrap();
+        assert_eq!(json_object.to_compact_string().unwrap(), "{\"list\":[1,\"two\",[true]]}");
+
+        let err = json_object.array_set("list", 3, 4).unwrap_err();
+        assert_eq!(err.downcast_ref::<JsonError>().unwrap().err_kind, JsonErrorKind::OutOfRange);
+        assert_eq!(
+            err.to_string(),
+            "Out of range : \"list\": index 3 is out of range for the array of length 3"
+        );
+        let err = json_object.array_set("missing", 0, 4).unwrap_err();
+        assert_eq!(err.downcast_ref::<JsonError>().unwrap().err_kind, JsonErrorKind::KeyNotFound);
+
+        json_object.insert("n", 1);
+        assert_eq!(json_object.array_len("n"), None);
+        assert_eq!(json_object.array_get("n", 0), None);
+        for err in [
+            json_object.push_to_array("n", 2).unwrap_err(),
+            json_object.array_set("n", 0, 2).unwrap_err(),
+        ] {
+            assert_eq!(err.downcast_ref::<JsonError>().unwrap().err_kind, JsonErrorKind::TypeMismatch);
+        }
+        assert_eq!(json_object["n"], JsonValue::from(1));
+    }
+
+    #[test]
+    fn typed_arrays() {
+        let json_object = JsonObject::parse(
+        r#"{"hosts": ["a", "b"], "ports": [80, 443], "ratios": [0.5, 1], "flags": [true, false], "mixed": ["a", 1, null], "empty": [], "s": "a"}"#,
+    )
+    .unwrap();
+        assert_eq!(json_object.get_str_array("hosts").unwrap(), ["a", "b"]);
+        assert_eq!(json_object.get_i64_array("ports").unwrap(), [80, 443]);
+        assert_eq!(json_object.get_f64_array("ratios").unwrap(), [0.5, 1.0]);
+        assert_eq!(json_object.get_bool_array("flags").unwrap(), [true, false]);
+        assert!(json_object.get_i64_array("empty").unwrap().is_empty());
+
+        let err = json_object.get_str_array("mixed").unwrap_err();
+        assert_eq!(err.downcast_ref::<JsonError>().unwrap().err_kind, JsonErrorKind::TypeMismatch);
+        assert_eq!(
+            err.to_string(),
+            "Type mismatch : \"mixed\"[1]: expected string, found integer"
+        );
+        let err = json_object.get_i64_array("ratios").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Type mismatch : \"ratios\"[0]: expected integer, found number"
+        );
+        let err = json_object.get_bool_array("s").unwrap_err();
+        assert_eq!(err.to_string(), "Type mismatch : \"s\": expected array, found string");
+        let err = json_object.get_f64_array("absent").unwrap_err();
+        assert_eq!(err.downcast_ref::<JsonError>().unwrap().err_kind, JsonErrorKind::KeyNotFound);
+
+        // null も型違い
+        let json_object = JsonObject::parse(r#"{"hosts": ["a", null]}"#).unwrap();
+        let err = json_object.get_str_array("hosts").unwrap_err();
+        assert_eq!(err.to_string(), "Type mismatch : \"hosts\"[1]: expected string, found null");
+    }
+
+    #[test]
+    fn number_coercion_getters() {
+        let json_object = JsonObject::parse(
+        r#"{"int": 5, "float": 5.0, "half": 1.5, "big_int": 9007199254740993, "big_float": 9007199254740993.0, "min": -9223372036854775808, "min_float": -9223372036854775808.0, "huge": 1e19, "s": "5"}"#,
+    )
+    .unwrap();
+        assert_eq!(json_object.get_number_as_f64("int"), Some(5.0));
+        assert_eq!(json_object.get_number_as_f64("float"), Some(5.0));
+        assert_eq!(json_object.get_number_as_i64("int"), Some(5));
+        assert_eq!(json_object.get_number_as_i64("float"), Some(5));
+        assert_eq!(json_object.get_number_as_i64("half"), None);
+        // 2^53 + 1 は float では 2^53 に丸められる
+        assert_eq!(json_object.get_f64("big_int"), None);
+        assert_eq!(json_object.get_number_as_f64("big_int"), Some(9007199254740992.0));
+        assert_eq!(json_object.get_number_as_i64("big_int"), Some(9007199254740993));
+        assert_eq!(json_object.get_number_as_i64("big_float"), Some(9007199254740992));
+        assert_eq!(json_object.get_number_as_i64("min"), Some(i64::MIN));
+        assert_eq!(json_object.get_number_as_i64("min_float"), Some(i64::MIN));
+        assert_eq!(json_object.get_number_as_i64("huge"), None);
+        assert_eq!(json_object.get_number_as_f64("s"), None);
+        assert_eq!(json_object.get_number_as_i64("absent"), None);
+
+        assert_eq!(json_object.try_get_number_as_f64("half").unwrap(), 1.5);
+        assert_eq!(json_object.try_get_number_as_i64("float").unwrap(), 5);
+        for (key_str, err_kind) in [
+            ("half", JsonErrorKind::OutOfRange),
+            ("huge", JsonErrorKind::OutOfRange),
+            ("s", JsonErrorKind::TypeMismatch),
+            ("absent", JsonErrorKind::KeyNotFound),
+        ] {
+            let err = json_object.try_get_number_as_i64(key_str).unwrap_err();
+            assert_eq!(err.downcast_ref::<JsonError>().unwrap().err_kind, err_kind, "{}", key_str);
+        }
+        let err = json_object.try_get_number_as_f64("s").unwrap_err();
+        assert_eq!(err.downcast_ref::<JsonError>().unwrap().err_kind, JsonErrorKind::TypeMismatch);
+    }
+
+    #[test]
+    fn getters_with_defaults() {
+        let json_object =
+            JsonObject::parse(r#"{"level": "debug", "port": 8080, "ratio": 0.5, "verbose": true, "hosts": ["a"], "wrong": {}}"#)
+                .unwrap();
+        // キーがあるとき、ないとき、型が違うとき
+        assert_eq!(json_object.get_str_or("level", "info"), "debug");
+        assert_eq!(json_object.get_str_or("absent", "info"), "info");
+        assert_eq!(json_object.get_str_or("port", "info"), "info");
+        assert_eq!(json_object.get_i64_or("port", 80), 8080);
+        assert_eq!(json_object.get_i64_or("absent", 80), 80);
+        assert_eq!(json_object.get_i64_or("level", 80), 80);
+        assert_eq!(json_object.get_f64_or("ratio", 1.0), 0.5);
+        assert_eq!(json_object.get_f64_or("absent", 1.0), 1.0);
+        assert_eq!(json_object.get_f64_or("level", 1.0), 1.0);
+        assert!(json_object.get_bool_or("verbose", false));
+        assert!(json_object.get_bool_or("absent", true));
+        assert!(!json_object.get_bool_or("level", false));
+        assert_eq!(json_object.get_array_or_empty("hosts"), [JsonValue::from("a")]);
+        assert!(json_object.get_array_or_empty("absent").is_empty());
+        assert!(json_object.get_array_or_empty("level").is_empty());
+
+        assert_eq!(json_object.try_get_str_or("level", "info").unwrap(), "debug");
+        assert_eq!(json_object.try_get_str_or("absent", "info").unwrap(), "info");
+        assert_eq!(json_object.try_get_i64_or("port", 80).unwrap(), 8080);
+        assert_eq!(json_object.try_get_i64_or("absent", 80).unwrap(), 80);
+        assert_eq!(json_object.try_get_f64_or("ratio", 1.0).unwrap(), 0.5);
+        assert_eq!(json_object.try_get_f64_or("absent", 1.0).unwrap(), 1.0);
+        assert!(json_object.try_get_bool_or("verbose", false).unwrap());
+        assert!(json_object.try_get_bool_or("absent", true).unwrap());
+        assert_eq!(json_object.try_get_array_or_empty("hosts").unwrap().len(), 1);
+        assert!(json_object.try_get_array_or_empty("absent").unwrap().is_empty());
+        let errs = [
+            json_object.try_get_str_or("wrong", "info").unwrap_err(),
+            json_object.try_get_i64_or("wrong", 80).unwrap_err(),
+            json_object.try_get_f64_or("wrong", 1.0).unwrap_err(),
+            json_object.try_get_bool_or("wrong", true).unwrap_err(),
+            json_object.try_get_array_or_empty("wrong").unwrap_err(),
+        ];
+        for err in errs {
+            assert_eq!(err.downcast_ref::<JsonError>().unwrap().err_kind, JsonErrorKind::TypeMismatch);
+        }
+    }
+
+    #[test]
+    fn project_members() {
+        let json_object = JsonObject::parse(
+        r#"{"meta": {"id": 7, "name": "a"}, "items": [{"name": "x", "size": 1}, {"name": "y"}, {"name": "z"}], "flag": true}"#,
+    )
+    .unwrap();
+
+        let json_projected_object = json_object.project(&["meta", "flag", "missing"]);
+        let json_expected_object = JsonObject::parse(r#"{"meta": {"id": 7, "name": "a"}, "flag": true}"#).unwrap();
+        assert!(json_projected_object.deep_eq(&json_expected_object, &JsonEqOptions::new()));
+
+        // 射影を変更しても元のオブジェクトは変わらない
+        json_projected_object
+            .get_object("meta")
+            .unwrap()
+            .write_object()
+            .insert("id", JsonValue::from(8));
+        assert_eq!(json_object.get_path("meta.id").unwrap().unwrap().as_i64(), Some(7));
+
+        let json_projected_object = json_object
+            .project_paths(&["meta.id", "items[2].name", "items[0].name", "items[5]", "flag.x", "none.x"])
+            .unwrap();
+        let json_expected_object = JsonObject::parse(r#"{"meta": {"id": 7}, "items": [{"name": "x"}, {"name": "z"}]}"#).unwrap();
+        assert!(json_projected_object.deep_eq(&json_expected_object, &JsonEqOptions::new()));
+
+        // 要素全体の選択が部分の選択より優先される
+        let json_projected_object = json_object.project_paths(&["items[0].name", "items[0]"]).unwrap();
+        let json_expected_object = JsonObject::parse(r#"{"items": [{"name": "x", "size": 1}]}"#).unwrap();
+        assert!(json_projected_object.deep_eq(&json_expected_object, &JsonEqOptions::new()));
+
+        assert!(json_object.project_paths(&["items["]).is_err());
+    }
+
+    #[test]
+    fn diff_report() {
+        let json_object = JsonObject::parse(
+            r#"{"meta": {"id": 7, "tags": ["a", "b"], "owner": {"name": "x"}}, "old": 1, "port": "80", "items": [1, 2, 3]}"#,
+        )
+        .unwrap();
+        let json_other_object = JsonObject::parse(
+            r#"{"meta": {"id": 8, "tags": ["a", "b", "c"], "owner": {"name": "y"}}, "new": null, "port": 80, "items": [1]}"#,
+        )
+        .unwrap();
+
+        let report = json_object.diff_report(&json_other_object);
+        let entries: Vec<(String, JsonDiffKind)> = report.iter().map(|entry| (entry.path.to_pointer(), entry.kind)).collect();
+        assert_eq!(
+            entries,
+            vec![
+                ("/items/1".to_string(), JsonDiffKind::Removed),
+                ("/items/2".to_string(), JsonDiffKind::Removed),
+                ("/meta/id".to_string(), JsonDiffKind::ValueChanged),
+                ("/meta/owner/name".to_string(), JsonDiffKind::ValueChanged),
+                ("/meta/tags/2".to_string(), JsonDiffKind::Added),
+                ("/new".to_string(), JsonDiffKind::Added),
+                ("/old".to_string(), JsonDiffKind::Removed),
+                ("/port".to_string(), JsonDiffKind::TypeChanged),
+            ]
+        );
+        assert_eq!(report.entries[2].old_value, Some(JsonValue::from(7)));
+        assert_eq!(report.entries[2].new_value, Some(JsonValue::from(8)));
+        assert_eq!(report.entries[4].old_value, None);
+        assert_eq!(
+            report.to_string(),
+            "/items/1: removed 2\n/items/2: removed 3\n/meta/id: 7 -> 8\n/meta/owner/name: \"x\" -> \"y\"\n\
+         /meta/tags/2: added \"c\"\n/new: added null\n/old: removed 1\n/port: \"80\" -> 80"
+        );
+
+        // 深く等しいオブジェクトは差分なし
+        let json_same_object = JsonObject::parse(
+            r#"{"items": [1, 2, 3], "port": "80", "old": 1, "meta": {"owner": {"name": "x"}, "tags": ["a", "b"], "id": 7}}"#,
+        )
+        .unwrap();
+        let report = json_object.diff_report(&json_same_object);
+        assert!(report.is_empty());
+        assert_eq!(report.to_string(), "");
+    }
+
+    #[test]
+    fn canonical_hash() {
+        let json_object =
+            JsonObject::parse(r#"{"id": 7, "tags": ["a", "b"], "meta": {"x": 1.5, "y": null, "z": true}}"#).unwrap();
+        let json_reordered_object =
+            JsonObject::parse(r#"{"meta": {"z": true, "y": null, "x": 1.5}, "tags": ["a", "b"], "id": 7}"#).unwrap();
+        assert_eq!(json_object.canonical_hash(), json_reordered_object.canonical_hash());
+        // プロセスや実行ごとに変わらない
+        assert_eq!(json_object.canonical_hash(), 2589328550177605970);
+
+        // 葉を 1 つ変えると変わる
+        let json_changed_object =
+            JsonObject::parse(r#"{"id": 7, "tags": ["a", "c"], "meta": {"x": 1.5, "y": null, "z": true}}"#).unwrap();
+        assert_ne!(json_object.canonical_hash(), json_changed_object.canonical_hash());
+        assert_ne!(
+            JsonObject::parse(r#"{"a": "1"}"#).unwrap().canonical_hash(),
+            JsonObject::parse(r#"{"a": 1}"#).unwrap().canonical_hash()
+        );
+        assert_ne!(
+            JsonObject::parse(r#"{"a": ["bc"]}"#).unwrap().canonical_hash(),
+            JsonObject::parse(r#"{"a": ["b", "c"]}"#).unwrap().canonical_hash()
+        );
+
+        // 数値の正規化
+        assert_eq!(JsonValue::from(1.0).canonical_hash(), JsonValue::from(1).canonical_hash());
+        assert_eq!(JsonValue::from(-0.0).canonical_hash(), JsonValue::from(0.0).canonical_hash());
+        assert_eq!(
+            JsonValue::from(f64::NAN).canonical_hash(),
+            JsonValue::from(-f64::NAN).canonical_hash()
+        );
+        assert_ne!(JsonValue::from(1.5).canonical_hash(), JsonValue::from(1).canonical_hash());
+        assert_eq!(
+            JsonObject::parse(r#"{"s": "é"}"#).unwrap().canonical_hash(),
+            JsonObject::parse(r#"{"s": "\u00e9"}"#).unwrap().canonical_hash()
+        );
+    }
+
+    // オブジェクトは Rc の中身を変えない限りキーに使える
+    #[allow(clippy::mutable_key_type)]
+    #[test]
+    fn hash_json_value() {
+        use std::collections::HashSet;
+
+        let mut json_value_set = HashSet::new();
+        assert!(json_value_set.insert(JsonValue::from("a")));
+        assert!(json_value_set.insert(JsonValue::from(1)));
+        assert!(json_value_set.insert(JsonValue::from(1.0)));
+        assert!(!json_value_set.insert(JsonValue::from("a")));
+        assert!(json_value_set.insert(JsonValue::from(0.0)));
+        assert!(!json_value_set.insert(JsonValue::from(-0.0)));
+        assert_eq!(json_value_set.len(), 4);
+
+        // 別々に作った入れ子の値は重複として除かれる
+        let json_nested_value = JsonValue::from(JsonObject::parse(r#"{"id": 7, "tags": ["a", {"x": null}]}"#).unwrap());
+        let json_other_nested_value = JsonValue::from(JsonObject::parse(r#"{"tags": ["a", {"x": null}], "id": 7}"#).unwrap());
+        assert!(json_value_set.insert(json_nested_value));
+        assert!(!json_value_set.insert(json_other_nested_value));
+        assert!(json_value_set.insert(JsonValue::from(vec![JsonValue::from("a"), JsonValue::from(1)])));
+        assert!(!json_value_set.insert(JsonValue::from(vec![JsonValue::from("a"), JsonValue::from(1)])));
+        assert_eq!(json_value_set.len(), 6);
+
+        // NaN どうしは等しいので一度だけ追加される
+        assert_eq!(JsonValue::from(f64::NAN), JsonValue::from(-f64::NAN));
+        assert_ne!(JsonValue::from(f64::NAN), JsonValue::from(1.5));
+        assert!(json_value_set.insert(JsonValue::from(vec![JsonValue::from(f64::NAN)])));
+        assert!(!json_value_set.insert(JsonValue::from(vec![JsonValue::from(f64::NAN)])));
+        assert!(json_value_set.contains(&JsonValue::from(vec![JsonValue::from(-f64::NAN)])));
+        assert_eq!(json_value_set.len(), 7);
+
+        let mut json_object_set = HashSet::new();
+        assert!(json_object_set.insert(JsonObject::parse(r#"{"a": 1, "b": [true]}"#).unwrap()));
+        assert!(!json_object_set.insert(JsonObject::parse(r#"{"b": [true], "a": 1}"#).unwrap()));
+        assert_eq!(json_object_set.len(), 1);
+    }
+
+    #[test]
+    fn default_values() {
+        assert_eq!(JsonValue::default(), JsonValue::ValueNull);
+        assert_eq!(JsonObject::default(), JsonObject::new());
+
+        let mut json_object = JsonObject::new();
+        json_object["missing"] = JsonValue::default();
+        assert_eq!(json_object.get("missing"), Some(&JsonValue::ValueNull));
+
+        let mut json_value = JsonValue::from("a");
+        assert_eq!(std::mem::take(&mut json_value), JsonValue::from("a"));
+        assert_eq!(json_value, JsonValue::default());
+    }
+
+    #[test]
+    fn from_str_object() {
+        fn parse_all<T: std::str::FromStr>(content_strs: &[&str]) -> Vec<std::result::Result<T, T::Err>> {
+            content_strs.iter().map(|content_str| content_str.parse::<T>()).collect()
+        }
+
+        let results = parse_all::<JsonObject>(&[r#"{"a": [1, 2]}"#, r#"{"a": }"#]);
+        assert_eq!(results[0].as_ref().unwrap().get_array("a").unwrap().len(), 2);
+        assert_eq!(results[1].as_ref().unwrap_err().err_kind, JsonErrorKind::ParseErrorInValue);
+
+        // JsonError は ? で crate の Result に変換できる
+        let parse_boxed = |content_str: &str| -> Result<JsonObject> { Ok(content_str.parse::<JsonObject>()?) };
+        let err = parse_boxed(r#"{"a": }"#).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<JsonError>().unwrap().err_kind,
+            JsonErrorKind::ParseErrorInValue
+        );
+    }
+
+    #[cfg(feature = "sync")]
+    #[test]
+    fn sync_send_values() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<JsonObject>();
+        assert_send_sync::<JsonValue>();
+        assert_send_sync::<JsonSharedObject>();
+
+        let json_object = JsonObject::parse(r#"{"server": {"host": "a", "ports": [80, 443]}, "tags": ["x"]}"#).unwrap();
+        let json_port_count = std::thread::spawn(move || {
+            let json_server_object = json_object.get_object("server").unwrap();
+            let json_port_count = json_server_object.read_object().get_array("ports").unwrap().len();
+            json_server_object.write_object().insert("host", "b");
+            (json_object, json_port_count)
+        })
+        .join()
+        .unwrap();
+        assert_eq!(json_port_count.1, 2);
+        assert_eq!(json_port_count.0.get_path("server.host").unwrap(), Some(JsonValue::from("b")));
+
+        // 共有オブジェクトは複数のスレッドから読める
+        let json_shared_object = JsonObject::parse(r#"{"v": 1}"#).unwrap().into_shared();
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let json_shared_object = json_shared_object.clone();
+                std::thread::spawn(move || json_shared_object.read_object().get_i64("v").unwrap())
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), 1);
+        }
+    }
+
+    #[test]
+    fn owned_objects() -> Result<()> {
+        let owned_options = JsonParserOptions::new().owned_objects(true);
+        let serializer_options = JsonSerializerOptions::new().sort_keys(true);
+        for file_str in [
+            "./for_test/parse_test_string.json",
+            "./for_test/parse_test_number.json",
+            "./for_test/parse_test_bool_null.json",
+            "./for_test/parse_test_array.json",
+            "./for_test/parse_test_object.json",
+            "./for_test/read_test1.json",
+        ] {
+            let mut content_string = String::new();
+            File::open(Path::new(file_str))?.read_to_string(&mut content_string)?;
+            let json_shared_object = JsonObject::parse(&content_string)?;
+            let json_owned_object = JsonParser::parse_with_options(&content_string, &owned_options)?;
+            assert!(
+                json_owned_object.values().all(|json_value| json_value.as_object().is_none()),
+                "{}",
+                file_str
+            );
+            assert_eq!(json_owned_object, json_shared_object, "{}", file_str);
+            assert!(
+                json_owned_object.deep_eq(&json_shared_object, &JsonEqOptions::new()),
+                "{}",
+                file_str
+            );
+            assert_eq!(
+                json_owned_object.canonical_hash(),
+                json_shared_object.canonical_hash(),
+                "{}",
+                file_str
+            );
+            let owned_string = json_owned_object.serialize_with_options(&serializer_options)?;
+            assert_eq!(
+                owned_string,
+                json_shared_object.serialize_with_options(&serializer_options)?,
+                "{}",
+                file_str
+            );
+            // 所有オブジェクトのまま読み直しても同じ
+            assert_eq!(
+                JsonParser::parse_with_options(&owned_string, &owned_options)?,
+                json_owned_object,
+                "{}",
+                file_str
+            );
+            assert_eq!(
+                json_owned_object.clone().into_shared_objects(),
+                json_shared_object,
+                "{}",
+                file_str
+            );
+            assert_eq!(json_shared_object.into_owned_objects(), json_owned_object, "{}", file_str);
+        }
+
+        // &mut で変更でき、clone は値としてコピーされる
+        let mut json_object = JsonParser::parse_with_options(r#"{"server": {"host": "a", "ports": [80]}}"#, &owned_options)?;
+        let json_copied_object = json_object.clone();
+        let json_server_object = json_object.get_owned_object_mut("server").unwrap();
+        json_server_object.insert("host", "b");
+        json_server_object
+            .get_mut("ports")
+            .and_then(JsonValue::as_array_mut)
+            .unwrap()
+            .push(JsonValue::from(443));
+        assert_eq!(json_object.get_path("server.host")?, Some(JsonValue::from("b")));
+        assert_eq!(
+            json_copied_object.get_owned_object("server").unwrap().get_str("host"),
+            Some("a")
+        );
+        assert_eq!(json_object.pointer("/server/ports/1"), Some(JsonValue::from(443)));
+        assert!(json_object.get_object("server").is_none());
+        assert_eq!(json_object.try_get_owned_object("server")?.len(), 2);
+        assert!(json_copied_object.try_get_owned_object("none").is_err());
+        assert_eq!(
+            json_object.serialize_canonical()?,
+            r#"{"server":{"host":"b","ports":[80,443]}}"#
+        );
+
+        // 共有オブジェクトは参照ごとに別の所有オブジェクトになり、循環参照は共有のまま残る
+        let json_shared_object = JsonObject::parse(r#"{"v": 1}"#)?.into_shared();
+        let mut json_object = JsonObject::new();
+        json_object.insert("a", json_shared_object.clone());
+        json_object.insert("b", json_shared_object.clone());
+        json_shared_object.write_object().insert("self", json_shared_object.clone());
+        let json_owned_object = json_object.into_owned_objects();
+        let json_a_object = json_owned_object.get_owned_object("a").unwrap();
+        assert_eq!(json_a_object.get_i64("v"), Some(1));
+        assert!(json_a_object
+            .get_object("self")
+            .is_some_and(|json_self_object| JsonSharedObject::ptr_eq(&json_self_object, &json_shared_object)));
+        json_shared_object.write_object().remove("self");
+
+        let json_value = JsonValue::from(Box::new(JsonObject::parse(r#"{"a": {"b": 1}}"#)?));
+        assert!(json_value.as_owned_object().is_some());
+        let json_value = json_value.into_shared_objects();
+        assert!(json_value.as_object().is_some());
+        assert_eq!(
+            json_value
+                .into_owned_objects()
+                .as_owned_object()
+                .unwrap()
+                .get_owned_object("a")
+                .unwrap()
+                .get_i64("b"),
+            Some(1)
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn serde_json_values() -> Result<()> {
+        for file_str in [
+            "./for_test/parse_test_string.json",
+            "./for_test/parse_test_number.json",
+            "./for_test/parse_test_bool_null.json",
+            "./for_test/parse_test_array.json",
+            "./for_test/parse_test_object.json",
+            "./for_test/read_test1.json",
+        ] {
+            let mut content_string = String::new();
+            File::open(Path::new(file_str))?.read_to_string(&mut content_string)?;
+            let json_object = JsonObject::parse(&content_string)?;
+            let map = serde_json::Map::from(&json_object);
+            // serde_json が直接パースした結果と同じになる
+            assert_eq!(
+                serde_json::Value::Object(map.clone()),
+                serde_json::from_str::<serde_json::Value>(&content_string)?,
+                "{}",
+                file_str
+            );
+            let json_round_trip_object = JsonObject::try_from(&map)?;
+            assert!(
+                json_round_trip_object.deep_eq(&json_object, &JsonEqOptions::new()),
+                "{}",
+                file_str
+            );
+            let json_owned_object =
+                JsonParser::parse_with_options(&content_string, &JsonParserOptions::new().owned_objects(true))?;
+            assert_eq!(serde_json::Map::from(&json_owned_object), map, "{}", file_str);
+        }
+
+        let json_value = JsonValue::from(vec![
+            JsonValue::from(i64::MIN),
+            JsonValue::ValueNumber(JsonNumberType::Uint(u64::MAX)),
+            JsonValue::from(1.5),
+            JsonValue::from(f64::NAN),
+            JsonValue::ValueRaw(JsonRawValue::new("[1, 2]")?),
+        ]);
+        let value = serde_json::Value::from(&json_value);
+        assert_eq!(value, serde_json::json!([i64::MIN, u64::MAX, 1.5, null, [1, 2]]));
+        let json_round_trip_value = JsonValue::try_from(&value)?;
+        assert_eq!(
+            json_round_trip_value[0],
+            JsonValue::ValueNumber(JsonNumberType::Int(i64::MIN))
+        );
+        assert_eq!(
+            json_round_trip_value[1],
+            JsonValue::ValueNumber(JsonNumberType::Uint(u64::MAX))
+        );
+        assert_eq!(json_round_trip_value[2], JsonValue::ValueNumber(JsonNumberType::Float(1.5)));
+        assert_eq!(
+            JsonValue::try_from(&serde_json::json!(i64::MAX as u64 + 1))?,
+            JsonValue::ValueNumber(JsonNumberType::Uint(i64::MAX as u64 + 1))
+        );
+
+        // 循環参照は null になる
+        let json_shared_object = JsonObject::parse(r#"{"v": 1}"#)?.into_shared();
+        json_shared_object.write_object().insert("self", json_shared_object.clone());
+        let value = serde_json::Value::from(&JsonValue::from(json_shared_object.clone()));
+        assert_eq!(value, serde_json::json!({"v": 1, "self": null}));
+        json_shared_object.write_object().remove("self");
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() -> Result<()> {
+        #[derive(serde::Serialize, serde::Deserialize, PartialEq, Debug)]
+        struct Document {
+            name: String,
+            body: JsonValue,
+        }
+
+        for file_str in [
+            "./for_test/parse_test_string.json",
+            "./for_test/parse_test_number.json",
+            "./for_test/parse_test_bool_null.json",
+            "./for_test/parse_test_array.json",
+            "./for_test/parse_test_object.json",
+            "./for_test/read_test1.json",
+        ] {
+            let mut content_string = String::new();
+            File::open(Path::new(file_str))?.read_to_string(&mut content_string)?;
+            let json_object = JsonObject::parse(&content_string)?;
+            // serde_json が直接パースした結果と同じ値になる
+            let json_string = serde_json::to_string(&json_object)?;
+            assert_eq!(
+                serde_json::from_str::<serde_json::Value>(&json_string)?,
+                serde_json::from_str::<serde_json::Value>(&content_string)?,
+                "{}",
+                file_str
+            );
+            let json_round_trip_object: JsonObject = serde_json::from_str(&json_string)?;
+            assert!(
+                json_round_trip_object.deep_eq(&json_object, &JsonEqOptions::new()),
+                "{}",
+                file_str
+            );
+            assert_eq!(
+                serde_json::from_str::<JsonObject>(&content_string)?,
+                json_object,
+                "{}",
+                file_str
+            );
+
+            // 自己記述的でない形式では値の種類も保たれる
+            let document = Document {
+                name: file_str.to_string(),
+                body: JsonValue::from(json_object),
+            };
+            let bytes = postcard::to_allocvec(&document)?;
+            assert_eq!(postcard::from_bytes::<Document>(&bytes)?, document, "{}", file_str);
+            assert_eq!(
+                serde_json::from_str::<Document>(&serde_json::to_string(&document)?)?,
+                document,
+                "{}",
+                file_str
+            );
+        }
+
+        let json_value = JsonValue::from(vec![
+            JsonValue::from(-1),
+            JsonValue::ValueNumber(JsonNumberType::Uint(u64::MAX)),
+            JsonValue::from(1.0),
+            JsonValue::ValueNumber(JsonNumberType::BigDecimal("3.14159265358979323846".to_string())),
+            JsonValue::ValueNull,
+            JsonValue::from(Box::new(JsonObject::parse(r#"{"b": true, "a": "x"}"#)?)),
+            JsonValue::ValueRaw(JsonRawValue::new("[1, {\"c\": null}]")?),
+        ]);
+        assert_eq!(
+            serde_json::to_string(&json_value)?,
+            r#"[-1,18446744073709551615,1.0,3.141592653589793,null,{"a":"x","b":true},[1,{"c":null}]]"#
+        );
+        let json_round_trip_value: JsonValue = postcard::from_bytes(&postcard::to_allocvec(&json_value)?)?;
+        assert_eq!(json_round_trip_value, json_value);
+        assert!(json_round_trip_value[5].as_object().is_some());
+        let json_round_trip_value: JsonValue = serde_json::from_str("[9223372036854775807, 9223372036854775808, -0.5]")?;
+        assert_eq!(
+            json_round_trip_value,
+            JsonValue::from(vec![
+                JsonValue::ValueNumber(JsonNumberType::Int(i64::MAX)),
+                JsonValue::ValueNumber(JsonNumberType::Uint(i64::MAX as u64 + 1)),
+                JsonValue::ValueNumber(JsonNumberType::Float(-0.5)),
+            ])
+        );
+
+        // 循環参照はエラーになる
+        let json_shared_object = JsonObject::parse(r#"{"v": 1}"#)?.into_shared();
+        json_shared_object.write_object().insert("self", json_shared_object.clone());
+        assert!(serde_json::to_string(&JsonValue::from(json_shared_object.clone())).is_err());
+        json_shared_object.write_object().remove("self");
+        Ok(())
+    }
+
+    #[cfg(feature = "sync")]
+    #[test]
+    fn sync_cyclic_root_hash() {
+        let json_shared_object = JsonObject::parse(r#"{"v": 1}"#).unwrap().into_shared();
+        json_shared_object.write_object().insert("self", json_shared_object.clone());
+        // 根を指す循環参照は、根の共有オブジェクトを渡せば sync なしと同じく一階層上として検出される
+        let root_hash = JsonValue::from(json_shared_object.clone()).canonical_hash();
+        assert_eq!(root_hash, 8258448622488133701);
+        let mut hasher = crate::hash::JsonFnvHasher::new();
+        crate::hash::object_hasher(&json_shared_object.read_object(), Some(&json_shared_object), &mut hasher);
+        assert_eq!(root_hash, hasher.finish());
+        // 借用した根からは、循環が一階層下で検出される
+        assert_ne!(root_hash, json_shared_object.read_object().canonical_hash());
+        json_shared_object.write_object().remove("self");
+    }
 }
@@ -0,0 +1,352 @@
+//! Traversal module.
+use std::collections::HashSet;
+
+use super::shared::{object_ptr, try_unwrap_object, JsonObjectPtr, JsonSharedObject};
+use super::JsonObjectLock;
+use super::{JsonObject, JsonPath, JsonValue, JsonWalkControl};
+
+/// Call `f` with the object and every object nested in it, including the objects in arrays, parents before children.
+/// Each object is visited once even if it is shared by several places, so reference cycles are not followed.
+/// Objects that are already borrowed mutably are skipped. `op_root_object` is the shared object that the root is borrowed from,
+/// so that the root is not visited again through a reference cycle.
+pub(crate) fn visit_objects<F>(json_object: &JsonObject, op_root_object: Option<&JsonSharedObject>, f: &mut F)
+where
+    F: FnMut(&JsonObject),
+{
+    let mut visited_ptrs: HashSet<JsonObjectPtr> = op_root_object.map(object_ptr).into_iter().collect();
+    object_visitor(json_object, f, &mut visited_ptrs);
+}
+
+fn object_visitor<F>(json_object: &JsonObject, f: &mut F, visited_ptrs: &mut HashSet<JsonObjectPtr>)
+where
+    F: FnMut(&JsonObject),
+{
+    f(json_object);
+    for json_value in json_object.members.values() {
+        value_visitor(json_value, f, visited_ptrs);
+    }
+}
+
+fn value_visitor<F>(json_value: &JsonValue, f: &mut F, visited_ptrs: &mut HashSet<JsonObjectPtr>)
+where
+    F: FnMut(&JsonObject),
+{
+    match json_value {
+        JsonValue::ValueObject(refcell_json_object) => {
+            if !visited_ptrs.insert(object_ptr(refcell_json_object)) {
+                return;
+            }
+            let Some(json_object) = refcell_json_object.try_read_object() else {
+                return;
+            };
+            object_visitor(&json_object, f, visited_ptrs);
+        }
+        JsonValue::ValueOwnedObject(json_object) => object_visitor(json_object, f, visited_ptrs),
+        JsonValue::ValueArray(json_array) => {
+            for json_element_value in json_array {
+                value_visitor(json_element_value, f, visited_ptrs);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Mutable version of `visit_objects`. `f` is called before the members are visited, so it can change which members are visited.
+/// Objects that are already borrowed, e.g. by the caller through a reference cycle, are skipped.
+pub(crate) fn visit_objects_mut<F>(json_object: &mut JsonObject, f: &mut F)
+where
+    F: FnMut(&mut JsonObject),
+{
+    let mut visited_ptrs = HashSet::new();
+    object_mut_visitor(json_object, f, &mut visited_ptrs);
+}
+
+fn object_mut_visitor<F>(json_object: &mut JsonObject, f: &mut F, visited_ptrs: &mut HashSet<JsonObjectPtr>)
+where
+    F: FnMut(&mut JsonObject),
+{
+    f(json_object);
+    for json_value in json_object.members.values_mut() {
+        value_mut_visitor(json_value, f, visited_ptrs);
+    }
+}
+
+fn value_mut_visitor<F>(json_value: &mut JsonValue, f: &mut F, visited_ptrs: &mut HashSet<JsonObjectPtr>)
+where
+    F: FnMut(&mut JsonObject),
+{
+    match json_value {
+        JsonValue::ValueObject(refcell_json_object) => {
+            if !visited_ptrs.insert(object_ptr(refcell_json_object)) {
+                return;
+            }
+            // 借用中のオブジェクト(呼び出し元が借用している祖先)はたどらない
+            let Some(mut json_object) = refcell_json_object.try_write_object() else {
+                return;
+            };
+            object_mut_visitor(&mut json_object, f, visited_ptrs);
+        }
+        JsonValue::ValueOwnedObject(json_object) => object_mut_visitor(json_object, f, visited_ptrs),
+        JsonValue::ValueArray(json_array) => {
+            for json_element_value in json_array {
+                value_mut_visitor(json_element_value, f, visited_ptrs);
+            }
+        }
+        _ => {}
+    }
+}
+
+pub(crate) fn walk_value<F>(json_value: &JsonValue, f: &mut F)
+where
+    F: FnMut(&JsonPath, &JsonValue) -> JsonWalkControl,
+{
+    Walker::new(f).value_walker(json_value);
+}
+
+/// `op_root_object` is the shared object that the root is borrowed from, so that a reference cycle to the root is not followed.
+pub(crate) fn walk_object<F>(json_object: &JsonObject, op_root_object: Option<&JsonSharedObject>, f: &mut F)
+where
+    F: FnMut(&JsonPath, &JsonValue) -> JsonWalkControl,
+{
+    let mut walker = Walker::new(f);
+    walker.ancestor_ptrs.extend(op_root_object.map(object_ptr));
+    walker.object_walker(json_object);
+}
+
+struct Walker<'a, F> {
+    f: &'a mut F,
+    path: JsonPath,
+    /// 循環を検出するため、たどっている途中のオブジェクト
+    ancestor_ptrs: Vec<JsonObjectPtr>,
+}
+
+impl<'a, F> Walker<'a, F>
+where
+    F: FnMut(&JsonPath, &JsonValue) -> JsonWalkControl,
+{
+    fn new(f: &'a mut F) -> Walker<'a, F> {
+        Walker {
+            f,
+            path: JsonPath::default(),
+            ancestor_ptrs: Vec::new(),
+        }
+    }
+
+    /// Walk the value and its children.
+    /// * Return:
+    ///     * `false` if the traversal is stopped.
+    fn value_walker(&mut self, json_value: &JsonValue) -> bool {
+        match (self.f)(&self.path, json_value) {
+            JsonWalkControl::Continue => {}
+            JsonWalkControl::SkipChildren => return true,
+            JsonWalkControl::Stop => return false,
+        }
+        match json_value {
+            JsonValue::ValueObject(refcell_json_object) => {
+                let json_object_ptr = object_ptr(refcell_json_object);
+                if self.ancestor_ptrs.contains(&json_object_ptr) {
+                    return true;
+                }
+                let Some(json_object) = refcell_json_object.try_read_object() else {
+                    return true;
+                };
+                self.ancestor_ptrs.push(json_object_ptr);
+                let is_continued = self.object_walker(&json_object);
+                self.ancestor_ptrs.pop();
+                is_continued
+            }
+            JsonValue::ValueOwnedObject(json_object) => self.object_walker(json_object),
+            JsonValue::ValueArray(json_array) => {
+                for (idx, json_element_value) in json_array.iter().enumerate() {
+                    self.path.push_index(idx);
+                    let is_continued = self.value_walker(json_element_value);
+                    self.path.pop();
+                    if !is_continued {
+                        return false;
+                    }
+                }
+                true
+            }
+            _ => true,
+        }
+    }
+
+    fn object_walker(&mut self, json_object: &JsonObject) -> bool {
+        for key_str in json_object.keys_sorted() {
+            self.path.push_key(key_str);
+            let is_continued = self.value_walker(&json_object[key_str]);
+            self.path.pop();
+            if !is_continued {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+pub(crate) fn walk_value_mut<F>(json_value: &mut JsonValue, f: &mut F)
+where
+    F: FnMut(&JsonPath, &mut JsonValue) -> JsonWalkControl,
+{
+    MutWalker {
+        f,
+        path: JsonPath::default(),
+    }
+    .value_walker(json_value);
+}
+
+pub(crate) fn walk_object_mut<F>(json_object: &mut JsonObject, f: &mut F)
+where
+    F: FnMut(&JsonPath, &mut JsonValue) -> JsonWalkControl,
+{
+    MutWalker {
+        f,
+        path: JsonPath::default(),
+    }
+    .object_walker(json_object);
+}
+
+/// Mutable version of `Walker`. Ancestors are borrowed mutably, so reference cycles are detected by `try_borrow_mut`.
+struct MutWalker<'a, F> {
+    f: &'a mut F,
+    path: JsonPath,
+}
+
+impl<F> MutWalker<'_, F>
+where
+    F: FnMut(&JsonPath, &mut JsonValue) -> JsonWalkControl,
+{
+    fn value_walker(&mut self, json_value: &mut JsonValue) -> bool {
+        match (self.f)(&self.path, json_value) {
+            JsonWalkControl::Continue => {}
+            JsonWalkControl::SkipChildren => return true,
+            JsonWalkControl::Stop => return false,
+        }
+        match json_value {
+            JsonValue::ValueObject(refcell_json_object) => {
+                let Some(mut json_object) = refcell_json_object.try_write_object() else {
+                    return true;
+                };
+                self.object_walker(&mut json_object)
+            }
+            JsonValue::ValueOwnedObject(json_object) => self.object_walker(json_object),
+            JsonValue::ValueArray(json_array) => {
+                for (idx, json_element_value) in json_array.iter_mut().enumerate() {
+                    self.path.push_index(idx);
+                    let is_continued = self.value_walker(json_element_value);
+                    self.path.pop();
+                    if !is_continued {
+                        return false;
+                    }
+                }
+                true
+            }
+            _ => true,
+        }
+    }
+
+    fn object_walker(&mut self, json_object: &mut JsonObject) -> bool {
+        let key_strings: Vec<String> = json_object.keys_sorted().into_iter().map(str::to_string).collect();
+        for key_string in key_strings {
+            let Some(json_value) = json_object.get_mut(&key_string) else {
+                continue;
+            };
+            self.path.push_key(&key_string);
+            let is_continued = self.value_walker(json_value);
+            self.path.pop();
+            if !is_continued {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Apply `f` to the scalars in the value and rebuild the arrays and objects around the results.
+/// Objects are rebuilt in new `Rc`s, so the result shares no objects with the value, except for the objects of reference cycles,
+/// which are kept as they are.
+pub(crate) fn map_value<F>(json_value: JsonValue, f: &mut F) -> JsonValue
+where
+    F: FnMut(JsonValue) -> JsonValue,
+{
+    value_mapper(json_value, f, &mut Vec::new())
+}
+
+fn value_mapper<F>(json_value: JsonValue, f: &mut F, ancestor_ptrs: &mut Vec<JsonObjectPtr>) -> JsonValue
+where
+    F: FnMut(JsonValue) -> JsonValue,
+{
+    match json_value {
+        JsonValue::ValueObject(refcell_json_object) => {
+            let json_object_ptr = object_ptr(&refcell_json_object);
+            if ancestor_ptrs.contains(&json_object_ptr) {
+                return JsonValue::ValueObject(refcell_json_object);
+            }
+            // 他から参照されていなければ、メンバーをコピーせずに取り出す
+            let members = match try_unwrap_object(refcell_json_object) {
+                Ok(json_object) => json_object.members,
+                Err(refcell_json_object) => {
+                    let Some(members) = refcell_json_object
+                        .try_read_object()
+                        .map(|json_object| json_object.members.clone())
+                    else {
+                        return JsonValue::ValueObject(refcell_json_object);
+                    };
+                    members
+                }
+            };
+            ancestor_ptrs.push(json_object_ptr);
+            let json_object = JsonObject {
+                members: members
+                    .into_iter()
+                    .map(|(json_key, json_member_value)| (json_key, value_mapper(json_member_value, f, ancestor_ptrs)))
+                    .collect(),
+            };
+            ancestor_ptrs.pop();
+            JsonValue::from(json_object)
+        }
+        JsonValue::ValueOwnedObject(json_object) => JsonValue::ValueOwnedObject(Box::new(JsonObject {
+            members: json_object
+                .members
+                .into_iter()
+                .map(|(json_key, json_member_value)| (json_key, value_mapper(json_member_value, f, ancestor_ptrs)))
+                .collect(),
+        })),
+        JsonValue::ValueArray(json_array) => JsonValue::ValueArray(
+            json_array
+                .into_iter()
+                .map(|json_element_value| value_mapper(json_element_value, f, ancestor_ptrs))
+                .collect(),
+        ),
+        _ => f(json_value),
+    }
+}
+
+/// Apply `f` to the scalars in the object in place. Objects shared by several places are changed once,
+/// and objects that are already borrowed, e.g. by a reference cycle, are skipped.
+pub(crate) fn map_object_in_place<F>(json_object: &mut JsonObject, f: &mut F)
+where
+    F: FnMut(JsonValue) -> JsonValue,
+{
+    // オブジェクトは visit_objects_mut がたどるので、ここでは配列だけたどる
+    visit_objects_mut(json_object, &mut |json_object: &mut JsonObject| {
+        for json_value in json_object.members.values_mut() {
+            scalar_mapper(json_value, f);
+        }
+    });
+}
+
+fn scalar_mapper<F>(json_value: &mut JsonValue, f: &mut F)
+where
+    F: FnMut(JsonValue) -> JsonValue,
+{
+    match json_value {
+        JsonValue::ValueObject(_) | JsonValue::ValueOwnedObject(_) => {}
+        JsonValue::ValueArray(json_array) => {
+            for json_element_value in json_array {
+                scalar_mapper(json_element_value, f);
+            }
+        }
+        _ => *json_value = f(json_value.take()),
+    }
+}
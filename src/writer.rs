@@ -0,0 +1,600 @@
+//! JSON Writer module.
+use std::io::Write;
+
+use super::serializer::{
+    make_colon_str, make_indent_string, make_newline_str, make_pointer_string, make_trailing_newline_str, validate_options,
+    JsonSerializer, JsonSerializerOutput, JsonWriterOutput, BOM_CHAR,
+};
+use super::{
+    JsonNumberType, JsonPathSegment, JsonRawValue, JsonSerializerArrayStyle, JsonSerializerBraceStyle, JsonSerializerEmptyStyle,
+    JsonSerializerNonFiniteFloatPolicy, JsonSerializerOptions,
+};
+
+use super::error::*;
+
+fn writer_error(kind: JsonErrorKind, detail_str: &str, status_str: &str) -> Box<dyn std::error::Error + Send + Sync + 'static> {
+    JsonError::new(kind, Some(format!("{} | {}", detail_str, status_str)))
+}
+
+/// Check that the options do not need the whole tree, which the writer never has, so that the output is the same as the serializer.
+fn unsupported_options_checker(options: &JsonSerializerOptions) -> Result<()> {
+    // レイアウトのオプションは compact では無視される
+    let is_layout = !options.compact;
+    let op_option_str = [
+        (
+            is_layout && options.brace_style != JsonSerializerBraceStyle::SameLine,
+            "brace_style",
+        ),
+        (is_layout && options.align_colons, "align_colons"),
+        (
+            is_layout && options.max_inline_array_width.is_some(),
+            "max_inline_array_width",
+        ),
+        (is_layout && options.array_wrap.is_some(), "array_wrap"),
+        (is_layout && options.inline_small_objects.is_some(), "inline_small_objects"),
+        (is_layout && options.inline_value.is_some(), "inline_value"),
+        (options.transform_value.is_some(), "transform_value"),
+    ]
+    .into_iter()
+    .find_map(|(is_set, option_str)| is_set.then_some(option_str));
+    match op_option_str {
+        Some(option_str) => Err(writer_error(
+            JsonErrorKind::SerializeErrorInOptions,
+            "Writer: Option is not supported by the writer.",
+            option_str,
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Object or array opened by the caller. Used to check the nesting of the calls.
+enum WriterFrame {
+    Object {
+        // 直前に書いたキー
+        key_string: Option<String>,
+        is_value_expected: bool,
+    },
+    Array {
+        // 書き始めた要素の数
+        len: usize,
+    },
+}
+
+/// Call whose output is deferred until the layout of the enclosing arrays is decided.
+enum WriterEvent {
+    BeginObject,
+    EndObject,
+    BeginArray,
+    EndArray,
+    Value(String),
+    Raw(String),
+}
+
+/// Object or array being written to the output.
+struct LayoutFrame {
+    is_object: bool,
+    is_multiline: bool,
+    // 書き出した要素の数。0 の間は開き括弧も書き出していない
+    count: usize,
+}
+
+/// Push-based JSON writer.
+/// Values are written one by one with `begin_object()`, `key()`, `value_str()`, `end_object()` and so on,
+/// and the output is the same as that of `JsonObject::serialize_with_options()` for the equivalent tree.
+/// * Members are written in the order of the calls, so `sort_keys` and `priority_keys` have no effect.
+/// * The options that need the whole object or array before writing it(`brace_style` other than `SameLine`, `align_colons`,
+///   `max_inline_array_width`, `array_wrap`, `inline_small_objects` and `inline_value`) and `transform_value` are not supported.
+///   `new` returns an error for them, except for the layout options that compact mode ignores anyway.
+/// * Whether an array spans multiple lines depends on its contents,
+///   so the output of an array is held until a non-empty object appears in it or it is closed.
+pub struct JsonWriter<'a, W: Write> {
+    options: &'a JsonSerializerOptions,
+    // スカラー値のシリアライズに使う
+    scalar_serializer: JsonSerializer<'a>,
+    output: JsonWriterOutput<W>,
+    newline_str: &'static str,
+    trailing_newline_str: &'static str,
+    indent_string: String,
+    indent_level: usize,
+    frames: Vec<WriterFrame>,
+    is_root_written: bool,
+    layout_frames: Vec<LayoutFrame>,
+    // レイアウトが決まっていない配列の中の呼び出し。先頭は必ず BeginArray
+    events: Vec<WriterEvent>,
+    // events の中で閉じられていない配列の数
+    pending_array_count: usize,
+}
+
+impl<'a> JsonWriter<'a, Vec<u8>> {
+    /// Create a JSON writer that writes to a string.
+    /// * Parameters:
+    ///     * `options` : Serializer options.
+    /// * Return:
+    ///     * JSON writer. The string is returned by `finish_string()`.
+    pub fn new_string(options: &'a JsonSerializerOptions) -> Result<JsonWriter<'a, Vec<u8>>> {
+        JsonWriter::new(Vec::new(), options)
+    }
+
+    /// Finish writing and return the string.
+    /// * Return:
+    ///     * Serialized string.
+    pub fn finish_string(self) -> Result<String> {
+        let bytes = self.finish()?;
+        String::from_utf8(bytes).map_err(|err| {
+            writer_error(
+                JsonErrorKind::SerializeErrorInString,
+                "Writer: Output is not UTF-8.",
+                &err.to_string(),
+            )
+        })
+    }
+}
+
+impl<'a, W: Write> JsonWriter<'a, W> {
+    /// Create a JSON writer that writes to `writer`.
+    /// * Parameters:
+    ///     * `writer` : Destination of the serialized string.
+    ///     * `options` : Serializer options.
+    /// * Return:
+    ///     * JSON writer. `SerializeErrorInOptions` error if the options contain an option that the writer does not support.
+    pub fn new(writer: W, options: &'a JsonSerializerOptions) -> Result<JsonWriter<'a, W>> {
+        validate_options(options)?;
+        unsupported_options_checker(options)?;
+        let mut output = JsonWriterOutput::new(writer);
+        if options.emit_bom {
+            output.push(BOM_CHAR)?;
+        }
+        Ok(JsonWriter {
+            options,
+            scalar_serializer: JsonSerializer::new(options, None),
+            output,
+            newline_str: make_newline_str(options),
+            trailing_newline_str: make_trailing_newline_str(options),
+            indent_string: make_indent_string(options),
+            indent_level: 0,
+            frames: Vec::new(),
+            is_root_written: false,
+            layout_frames: Vec::new(),
+            events: Vec::new(),
+            pending_array_count: 0,
+        })
+    }
+
+    /// Begin an object.
+    pub fn begin_object(&mut self) -> Result<()> {
+        self.depth_checker(JsonErrorKind::SerializeErrorInObject)?;
+        self.value_checker(false)?;
+        self.frames.push(WriterFrame::Object {
+            key_string: None,
+            is_value_expected: false,
+        });
+        self.event_writer(WriterEvent::BeginObject)
+    }
+
+    /// Write the key of the next member of the current object.
+    /// * Parameters:
+    ///     * `key_str` : Key.
+    pub fn key(&mut self, key_str: &str) -> Result<()> {
+        match self.frames.last_mut() {
+            Some(WriterFrame::Object {
+                key_string,
+                is_value_expected,
+            }) => {
+                if *is_value_expected {
+                    return Err(self.state_error(
+                        JsonErrorKind::SerializeErrorInKey,
+                        "Writer: Key is written instead of a value.",
+                    ));
+                }
+                *key_string = Some(key_str.to_string());
+                *is_value_expected = true;
+            }
+            _ => {
+                return Err(self.state_error(
+                    JsonErrorKind::SerializeErrorInKey,
+                    "Writer: Key is written outside of an object.",
+                ));
+            }
+        }
+        // キーは値が書かれるときに出力する
+        Ok(())
+    }
+
+    /// End the current object.
+    pub fn end_object(&mut self) -> Result<()> {
+        match self.frames.last() {
+            Some(WriterFrame::Object {
+                is_value_expected: false,
+                ..
+            }) => {}
+            Some(WriterFrame::Object { .. }) => {
+                return Err(self.state_error(
+                    JsonErrorKind::SerializeErrorInObject,
+                    "Writer: Object is ended without a value.",
+                ));
+            }
+            _ => {
+                return Err(self.state_error(JsonErrorKind::SerializeErrorInObject, "Writer: There is no object to end."));
+            }
+        }
+        self.frames.pop();
+        self.event_writer(WriterEvent::EndObject)?;
+        if self.pending_array_count > 0 && self.options.empty_object_style == JsonSerializerEmptyStyle::Expanded {
+            // 保留中のオブジェクトは空。展開する空オブジェクトを含む配列は複数行になる
+            self.pending_events_writer(true)?;
+        }
+        Ok(())
+    }
+
+    /// Begin an array.
+    pub fn begin_array(&mut self) -> Result<()> {
+        self.depth_checker(JsonErrorKind::SerializeErrorInArray)?;
+        self.value_checker(false)?;
+        self.frames.push(WriterFrame::Array { len: 0 });
+        if self.pending_array_count > 0 || self.is_array_layout_undecided() {
+            self.events.push(WriterEvent::BeginArray);
+            self.pending_array_count += 1;
+            Ok(())
+        } else {
+            let is_multiline = !self.options.compact && self.options.array_style == JsonSerializerArrayStyle::Expanded;
+            self.begin_array_emitter(is_multiline)
+        }
+    }
+
+    /// End the current array.
+    pub fn end_array(&mut self) -> Result<()> {
+        if !matches!(self.frames.last(), Some(WriterFrame::Array { .. })) {
+            return Err(self.state_error(JsonErrorKind::SerializeErrorInArray, "Writer: There is no array to end."));
+        }
+        self.frames.pop();
+        if self.pending_array_count == 0 {
+            return self.end_array_emitter();
+        }
+        let is_empty = matches!(self.events.last(), Some(WriterEvent::BeginArray));
+        self.events.push(WriterEvent::EndArray);
+        self.pending_array_count -= 1;
+        if self.pending_array_count == 0 {
+            // 空でないオブジェクトが現れないまま閉じられたので一行で出力する
+            self.pending_events_writer(false)
+        } else if is_empty && self.options.empty_array_style == JsonSerializerEmptyStyle::Expanded {
+            self.pending_events_writer(true)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Write a string value.
+    /// * Parameters:
+    ///     * `value_str` : String value.
+    pub fn value_str(&mut self, value_str: &str) -> Result<()> {
+        self.value_checker(false)?;
+        let mut value_string = String::new();
+        self.scalar_serializer.string_serializer(value_str, &mut value_string)?;
+        self.event_writer(WriterEvent::Value(value_string))
+    }
+
+    /// Write a number value.
+    /// * Parameters:
+    ///     * `value_number` : Number value.
+    pub fn value_number(&mut self, value_number: JsonNumberType) -> Result<()> {
+        if let JsonNumberType::Float(float_number) = value_number {
+            if !float_number.is_finite() && self.options.non_finite_float == JsonSerializerNonFiniteFloatPolicy::Error {
+                return Err(writer_error(
+                    JsonErrorKind::SerializeErrorInNumber,
+                    "Number:  Number is NaN or Infinite.",
+                    &format!("{} | path:{}", float_number, self.pointer_string()),
+                ));
+            }
+        }
+        self.value_checker(false)?;
+        let mut value_string = String::new();
+        self.scalar_serializer.number_serializer(&value_number, &mut value_string)?;
+        self.event_writer(WriterEvent::Value(value_string))
+    }
+
+    /// Write a bool value.
+    /// * Parameters:
+    ///     * `value_bool` : Bool value.
+    pub fn value_bool(&mut self, value_bool: bool) -> Result<()> {
+        self.value_checker(false)?;
+        let mut value_string = String::new();
+        self.scalar_serializer.bool_serializer(&value_bool, &mut value_string)?;
+        self.event_writer(WriterEvent::Value(value_string))
+    }
+
+    /// Write a null value.
+    pub fn value_null(&mut self) -> Result<()> {
+        if !self.value_checker(true)? {
+            return Ok(());
+        }
+        let mut value_string = String::new();
+        self.scalar_serializer.null_serializer(&mut value_string)?;
+        self.event_writer(WriterEvent::Value(value_string))
+    }
+
+    /// Write a raw value as it is.
+    /// * Parameters:
+    ///     * `value_raw` : Raw value.
+    pub fn value_raw(&mut self, value_raw: &JsonRawValue) -> Result<()> {
+        self.value_checker(false)?;
+        self.event_writer(WriterEvent::Raw(value_raw.as_str().to_string()))
+    }
+
+    /// Finish writing. All objects and arrays must be ended.
+    /// * Return:
+    ///     * Inner writer.
+    pub fn finish(mut self) -> Result<W> {
+        if !self.frames.is_empty() {
+            return Err(self.state_error(JsonErrorKind::SerializeErrorInValue, "Writer: Object or array is not ended."));
+        }
+        if !self.is_root_written {
+            return Err(self.state_error(JsonErrorKind::SerializeErrorInValue, "Writer: No value is written."));
+        }
+        if self.options.trailing_newline {
+            // 最後に改行する
+            self.output.push_str(self.trailing_newline_str)?;
+        }
+        self.output.into_inner()
+    }
+
+    /// Check that a value can be written at the current position, and advance the position.
+    /// The key is written here if the value is a member of an object.
+    /// Returns whether the value is written, i.e. it is not a null member skipped by `skip_null_members`.
+    fn value_checker(&mut self, is_null: bool) -> Result<bool> {
+        let member_key_string = match self.frames.last_mut() {
+            None => {
+                if self.is_root_written {
+                    return Err(self.state_error(JsonErrorKind::SerializeErrorInValue, "Writer: Root value is already written."));
+                }
+                self.is_root_written = true;
+                return Ok(true);
+            }
+            Some(WriterFrame::Object {
+                key_string,
+                is_value_expected,
+            }) => {
+                if !*is_value_expected {
+                    return Err(self.state_error(
+                        JsonErrorKind::SerializeErrorInValue,
+                        "Writer: Value is written without a key.",
+                    ));
+                }
+                *is_value_expected = false;
+                if is_null && self.options.skip_null_members {
+                    return Ok(false);
+                }
+                key_string.clone().unwrap_or_default()
+            }
+            Some(WriterFrame::Array { len }) => {
+                *len += 1;
+                return Ok(true);
+            }
+        };
+        self.key_writer(&member_key_string)?;
+        Ok(true)
+    }
+
+    fn key_writer(&mut self, key_str: &str) -> Result<()> {
+        // 空でないオブジェクトを含む配列は複数行になる
+        self.pending_events_writer(true)?;
+
+        let mut key_string = String::new();
+        self.scalar_serializer.key_str_serializer(key_str, &mut key_string)?;
+        let newline_str = self.newline_str;
+        if let Some(layout_frame) = self.layout_frames.last_mut() {
+            if layout_frame.count == 0 {
+                self.output.push('{')?;
+                self.indent_level += 1;
+            } else {
+                self.output.push(',')?;
+            }
+            layout_frame.count += 1;
+        }
+        self.output.push_str(newline_str)?;
+        self.indent_serializer()?;
+        self.output.push_str(&key_string)?;
+        self.output.push_str(make_colon_str(self.options))
+    }
+
+    /// Check that the object or array to be begun does not go beyond `max_depth`.
+    fn depth_checker(&self, kind: JsonErrorKind) -> Result<()> {
+        let depth = self.frames.len() + 1;
+        match self.options.max_depth {
+            Some(max_depth) if depth > max_depth => Err(writer_error(
+                kind,
+                "Depth: Nesting is too deep.",
+                &format!("depth:{} | path:{}", depth, self.pointer_string()),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// JSON Pointer of the value to be written next.
+    fn pointer_string(&self) -> String {
+        let last_idx = self.frames.len().saturating_sub(1);
+        let path: Vec<JsonPathSegment> = self
+            .frames
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, frame)| match frame {
+                WriterFrame::Object { key_string, .. } => key_string.as_deref().map(JsonPathSegment::Key),
+                // 開いている子の配列やオブジェクトは最後に書き始めた要素
+                WriterFrame::Array { len } if idx < last_idx => Some(JsonPathSegment::Index(len.saturating_sub(1))),
+                WriterFrame::Array { len } => Some(JsonPathSegment::Index(*len)),
+            })
+            .collect();
+        make_pointer_string(&path)
+    }
+
+    fn state_error(&self, kind: JsonErrorKind, detail_str: &str) -> Box<dyn std::error::Error + Send + Sync + 'static> {
+        writer_error(kind, detail_str, &format!("path:{}", self.pointer_string()))
+    }
+
+    /// Whether the layout of a new array depends on its contents.
+    fn is_array_layout_undecided(&self) -> bool {
+        !self.options.compact && self.options.array_style == JsonSerializerArrayStyle::Inline
+    }
+
+    /// Hold the event while the layout of an enclosing array is undecided, otherwise write it.
+    fn event_writer(&mut self, event: WriterEvent) -> Result<()> {
+        if self.pending_array_count > 0 {
+            self.events.push(event);
+            Ok(())
+        } else {
+            self.event_emitter(event, false)
+        }
+    }
+
+    /// Write the held events. Arrays not ended yet span multiple lines if `is_multiline` is true,
+    /// and arrays already ended are inline because they do not contain non-empty objects.
+    fn pending_events_writer(&mut self, is_multiline: bool) -> Result<()> {
+        if self.events.is_empty() {
+            return Ok(());
+        }
+        let events = std::mem::take(&mut self.events);
+        self.pending_array_count = 0;
+        // 各 BeginArray が events の中で閉じられているかどうか
+        let mut are_arrays_ended = vec![false; events.len()];
+        let mut begin_idxs: Vec<usize> = Vec::new();
+        for (idx, event) in events.iter().enumerate() {
+            match event {
+                WriterEvent::BeginArray => begin_idxs.push(idx),
+                WriterEvent::EndArray => {
+                    if let Some(begin_idx) = begin_idxs.pop() {
+                        are_arrays_ended[begin_idx] = true;
+                    }
+                }
+                _ => {}
+            }
+        }
+        for (event, is_array_ended) in events.into_iter().zip(are_arrays_ended) {
+            self.event_emitter(event, is_multiline && !is_array_ended)?;
+        }
+        Ok(())
+    }
+
+    fn event_emitter(&mut self, event: WriterEvent, is_multiline: bool) -> Result<()> {
+        match event {
+            WriterEvent::BeginObject => {
+                self.value_emitter()?;
+                self.layout_frames.push(LayoutFrame {
+                    is_object: true,
+                    is_multiline: true,
+                    count: 0,
+                });
+                Ok(())
+            }
+            WriterEvent::EndObject => self.end_object_emitter(),
+            WriterEvent::BeginArray => self.begin_array_emitter(is_multiline),
+            WriterEvent::EndArray => self.end_array_emitter(),
+            WriterEvent::Value(value_string) => {
+                self.value_emitter()?;
+                self.output.push_str(&value_string)
+            }
+            WriterEvent::Raw(raw_string) => {
+                self.value_emitter()?;
+                // インデントは出力する位置で決まる
+                self.scalar_serializer.indent_level = self.indent_level;
+                self.scalar_serializer.raw_serializer(&raw_string, &mut self.output)
+            }
+        }
+    }
+
+    /// Write what precedes a value, i.e. the opening bracket of the array and the separator of the elements.
+    fn value_emitter(&mut self) -> Result<()> {
+        let newline_str = self.newline_str;
+        let compact = self.options.compact;
+        let Some(layout_frame) = self.layout_frames.last_mut() else {
+            return Ok(());
+        };
+        if layout_frame.is_object {
+            return Ok(());
+        }
+        let is_first = layout_frame.count == 0;
+        layout_frame.count += 1;
+        if layout_frame.is_multiline {
+            if is_first {
+                self.output.push('[')?;
+                self.indent_level += 1;
+            } else {
+                self.output.push(',')?;
+            }
+            self.output.push_str(newline_str)?;
+            self.indent_serializer()
+        } else {
+            if is_first {
+                self.output.push('[')?;
+            } else {
+                self.output.push(',')?;
+            }
+            if !compact {
+                self.output.push(' ')?;
+            }
+            Ok(())
+        }
+    }
+
+    fn begin_array_emitter(&mut self, is_multiline: bool) -> Result<()> {
+        self.value_emitter()?;
+        self.layout_frames.push(LayoutFrame {
+            is_object: false,
+            is_multiline,
+            count: 0,
+        });
+        Ok(())
+    }
+
+    fn end_object_emitter(&mut self) -> Result<()> {
+        match self.layout_frames.pop() {
+            Some(LayoutFrame { count: 0, .. }) => self.empty_emitter('{', '}', self.options.empty_object_style),
+            _ => self.close_emitter('}'),
+        }
+    }
+
+    fn end_array_emitter(&mut self) -> Result<()> {
+        match self.layout_frames.pop() {
+            Some(LayoutFrame { count: 0, .. }) => self.empty_emitter('[', ']', self.options.empty_array_style),
+            Some(LayoutFrame { is_multiline: false, .. }) => self.output.push(']'),
+            _ => self.close_emitter(']'),
+        }
+    }
+
+    /// Close the multiline object or array.
+    fn close_emitter(&mut self, close_char: char) -> Result<()> {
+        self.indent_level -= 1;
+        if self.options.trailing_commas {
+            self.output.push(',')?;
+        }
+        self.output.push_str(self.newline_str)?;
+        self.indent_serializer()?;
+        self.output.push(close_char)
+    }
+
+    fn empty_emitter(&mut self, open_char: char, close_char: char, empty_style: JsonSerializerEmptyStyle) -> Result<()> {
+        self.output.push(open_char)?;
+        match empty_style {
+            JsonSerializerEmptyStyle::Collapsed => {}
+            JsonSerializerEmptyStyle::Spaced => {
+                if !self.options.compact {
+                    self.output.push(' ')?;
+                }
+            }
+            JsonSerializerEmptyStyle::Expanded => {
+                // 閉じ括弧は開き括弧の行と同じインデント
+                self.output.push_str(self.newline_str)?;
+                self.indent_serializer()?;
+            }
+        }
+        self.output.push(close_char)
+    }
+
+    fn indent_serializer(&mut self) -> Result<()> {
+        if self.indent_string.is_empty() {
+            return Ok(());
+        }
+        for _ in 0..self.indent_level {
+            self.output.push_str(&self.indent_string)?;
+        }
+        Ok(())
+    }
+}